@@ -567,6 +567,99 @@ app.get('/api/stats', (req, res) => {
   }
 });
 
+// 多设备同步：按同步 id 分别保存客户端推送的变更，每个 id 一个日志文件，游标为变更在该日志中的序号。
+// 同一学习者的各台设备使用同一个同步 id，不同 id 之间互不可见。
+// 服务端不解决冲突，客户端根据版本向量自行合并
+const SYNC_DIR = path.join(__dirname, 'data', 'sync');
+const SYNC_TOKEN = process.env.SYNC_TOKEN || null;
+const SYNC_ID_PATTERN = /^[A-Za-z0-9_-]{8,64}$/;
+const syncLogs = new Map();
+
+function syncLogPath(syncId) {
+  return path.join(SYNC_DIR, `${syncId}.jsonl`);
+}
+
+// 首次访问某个同步 id 时从文件加载
+function loadSyncChanges(syncId) {
+  if (syncLogs.has(syncId)) {
+    return syncLogs.get(syncId);
+  }
+  const logPath = syncLogPath(syncId);
+  const changes = fs.existsSync(logPath)
+    ? fs.readFileSync(logPath, 'utf8')
+      .split('\n')
+      .filter(line => line.trim())
+      .map(line => JSON.parse(line))
+    : [];
+  syncLogs.set(syncId, changes);
+  return changes;
+}
+
+function checkSyncToken(req, res) {
+  if (SYNC_TOKEN && req.headers.authorization !== `Bearer ${SYNC_TOKEN}`) {
+    res.status(401).json({ error: '同步令牌无效' });
+    return false;
+  }
+  return true;
+}
+
+function checkSyncId(syncId, res) {
+  if (typeof syncId !== 'string' || !SYNC_ID_PATTERN.test(syncId)) {
+    res.status(400).json({ error: '缺少或无效的 sync_id' });
+    return false;
+  }
+  return true;
+}
+
+// 推送本地变更
+app.post('/api/sync/push', (req, res) => {
+  try {
+    if (!checkSyncToken(req, res)) return;
+    const { sync_id, device_id, changes } = req.body;
+    if (!checkSyncId(sync_id, res)) return;
+    if (!device_id || !Array.isArray(changes)) {
+      return res.status(400).json({ error: '缺少 device_id 或 changes' });
+    }
+
+    const syncChanges = loadSyncChanges(sync_id);
+    fs.mkdirSync(SYNC_DIR, { recursive: true });
+    fs.appendFileSync(syncLogPath(sync_id), changes.map(change => JSON.stringify(change) + '\n').join(''));
+    syncChanges.push(...changes);
+
+    res.json({ accepted: changes.length, cursor: syncChanges.length });
+  } catch (error) {
+    console.error('同步推送错误:', error);
+    res.status(500).json({ error: '同步推送失败' });
+  }
+});
+
+// 拉取同一同步 id 下其他设备的变更
+app.get('/api/sync/pull', (req, res) => {
+  try {
+    if (!checkSyncToken(req, res)) return;
+    const syncId = req.query.sync_id;
+    if (!checkSyncId(syncId, res)) return;
+    const since = Math.max(0, parseInt(req.query.since) || 0);
+    const limit = Math.min(500, parseInt(req.query.limit) || 100);
+    const deviceId = req.query.device_id;
+
+    const syncChanges = loadSyncChanges(syncId);
+    const changes = [];
+    let cursor = since;
+    for (let i = since; i < syncChanges.length && changes.length < limit; i++) {
+      cursor = i + 1;
+      if (syncChanges[i].device_id !== deviceId) {
+        changes.push(syncChanges[i]);
+      }
+    }
+
+    res.json({ changes, cursor, has_more: cursor < syncChanges.length });
+  } catch (error) {
+    console.error('同步拉取错误:', error);
+    res.status(500).json({ error: '同步拉取失败' });
+  }
+});
+
 // 配置检查接口
 app.get('/api/config-check', (req, res) => {
   try {
@@ -0,0 +1,494 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::database::Database;
+use crate::goals;
+
+// 徽章的解鎖條件
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    // 任意一次練習的某項得分達到
+    ScoreAtLeast(&'static str, f64),
+    // 練習次數（保存的練習記錄數）
+    Sessions(u32),
+    // 練習過的句子數（練習記錄加逐句練習的片段）
+    Sentences(u32),
+    // 最長連續達標天數
+    Streak(u32),
+    // 累計練習分鐘數
+    PracticeMinutes(u32),
+    // 練習過的不同話題數
+    Topics(u32),
+    // 生詞本收錄的詞數
+    Vocabulary(u32),
+    // 複習間隔達到 21 天的詞數
+    VocabularyMastered(u32),
+    // 某組最小對立音最近 20 次練習中答對的次數
+    ContrastMastered(&'static str, u32),
+}
+
+// 判斷「掌握」一組對立音時看最近多少次練習
+const MASTERY_WINDOW: u32 = 20;
+
+struct BadgeDef {
+    id: &'static str,
+    title: &'static str,
+    description: &'static str,
+    icon: &'static str,
+    rule: Rule,
+}
+
+const BADGES: &[BadgeDef] = &[
+    BadgeDef {
+        id: "first_practice",
+        title: "開口第一步",
+        description: "完成第一次口語練習",
+        icon: "🎤",
+        rule: Rule::Sessions(1),
+    },
+    BadgeDef {
+        id: "first_90",
+        title: "九十分俱樂部",
+        description: "第一次獲得 90 分以上",
+        icon: "🌟",
+        rule: Rule::ScoreAtLeast("overall", 90.0),
+    },
+    BadgeDef {
+        id: "near_perfect",
+        title: "字正腔圓",
+        description: "單次練習獲得 98 分以上",
+        icon: "💎",
+        rule: Rule::ScoreAtLeast("overall", 98.0),
+    },
+    BadgeDef {
+        id: "fluent_95",
+        title: "行雲流水",
+        description: "流利度得分達到 95 分",
+        icon: "🌊",
+        rule: Rule::ScoreAtLeast("fluency", 95.0),
+    },
+    BadgeDef {
+        id: "sessions_10",
+        title: "漸入佳境",
+        description: "累計完成 10 次練習",
+        icon: "📈",
+        rule: Rule::Sessions(10),
+    },
+    BadgeDef {
+        id: "sessions_50",
+        title: "勤學苦練",
+        description: "累計完成 50 次練習",
+        icon: "🏋️",
+        rule: Rule::Sessions(50),
+    },
+    BadgeDef {
+        id: "sentences_100",
+        title: "百句達人",
+        description: "累計練習 100 個句子",
+        icon: "💯",
+        rule: Rule::Sentences(100),
+    },
+    BadgeDef {
+        id: "sentences_500",
+        title: "出口成章",
+        description: "累計練習 500 個句子",
+        icon: "📚",
+        rule: Rule::Sentences(500),
+    },
+    BadgeDef {
+        id: "streak_3",
+        title: "三天不斷",
+        description: "連續 3 天達成每日目標",
+        icon: "🔥",
+        rule: Rule::Streak(3),
+    },
+    BadgeDef {
+        id: "streak_7",
+        title: "一週堅持",
+        description: "連續 7 天達成每日目標",
+        icon: "📅",
+        rule: Rule::Streak(7),
+    },
+    BadgeDef {
+        id: "streak_30",
+        title: "習慣養成",
+        description: "連續 30 天達成每日目標",
+        icon: "🏆",
+        rule: Rule::Streak(30),
+    },
+    BadgeDef {
+        id: "minutes_60",
+        title: "第一個小時",
+        description: "累計練習 60 分鐘",
+        icon: "⏱️",
+        rule: Rule::PracticeMinutes(60),
+    },
+    BadgeDef {
+        id: "minutes_600",
+        title: "十小時功力",
+        description: "累計練習 600 分鐘",
+        icon: "⌛",
+        rule: Rule::PracticeMinutes(600),
+    },
+    BadgeDef {
+        id: "topics_5",
+        title: "話題廣泛",
+        description: "練習過 5 種不同的話題",
+        icon: "🧭",
+        rule: Rule::Topics(5),
+    },
+    BadgeDef {
+        id: "vocabulary_50",
+        title: "詞彙收藏家",
+        description: "生詞本收錄 50 個詞",
+        icon: "📒",
+        rule: Rule::Vocabulary(50),
+    },
+    BadgeDef {
+        id: "vocabulary_mastered_20",
+        title: "過目不忘",
+        description: "20 個生詞的複習間隔達到 21 天",
+        icon: "🧠",
+        rule: Rule::VocabularyMastered(20),
+    },
+    BadgeDef {
+        id: "mastered_th",
+        title: "咬舌音大師",
+        description: "掌握 /θ/：th 與 s 的對比練習最近 20 次答對 18 次",
+        icon: "👅",
+        rule: Rule::ContrastMastered("th-s", 18),
+    },
+    BadgeDef {
+        id: "mastered_l_r",
+        title: "分清 L 和 R",
+        description: "掌握 /l/ 與 /r/：最近 20 次答對 18 次",
+        icon: "🎯",
+        rule: Rule::ContrastMastered("l-r", 18),
+    },
+    BadgeDef {
+        id: "mastered_v_w",
+        title: "V W 不再混",
+        description: "掌握 /v/ 與 /w/：最近 20 次答對 18 次",
+        icon: "✌️",
+        rule: Rule::ContrastMastered("v-w", 18),
+    },
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub icon: String,
+    pub current: f64,
+    pub target: f64,
+    pub progress: f64, // 0-1
+    pub unlocked_at: Option<String>,
+}
+
+// 同一次評估中多條規則用到的同一統計只查詢一次
+struct Metrics<'a> {
+    db: &'a Database,
+    cache: HashMap<String, f64>,
+}
+
+// 累計值的合併方式
+#[derive(Clone, Copy)]
+enum Combine {
+    Sum,
+    Max,
+}
+
+impl<'a> Metrics<'a> {
+    fn cached(&mut self, key: String, query: impl FnOnce(&Database) -> Result<f64, String>) -> Result<f64, String> {
+        if let Some(&value) = self.cache.get(&key) {
+            return Ok(value);
+        }
+        let value = query(self.db)?;
+        self.cache.insert(key, value);
+        Ok(value)
+    }
+
+    fn scalar(&mut self, key: &str, sql: &str) -> Result<f64, String> {
+        self.cached(key.to_string(), |db| {
+            db.conn()
+                .query_row(sql, [], |row| row.get::<_, Option<f64>>(0))
+                .map(Option::unwrap_or_default)
+                .map_err(|e| format!("統計練習數據失敗：{}", e))
+        })
+    }
+
+    // 歷史累計型統計。sql 以 ?1 為上次的 cursor，返回新增行的統計值和其中最大的 id；
+    // 練習記錄越來越多時每次評估只需掃描新增的幾行
+    fn counter(&mut self, metric: &str, sql: &str, combine: Combine) -> Result<f64, String> {
+        self.cached(metric.to_string(), |db| {
+            let (value, cursor) = load_counter(db, metric)?;
+            let (delta, last_id): (Option<f64>, Option<i64>) = db
+                .conn()
+                .query_row(sql, [cursor], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| format!("統計練習數據失敗：{}", e))?;
+            let Some(last_id) = last_id else {
+                return Ok(value);
+            };
+            let delta = delta.unwrap_or_default();
+            let value = match combine {
+                Combine::Sum => value + delta,
+                Combine::Max => value.max(delta),
+            };
+            store_counter(db, metric, value, last_id)?;
+            Ok(value)
+        })
+    }
+
+    // 返回規則的當前值和目標值
+    fn measure(&mut self, rule: Rule) -> Result<(f64, f64), String> {
+        Ok(match rule {
+            Rule::ScoreAtLeast(metric, score) => {
+                let sql = format!(
+                    "SELECT MAX(json_extract(scores, '$.{}')), MAX(id) FROM practice_records WHERE id > ?1",
+                    metric
+                );
+                (self.counter(&format!("best:{}", metric), &sql, Combine::Max)?, score)
+            }
+            Rule::Sessions(target) => (self.sessions()?, target as f64),
+            Rule::Sentences(target) => {
+                let segments = self.counter(
+                    "segment_attempts",
+                    "SELECT COUNT(*), MAX(id) FROM segment_attempts WHERE id > ?1",
+                    Combine::Sum,
+                )?;
+                (self.sessions()? + segments, target as f64)
+            }
+            Rule::Streak(target) => {
+                let streak = self.cached("streak".to_string(), |db| {
+                    let (longest, cursor) = load_counter(db, "streak")?;
+                    let last_id: Option<i64> = db
+                        .conn()
+                        .query_row("SELECT MAX(id) FROM practice_time", [], |row| row.get(0))
+                        .map_err(|e| format!("統計練習數據失敗：{}", e))?;
+                    let Some(last_id) = last_id.filter(|&id| id > cursor) else {
+                        return Ok(longest);
+                    };
+                    // 首次統計時取歷史最長記錄，之後只需把當前連續天數併入
+                    let progress = goals::goal_progress(db)?;
+                    let streak = if cursor == 0 { progress.longest_streak } else { progress.current_streak };
+                    let longest = longest.max(streak as f64);
+                    store_counter(db, "streak", longest, last_id)?;
+                    Ok(longest)
+                })?;
+                (streak, target as f64)
+            }
+            Rule::PracticeMinutes(target) => (
+                self.counter(
+                    "minutes",
+                    "SELECT SUM(seconds) / 60.0, MAX(id) FROM practice_time WHERE id > ?1",
+                    Combine::Sum,
+                )?,
+                target as f64,
+            ),
+            Rule::Topics(target) => {
+                let topics = self.cached("topics".to_string(), |db| {
+                    let (_, cursor) = load_counter(db, "topics")?;
+                    db.conn()
+                        .execute(
+                            "INSERT INTO achievement_topics (topic)
+                             SELECT DISTINCT topic FROM practice_records WHERE id > ?1
+                             ON CONFLICT (topic) DO NOTHING",
+                            [cursor],
+                        )
+                        .map_err(|e| format!("統計練習數據失敗：{}", e))?;
+                    let (count, last_id): (f64, Option<i64>) = db
+                        .conn()
+                        .query_row(
+                            "SELECT (SELECT COUNT(*) FROM achievement_topics), (SELECT MAX(id) FROM practice_records)",
+                            [],
+                            |row| Ok((row.get(0)?, row.get(1)?)),
+                        )
+                        .map_err(|e| format!("統計練習數據失敗：{}", e))?;
+                    store_counter(db, "topics", count, last_id.unwrap_or(cursor).max(cursor))?;
+                    Ok(count)
+                })?;
+                (topics, target as f64)
+            }
+            // 生詞本和對比練習只看當前狀態或最近若干次，查詢量不隨歷史增長
+            Rule::Vocabulary(target) => (self.scalar("vocabulary", "SELECT COUNT(*) FROM vocabulary")?, target as f64),
+            Rule::VocabularyMastered(target) => (
+                self.scalar("vocabulary_mastered", "SELECT COUNT(*) FROM vocabulary WHERE interval_days >= 21")?,
+                target as f64,
+            ),
+            Rule::ContrastMastered(contrast, target) => {
+                let correct = self.cached(format!("contrast:{}", contrast), |db| {
+                    db.conn()
+                        .query_row(
+                            "SELECT SUM(correct) FROM (SELECT correct FROM contrast_attempts WHERE contrast = ?1
+                             ORDER BY created_at DESC, id DESC LIMIT ?2)",
+                            params![contrast, MASTERY_WINDOW],
+                            |row| row.get::<_, Option<f64>>(0),
+                        )
+                        .map(Option::unwrap_or_default)
+                        .map_err(|e| format!("統計練習數據失敗：{}", e))
+                })?;
+                (correct, target as f64)
+            }
+        })
+    }
+
+    fn sessions(&mut self) -> Result<f64, String> {
+        self.counter("sessions", "SELECT COUNT(*), MAX(id) FROM practice_records WHERE id > ?1", Combine::Sum)
+    }
+}
+
+fn load_counter(db: &Database, metric: &str) -> Result<(f64, i64), String> {
+    db.conn()
+        .query_row("SELECT value, cursor FROM achievement_counters WHERE metric = ?1", [metric], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("讀取成就統計失敗：{}", e))
+}
+
+fn store_counter(db: &Database, metric: &str, value: f64, cursor: i64) -> Result<(), String> {
+    db.conn()
+        .execute(
+            "INSERT INTO achievement_counters (metric, value, cursor) VALUES (?1, ?2, ?3)
+             ON CONFLICT (metric) DO UPDATE SET value = excluded.value, cursor = excluded.cursor",
+            params![metric, value, cursor],
+        )
+        .map_err(|e| format!("保存成就統計失敗：{}", e))?;
+    Ok(())
+}
+
+fn to_achievement(badge: &BadgeDef, current: f64, target: f64, unlocked_at: Option<String>) -> Achievement {
+    let progress = if unlocked_at.is_some() { 1.0 } else { (current / target).clamp(0.0, 1.0) };
+    Achievement {
+        id: badge.id.to_string(),
+        title: badge.title.to_string(),
+        description: badge.description.to_string(),
+        icon: badge.icon.to_string(),
+        current: (current * 10.0).round() / 10.0,
+        target,
+        progress: (progress * 100.0).round() / 100.0,
+        unlocked_at,
+    }
+}
+
+fn unlocked(db: &Database) -> Result<HashMap<String, String>, String> {
+    let mut stmt = db.conn().prepare("SELECT badge_id, unlocked_at FROM achievements").map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("查詢成就失敗：{}", e))?;
+    rows.collect::<Result<_, _>>().map_err(|e| format!("查詢成就失敗：{}", e))
+}
+
+// 檢查尚未解鎖的徽章，返回本次新解鎖的。已解鎖的不再計算，歷史統計只累加上次評估之後的新記錄
+pub fn evaluate(db: &Database) -> Result<Vec<Achievement>, String> {
+    let unlocked = unlocked(db)?;
+    let mut metrics = Metrics { db, cache: HashMap::new() };
+    let mut earned = Vec::new();
+    for badge in BADGES.iter().filter(|badge| !unlocked.contains_key(badge.id)) {
+        let (current, target) = metrics.measure(badge.rule)?;
+        if current < target {
+            continue;
+        }
+        db.conn()
+            .execute(
+                "INSERT INTO achievements (badge_id, value) VALUES (?1, ?2) ON CONFLICT (badge_id) DO NOTHING",
+                params![badge.id, current],
+            )
+            .map_err(|e| format!("保存成就失敗：{}", e))?;
+        let unlocked_at: Option<String> = db
+            .conn()
+            .query_row("SELECT unlocked_at FROM achievements WHERE badge_id = ?1", [badge.id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        earned.push(to_achievement(badge, current, target, unlocked_at));
+    }
+    Ok(earned)
+}
+
+// 全部徽章及進度，已解鎖的排在前面
+pub fn list(db: &Database) -> Result<Vec<Achievement>, String> {
+    let unlocked = unlocked(db)?;
+    let mut metrics = Metrics { db, cache: HashMap::new() };
+    let mut achievements = BADGES
+        .iter()
+        .map(|badge| {
+            let (current, target) = metrics.measure(badge.rule)?;
+            Ok(to_achievement(badge, current, target, unlocked.get(badge.id).cloned()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    achievements.sort_by_key(|a| a.unlocked_at.is_none());
+    Ok(achievements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_db() -> Database {
+        Database::open(&std::env::temp_dir().join(format!("web-chat-achievements-{:016x}", rand::random::<u64>()))).unwrap()
+    }
+
+    fn practice(db: &Database, topic: &str, overall: f64) {
+        db.insert_practice_record(topic, &HashMap::from([("overall".to_string(), overall)]), "", None).unwrap();
+    }
+
+    fn earned_ids(db: &Database) -> Vec<String> {
+        evaluate(db).unwrap().into_iter().map(|a| a.id).collect()
+    }
+
+    fn progress(db: &Database, id: &str) -> Achievement {
+        list(db).unwrap().into_iter().find(|a| a.id == id).unwrap()
+    }
+
+    #[test]
+    fn unlocks_each_badge_once() {
+        let db = open_db();
+        assert!(earned_ids(&db).is_empty());
+        practice(&db, "travel", 92.0);
+        assert_eq!(earned_ids(&db), vec!["first_practice", "first_90"]);
+        practice(&db, "travel", 95.0);
+        assert!(earned_ids(&db).is_empty());
+
+        let badge = progress(&db, "first_90");
+        assert!(badge.unlocked_at.is_some());
+        assert_eq!(badge.progress, 1.0);
+        assert_eq!(progress(&db, "near_perfect").current, 95.0);
+    }
+
+    #[test]
+    fn counters_accumulate_across_evaluations() {
+        let db = open_db();
+        for topic in ["travel", "work", "travel"] {
+            practice(&db, topic, 60.0);
+        }
+        evaluate(&db).unwrap();
+        assert_eq!(load_counter(&db, "sessions").unwrap().0, 3.0);
+        assert_eq!(progress(&db, "topics_5").current, 2.0);
+
+        for topic in ["food", "work", "sport", "music", "food", "film", "news"] {
+            practice(&db, topic, 60.0);
+        }
+        assert_eq!(earned_ids(&db), vec!["sessions_10", "topics_5"]);
+        assert_eq!(progress(&db, "topics_5").current, 7.0);
+        let (sessions, cursor) = load_counter(&db, "sessions").unwrap();
+        assert_eq!(sessions, 10.0);
+        let last_id: i64 = db.conn().query_row("SELECT MAX(id) FROM practice_records", [], |row| row.get(0)).unwrap();
+        assert_eq!(cursor, last_id);
+    }
+
+    #[test]
+    fn counts_practice_minutes_and_streak() {
+        let db = open_db();
+        goals::record_practice_time(&db, 1800.0, "shadowing", None).unwrap();
+        evaluate(&db).unwrap();
+        assert_eq!(progress(&db, "minutes_60").current, 30.0);
+
+        goals::record_practice_time(&db, 1800.0, "drill", None).unwrap();
+        let earned = earned_ids(&db);
+        assert!(earned.contains(&"minutes_60".to_string()));
+        assert_eq!(load_counter(&db, "streak").unwrap().0, 1.0);
+    }
+}
@@ -0,0 +1,1070 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::database::Database;
+use crate::export;
+use crate::goals;
+use crate::recordings;
+
+// 作業包和結果包都是 zip 文件：一份 JSON 文檔加上對它的 ed25519 簽名，結果包另帶錄音。
+// 簽名覆蓋文檔的原始字節，錄音以 SHA-256 記錄在文檔裡，因此同樣受簽名保護
+const ASSIGNMENT_FORMAT: &str = "web-chat-assignment";
+const RESULT_FORMAT: &str = "web-chat-result";
+const BUNDLE_VERSION: u32 = 1;
+const ASSIGNMENT_FILE: &str = "assignment.json";
+const RESULT_FILE: &str = "result.json";
+const SIGNATURE_FILE: &str = "signature.json";
+
+// 簽名私鑰放在檔案目錄下的單獨文件裡，不放進設置表，避免被同步或導出
+const SIGNING_KEY_FILE: &str = "signing.key";
+
+// 導入時單個文件的大小上限，防止異常文件佔滿內存
+const MAX_DOCUMENT_BYTES: u64 = 4 * 1024 * 1024;
+const MAX_RECORDING_BYTES: u64 = 64 * 1024 * 1024;
+const MAX_PASSAGES: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Passage {
+    pub id: String,
+    pub text: String,
+    pub target_score: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assignment {
+    pub format: String,
+    pub version: u32,
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub instructions: String,
+    pub teacher_name: String,
+    pub teacher_key: String,      // ed25519 公鑰，十六進制
+    pub due_date: Option<String>, // YYYY-MM-DD，當天結束前提交都不算遲交
+    pub passages: Vec<Passage>,
+    pub rubric: BTreeMap<String, f64>, // 各項評分的權重，總和為 1
+    pub created_at: String,
+}
+
+// 老師編寫作業時填寫的內容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentDraft {
+    pub title: String,
+    #[serde(default)]
+    pub instructions: String,
+    pub teacher_name: String,
+    pub due_date: Option<String>,
+    pub passages: Vec<PassageDraft>,
+    #[serde(default)]
+    pub target_score: Option<f64>, // 段落沒有單獨設置目標分時使用
+    #[serde(default)]
+    pub rubric: BTreeMap<String, f64>, // 不填時只看 overall
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassageDraft {
+    pub text: String,
+    #[serde(default)]
+    pub target_score: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingRef {
+    pub file: String, // zip 內的路徑
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassageResult {
+    pub passage_id: String,
+    pub scores: BTreeMap<String, f64>,
+    pub weighted_score: f64,
+    pub met_target: bool,
+    pub transcript: String,
+    pub attempts: u32,
+    pub recording: Option<RecordingRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentResult {
+    pub format: String,
+    pub version: u32,
+    pub assignment_id: String,
+    pub assignment_sha256: String, // 作業文檔的哈希，確認學生做的是同一版作業
+    pub teacher_key: String,
+    pub student_name: String,
+    pub student_key: String,
+    pub submitted_at: String,
+    pub late: bool,
+    pub total_score: f64, // 各段最佳加權分的平均，沒練習的段落按 0 分計
+    pub passages: Vec<PassageResult>,
+}
+
+// 寫出的作業包或結果包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentBundle {
+    pub path: String,
+    pub assignment: Assignment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultBundle {
+    pub path: String,
+    pub result: AssignmentResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SignatureFile {
+    algorithm: String,
+    public_key: String,
+    signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningIdentity {
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentSummary {
+    pub id: String,
+    pub role: String,
+    pub title: String,
+    pub teacher_name: String,
+    pub teacher_fingerprint: String,
+    pub due_date: Option<String>,
+    pub passage_count: usize,
+    pub completed: usize, // 學生為已練習的段落數，老師為收到的結果數
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedAssignment {
+    pub assignment: Assignment,
+    pub teacher_fingerprint: String,
+    pub known_teacher: bool, // 之前導入過同一公鑰簽名的作業；第一次見到時應與老師核對指紋
+    pub updated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassageProgress {
+    pub passage: Passage,
+    pub attempts: u32,
+    pub best_score: Option<f64>,
+    pub met_target: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentProgress {
+    pub assignment: Assignment,
+    pub teacher_fingerprint: String,
+    pub passages: Vec<PassageProgress>,
+    pub total_score: f64,
+    pub overdue: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultImport {
+    pub assignment_id: String,
+    pub student_name: String,
+    pub student_fingerprint: String,
+    pub total_score: f64,
+    pub late: bool,
+    pub replaced: bool, // 覆蓋了該學生之前的提交
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudentPassage {
+    #[serde(flatten)]
+    pub result: PassageResult,
+    pub recording_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudentResult {
+    pub student_name: String,
+    pub student_fingerprint: String,
+    pub total_score: f64,
+    pub late: bool,
+    pub submitted_at: String,
+    pub completed: usize,
+    pub passages: Vec<StudentPassage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassageStats {
+    pub passage_id: String,
+    pub text: String,
+    pub target_score: Option<f64>,
+    pub submissions: u32,
+    pub average_score: Option<f64>,
+    pub met_target: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentResults {
+    pub assignment: Assignment,
+    pub students: Vec<StudentResult>,
+    pub passages: Vec<PassageStats>,
+    pub metric_averages: BTreeMap<String, f64>,
+    pub average_score: Option<f64>,
+    pub late_count: u32,
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+// 公鑰指紋：公鑰哈希的前 8 字節，四位一組，便於師生當面或電話核對
+pub fn fingerprint(public_key: &str) -> String {
+    let hash = sha256_hex(public_key.as_bytes());
+    (0..4).map(|i| &hash[i * 4..i * 4 + 4]).collect::<Vec<_>>().join(" ")
+}
+
+fn signing_key(db: &Database) -> Result<SigningKey, String> {
+    let path = db.data_dir().join(SIGNING_KEY_FILE);
+    if path.exists() {
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("讀取簽名密鑰失敗：{}", e))?;
+        let bytes: [u8; 32] = from_hex(content.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("簽名密鑰文件已損壞")?;
+        return Ok(SigningKey::from_bytes(&bytes));
+    }
+    let key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+    std::fs::write(&path, to_hex(&key.to_bytes())).map_err(|e| format!("保存簽名密鑰失敗：{}", e))?;
+    Ok(key)
+}
+
+// 當前檔案的簽名身份，第一次使用時生成密鑰
+pub fn identity(db: &Database) -> Result<SigningIdentity, String> {
+    let public_key = to_hex(signing_key(db)?.verifying_key().as_bytes());
+    Ok(SigningIdentity { fingerprint: fingerprint(&public_key), public_key })
+}
+
+fn sign(key: &SigningKey, document: &[u8]) -> SignatureFile {
+    SignatureFile {
+        algorithm: "ed25519".to_string(),
+        public_key: to_hex(key.verifying_key().as_bytes()),
+        signature: to_hex(&key.sign(document).to_bytes()),
+    }
+}
+
+// 校驗簽名，成功時返回簽名者的公鑰
+fn verify(document: &[u8], signature: &SignatureFile) -> Result<String, String> {
+    if signature.algorithm != "ed25519" {
+        return Err(format!("不支持的簽名算法：{}", signature.algorithm));
+    }
+    let key: [u8; 32] = from_hex(&signature.public_key)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("簽名中的公鑰格式錯誤")?;
+    let bytes: [u8; 64] = from_hex(&signature.signature)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("簽名格式錯誤")?;
+    let key = VerifyingKey::from_bytes(&key).map_err(|_| "簽名中的公鑰無效".to_string())?;
+    key.verify_strict(document, &Signature::from_bytes(&bytes))
+        .map_err(|_| "簽名校驗失敗，文件可能被修改過".to_string())?;
+    Ok(signature.public_key.clone())
+}
+
+// 權重只保留正數並歸一化；不填時只看 overall
+fn normalize_rubric(rubric: &BTreeMap<String, f64>) -> Result<BTreeMap<String, f64>, String> {
+    if rubric.values().any(|w| !w.is_finite() || *w < 0.0) {
+        return Err("評分權重必須是非負數".to_string());
+    }
+    let weights: BTreeMap<String, f64> =
+        rubric.iter().filter(|(key, w)| **w > 0.0 && !key.trim().is_empty()).map(|(k, w)| (k.trim().to_string(), *w)).collect();
+    let total: f64 = weights.values().sum();
+    if total <= 0.0 {
+        return Ok(BTreeMap::from([("overall".to_string(), 1.0)]));
+    }
+    Ok(weights.into_iter().map(|(key, w)| (key, (w / total * 10_000.0).round() / 10_000.0)).collect())
+}
+
+// 按作業的權重計算加權分，只計入評分中有的項目；一項都沒有時退回 overall
+pub fn weighted_score(rubric: &BTreeMap<String, f64>, scores: &BTreeMap<String, f64>) -> Option<f64> {
+    let (sum, weight) = rubric
+        .iter()
+        .filter_map(|(key, w)| scores.get(key).map(|score| (score * w, *w)))
+        .fold((0.0, 0.0), |(sum, weight), (s, w)| (sum + s, weight + w));
+    if weight > 0.0 {
+        Some(round1(sum / weight))
+    } else {
+        scores.get("overall").copied().map(round1)
+    }
+}
+
+fn local_now(db: &Database) -> Result<(String, String), String> {
+    db.conn()
+        .query_row("SELECT date('now', 'localtime'), datetime('now', 'localtime')", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| e.to_string())
+}
+
+fn write_bundle(path: &Path, entries: &[(String, Vec<u8>)]) -> Result<(), String> {
+    export::create_parent(path)?;
+    let file = std::fs::File::create(path).map_err(|e| format!("創建文件失敗：{}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, content) in entries {
+        zip.start_file(name.as_str(), options).map_err(|e| format!("寫入文件失敗：{}", e))?;
+        zip.write_all(content).map_err(|e| format!("寫入文件失敗：{}", e))?;
+    }
+    zip.finish().map_err(|e| format!("寫入文件失敗：{}", e))?;
+    Ok(())
+}
+
+struct Bundle {
+    archive: zip::ZipArchive<std::fs::File>,
+}
+
+impl Bundle {
+    fn open(path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| format!("打開文件失敗：{}", e))?;
+        let archive = zip::ZipArchive::new(file).map_err(|_| "文件格式錯誤，不是有效的作業文件".to_string())?;
+        Ok(Self { archive })
+    }
+
+    fn read(&mut self, name: &str, limit: u64) -> Result<Vec<u8>, String> {
+        let entry = self.archive.by_name(name).map_err(|_| format!("文件中缺少 {}", name))?;
+        if entry.size() > limit {
+            return Err(format!("{} 過大", name));
+        }
+        let mut content = Vec::with_capacity(entry.size() as usize);
+        entry.take(limit + 1).read_to_end(&mut content).map_err(|e| format!("讀取 {} 失敗：{}", name, e))?;
+        if content.len() as u64 > limit {
+            return Err(format!("{} 過大", name));
+        }
+        Ok(content)
+    }
+
+    // 讀出文檔並校驗簽名，返回文檔原始字節和簽名者公鑰
+    fn signed_document(&mut self, name: &str) -> Result<(Vec<u8>, String), String> {
+        let document = self.read(name, MAX_DOCUMENT_BYTES)?;
+        let signature: SignatureFile = serde_json::from_slice(&self.read(SIGNATURE_FILE, MAX_DOCUMENT_BYTES)?)
+            .map_err(|e| format!("簽名文件格式錯誤：{}", e))?;
+        let signer = verify(&document, &signature)?;
+        Ok((document, signer))
+    }
+}
+
+fn parse_assignment(document: &str) -> Result<Assignment, String> {
+    let assignment: Assignment = serde_json::from_str(document).map_err(|e| format!("作業格式錯誤：{}", e))?;
+    if assignment.format != ASSIGNMENT_FORMAT {
+        return Err("不是作業文件".to_string());
+    }
+    if assignment.version > BUNDLE_VERSION {
+        return Err(format!("作業文件版本 {} 過新，請先升級應用", assignment.version));
+    }
+    Ok(assignment)
+}
+
+// 返回 (角色, 原始文檔, 作業)
+fn load(db: &Database, id: &str) -> Result<(String, String, Assignment), String> {
+    let (role, document): (String, String) = db
+        .conn()
+        .query_row("SELECT role, document FROM assignments WHERE id = ?1", [id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()
+        .map_err(|e| format!("查詢作業失敗：{}", e))?
+        .ok_or("作業不存在")?;
+    let assignment = parse_assignment(&document)?;
+    Ok((role, document, assignment))
+}
+
+fn validate_date(date: Option<&str>) -> Result<Option<String>, String> {
+    match date.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(date) => {
+            goals::day_number(date).filter(|_| date.len() == 10).ok_or("截止日期格式錯誤，應為 YYYY-MM-DD")?;
+            Ok(Some(date.to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+fn validate_target(target: Option<f64>) -> Result<Option<f64>, String> {
+    match target {
+        Some(t) if !(0.0..=100.0).contains(&t) => Err("目標分數必須在 0 到 100 之間".to_string()),
+        other => Ok(other),
+    }
+}
+
+// 老師編寫並簽名一份作業，保存到本地後寫出作業包
+pub fn create(db: &Database, draft: &AssignmentDraft, path: &Path) -> Result<Assignment, String> {
+    let title = draft.title.trim();
+    if title.is_empty() {
+        return Err("作業標題不能為空".to_string());
+    }
+    let teacher_name = draft.teacher_name.trim();
+    if teacher_name.is_empty() {
+        return Err("老師名稱不能為空".to_string());
+    }
+    let texts: Vec<&PassageDraft> = draft.passages.iter().filter(|p| !p.text.trim().is_empty()).collect();
+    if texts.is_empty() {
+        return Err("作業至少需要一段練習內容".to_string());
+    }
+    if texts.len() > MAX_PASSAGES {
+        return Err(format!("每份作業最多 {} 段內容", MAX_PASSAGES));
+    }
+    let default_target = validate_target(draft.target_score)?;
+    let passages = texts
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            Ok(Passage {
+                id: format!("p{}", i + 1),
+                text: p.text.trim().to_string(),
+                target_score: validate_target(p.target_score)?.or(default_target),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let identity = identity(db)?;
+    let (_, created_at) = local_now(db)?;
+    let assignment = Assignment {
+        format: ASSIGNMENT_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        id: to_hex(&rand::random::<[u8; 8]>()),
+        title: title.to_string(),
+        instructions: draft.instructions.trim().to_string(),
+        teacher_name: teacher_name.to_string(),
+        teacher_key: identity.public_key,
+        due_date: validate_date(draft.due_date.as_deref())?,
+        passages,
+        rubric: normalize_rubric(&draft.rubric)?,
+        created_at,
+    };
+    let document = serde_json::to_string_pretty(&assignment).map_err(|e| e.to_string())?;
+    db.conn()
+        .execute(
+            "INSERT INTO assignments (id, role, title, due_date, teacher_key, document) VALUES (?1, 'teacher', ?2, ?3, ?4, ?5)",
+            params![assignment.id, assignment.title, assignment.due_date, assignment.teacher_key, document],
+        )
+        .map_err(|e| format!("保存作業失敗：{}", e))?;
+    export_bundle(db, &assignment.id, path)?;
+    Ok(assignment)
+}
+
+// 重新寫出自己佈置的作業包；ed25519 簽名是確定性的，多次導出的文件內容相同
+pub fn export_bundle(db: &Database, id: &str, path: &Path) -> Result<(), String> {
+    let (role, document, _) = load(db, id)?;
+    if role != "teacher" {
+        return Err("只能導出自己佈置的作業".to_string());
+    }
+    let signature = sign(&signing_key(db)?, document.as_bytes());
+    let signature = serde_json::to_vec_pretty(&signature).map_err(|e| e.to_string())?;
+    write_bundle(path, &[(ASSIGNMENT_FILE.to_string(), document.into_bytes()), (SIGNATURE_FILE.to_string(), signature)])
+}
+
+// 學生導入作業包。同一作業再次導入時（老師修改了截止日期等）更新內容，練習記錄保留
+pub fn import_assignment(db: &Database, path: &Path) -> Result<ImportedAssignment, String> {
+    let mut bundle = Bundle::open(path)?;
+    let (document, signer) = bundle.signed_document(ASSIGNMENT_FILE)?;
+    let document = String::from_utf8(document).map_err(|_| "作業格式錯誤".to_string())?;
+    let assignment = parse_assignment(&document)?;
+    if assignment.teacher_key != signer {
+        return Err("作業的簽名者與作業中的老師不一致".to_string());
+    }
+    if assignment.passages.is_empty() || assignment.passages.len() > MAX_PASSAGES {
+        return Err("作業內容無效".to_string());
+    }
+    let existing: Option<(String, String)> = db
+        .conn()
+        .query_row("SELECT role, teacher_key FROM assignments WHERE id = ?1", [&assignment.id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()
+        .map_err(|e| format!("查詢作業失敗：{}", e))?;
+    match &existing {
+        Some((role, _)) if role == "teacher" => return Err("這是你自己佈置的作業".to_string()),
+        Some((_, key)) if key != &assignment.teacher_key => {
+            return Err("已有同一編號但由其他老師簽名的作業，拒絕導入".to_string())
+        }
+        _ => {}
+    }
+    let known_teacher: bool = db
+        .conn()
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM assignments WHERE role = 'student' AND teacher_key = ?1 AND id != ?2)",
+            params![assignment.teacher_key, assignment.id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    db.conn()
+        .execute(
+            "INSERT INTO assignments (id, role, title, due_date, teacher_key, document) VALUES (?1, 'student', ?2, ?3, ?4, ?5)
+             ON CONFLICT (id) DO UPDATE SET title = excluded.title, due_date = excluded.due_date, document = excluded.document",
+            params![assignment.id, assignment.title, assignment.due_date, assignment.teacher_key, document],
+        )
+        .map_err(|e| format!("保存作業失敗：{}", e))?;
+    Ok(ImportedAssignment {
+        teacher_fingerprint: fingerprint(&assignment.teacher_key),
+        known_teacher,
+        updated: existing.is_some(),
+        assignment,
+    })
+}
+
+pub fn list(db: &Database) -> Result<Vec<AssignmentSummary>, String> {
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT role, document, created_at,
+                    CASE role WHEN 'student' THEN (SELECT COUNT(DISTINCT passage_id) FROM assignment_attempts
+                                                   WHERE assignment_id = assignments.id)
+                              ELSE (SELECT COUNT(*) FROM assignment_results WHERE assignment_id = assignments.id) END
+             FROM assignments ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, usize>(3)?))
+        })
+        .map_err(|e| format!("查詢作業失敗：{}", e))?;
+    let mut summaries = Vec::new();
+    for row in rows {
+        let (role, document, created_at, completed) = row.map_err(|e| e.to_string())?;
+        let assignment = parse_assignment(&document)?;
+        summaries.push(AssignmentSummary {
+            teacher_fingerprint: fingerprint(&assignment.teacher_key),
+            id: assignment.id,
+            role,
+            title: assignment.title,
+            teacher_name: assignment.teacher_name,
+            due_date: assignment.due_date,
+            passage_count: assignment.passages.len(),
+            completed,
+            created_at,
+        });
+    }
+    Ok(summaries)
+}
+
+fn results_dir(db: &Database, id: &str) -> PathBuf {
+    db.data_dir().join("assignments").join(id)
+}
+
+pub fn delete(db: &Database, id: &str) -> Result<(), String> {
+    let deleted =
+        db.conn().execute("DELETE FROM assignments WHERE id = ?1", [id]).map_err(|e| format!("刪除作業失敗：{}", e))?;
+    if deleted == 0 {
+        return Err("作業不存在".to_string());
+    }
+    let dir = results_dir(db, id);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| format!("刪除作業錄音失敗：{}", e))?;
+    }
+    Ok(())
+}
+
+fn student_assignment(db: &Database, id: &str) -> Result<(String, Assignment), String> {
+    let (role, document, assignment) = load(db, id)?;
+    if role != "student" {
+        return Err("只能完成導入的作業".to_string());
+    }
+    Ok((document, assignment))
+}
+
+fn meets(target: Option<f64>, score: Option<f64>) -> bool {
+    match (target, score) {
+        (Some(target), Some(score)) => score >= target,
+        (None, Some(_)) => true,
+        _ => false,
+    }
+}
+
+// 學生在某段上的一次練習，按作業的權重計算加權分
+pub fn record_attempt(
+    db: &Database,
+    id: &str,
+    passage_id: &str,
+    scores: &BTreeMap<String, f64>,
+    transcript: &str,
+    recording_id: Option<i64>,
+) -> Result<PassageProgress, String> {
+    let (_, assignment) = student_assignment(db, id)?;
+    let passage = assignment.passages.iter().find(|p| p.id == passage_id).ok_or("作業中沒有這段內容")?;
+    // 附帶的錄音必須是朗讀這一段的錄音，避免把其他內容的錄音當作作業提交
+    if let Some(recording_id) = recording_id {
+        let text: String = db
+            .conn()
+            .query_row("SELECT reference_text FROM recordings WHERE id = ?1", [recording_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("查詢錄音失敗：{}", e))?
+            .ok_or_else(|| format!("錄音 {} 不存在", recording_id))?;
+        if text != passage.text {
+            return Err("錄音的朗讀內容與作業段落不一致".to_string());
+        }
+    }
+    let weighted = weighted_score(&assignment.rubric, scores).ok_or("評分中沒有可用於計算的項目")?;
+    db.conn()
+        .execute(
+            "INSERT INTO assignment_attempts (assignment_id, passage_id, scores, weighted_score, transcript, recording_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, passage_id, serde_json::to_string(scores).map_err(|e| e.to_string())?, weighted, transcript, recording_id],
+        )
+        .map_err(|e| format!("保存作業練習失敗：{}", e))?;
+    progress(db, id)?
+        .passages
+        .into_iter()
+        .find(|p| p.passage.id == passage_id)
+        .ok_or_else(|| "作業中沒有這段內容".to_string())
+}
+
+pub fn progress(db: &Database, id: &str) -> Result<AssignmentProgress, String> {
+    let (_, assignment) = student_assignment(db, id)?;
+    let best: HashMap<String, (u32, f64)> = {
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT passage_id, COUNT(*), MAX(weighted_score) FROM assignment_attempts
+                 WHERE assignment_id = ?1 GROUP BY passage_id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([id], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| format!("查詢作業練習失敗：{}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| format!("查詢作業練習失敗：{}", e))?
+    };
+    let passages: Vec<PassageProgress> = assignment
+        .passages
+        .iter()
+        .map(|passage| {
+            let (attempts, best_score) = best.get(&passage.id).map(|(n, s)| (*n, Some(*s))).unwrap_or((0, None));
+            PassageProgress { met_target: meets(passage.target_score, best_score), passage: passage.clone(), attempts, best_score }
+        })
+        .collect();
+    let total = passages.iter().filter_map(|p| p.best_score).sum::<f64>() / passages.len().max(1) as f64;
+    let (today, _) = local_now(db)?;
+    Ok(AssignmentProgress {
+        teacher_fingerprint: fingerprint(&assignment.teacher_key),
+        overdue: assignment.due_date.as_ref().is_some_and(|due| today > *due),
+        total_score: round1(total),
+        passages,
+        assignment,
+    })
+}
+
+// 學生生成結果包：每段取加權分最高的一次練習，include_recordings 時附上該次錄音
+pub fn export_result(
+    db: &Database,
+    id: &str,
+    student_name: &str,
+    include_recordings: bool,
+    path: &Path,
+) -> Result<AssignmentResult, String> {
+    let (document, assignment) = student_assignment(db, id)?;
+    let student_name = student_name.trim();
+    if student_name.is_empty() {
+        return Err("學生名稱不能為空".to_string());
+    }
+    let mut entries = Vec::new();
+    let mut passages = Vec::new();
+    for passage in &assignment.passages {
+        let best: Option<(String, f64, String, Option<i64>, u32)> = db
+            .conn()
+            .query_row(
+                "SELECT scores, weighted_score, transcript, recording_id,
+                        (SELECT COUNT(*) FROM assignment_attempts WHERE assignment_id = ?1 AND passage_id = ?2)
+                 FROM assignment_attempts WHERE assignment_id = ?1 AND passage_id = ?2
+                 ORDER BY weighted_score DESC, created_at DESC, id DESC LIMIT 1",
+                params![id, passage.id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .optional()
+            .map_err(|e| format!("查詢作業練習失敗：{}", e))?;
+        let Some((scores, weighted, transcript, recording_id, attempts)) = best else {
+            continue;
+        };
+        let recording = match recording_id.filter(|_| include_recordings) {
+            Some(recording_id) => match recordings::recording_bytes(db, recording_id) {
+                Ok(bytes) => {
+                    let file = format!("recordings/{}.wav", passage.id);
+                    let sha256 = sha256_hex(&bytes);
+                    entries.push((file.clone(), bytes));
+                    Some(RecordingRef { file, sha256 })
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, passage = %passage.id, "Assignment recording skipped");
+                    None
+                }
+            },
+            None => None,
+        };
+        passages.push(PassageResult {
+            passage_id: passage.id.clone(),
+            scores: serde_json::from_str(&scores).unwrap_or_default(),
+            weighted_score: weighted,
+            met_target: meets(passage.target_score, Some(weighted)),
+            transcript,
+            attempts,
+            recording,
+        });
+    }
+    if passages.is_empty() {
+        return Err("還沒有練習作業中的任何內容".to_string());
+    }
+
+    let key = signing_key(db)?;
+    let (_, submitted_at) = local_now(db)?;
+    let total = passages.iter().map(|p| p.weighted_score).sum::<f64>() / assignment.passages.len() as f64;
+    let result = AssignmentResult {
+        format: RESULT_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        assignment_id: assignment.id.clone(),
+        assignment_sha256: sha256_hex(document.as_bytes()),
+        teacher_key: assignment.teacher_key.clone(),
+        student_name: student_name.to_string(),
+        student_key: to_hex(key.verifying_key().as_bytes()),
+        late: assignment.due_date.as_ref().is_some_and(|due| &submitted_at[..10] > due.as_str()),
+        submitted_at,
+        total_score: round1(total),
+        passages,
+    };
+    let content = serde_json::to_vec_pretty(&result).map_err(|e| e.to_string())?;
+    let signature = serde_json::to_vec_pretty(&sign(&key, &content)).map_err(|e| e.to_string())?;
+    entries.insert(0, (RESULT_FILE.to_string(), content));
+    entries.insert(1, (SIGNATURE_FILE.to_string(), signature));
+    write_bundle(path, &entries)?;
+    Ok(result)
+}
+
+// 老師導入學生的結果包。只接受自己佈置的、且文檔未被改動過的作業的結果；
+// 每個段落最多出現一次，加權分和總分按作業中的權重重新計算。同一學生（以公鑰區分）的新提交覆蓋舊提交
+pub fn import_result(db: &Database, path: &Path) -> Result<ResultImport, String> {
+    let mut bundle = Bundle::open(path)?;
+    let (content, signer) = bundle.signed_document(RESULT_FILE)?;
+    let mut result: AssignmentResult = serde_json::from_slice(&content).map_err(|e| format!("結果格式錯誤：{}", e))?;
+    if result.format != RESULT_FORMAT {
+        return Err("不是作業結果文件".to_string());
+    }
+    if result.student_key != signer {
+        return Err("結果的簽名者與結果中的學生不一致".to_string());
+    }
+    let (role, document, assignment) = load(db, &result.assignment_id).map_err(|_| "找不到對應的作業".to_string())?;
+    if role != "teacher" || result.teacher_key != identity(db)?.public_key {
+        return Err("只能導入自己佈置的作業的結果".to_string());
+    }
+    if result.assignment_sha256 != sha256_hex(document.as_bytes()) {
+        return Err("結果對應的作業內容與本地不一致".to_string());
+    }
+
+    let previous: Option<String> = db
+        .conn()
+        .query_row(
+            "SELECT submitted_at FROM assignment_results WHERE assignment_id = ?1 AND student_key = ?2",
+            params![result.assignment_id, result.student_key],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if previous.as_ref().is_some_and(|previous| *previous > result.submitted_at) {
+        return Err("已導入過該學生更新的提交".to_string());
+    }
+
+    let student_dir = results_dir(db, &result.assignment_id).join(&result.student_key[..16.min(result.student_key.len())]);
+    let mut recordings = Vec::new();
+    let mut seen = HashSet::new();
+    for passage in &mut result.passages {
+        let target = assignment.passages.iter().find(|p| p.id == passage.passage_id).ok_or("結果中有作業裡不存在的段落")?;
+        if !seen.insert(passage.passage_id.clone()) {
+            return Err(format!("結果中段落 {} 重複出現", passage.passage_id));
+        }
+        if passage.scores.values().any(|score| !(0.0..=100.0).contains(score)) {
+            return Err(format!("結果中段落 {} 的分數超出範圍", passage.passage_id));
+        }
+        // 不採用結果包中的加權分；按作業權重算不出來時記為 0
+        passage.weighted_score = weighted_score(&assignment.rubric, &passage.scores).unwrap_or(0.0);
+        passage.met_target = meets(target.target_score, Some(passage.weighted_score));
+        if let Some(recording) = &passage.recording {
+            let bytes = bundle.read(&recording.file, MAX_RECORDING_BYTES)?;
+            if sha256_hex(&bytes) != recording.sha256 {
+                return Err(format!("錄音 {} 校驗失敗", recording.file));
+            }
+            recordings.push((student_dir.join(format!("{}.wav", passage.passage_id)), bytes));
+        }
+    }
+    result.total_score =
+        round1(result.passages.iter().map(|p| p.weighted_score).sum::<f64>() / assignment.passages.len() as f64);
+    result.late = assignment.due_date.as_ref().is_some_and(|due| result.submitted_at.get(..10).unwrap_or_default() > due.as_str());
+
+    if student_dir.exists() {
+        std::fs::remove_dir_all(&student_dir).map_err(|e| format!("清理舊錄音失敗：{}", e))?;
+    }
+    if !recordings.is_empty() {
+        std::fs::create_dir_all(&student_dir).map_err(|e| format!("無法創建錄音目錄：{}", e))?;
+    }
+    for (path, bytes) in recordings {
+        std::fs::write(path, bytes).map_err(|e| format!("保存錄音失敗：{}", e))?;
+    }
+    db.conn()
+        .execute(
+            "INSERT OR REPLACE INTO assignment_results
+             (assignment_id, student_key, student_name, total_score, late, submitted_at, document)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                result.assignment_id,
+                result.student_key,
+                result.student_name,
+                result.total_score,
+                result.late,
+                result.submitted_at,
+                serde_json::to_string(&result).map_err(|e| e.to_string())?
+            ],
+        )
+        .map_err(|e| format!("保存作業結果失敗：{}", e))?;
+
+    Ok(ResultImport {
+        student_fingerprint: fingerprint(&result.student_key),
+        assignment_id: result.assignment_id,
+        student_name: result.student_name,
+        total_score: result.total_score,
+        late: result.late,
+        replaced: previous.is_some(),
+    })
+}
+
+// 老師查看一份作業的全部提交：每個學生的成績，以及按段落和評分項目的平均
+pub fn results(db: &Database, id: &str) -> Result<AssignmentResults, String> {
+    let (role, _, assignment) = load(db, id)?;
+    if role != "teacher" {
+        return Err("只能查看自己佈置的作業的結果".to_string());
+    }
+    let documents: Vec<String> = {
+        let mut stmt = db
+            .conn()
+            .prepare("SELECT document FROM assignment_results WHERE assignment_id = ?1 ORDER BY total_score DESC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([id], |row| row.get(0)).map_err(|e| format!("查詢作業結果失敗：{}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| format!("查詢作業結果失敗：{}", e))?
+    };
+
+    let mut students = Vec::new();
+    let mut metric_totals: BTreeMap<String, (f64, u32)> = BTreeMap::new();
+    let mut passage_totals: HashMap<String, (f64, u32, u32)> = HashMap::new();
+    for document in documents {
+        let result: AssignmentResult = serde_json::from_str(&document).map_err(|e| format!("作業結果格式錯誤：{}", e))?;
+        let student_dir = results_dir(db, id).join(&result.student_key[..16.min(result.student_key.len())]);
+        for passage in &result.passages {
+            let entry = passage_totals.entry(passage.passage_id.clone()).or_default();
+            *entry = (entry.0 + passage.weighted_score, entry.1 + 1, entry.2 + passage.met_target as u32);
+            for (metric, score) in &passage.scores {
+                let entry = metric_totals.entry(metric.clone()).or_default();
+                *entry = (entry.0 + score, entry.1 + 1);
+            }
+        }
+        students.push(StudentResult {
+            student_fingerprint: fingerprint(&result.student_key),
+            student_name: result.student_name,
+            total_score: result.total_score,
+            late: result.late,
+            submitted_at: result.submitted_at,
+            completed: result.passages.len(),
+            passages: result
+                .passages
+                .into_iter()
+                .map(|passage| {
+                    let path = student_dir.join(format!("{}.wav", passage.passage_id));
+                    let recording_path = (passage.recording.is_some() && path.exists()).then(|| path.display().to_string());
+                    StudentPassage { result: passage, recording_path }
+                })
+                .collect(),
+        });
+    }
+
+    let passages = assignment
+        .passages
+        .iter()
+        .map(|passage| {
+            let (sum, submissions, met_target) = passage_totals.get(&passage.id).copied().unwrap_or_default();
+            PassageStats {
+                passage_id: passage.id.clone(),
+                text: passage.text.clone(),
+                target_score: passage.target_score,
+                submissions,
+                average_score: (submissions > 0).then(|| round1(sum / submissions as f64)),
+                met_target,
+            }
+        })
+        .collect();
+    let average_score = (!students.is_empty())
+        .then(|| round1(students.iter().map(|s| s.total_score).sum::<f64>() / students.len() as f64));
+    Ok(AssignmentResults {
+        late_count: students.iter().filter(|s| s.late).count() as u32,
+        metric_averages: metric_totals.into_iter().map(|(metric, (sum, n))| (metric, round1(sum / n as f64))).collect(),
+        average_score,
+        passages,
+        students,
+        assignment,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioClip;
+    use crate::recordings::RetentionPolicy;
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog.";
+
+    fn open_db(name: &str) -> Database {
+        Database::open(&std::env::temp_dir().join(format!("web-chat-{}-{:016x}", name, rand::random::<u64>()))).unwrap()
+    }
+
+    fn draft() -> AssignmentDraft {
+        AssignmentDraft {
+            title: "Week 1".to_string(),
+            instructions: String::new(),
+            teacher_name: "Ms. Lee".to_string(),
+            due_date: None,
+            passages: vec![
+                PassageDraft { text: TEXT.to_string(), target_score: None },
+                PassageDraft { text: "Hello there.".to_string(), target_score: Some(90.0) },
+            ],
+            target_score: Some(70.0),
+            rubric: BTreeMap::from([("pronunciation".to_string(), 3.0), ("fluency".to_string(), 1.0)]),
+        }
+    }
+
+    fn scores(pronunciation: f64, fluency: f64) -> BTreeMap<String, f64> {
+        BTreeMap::from([("pronunciation".to_string(), pronunciation), ("fluency".to_string(), fluency)])
+    }
+
+    fn record(db: &Database, text: &str) -> i64 {
+        recordings::save_recording(db, text, &AudioClip::new(vec![0.1; 1600], 16_000), Some(80.0)).unwrap()
+    }
+
+    // 老師佈置作業、學生導入並練習第一段，返回 (老師, 學生, 作業 id)
+    fn setup() -> (Database, Database, String) {
+        let teacher = open_db("teacher");
+        let student = open_db("student");
+        let bundle = teacher.data_dir().join("week1.wcassign");
+        let assignment = create(&teacher, &draft(), &bundle).unwrap();
+        let imported = import_assignment(&student, &bundle).unwrap();
+        assert!(!imported.known_teacher);
+        assert_eq!(imported.teacher_fingerprint, identity(&teacher).unwrap().fingerprint);
+        let recording_id = record(&student, TEXT);
+        record_attempt(&student, &assignment.id, "p1", &scores(80.0, 60.0), "the quick", Some(recording_id)).unwrap();
+        record_attempt(&student, &assignment.id, "p1", &scores(90.0, 90.0), "", None).unwrap();
+        (teacher, student, assignment.id)
+    }
+
+    fn read_entries(path: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut entry = archive.by_index(i).unwrap();
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content).unwrap();
+                (entry.name().to_string(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn result_bundle_round_trip() {
+        let (teacher, student, id) = setup();
+        let path = student.data_dir().join("result.wcresult");
+        let result = export_result(&student, &id, "Amy", true, &path).unwrap();
+        assert_eq!(result.passages.len(), 1);
+        assert_eq!((result.passages[0].weighted_score, result.passages[0].attempts), (90.0, 2));
+        assert_eq!(result.total_score, 45.0);
+
+        let imported = import_result(&teacher, &path).unwrap();
+        assert_eq!((imported.student_name.as_str(), imported.total_score, imported.replaced), ("Amy", 45.0, false));
+        let results = results(&teacher, &id).unwrap();
+        assert_eq!(results.students.len(), 1);
+        assert_eq!(results.passages[0].met_target, 1);
+        assert_eq!(results.passages[1].submissions, 0);
+
+        // 學生不能導入結果包，老師不能再次導入同一份作業
+        assert!(import_result(&student, &path).is_err());
+        assert!(import_assignment(&teacher, &teacher.data_dir().join("week1.wcassign")).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_results() {
+        let (teacher, student, id) = setup();
+        let path = student.data_dir().join("result.wcresult");
+        export_result(&student, &id, "Amy", false, &path).unwrap();
+        let entries = read_entries(&path);
+        let (name, content) = &entries[0];
+        assert_eq!(name, RESULT_FILE);
+        let mut result: AssignmentResult = serde_json::from_slice(content).unwrap();
+
+        // 改動內容後簽名不再有效
+        let tampered = path.with_file_name("tampered.wcresult");
+        let forged = String::from_utf8(content.clone()).unwrap().replace("\"pronunciation\": 90.0", "\"pronunciation\": 100.0");
+        assert_ne!(forged.as_bytes(), content.as_slice());
+        write_bundle(&tampered, &[(RESULT_FILE.to_string(), forged.into_bytes()), entries[1].clone()]).unwrap();
+        assert!(import_result(&teacher, &tampered).unwrap_err().contains("簽名校驗失敗"));
+
+        // 學生用自己的密鑰重新簽名：超出範圍的分數拒絕導入，加權分和總分按作業權重重新計算
+        let resign = |result: &AssignmentResult| {
+            let content = serde_json::to_vec_pretty(result).unwrap();
+            let signature = serde_json::to_vec_pretty(&sign(&signing_key(&student).unwrap(), &content)).unwrap();
+            write_bundle(&tampered, &[(RESULT_FILE.to_string(), content), (SIGNATURE_FILE.to_string(), signature)]).unwrap();
+        };
+        result.passages[0].scores.insert("fluency".to_string(), 150.0);
+        resign(&result);
+        assert!(import_result(&teacher, &tampered).unwrap_err().contains("超出範圍"));
+
+        result.passages[0].scores = scores(90.0, 90.0);
+        result.passages[0].weighted_score = 100.0;
+        result.total_score = 100.0;
+        resign(&result);
+        assert_eq!(import_result(&teacher, &tampered).unwrap().total_score, 45.0);
+    }
+
+    #[test]
+    fn attempts_require_matching_recordings() {
+        let (_, student, id) = setup();
+        let other = record(&student, "Something else entirely.");
+        assert!(record_attempt(&student, &id, "p1", &scores(80.0, 80.0), "", Some(other)).is_err());
+        assert!(record_attempt(&student, &id, "p1", &scores(80.0, 80.0), "", Some(other + 100)).is_err());
+        assert!(record_attempt(&student, &id, "p3", &scores(80.0, 80.0), "", None).is_err());
+        assert_eq!(progress(&student, &id).unwrap().passages[0].attempts, 2);
+    }
+
+    #[test]
+    fn retention_keeps_recordings_used_by_assignments() {
+        let (_, student, id) = setup();
+        let attempt_recording: i64 = student
+            .conn()
+            .query_row("SELECT recording_id FROM assignment_attempts WHERE recording_id IS NOT NULL", [], |row| row.get(0))
+            .unwrap();
+        for _ in 0..3 {
+            record(&student, TEXT);
+        }
+        recordings::set_retention_policy(&student, &RetentionPolicy { keep_last_per_text: Some(1), max_age_days: None })
+            .unwrap();
+        assert!(recordings::recording_bytes(&student, attempt_recording).is_ok());
+        assert_eq!(recordings::list_attempts(&student, TEXT).unwrap().len(), 2);
+
+        // 被引用的錄音成為最佳成績時隨結果包導出
+        record_attempt(&student, &id, "p1", &scores(95.0, 95.0), "", Some(attempt_recording)).unwrap();
+        let path = student.data_dir().join("result.wcresult");
+        let result = export_result(&student, &id, "Amy", true, &path).unwrap();
+        assert!(result.passages[0].recording.is_some());
+    }
+}
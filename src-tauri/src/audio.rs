@@ -0,0 +1,463 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use opus_decoder::OpusDecoder;
+use serde::Serialize;
+use std::io::Cursor;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{CodecParameters, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, Packet};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+// 所有分析模塊統一使用的採樣率
+pub const ANALYSIS_SAMPLE_RATE: u32 = 16_000;
+
+// Opus 解碼器固定輸出 48 kHz
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+
+// 支持的上傳音頻容器格式（各平台 MediaRecorder 的輸出不同）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Wav,
+    WebM, // WebM/Matroska，通常為 Opus 編碼（Chrome、Edge、Android）
+    Ogg,  // Ogg Opus 或 Vorbis（Firefox）
+    Mp4,  // MP4/M4A，通常為 AAC 編碼（Safari、iOS）
+    Aac,  // 裸 ADTS AAC 流
+    Mp3,
+    Flac,
+}
+
+impl AudioFormat {
+    // 根據文件頭識別容器格式
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Some(Self::Wav),
+            [0x1A, 0x45, 0xDF, 0xA3, ..] => Some(Self::WebM),
+            [b'O', b'g', b'g', b'S', ..] => Some(Self::Ogg),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some(Self::Mp4),
+            [b'I', b'D', b'3', ..] => Some(Self::Mp3),
+            [b'f', b'L', b'a', b'C', ..] => Some(Self::Flac),
+            // MPEG 幀同步字：layer 位為 00 的是 ADTS AAC，其餘為 MP3
+            [0xFF, second, ..] if second & 0xF6 == 0xF0 => Some(Self::Aac),
+            [0xFF, second, ..] if second & 0xE0 == 0xE0 && second & 0x06 != 0 => Some(Self::Mp3),
+            _ => None,
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Wav => "audio/wav",
+            Self::WebM => "audio/webm",
+            Self::Ogg => "audio/ogg",
+            Self::Mp4 => "audio/mp4",
+            Self::Aac => "audio/aac",
+            Self::Mp3 => "audio/mpeg",
+            Self::Flac => "audio/flac",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::WebM => "webm",
+            Self::Ogg => "ogg",
+            Self::Mp4 => "m4a",
+            Self::Aac => "aac",
+            Self::Mp3 => "mp3",
+            Self::Flac => "flac",
+        }
+    }
+}
+
+// 單聲道 PCM 音頻片段，樣本值範圍 [-1.0, 1.0]
+#[derive(Debug, Clone)]
+pub struct AudioClip {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl AudioClip {
+    pub fn new(samples: Vec<f32>, sample_rate: u32) -> Self {
+        Self { samples, sample_rate }
+    }
+
+    // 解碼前端傳來的 base64 音頻
+    pub fn from_base64(data: &str) -> Result<Self, String> {
+        Self::decode(&decode_base64(data)?)
+    }
+
+    // 解碼 Gemini 返回的音頻：原始 PCM（audio/L16;codec=pcm;rate=24000）或常見容器格式
+    pub fn from_inline_audio(mime_type: &str, data: &str) -> Result<Self, String> {
+        let mime = mime_type.to_ascii_lowercase();
+        if mime.starts_with("audio/l16") || mime.starts_with("audio/pcm") {
+            let rate = mime
+                .split(';')
+                .find_map(|param| param.trim().strip_prefix("rate="))
+                .and_then(|rate| rate.parse().ok())
+                .unwrap_or(24_000);
+            return Ok(Self::new(pcm16_from_base64(data)?, rate).resampled(ANALYSIS_SAMPLE_RATE));
+        }
+        Self::from_base64(data)
+    }
+
+    // 識別容器格式並解碼為單聲道 PCM，統一重採樣到分析採樣率，
+    // 保證同一段錄音無論來自哪個平台都按相同方式評分
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let format = AudioFormat::sniff(bytes).ok_or_else(|| {
+            let header: Vec<String> = bytes.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+            format!(
+                "不支持的音頻格式（文件頭：{}），請使用 WAV、WebM/Opus、Ogg、MP3、M4A/AAC 或 FLAC",
+                if header.is_empty() { "空".to_string() } else { header.join(" ") }
+            )
+        })?;
+
+        let clip = match format {
+            AudioFormat::Wav => Self::from_wav_bytes(bytes)?,
+            _ => decode_container(bytes, format)?,
+        };
+        if clip.samples.is_empty() {
+            return Err(format!("{} 音頻中沒有可解碼的樣本", format.mime_type()));
+        }
+        Ok(clip.resampled(ANALYSIS_SAMPLE_RATE))
+    }
+
+    pub fn from_wav_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let reader = hound::WavReader::new(Cursor::new(bytes))
+            .map_err(|e| format!("無法解析 WAV 音頻：{}", e))?;
+        let spec = reader.spec();
+        // 文件頭中的採樣率或位深為 0 時無法換算樣本值，也無法重採樣
+        if spec.sample_rate == 0 {
+            return Err("無法解析 WAV 音頻：採樣率為 0".to_string());
+        }
+        if spec.bits_per_sample == 0 || spec.bits_per_sample > 32 {
+            return Err(format!("無法解析 WAV 音頻：不支持 {} 位樣本", spec.bits_per_sample));
+        }
+        let channels = spec.channels.max(1) as usize;
+
+        let interleaved: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("讀取 WAV 樣本失敗：{}", e))?,
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|s| s.map(|v| v as f32 * scale))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| format!("讀取 WAV 樣本失敗：{}", e))?
+            }
+        };
+
+        let mut samples = Vec::with_capacity(interleaved.len() / channels);
+        downmix_into(&mut samples, &interleaved, channels);
+        Ok(Self::new(samples, spec.sample_rate))
+    }
+
+    // 編碼為 16-bit PCM WAV
+    pub fn to_wav_bytes(&self) -> Result<Vec<u8>, String> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec)
+            .map_err(|e| format!("WAV 編碼失敗：{}", e))?;
+        for &sample in &self.samples {
+            writer
+                .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+                .map_err(|e| format!("WAV 編碼失敗：{}", e))?;
+        }
+        writer.finalize().map_err(|e| format!("WAV 編碼失敗：{}", e))?;
+        Ok(cursor.into_inner())
+    }
+
+    // 線性插值重採樣，降採樣前先做簡單的滑動平均低通
+    pub fn resampled(&self, target_rate: u32) -> AudioClip {
+        if self.sample_rate == target_rate || self.sample_rate == 0 || target_rate == 0 || self.samples.is_empty() {
+            return self.clone();
+        }
+
+        let ratio = self.sample_rate as f64 / target_rate as f64;
+        let source: Vec<f32> = if ratio > 1.0 {
+            let width = ratio.ceil() as usize;
+            moving_average(&self.samples, width)
+        } else {
+            self.samples.clone()
+        };
+
+        let out_len = (source.len() as f64 / ratio).floor() as usize;
+        let samples = (0..out_len)
+            .map(|i| {
+                let pos = i as f64 * ratio;
+                let idx = pos.floor() as usize;
+                let frac = (pos - idx as f64) as f32;
+                let a = source[idx];
+                let b = source.get(idx + 1).copied().unwrap_or(a);
+                a + (b - a) * frac
+            })
+            .collect();
+
+        AudioClip::new(samples, target_rate)
+    }
+}
+
+// 去掉 data URL 前綴（data:audio/webm;base64,）
+pub fn base64_payload(data: &str) -> &str {
+    match data.split_once(',') {
+        Some((prefix, body)) if prefix.starts_with("data:") => body.trim(),
+        _ => data.trim(),
+    }
+}
+
+pub fn decode_base64(data: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(base64_payload(data))
+        .map_err(|e| format!("音頻數據不是有效的 base64：{}", e))
+}
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+// 錄音過程中前端推送的原始 PCM 數據（16-bit 小端、單聲道）
+pub fn pcm16_from_base64(data: &str) -> Result<Vec<f32>, String> {
+    let bytes = decode_base64(data)?;
+    if bytes.len() % 2 != 0 {
+        return Err("PCM 數據長度必須是偶數字節".to_string());
+    }
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]) as f32 / 32768.0)
+        .collect())
+}
+
+// 使用 symphonia 解封裝並解碼壓縮音頻；Opus 音軌交給純 Rust 的 Opus 解碼器
+fn decode_container(bytes: &[u8], format: AudioFormat) -> Result<AudioClip, String> {
+    let mut hint = Hint::new();
+    hint.with_extension(format.extension()).mime_type(format.mime_type());
+    let source = MediaSourceStream::new(Box::new(Cursor::new(bytes.to_vec())), Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("無法解析 {} 音頻：{}", format.mime_type(), e))?;
+    let mut reader = probed.format;
+
+    let track = reader
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("{} 文件中沒有音軌", format.mime_type()))?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+
+    if params.codec == CODEC_TYPE_OPUS {
+        decode_opus(reader.as_mut(), track_id, &params)
+    } else {
+        decode_symphonia(reader.as_mut(), track_id, &params)
+    }
+}
+
+fn decode_symphonia(
+    reader: &mut dyn FormatReader,
+    track_id: u32,
+    params: &CodecParameters,
+) -> Result<AudioClip, String> {
+    let mut decoder = symphonia::default::get_codecs()
+        .make(params, &DecoderOptions::default())
+        .map_err(|e| format!("不支持的音頻編碼：{}", e))?;
+
+    let mut samples = Vec::new();
+    let mut sample_rate = params.sample_rate.unwrap_or(0);
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    while let Some(packet) = next_packet(reader, track_id)? {
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // 個別損壞的數據包直接跳過
+            Err(SymphoniaError::DecodeError(e)) => {
+                tracing::warn!(error = %e, "Skipping corrupt audio packet");
+                continue;
+            }
+            Err(e) => return Err(format!("音頻解碼失敗：{}", e)),
+        };
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        let buffer = match &mut buffer {
+            Some(buffer) if buffer.capacity() >= decoded.capacity() * spec.channels.count() => buffer,
+            _ => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        downmix_into(&mut samples, buffer.samples(), spec.channels.count());
+    }
+
+    if sample_rate == 0 {
+        return Err("無法確定音頻採樣率".to_string());
+    }
+    Ok(AudioClip::new(samples, sample_rate))
+}
+
+fn decode_opus(
+    reader: &mut dyn FormatReader,
+    track_id: u32,
+    params: &CodecParameters,
+) -> Result<AudioClip, String> {
+    let channels = params.channels.map(|c| c.count()).unwrap_or(1);
+    if channels > 2 {
+        return Err(format!("不支持 {} 聲道的 Opus 音頻", channels));
+    }
+    let mut decoder = OpusDecoder::new(OPUS_SAMPLE_RATE, channels)
+        .map_err(|e| format!("無法創建 Opus 解碼器：{}", e))?;
+
+    let mut samples = Vec::new();
+    let mut buffer = vec![0.0f32; OpusDecoder::MAX_FRAME_SIZE_48K * channels];
+    while let Some(packet) = next_packet(reader, track_id)? {
+        match decoder.decode_float(&packet.data, &mut buffer, false) {
+            Ok(frames) => downmix_into(&mut samples, &buffer[..frames * channels], channels),
+            Err(e) => tracing::warn!(error = %e, "Skipping corrupt Opus packet"),
+        }
+    }
+
+    // 去掉編碼器預留的前導樣本（Ogg 頭中的 pre-skip）
+    let pre_skip = (params.delay.unwrap_or(0) as usize).min(samples.len());
+    samples.drain(..pre_skip);
+    Ok(AudioClip::new(samples, OPUS_SAMPLE_RATE))
+}
+
+// 讀取指定音軌的下一個數據包，到達文件末尾時返回 None
+fn next_packet(reader: &mut dyn FormatReader, track_id: u32) -> Result<Option<Packet>, String> {
+    loop {
+        match reader.next_packet() {
+            Ok(packet) if packet.track_id() == track_id => return Ok(Some(packet)),
+            Ok(_) => continue,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Ok(None)
+            }
+            Err(SymphoniaError::ResetRequired) => return Ok(None),
+            Err(e) => return Err(format!("讀取音頻數據失敗：{}", e)),
+        }
+    }
+}
+
+// 多聲道取平均混為單聲道
+fn downmix_into(output: &mut Vec<f32>, interleaved: &[f32], channels: usize) {
+    output.extend(
+        interleaved
+            .chunks(channels.max(1))
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32),
+    );
+}
+
+fn moving_average(samples: &[f32], width: usize) -> Vec<f32> {
+    if width <= 1 {
+        return samples.to_vec();
+    }
+    let half = width / 2;
+    let mut prefix = Vec::with_capacity(samples.len() + 1);
+    prefix.push(0.0f64);
+    for &s in samples {
+        prefix.push(prefix.last().unwrap() + s as f64);
+    }
+    (0..samples.len())
+        .map(|i| {
+            let start = i.saturating_sub(half);
+            let end = (i + half + 1).min(samples.len());
+            ((prefix[end] - prefix[start]) / (end - start) as f64) as f32
+        })
+        .collect()
+}
+
+// 每幀的均方根能量（dBFS）
+pub fn frame_energy_db(samples: &[f32], frame_len: usize, hop: usize) -> Vec<f32> {
+    if frame_len == 0 || hop == 0 || samples.len() < frame_len {
+        return Vec::new();
+    }
+    (0..=(samples.len() - frame_len))
+        .step_by(hop)
+        .map(|start| {
+            let frame = &samples[start..start + frame_len];
+            let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame_len as f32;
+            10.0 * (mean_square + 1e-10).log10()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 手工構造 16-bit PCM WAV，用於測試文件頭異常的情況
+    fn raw_wav(channels: u16, sample_rate: u32, bits: u16, samples: &[i16]) -> Vec<u8> {
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let block_align = channels * bits / 8;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        bytes.extend_from_slice(&block_align.to_le_bytes());
+        bytes.extend_from_slice(&bits.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    #[test]
+    fn sniffs_container_formats() {
+        assert_eq!(AudioFormat::sniff(&raw_wav(1, 16_000, 16, &[0])), Some(AudioFormat::Wav));
+        assert_eq!(AudioFormat::sniff(&[0x1A, 0x45, 0xDF, 0xA3, 0]), Some(AudioFormat::WebM));
+        assert_eq!(AudioFormat::sniff(b"OggS\0"), Some(AudioFormat::Ogg));
+        assert_eq!(AudioFormat::sniff(b"\0\0\0\x20ftypM4A "), Some(AudioFormat::Mp4));
+        assert_eq!(AudioFormat::sniff(b"ID3\x04"), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::sniff(&[0xFF, 0xFB, 0x90]), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::sniff(&[0xFF, 0xF1, 0x50]), Some(AudioFormat::Aac));
+        assert_eq!(AudioFormat::sniff(b"fLaC"), Some(AudioFormat::Flac));
+        assert_eq!(AudioFormat::sniff(b"hello"), None);
+        assert!(AudioClip::decode(b"hello").unwrap_err().contains("68 65 6c 6c 6f"));
+    }
+
+    #[test]
+    fn decodes_wav_to_analysis_rate() {
+        // 8 kHz 立體聲，左右聲道相反時混成靜音，相同時保留原值
+        let stereo: Vec<i16> = (0..800).flat_map(|i| if i < 400 { [8_000, -8_000] } else { [16_384, 16_384] }).collect();
+        let clip = AudioClip::decode(&raw_wav(2, 8_000, 16, &stereo)).unwrap();
+        assert_eq!(clip.sample_rate, ANALYSIS_SAMPLE_RATE);
+        assert_eq!(clip.samples.len(), 1_600);
+        assert!(clip.samples[100].abs() < 1e-3);
+        assert!((clip.samples[1_400] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn wav_round_trip() {
+        let clip = AudioClip::new((0..160).map(|i| (i as f32 / 160.0) - 0.5).collect(), ANALYSIS_SAMPLE_RATE);
+        let decoded = AudioClip::decode(&clip.to_wav_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.samples.len(), clip.samples.len());
+        assert!(decoded.samples.iter().zip(&clip.samples).all(|(a, b)| (a - b).abs() < 1e-3));
+    }
+
+    #[test]
+    fn rejects_zero_sample_rate_and_bit_depth() {
+        let error = AudioClip::decode(&raw_wav(1, 0, 16, &[0; 100])).unwrap_err();
+        assert!(error.starts_with("無法解析 WAV 音頻"), "{}", error);
+        assert!(AudioClip::decode(&raw_wav(1, 16_000, 0, &[0; 100])).is_err());
+        // 採樣率為 0 時重採樣直接返回原數據
+        assert_eq!(AudioClip::new(vec![0.1; 10], 0).resampled(ANALYSIS_SAMPLE_RATE).samples.len(), 10);
+    }
+
+    #[test]
+    fn decodes_pcm_and_data_urls() {
+        let bytes: Vec<u8> = [0i16, 16_384, -32_768].iter().flat_map(|s| s.to_le_bytes()).collect();
+        let data = format!("data:audio/pcm;base64,{}", encode_base64(&bytes));
+        assert_eq!(pcm16_from_base64(&data).unwrap(), vec![0.0, 0.5, -1.0]);
+        assert!(pcm16_from_base64(&encode_base64(&[1, 2, 3])).is_err());
+        let clip = AudioClip::from_inline_audio("audio/L16;codec=pcm;rate=8000", &encode_base64(&bytes)).unwrap();
+        assert_eq!((clip.sample_rate, clip.samples.len()), (ANALYSIS_SAMPLE_RATE, 6));
+    }
+}
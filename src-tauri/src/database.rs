@@ -0,0 +1,400 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// 數據庫結構遷移腳本，按順序執行，當前版本記錄在 PRAGMA user_version
+const MIGRATIONS: &[&str] = &[r#"
+    CREATE TABLE recordings (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        reference_text TEXT NOT NULL,
+        text_key TEXT NOT NULL,
+        file_name TEXT NOT NULL,
+        duration_secs REAL NOT NULL,
+        overall_score REAL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_recordings_text_key ON recordings (text_key, created_at);
+
+    CREATE TABLE practice_records (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        topic TEXT NOT NULL,
+        scores TEXT NOT NULL,
+        feedback TEXT NOT NULL,
+        recording_id INTEGER REFERENCES recordings (id) ON DELETE SET NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_practice_records_created_at ON practice_records (created_at);
+
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+"#, r#"
+    CREATE TABLE drill_sessions (
+        id TEXT PRIMARY KEY,
+        content_id TEXT NOT NULL,
+        text TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+
+    CREATE TABLE segment_attempts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id TEXT NOT NULL REFERENCES drill_sessions (id) ON DELETE CASCADE,
+        segment_id TEXT NOT NULL,
+        segment_text TEXT NOT NULL,
+        overall_score REAL,
+        scores TEXT NOT NULL,
+        recording_id INTEGER REFERENCES recordings (id) ON DELETE SET NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_segment_attempts_session ON segment_attempts (session_id, segment_id);
+"#, r#"
+    CREATE TABLE grammar_checks (
+        text TEXT PRIMARY KEY,
+        result TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+"#, r#"
+    CREATE TABLE vocabulary (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        word TEXT NOT NULL,
+        word_key TEXT NOT NULL UNIQUE,
+        part_of_speech TEXT NOT NULL DEFAULT '',
+        ipa TEXT NOT NULL DEFAULT '',
+        definition TEXT NOT NULL DEFAULT '',
+        translation TEXT NOT NULL DEFAULT '',
+        example TEXT NOT NULL DEFAULT '',
+        source_passage TEXT NOT NULL DEFAULT '',
+        level TEXT NOT NULL DEFAULT '',
+        tags TEXT NOT NULL DEFAULT '[]',
+        ease_factor REAL NOT NULL DEFAULT 2.5,
+        interval_days INTEGER NOT NULL DEFAULT 0,
+        repetitions INTEGER NOT NULL DEFAULT 0,
+        lapses INTEGER NOT NULL DEFAULT 0,
+        due_at TEXT NOT NULL DEFAULT (datetime('now')),
+        last_reviewed_at TEXT,
+        created_at TEXT NOT NULL DEFAULT (datetime('now')),
+        updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_vocabulary_due_at ON vocabulary (due_at);
+"#, r#"
+    CREATE TABLE phoneme_errors (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        word TEXT NOT NULL,
+        heard TEXT NOT NULL,
+        expected TEXT NOT NULL,
+        produced TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_phoneme_errors_created_at ON phoneme_errors (created_at);
+
+    CREATE TABLE minimal_pair_items (
+        id TEXT PRIMARY KEY,
+        drill_id TEXT NOT NULL,
+        contrast TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        word_a TEXT NOT NULL,
+        word_b TEXT NOT NULL,
+        target TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+
+    CREATE TABLE contrast_attempts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        item_id TEXT NOT NULL REFERENCES minimal_pair_items (id) ON DELETE CASCADE,
+        contrast TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        response TEXT NOT NULL,
+        correct INTEGER NOT NULL,
+        score REAL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_contrast_attempts_contrast ON contrast_attempts (contrast, created_at);
+"#, r#"
+    CREATE TABLE daily_plans (
+        date TEXT PRIMARY KEY,
+        time_budget INTEGER NOT NULL,
+        level TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+
+    CREATE TABLE plan_activities (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        plan_date TEXT NOT NULL REFERENCES daily_plans (date) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        title TEXT NOT NULL,
+        details TEXT NOT NULL DEFAULT '{}',
+        minutes INTEGER NOT NULL,
+        completed_at TEXT
+    );
+    CREATE INDEX idx_plan_activities_date ON plan_activities (plan_date, position);
+"#, r#"
+    CREATE TABLE practice_time (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        activity TEXT NOT NULL,
+        seconds REAL NOT NULL,
+        plan_activity_id INTEGER REFERENCES plan_activities (id) ON DELETE SET NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_practice_time_created_at ON practice_time (created_at);
+"#, r#"
+    ALTER TABLE practice_records ADD COLUMN uid TEXT;
+    UPDATE practice_records SET uid = lower(hex(randomblob(16)));
+    CREATE UNIQUE INDEX idx_practice_records_uid ON practice_records (uid);
+"#, r#"
+    CREATE TABLE sync_outbox (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        entity TEXT NOT NULL,
+        entity_key TEXT NOT NULL,
+        changed_at INTEGER NOT NULL DEFAULT (CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)),
+        payload TEXT,
+        UNIQUE (entity, entity_key)
+    );
+    CREATE TABLE sync_versions (
+        entity TEXT NOT NULL,
+        entity_key TEXT NOT NULL,
+        vector TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        device_id TEXT NOT NULL,
+        PRIMARY KEY (entity, entity_key)
+    );
+    CREATE TABLE sync_suppress (active INTEGER NOT NULL);
+
+    -- 觸發器記錄本地修改；先刪後插使每次修改都得到新的 id，推送期間再次修改的條目不會被誤確認
+    CREATE TRIGGER sync_practice_records_insert AFTER INSERT ON practice_records
+    WHEN NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'practice_record' AND entity_key = NEW.uid;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('practice_record', NEW.uid);
+    END;
+    CREATE TRIGGER sync_practice_records_update AFTER UPDATE OF topic, scores, feedback ON practice_records
+    WHEN NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'practice_record' AND entity_key = NEW.uid;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('practice_record', NEW.uid);
+    END;
+    CREATE TRIGGER sync_practice_records_delete AFTER DELETE ON practice_records
+    WHEN NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'practice_record' AND entity_key = OLD.uid;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('practice_record', OLD.uid);
+    END;
+
+    CREATE TRIGGER sync_vocabulary_insert AFTER INSERT ON vocabulary
+    WHEN NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'vocabulary' AND entity_key = NEW.word_key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('vocabulary', NEW.word_key);
+    END;
+    CREATE TRIGGER sync_vocabulary_update AFTER UPDATE ON vocabulary
+    WHEN NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'vocabulary' AND entity_key = NEW.word_key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('vocabulary', NEW.word_key);
+    END;
+    CREATE TRIGGER sync_vocabulary_rename AFTER UPDATE OF word_key ON vocabulary
+    WHEN OLD.word_key <> NEW.word_key AND NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'vocabulary' AND entity_key = OLD.word_key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('vocabulary', OLD.word_key);
+    END;
+    CREATE TRIGGER sync_vocabulary_delete AFTER DELETE ON vocabulary
+    WHEN NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'vocabulary' AND entity_key = OLD.word_key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('vocabulary', OLD.word_key);
+    END;
+
+    CREATE TRIGGER sync_settings_insert AFTER INSERT ON settings
+    WHEN NEW.key NOT LIKE 'sync\_%' ESCAPE '\' AND NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'setting' AND entity_key = NEW.key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('setting', NEW.key);
+    END;
+    CREATE TRIGGER sync_settings_update AFTER UPDATE ON settings
+    WHEN NEW.key NOT LIKE 'sync\_%' ESCAPE '\' AND NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'setting' AND entity_key = NEW.key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('setting', NEW.key);
+    END;
+    CREATE TRIGGER sync_settings_delete AFTER DELETE ON settings
+    WHEN OLD.key NOT LIKE 'sync\_%' ESCAPE '\' AND NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'setting' AND entity_key = OLD.key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('setting', OLD.key);
+    END;
+
+    INSERT INTO sync_outbox (entity, entity_key) SELECT 'practice_record', uid FROM practice_records;
+    INSERT INTO sync_outbox (entity, entity_key) SELECT 'vocabulary', word_key FROM vocabulary;
+    INSERT INTO sync_outbox (entity, entity_key) SELECT 'setting', key FROM settings WHERE key NOT LIKE 'sync\_%' ESCAPE '\';
+"#, r#"
+    CREATE TABLE achievements (
+        badge_id TEXT PRIMARY KEY,
+        value REAL NOT NULL,
+        unlocked_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+"#, r#"
+    -- role 為 teacher 時是自己佈置的作業，為 student 時是導入的作業；
+    -- document 保存簽名時的原始 JSON，校驗學生結果包時按原樣計算哈希
+    CREATE TABLE assignments (
+        id TEXT PRIMARY KEY,
+        role TEXT NOT NULL,
+        title TEXT NOT NULL,
+        due_date TEXT,
+        teacher_key TEXT NOT NULL,
+        document TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+
+    CREATE TABLE assignment_attempts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        assignment_id TEXT NOT NULL REFERENCES assignments (id) ON DELETE CASCADE,
+        passage_id TEXT NOT NULL,
+        scores TEXT NOT NULL,
+        weighted_score REAL NOT NULL,
+        transcript TEXT NOT NULL DEFAULT '',
+        recording_id INTEGER REFERENCES recordings (id) ON DELETE SET NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX idx_assignment_attempts_passage ON assignment_attempts (assignment_id, passage_id);
+
+    CREATE TABLE assignment_results (
+        assignment_id TEXT NOT NULL REFERENCES assignments (id) ON DELETE CASCADE,
+        student_key TEXT NOT NULL,
+        student_name TEXT NOT NULL,
+        total_score REAL NOT NULL,
+        late INTEGER NOT NULL,
+        submitted_at TEXT NOT NULL,
+        document TEXT NOT NULL,
+        imported_at TEXT NOT NULL DEFAULT (datetime('now')),
+        PRIMARY KEY (assignment_id, student_key)
+    );
+"#, r#"
+    -- local_ 開頭的設置只保存在本設備（如各檔案的 API 密鑰），和 sync_ 一樣不參與同步
+    DROP TRIGGER sync_settings_insert;
+    DROP TRIGGER sync_settings_update;
+    DROP TRIGGER sync_settings_delete;
+    CREATE TRIGGER sync_settings_insert AFTER INSERT ON settings
+    WHEN NEW.key NOT LIKE 'sync\_%' ESCAPE '\' AND NEW.key NOT LIKE 'local\_%' ESCAPE '\'
+        AND NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'setting' AND entity_key = NEW.key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('setting', NEW.key);
+    END;
+    CREATE TRIGGER sync_settings_update AFTER UPDATE ON settings
+    WHEN NEW.key NOT LIKE 'sync\_%' ESCAPE '\' AND NEW.key NOT LIKE 'local\_%' ESCAPE '\'
+        AND NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'setting' AND entity_key = NEW.key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('setting', NEW.key);
+    END;
+    CREATE TRIGGER sync_settings_delete AFTER DELETE ON settings
+    WHEN OLD.key NOT LIKE 'sync\_%' ESCAPE '\' AND OLD.key NOT LIKE 'local\_%' ESCAPE '\'
+        AND NOT EXISTS (SELECT 1 FROM sync_suppress)
+    BEGIN
+        DELETE FROM sync_outbox WHERE entity = 'setting' AND entity_key = OLD.key;
+        INSERT INTO sync_outbox (entity, entity_key) VALUES ('setting', OLD.key);
+    END;
+"#, r#"
+    -- 評分時按錄音時長計入練習時間，同一段錄音只計一次
+    ALTER TABLE practice_time ADD COLUMN recording_id INTEGER REFERENCES recordings (id) ON DELETE SET NULL;
+    CREATE UNIQUE INDEX idx_practice_time_recording ON practice_time (recording_id);
+"#, r#"
+    -- 成就統計的累計值。cursor 為已計入的源表最大 id，每次評估只統計之後新增的行
+    CREATE TABLE achievement_counters (
+        metric TEXT PRIMARY KEY,
+        value REAL NOT NULL,
+        cursor INTEGER NOT NULL
+    );
+    CREATE TABLE achievement_topics (topic TEXT PRIMARY KEY);
+"#];
+
+// 本地 SQLite 數據庫，保存練習記錄、錄音索引與設置
+pub struct Database {
+    conn: Connection,
+    data_dir: PathBuf,
+}
+
+impl Database {
+    pub fn open(data_dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(data_dir).map_err(|e| format!("無法創建數據目錄：{}", e))?;
+        let conn = Connection::open(data_dir.join("practice.db"))
+            .map_err(|e| format!("無法打開數據庫：{}", e))?;
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA journal_mode = WAL;")
+            .map_err(|e| format!("數據庫初始化失敗：{}", e))?;
+
+        let mut database = Self { conn, data_dir: data_dir.to_path_buf() };
+        database.migrate()?;
+        Ok(database)
+    }
+
+    fn migrate(&mut self) -> Result<(), String> {
+        let version: usize = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| format!("讀取數據庫版本失敗：{}", e))?;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+            tx.execute_batch(migration)
+                .map_err(|e| format!("數據庫遷移 {} 失敗：{}", index + 1, e))?;
+            tx.pragma_update(None, "user_version", index + 1)
+                .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    // 設置以 JSON 形式保存
+    pub fn get_setting<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        let value: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("讀取設置失敗：{}", e))?;
+        value
+            .map(|v| serde_json::from_str(&v).map_err(|e| format!("設置 {} 格式錯誤：{}", key, e)))
+            .transpose()
+    }
+
+    pub fn set_setting<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        self.conn
+            .execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![key, json],
+            )
+            .map_err(|e| format!("保存設置失敗：{}", e))?;
+        Ok(())
+    }
+
+    pub fn insert_practice_record(
+        &self,
+        topic: &str,
+        scores: &HashMap<String, f64>,
+        feedback: &str,
+        recording_id: Option<i64>,
+    ) -> Result<i64, String> {
+        let scores = serde_json::to_string(scores).map_err(|e| e.to_string())?;
+        // uid 在各設備間唯一，導入和同步時據此去重
+        let uid = format!("{:032x}", rand::random::<u128>());
+        self.conn
+            .execute(
+                "INSERT INTO practice_records (uid, topic, scores, feedback, recording_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![uid, topic, scores, feedback, recording_id],
+            )
+            .map_err(|e| format!("保存練習記錄失敗：{}", e))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+}
@@ -0,0 +1,510 @@
+// 各子系統的健康檢查，結果可以打包成脫敏的診斷文件，附在問題反饋中
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::audio::AudioClip;
+use crate::database::Database;
+use crate::export;
+use crate::gemini_service::GeminiService;
+use crate::goals;
+use crate::logging;
+use crate::preprocess;
+use crate::pronunciation;
+use crate::vad::VadConfig;
+
+// 數據目錄中的緩存和歸檔超過這個大小時提醒清理
+const CACHE_WARN_BYTES: u64 = 1 << 30;
+const DISK_WARN_BYTES: u64 = 1 << 30;
+const DISK_FAIL_BYTES: u64 = 100 << 20;
+// 峰值低於此電平視為沒有輸入信號（麥克風被靜音或沒有權限時錄到的是全零）
+const SILENT_PEAK_DB: f64 = -80.0;
+// 完整的 CMU 詞典約有 13 萬詞條，明顯偏少說明數據文件不完整
+const MIN_DICTIONARY_ENTRIES: usize = 100_000;
+const BUNDLE_LOG_ENTRIES: usize = 1000;
+const CACHE_DIRS: &[&str] = &["tts", "recordings", "exports", "assignments"];
+
+// 按嚴重程度排序，整體狀態取最差的一項
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub id: String,
+    pub name: String,
+    pub status: CheckStatus,
+    pub latency_ms: u64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub generated_at: String, // UTC
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub status: CheckStatus,
+    pub checks: Vec<CheckResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsBundle {
+    pub path: String,
+    pub report: DiagnosticsReport,
+}
+
+fn check(id: &str, name: &str, status: CheckStatus, started: Instant, message: impl Into<String>) -> CheckResult {
+    CheckResult {
+        id: id.to_string(),
+        name: name.to_string(),
+        status,
+        latency_ms: started.elapsed().as_millis() as u64,
+        message: message.into(),
+        details: Value::Null,
+    }
+}
+
+impl CheckResult {
+    fn with_details(self, details: Value) -> Self {
+        Self { details, ..self }
+    }
+}
+
+// reqwest 的錯誤信息只有一句「error sending request」，具體原因（DNS、TLS 等）在 source 鏈裡
+fn error_chain(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut messages = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(cause) = source {
+        // 有的錯誤會把 source 的信息拼進自己的描述裡，已經出現過的不再重複
+        let message = cause.to_string();
+        if !messages.iter().any(|m| m.contains(&message)) {
+            messages.push(message);
+        }
+        source = cause.source();
+    }
+    messages.join(": ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionFailure {
+    Dns,
+    Tls,
+    Timeout,
+    Network,
+    Auth,
+    Quota,
+    Region,
+    Model,
+    Server,
+    Unknown,
+}
+
+impl ConnectionFailure {
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::Dns => "無法解析服務域名，請檢查網絡或 DNS 設置",
+            Self::Tls => "安全連接失敗，可能是證書問題或網絡被代理攔截",
+            Self::Timeout => "連接超時，請檢查網絡或代理設置",
+            Self::Network => "無法連接到服務，請檢查網絡",
+            Self::Auth => "API 密鑰無效或沒有訪問權限",
+            Self::Quota => "配額已用完或請求過於頻繁，請稍後再試",
+            Self::Region => "所在地區不支持使用 Gemini API",
+            Self::Model => "模型不存在或當前密鑰無權使用",
+            Self::Server => "服務端出錯，請稍後再試",
+            Self::Unknown => "請求失敗，原因未知",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionTest {
+    pub success: bool,
+    pub latency_ms: u64,
+    pub model: String,
+    pub http_status: Option<u16>,
+    pub failure: Option<ConnectionFailure>,
+    pub message: String,
+    pub activated: bool, // 是否已替換當前使用的服務
+}
+
+// 請求沒有得到響應時，根據錯誤鏈判斷是哪一步出了問題
+pub fn classify_error(error: &(dyn std::error::Error + 'static)) -> ConnectionFailure {
+    if error.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_timeout()) {
+        return ConnectionFailure::Timeout;
+    }
+    let chain = error_chain(error).to_lowercase();
+    let mentions = |words: &[&str]| words.iter().any(|word| chain.contains(word));
+    if mentions(&["dns error", "failed to lookup address", "name or service not known", "no such host", "nodename nor servname"]) {
+        ConnectionFailure::Dns
+    } else if mentions(&["certificate", "tls", "ssl", "handshake"]) {
+        ConnectionFailure::Tls
+    } else if mentions(&["timed out", "timeout"]) {
+        ConnectionFailure::Timeout
+    } else {
+        ConnectionFailure::Network
+    }
+}
+
+// 根據狀態碼和錯誤正文分類。地區限制時 Google 返回 400 FAILED_PRECONDITION，
+// 密鑰無效時返回 400 並帶 API_KEY_INVALID，代理服務通常返回 401/403
+pub fn classify_response(status: u16, body: &str) -> Option<ConnectionFailure> {
+    if (200..300).contains(&status) {
+        return None;
+    }
+    let body = body.to_lowercase();
+    Some(if body.contains("location is not supported") || body.contains("unsupported_country") {
+        ConnectionFailure::Region
+    } else if matches!(status, 401 | 403) || (status == 400 && (body.contains("api_key") || body.contains("api key"))) {
+        ConnectionFailure::Auth
+    } else if status == 429 || body.contains("resource_exhausted") {
+        ConnectionFailure::Quota
+    } else if status == 404 {
+        ConnectionFailure::Model
+    } else if status >= 500 {
+        ConnectionFailure::Server
+    } else {
+        ConnectionFailure::Unknown
+    })
+}
+
+// 只查詢模型信息，不生成內容，不消耗配額
+pub async fn test_connection(service: &GeminiService, model: &str) -> ConnectionTest {
+    let started = Instant::now();
+    let probe = service.probe_model(model).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let (http_status, failure, detail) = match probe {
+        Ok((status, body)) => (Some(status), classify_response(status, &body), format!("HTTP {}", status)),
+        Err(e) => (None, Some(classify_error(e.as_ref())), error_chain(e.as_ref())),
+    };
+    let message = match failure {
+        Some(failure) => format!("{}（{}）", failure.message(), detail),
+        None => format!("連接正常，模型 {} 可用，耗時 {} 毫秒", model, latency_ms),
+    };
+    ConnectionTest {
+        success: failure.is_none(),
+        latency_ms,
+        model: model.to_string(),
+        http_status,
+        failure,
+        message,
+        activated: false,
+    }
+}
+
+fn model_message(status: u16) -> &'static str {
+    match status {
+        200 => "可用",
+        404 => "不存在或當前密鑰無權使用",
+        429 => "配額已用完",
+        _ => "狀態未知",
+    }
+}
+
+// 服務連接、API 密鑰和模型可用性。通過查詢模型信息來檢查，不消耗生成配額
+pub async fn check_gemini(service: Option<&GeminiService>) -> Vec<CheckResult> {
+    let started = Instant::now();
+    let Some(service) = service else {
+        return [("endpoint", "服務連接"), ("auth", "API 密鑰"), ("models", "模型可用性")]
+            .iter()
+            .map(|(id, name)| check(id, name, CheckStatus::Warn, started, "尚未設置 API 密鑰，無法檢查"))
+            .collect();
+    };
+    let config = service.config();
+    let host = reqwest::Url::parse(&config.base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| config.base_url.clone());
+
+    let (status, body) = match service.probe_model(&config.model).await {
+        Ok(response) => response,
+        Err(e) => {
+            return vec![
+                check("endpoint", "服務連接", CheckStatus::Fail, started, format!("無法連接 {}：{}", host, error_chain(e.as_ref())))
+                    .with_details(json!({ "host": host, "failure": classify_error(e.as_ref()) })),
+                check("auth", "API 密鑰", CheckStatus::Warn, started, "無法連接服務，未檢查"),
+                check("models", "模型可用性", CheckStatus::Warn, started, "無法連接服務，未檢查"),
+            ];
+        }
+    };
+    let mut checks = Vec::new();
+    let endpoint = if status >= 500 {
+        check("endpoint", "服務連接", CheckStatus::Warn, started, format!("{} 服務端出錯（HTTP {}）", host, status))
+    } else {
+        check("endpoint", "服務連接", CheckStatus::Pass, started, format!("{} 可以訪問", host))
+    };
+    checks.push(endpoint.with_details(json!({ "host": host, "http_status": status })));
+
+    let failure = classify_response(status, &body);
+    let auth = match failure {
+        None => check("auth", "API 密鑰", CheckStatus::Pass, started, "API 密鑰有效"),
+        Some(failure @ (ConnectionFailure::Auth | ConnectionFailure::Region)) => {
+            check("auth", "API 密鑰", CheckStatus::Fail, started, failure.message())
+        }
+        Some(ConnectionFailure::Quota) => {
+            check("auth", "API 密鑰", CheckStatus::Warn, started, "密鑰有效，但配額已用完或請求過於頻繁")
+        }
+        Some(failure) => {
+            check("auth", "API 密鑰", CheckStatus::Warn, started, format!("{}，無法確認密鑰是否有效", failure.message()))
+        }
+    };
+    checks.push(auth);
+
+    if matches!(failure, Some(ConnectionFailure::Auth | ConnectionFailure::Region)) {
+        checks.push(check("models", "模型可用性", CheckStatus::Warn, started, "無法使用 API，未檢查"));
+        return checks;
+    }
+    let started = Instant::now();
+    let mut models = vec![(config.model.clone(), status)];
+    // 音頻模型與文本模型相同時不重複查詢
+    let extra = [(&config.tts_model, "TTS"), (&config.audio_model, "Audio")];
+    for (model, kind) in extra {
+        if models.iter().any(|(probed, _)| probed == model) {
+            continue;
+        }
+        let status = match service.probe_model(model).await {
+            Ok((status, _)) => status,
+            Err(e) => {
+                tracing::warn!(error = %error_chain(e.as_ref()), kind, "Model probe failed");
+                0
+            }
+        };
+        models.push((model.clone(), status));
+    }
+    let status = if models.iter().any(|(_, status)| *status == 404) {
+        CheckStatus::Fail
+    } else if models.iter().all(|(_, status)| *status == 200) {
+        CheckStatus::Pass
+    } else {
+        CheckStatus::Warn
+    };
+    let message = models
+        .iter()
+        .map(|(model, status)| format!("{}：{}", model, model_message(*status)))
+        .collect::<Vec<_>>()
+        .join("；");
+    let details = models.iter().map(|(model, status)| json!({ "model": model, "http_status": status })).collect();
+    checks.push(check("models", "模型可用性", status, started, message).with_details(Value::Array(details)));
+    checks
+}
+
+fn check_database(db: Option<&Database>) -> CheckResult {
+    let started = Instant::now();
+    let Some(db) = db else {
+        return check("database", "數據庫完整性", CheckStatus::Fail, started, "數據庫未初始化或打開失敗，詳見日誌");
+    };
+    let quick_check = db.conn().prepare("PRAGMA quick_check").and_then(|mut stmt| {
+        stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<Result<Vec<_>, _>>()
+    });
+    let problems = match quick_check {
+        Ok(rows) => rows.into_iter().filter(|row| row != "ok").collect::<Vec<_>>(),
+        Err(e) => {
+            return check("database", "數據庫完整性", CheckStatus::Fail, started, format!("完整性檢查失敗：{}", e));
+        }
+    };
+    let orphans: i64 = db
+        .conn()
+        .query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))
+        .unwrap_or_default();
+    let version: i64 = db.conn().query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap_or_default();
+    let size = std::fs::metadata(db.data_dir().join("practice.db")).map(|m| m.len()).unwrap_or_default();
+    let details = json!({ "schema_version": version, "size_bytes": size, "problems": problems, "foreign_key_violations": orphans });
+
+    let result = if !problems.is_empty() {
+        check("database", "數據庫完整性", CheckStatus::Fail, started, format!("發現 {} 處損壞：{}", problems.len(), problems[0]))
+    } else if orphans > 0 {
+        check("database", "數據庫完整性", CheckStatus::Warn, started, format!("有 {} 條記錄引用了已刪除的數據", orphans))
+    } else {
+        check("database", "數據庫完整性", CheckStatus::Pass, started, format!("完整，版本 {}", version))
+    };
+    result.with_details(details)
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn megabytes(bytes: u64) -> f64 {
+    (bytes as f64 / 1024.0 / 1024.0 * 10.0).round() / 10.0
+}
+
+fn check_cache(data_dir: &Path) -> CheckResult {
+    let started = Instant::now();
+    let sizes: Vec<(&str, u64)> = CACHE_DIRS.iter().map(|dir| (*dir, dir_size(&data_dir.join(dir)))).collect();
+    let total: u64 = sizes.iter().map(|(_, size)| size).sum();
+    let details = sizes.iter().map(|(dir, size)| (dir.to_string(), json!(size))).collect::<serde_json::Map<_, _>>();
+    let result = if total >= CACHE_WARN_BYTES {
+        check(
+            "cache",
+            "緩存大小",
+            CheckStatus::Warn,
+            started,
+            format!("緩存和錄音共 {} MB，可以縮短錄音保留時間或刪除舊的導出文件", megabytes(total)),
+        )
+    } else {
+        check("cache", "緩存大小", CheckStatus::Pass, started, format!("共 {} MB", megabytes(total)))
+    };
+    result.with_details(Value::Object(details))
+}
+
+// 麥克風由前端採集，這裡檢查前端錄下的一小段測試音頻是否有信號
+fn check_audio_input(sample: Option<&str>) -> CheckResult {
+    let started = Instant::now();
+    let Some(sample) = sample else {
+        return check("audio_input", "麥克風輸入", CheckStatus::Warn, started, "沒有提供測試錄音，未檢查");
+    };
+    let clip = match AudioClip::from_base64(sample) {
+        Ok(clip) => clip,
+        Err(e) => return check("audio_input", "麥克風輸入", CheckStatus::Fail, started, format!("測試錄音無法解碼：{}", e)),
+    };
+    let quality = preprocess::prepare_recording(&clip, &VadConfig::default()).quality;
+    let details = json!({
+        "sample_rate": clip.sample_rate,
+        "duration_secs": (clip.samples.len() as f64 / clip.sample_rate.max(1) as f64 * 100.0).round() / 100.0,
+        "peak_db": quality.peak_db,
+        "noise_level_db": quality.noise_level_db,
+        "clipping_ratio": quality.clipping_ratio,
+    });
+    let result = if clip.samples.is_empty() || quality.peak_db < SILENT_PEAK_DB {
+        check("audio_input", "麥克風輸入", CheckStatus::Fail, started, "錄音沒有信號，請檢查麥克風是否被靜音或沒有授權")
+    } else if let Some(issue) = quality.issue.filter(|issue| *issue != preprocess::QualityIssue::NoSpeech) {
+        check("audio_input", "麥克風輸入", CheckStatus::Warn, started, issue.message())
+    } else {
+        check("audio_input", "麥克風輸入", CheckStatus::Pass, started, "麥克風有信號")
+    };
+    result.with_details(details)
+}
+
+// 本地只有內置的發音詞典，首次加載需要解析，順便記錄耗時
+fn check_local_models() -> CheckResult {
+    let started = Instant::now();
+    let entries = pronunciation::dictionary_size();
+    let details = json!({ "cmudict_entries": entries });
+    let result = if entries == 0 {
+        check("local_models", "本地模型文件", CheckStatus::Fail, started, "發音詞典為空")
+    } else if entries < MIN_DICTIONARY_ENTRIES {
+        check("local_models", "本地模型文件", CheckStatus::Warn, started, format!("發音詞典只有 {} 個詞條，可能不完整", entries))
+    } else {
+        check("local_models", "本地模型文件", CheckStatus::Pass, started, format!("發音詞典已加載，{} 個詞條", entries))
+    };
+    result.with_details(details)
+}
+
+// 取數據目錄所在的磁盤，即掛載點為其最長前綴的那個
+fn check_disk_space(data_dir: &Path) -> CheckResult {
+    let started = Instant::now();
+    let dir = data_dir.canonicalize().unwrap_or_else(|_| data_dir.to_path_buf());
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let Some(disk) = disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    else {
+        return check("disk_space", "磁盤空間", CheckStatus::Warn, started, "找不到數據目錄所在的磁盤");
+    };
+    let available = disk.available_space();
+    let details = json!({ "available_bytes": available, "total_bytes": disk.total_space() });
+    let message = format!("剩餘 {} MB", megabytes(available));
+    let status = if available < DISK_FAIL_BYTES {
+        CheckStatus::Fail
+    } else if available < DISK_WARN_BYTES {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Pass
+    };
+    check("disk_space", "磁盤空間", status, started, message).with_details(details)
+}
+
+// 本地檢查，不涉及網絡
+pub fn check_local(db: Option<&Database>, data_dir: &Path, audio_sample: Option<&str>) -> Vec<CheckResult> {
+    vec![
+        check_database(db),
+        check_cache(data_dir),
+        check_audio_input(audio_sample),
+        check_local_models(),
+        check_disk_space(data_dir),
+    ]
+}
+
+fn utc_now() -> (u64, String) {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    let time = secs % 86_400;
+    let formatted = format!(
+        "{} {:02}:{:02}:{:02}",
+        goals::date_string((secs / 86_400) as i64),
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    );
+    (secs, formatted)
+}
+
+pub fn report(checks: Vec<CheckResult>) -> DiagnosticsReport {
+    let status = checks.iter().map(|check| check.status).max().unwrap_or(CheckStatus::Pass);
+    DiagnosticsReport {
+        generated_at: utc_now().1,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        status,
+        checks,
+    }
+}
+
+// 未指定路徑時與其他導出一樣放在數據目錄的 exports 下
+pub fn default_bundle_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("exports").join(format!("diagnostics-{}.zip", utc_now().0))
+}
+
+// 除日誌本身的脫敏外，再把用戶主目錄換成 ~，路徑中常帶有用戶名
+fn redact(text: &str) -> String {
+    let text = logging::redact(text);
+    match std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+        Ok(home) if home.len() > 1 => text.replace(&home, "~"),
+        _ => text,
+    }
+}
+
+// 打包診斷結果和最近的日誌
+pub fn export_bundle(report: &DiagnosticsReport, path: &Path) -> Result<(), String> {
+    let logs = match logging::recent(Some("debug"), Some(BUNDLE_LOG_ENTRIES)) {
+        Ok(entries) => serde_json::to_value(entries).map_err(|e| e.to_string())?,
+        Err(e) => json!({ "error": e }),
+    };
+    let files = [
+        ("diagnostics.json", serde_json::to_string_pretty(report).map_err(|e| e.to_string())?),
+        ("logs.json", serde_json::to_string_pretty(&logs).map_err(|e| e.to_string())?),
+    ];
+
+    export::create_parent(path)?;
+    let file = std::fs::File::create(path).map_err(|e| format!("無法創建診斷文件：{}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let zip_error = |e: zip::result::ZipError| format!("寫入診斷文件失敗：{}", e);
+    for (name, content) in files {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(redact(&content).as_bytes()).map_err(|e| format!("寫入診斷文件失敗：{}", e))?;
+    }
+    zip.finish().map_err(zip_error)?;
+    Ok(())
+}
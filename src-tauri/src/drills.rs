@@ -0,0 +1,205 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::audio::AudioClip;
+use crate::database::Database;
+use crate::segmentation::{self, stable_id, SegmentedText};
+
+// 片段最近一次得分低於此值時需要重練
+pub const PASS_THRESHOLD: f64 = 70.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrillSessionInfo {
+    pub session_id: String,
+    pub content: SegmentedText,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentScore {
+    pub segment_id: String,
+    pub text: String,
+    pub attempts: usize,
+    pub latest_score: Option<f64>,
+    pub best_score: Option<f64>,
+    pub passed: Option<bool>, // 尚未練習時為空
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentenceScore {
+    #[serde(flatten)]
+    pub score: SegmentScore,
+    pub chunks: Vec<SegmentScore>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrillSessionResult {
+    pub session_id: String,
+    pub content_id: String,
+    pub sentence_count: usize,
+    pub attempted_count: usize,
+    pub passed_count: usize,
+    pub overall_score: Option<f64>,
+    pub sentences: Vec<SentenceScore>,
+    // 需要重練的片段：句內有短語塊不合格時只列出這些短語塊，否則列出整句
+    pub needs_practice: Vec<String>,
+}
+
+// 片段參考音頻，統一以 WAV 返回給前端播放
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentAudio {
+    pub segment_id: String,
+    pub text: String,
+    pub duration_secs: f64,
+    pub mime_type: String,
+    pub audio_data: String,
+}
+
+pub fn start_session(db: &Database, text: &str) -> Result<DrillSessionInfo, String> {
+    let content = segmentation::segment_text(text);
+    if content.sentences.is_empty() {
+        return Err("練習內容為空".to_string());
+    }
+
+    let session_id = format!("{:016x}", rand::random::<u64>());
+    db.conn()
+        .execute(
+            "INSERT INTO drill_sessions (id, content_id, text) VALUES (?1, ?2, ?3)",
+            params![session_id, content.content_id, content.text],
+        )
+        .map_err(|e| format!("創建練習失敗：{}", e))?;
+    Ok(DrillSessionInfo { session_id, content })
+}
+
+// 會話只保存原文，切分結果按原文重新計算（切分是確定性的，id 不變）
+pub fn load_session(db: &Database, session_id: &str) -> Result<SegmentedText, String> {
+    let text: String = db
+        .conn()
+        .query_row("SELECT text FROM drill_sessions WHERE id = ?1", [session_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("查詢練習失敗：{}", e))?
+        .ok_or("練習不存在")?;
+    Ok(segmentation::segment_text(&text))
+}
+
+pub fn record_attempt(
+    db: &Database,
+    session_id: &str,
+    segment_id: &str,
+    segment_text: &str,
+    scores: &HashMap<String, f64>,
+    recording_id: Option<i64>,
+) -> Result<i64, String> {
+    let overall_score = scores.get("overall").copied();
+    let scores = serde_json::to_string(scores).map_err(|e| e.to_string())?;
+    db.conn()
+        .execute(
+            "INSERT INTO segment_attempts (session_id, segment_id, segment_text, overall_score, scores, recording_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![session_id, segment_id, segment_text, overall_score, scores, recording_id],
+        )
+        .map_err(|e| format!("保存練習結果失敗：{}", e))?;
+    Ok(db.conn().last_insert_rowid())
+}
+
+pub fn session_result(db: &Database, session_id: &str) -> Result<DrillSessionResult, String> {
+    let content = load_session(db, session_id)?;
+
+    // 按片段收集得分，按時間順序排列
+    let mut attempts: HashMap<String, Vec<f64>> = HashMap::new();
+    {
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT segment_id, overall_score FROM segment_attempts
+                 WHERE session_id = ?1 AND overall_score IS NOT NULL ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([session_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+            .map_err(|e| format!("查詢練習結果失敗：{}", e))?;
+        for row in rows {
+            let (segment_id, score) = row.map_err(|e| e.to_string())?;
+            attempts.entry(segment_id).or_default().push(score);
+        }
+    }
+
+    let score_of = |segment_id: &str, text: &str| {
+        let scores = attempts.get(segment_id).map(Vec::as_slice).unwrap_or_default();
+        let latest_score = scores.last().copied();
+        SegmentScore {
+            segment_id: segment_id.to_string(),
+            text: text.to_string(),
+            attempts: scores.len(),
+            latest_score,
+            best_score: scores.iter().copied().reduce(f64::max),
+            passed: latest_score.map(|score| score >= PASS_THRESHOLD),
+        }
+    };
+
+    let mut needs_practice = Vec::new();
+    let sentences: Vec<SentenceScore> = content
+        .sentences
+        .iter()
+        .map(|sentence| {
+            let mut score = score_of(&sentence.id, &sentence.text);
+            let chunks: Vec<SegmentScore> =
+                sentence.chunks.iter().map(|chunk| score_of(&chunk.id, &chunk.text)).collect();
+
+            // 整句沒練過但所有短語塊都練過時，按短語塊中最低的最近得分估計整句
+            if score.attempts == 0 && !chunks.is_empty() && chunks.iter().all(|c| c.attempts > 0) {
+                score.latest_score = chunks.iter().filter_map(|c| c.latest_score).reduce(f64::min);
+                score.passed = score.latest_score.map(|s| s >= PASS_THRESHOLD);
+            }
+
+            let failed_chunks: Vec<String> = chunks
+                .iter()
+                .filter(|c| c.passed == Some(false))
+                .map(|c| c.segment_id.clone())
+                .collect();
+            if !failed_chunks.is_empty() {
+                needs_practice.extend(failed_chunks);
+            } else if score.passed == Some(false) {
+                needs_practice.push(score.segment_id.clone());
+            }
+            SentenceScore { score, chunks }
+        })
+        .collect();
+
+    let latest: Vec<f64> = sentences.iter().filter_map(|s| s.score.latest_score).collect();
+    let overall_score = (!latest.is_empty())
+        .then(|| (latest.iter().sum::<f64>() / latest.len() as f64 * 10.0).round() / 10.0);
+
+    Ok(DrillSessionResult {
+        session_id: session_id.to_string(),
+        content_id: content.content_id,
+        sentence_count: sentences.len(),
+        attempted_count: latest.len(),
+        passed_count: sentences.iter().filter(|s| s.score.passed == Some(true)).count(),
+        overall_score,
+        sentences,
+        needs_practice,
+    })
+}
+
+// 片段 TTS 音頻緩存，按文本和音色區分，重練同一片段時不再重複合成
+fn tts_cache_path(db: &Database, text: &str, voice: &str) -> PathBuf {
+    let voice: String = voice.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    db.data_dir().join("tts").join(format!("{}-{}.wav", stable_id("t", text), voice))
+}
+
+pub fn cached_segment_audio(db: &Database, text: &str, voice: &str) -> Option<AudioClip> {
+    let bytes = std::fs::read(tts_cache_path(db, text, voice)).ok()?;
+    AudioClip::from_wav_bytes(&bytes)
+        .map_err(|e| tracing::warn!(error = %e, "Invalid cached TTS audio"))
+        .ok()
+}
+
+pub fn cache_segment_audio(db: &Database, text: &str, voice: &str, clip: &AudioClip) -> Result<(), String> {
+    let path = tts_cache_path(db, text, voice);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("無法創建緩存目錄：{}", e))?;
+    }
+    std::fs::write(path, clip.to_wav_bytes()?).map_err(|e| format!("保存合成音頻失敗：{}", e))
+}
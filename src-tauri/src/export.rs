@@ -0,0 +1,583 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::database::Database;
+use crate::segmentation::stable_id;
+use crate::vocabulary::{self, MergeOutcome, VocabularyEntry, VocabularyFilter};
+
+const EXPORT_FORMAT: &str = "web-chat-export";
+const EXPORT_VERSION: u32 = 1;
+
+// Anki 字段分隔符
+const FIELD_SEPARATOR: char = '\u{1f}';
+const ANKI_FIELDS: [&str; 7] = ["Word", "IPA", "PartOfSpeech", "Definition", "Translation", "Example", "Audio"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeRecordExport {
+    pub uid: String,
+    pub topic: String,
+    pub scores: HashMap<String, f64>,
+    pub feedback: String,
+    pub created_at: String,
+}
+
+// 錄音評分歷史只導出文本和分數，不含音頻，導入時忽略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreHistoryExport {
+    pub reference_text: String,
+    pub duration_secs: f64,
+    pub overall_score: Option<f64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningDataExport {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub practice_records: Vec<PracticeRecordExport>,
+    pub vocabulary: Vec<VocabularyEntry>,
+    #[serde(default)]
+    pub score_history: Vec<ScoreHistoryExport>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub practice_records_added: u32,
+    pub practice_records_skipped: u32,
+    pub vocabulary_added: u32,
+    pub vocabulary_merged: u32,
+    pub vocabulary_skipped: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnkiExportResult {
+    pub path: String,
+    pub note_count: usize,
+    pub audio_count: usize,
+}
+
+// 未指定路徑時寫到數據目錄下的 exports 文件夾，文件名帶時間戳
+pub fn default_export_path(db: &Database, name: &str, extension: &str) -> Result<PathBuf, String> {
+    let timestamp: String = db
+        .conn()
+        .query_row("SELECT strftime('%Y%m%d-%H%M%S', 'now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let file_name = if extension.is_empty() {
+        format!("{}-{}", name, timestamp)
+    } else {
+        format!("{}-{}.{}", name, timestamp, extension)
+    };
+    Ok(db.data_dir().join("exports").join(file_name))
+}
+
+pub fn create_parent(path: &Path) -> Result<(), String> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            std::fs::create_dir_all(dir).map_err(|e| format!("無法創建導出目錄：{}", e))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn collect(db: &Database) -> Result<LearningDataExport, String> {
+    let exported_at: String = db
+        .conn()
+        .query_row("SELECT datetime('now')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT uid, topic, scores, feedback, created_at FROM practice_records ORDER BY created_at, id")
+        .map_err(|e| e.to_string())?;
+    let practice_records = stmt
+        .query_map([], |row| {
+            let scores: String = row.get(2)?;
+            Ok(PracticeRecordExport {
+                uid: row.get(0)?,
+                topic: row.get(1)?,
+                scores: serde_json::from_str(&scores).unwrap_or_default(),
+                feedback: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("查詢練習記錄失敗：{}", e))?;
+
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT reference_text, duration_secs, overall_score, created_at FROM recordings ORDER BY created_at, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let score_history = stmt
+        .query_map([], |row| {
+            Ok(ScoreHistoryExport {
+                reference_text: row.get(0)?,
+                duration_secs: row.get(1)?,
+                overall_score: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("查詢錄音記錄失敗：{}", e))?;
+
+    let mut vocabulary = vocabulary::list_entries(db, &VocabularyFilter::default())?;
+    vocabulary.sort_by_key(|entry| entry.id);
+
+    Ok(LearningDataExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        exported_at,
+        practice_records,
+        vocabulary,
+        score_history,
+    })
+}
+
+pub fn export_json(db: &Database, path: &Path) -> Result<(), String> {
+    let data = collect(db)?;
+    let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+    create_parent(path)?;
+    std::fs::write(path, json).map_err(|e| format!("寫入導出文件失敗：{}", e))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// 帶 BOM 的 UTF-8，Excel 打開時中文不會亂碼
+fn write_csv(path: &Path, header: &[String], rows: &[Vec<String>]) -> Result<(), String> {
+    let mut content = String::from("\u{feff}");
+    for row in std::iter::once(header).chain(rows.iter().map(Vec::as_slice)) {
+        let line: Vec<String> = row.iter().map(|value| csv_field(value)).collect();
+        content.push_str(&line.join(","));
+        content.push_str("\r\n");
+    }
+    std::fs::write(path, content).map_err(|e| format!("寫入導出文件失敗：{}", e))
+}
+
+// 導出為目錄下的三個 CSV 文件，分數按項目展開成列
+pub fn export_csv(db: &Database, dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let data = collect(db)?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("無法創建導出目錄：{}", e))?;
+
+    let mut score_keys: Vec<&String> = data.practice_records.iter().flat_map(|r| r.scores.keys()).collect();
+    score_keys.sort();
+    score_keys.dedup();
+    let mut header: Vec<String> = ["uid", "created_at", "topic"].iter().map(|s| s.to_string()).collect();
+    header.extend(score_keys.iter().map(|key| key.to_string()));
+    header.push("feedback".to_string());
+    let rows: Vec<Vec<String>> = data
+        .practice_records
+        .iter()
+        .map(|record| {
+            let mut row = vec![record.uid.clone(), record.created_at.clone(), record.topic.clone()];
+            row.extend(score_keys.iter().map(|key| record.scores.get(*key).map(|s| s.to_string()).unwrap_or_default()));
+            row.push(record.feedback.clone());
+            row
+        })
+        .collect();
+    let practice_path = dir.join("practice_records.csv");
+    write_csv(&practice_path, &header, &rows)?;
+
+    let header: Vec<String> = [
+        "word",
+        "part_of_speech",
+        "ipa",
+        "definition",
+        "translation",
+        "example",
+        "level",
+        "tags",
+        "repetitions",
+        "interval_days",
+        "due_at",
+        "last_reviewed_at",
+        "created_at",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let rows: Vec<Vec<String>> = data
+        .vocabulary
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.word.clone(),
+                entry.part_of_speech.clone(),
+                entry.ipa.clone(),
+                entry.definition.clone(),
+                entry.translation.clone(),
+                entry.example.clone(),
+                entry.level.clone(),
+                entry.tags.join(";"),
+                entry.repetitions.to_string(),
+                entry.interval_days.to_string(),
+                entry.due_at.clone(),
+                entry.last_reviewed_at.clone().unwrap_or_default(),
+                entry.created_at.clone(),
+            ]
+        })
+        .collect();
+    let vocabulary_path = dir.join("vocabulary.csv");
+    write_csv(&vocabulary_path, &header, &rows)?;
+
+    let header: Vec<String> =
+        ["created_at", "reference_text", "duration_secs", "overall_score"].iter().map(|s| s.to_string()).collect();
+    let rows: Vec<Vec<String>> = data
+        .score_history
+        .iter()
+        .map(|item| {
+            vec![
+                item.created_at.clone(),
+                item.reference_text.clone(),
+                format!("{:.2}", item.duration_secs),
+                item.overall_score.map(|s| s.to_string()).unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let history_path = dir.join("score_history.csv");
+    write_csv(&history_path, &header, &rows)?;
+
+    Ok(vec![practice_path, vocabulary_path, history_path])
+}
+
+// 導入 JSON 導出文件：練習記錄按 uid 去重，生詞按詞合併，整個導入在一個事務中完成
+pub fn import_json(db: &Database, path: &Path) -> Result<ImportSummary, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("讀取導入文件失敗：{}", e))?;
+    let data: LearningDataExport = serde_json::from_str(&content).map_err(|e| format!("導入文件格式錯誤：{}", e))?;
+    if data.format != EXPORT_FORMAT {
+        return Err("不是本應用導出的文件".to_string());
+    }
+    if data.version > EXPORT_VERSION {
+        return Err(format!("導入文件版本 {} 過新，請先更新應用", data.version));
+    }
+
+    let tx = db.conn().unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut summary = ImportSummary::default();
+    for record in &data.practice_records {
+        let scores = serde_json::to_string(&record.scores).map_err(|e| e.to_string())?;
+        let inserted = db
+            .conn()
+            .execute(
+                "INSERT INTO practice_records (uid, topic, scores, feedback, created_at) VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (uid) DO NOTHING",
+                params![record.uid, record.topic, scores, record.feedback, record.created_at],
+            )
+            .map_err(|e| format!("導入練習記錄失敗：{}", e))?;
+        if inserted > 0 {
+            summary.practice_records_added += 1;
+        } else {
+            summary.practice_records_skipped += 1;
+        }
+    }
+    for entry in &data.vocabulary {
+        match vocabulary::merge_entry(db, entry)? {
+            MergeOutcome::Added => summary.vocabulary_added += 1,
+            MergeOutcome::Merged => summary.vocabulary_merged += 1,
+            MergeOutcome::Unchanged => summary.vocabulary_skipped += 1,
+        }
+    }
+    tx.commit().map_err(|e| format!("導入失敗：{}", e))?;
+    Ok(summary)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// 由名稱推出固定的 id，重複導出同名牌組時 Anki 會更新而不是新建
+fn anki_id(name: &str) -> i64 {
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x100_0000_01b3));
+    (1 << 30) + (hash % (1 << 30)) as i64
+}
+
+// 帶上詞條 id，避免 "naïve" 和 "na_ve" 這類只差非字母字符的詞共用同一個文件名
+fn media_file_name(id: i64, word: &str) -> String {
+    let safe: String =
+        word.trim().chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+    format!("webchat_{}_{}.wav", id, safe)
+}
+
+const ANKI_SCHEMA: &str = r#"
+    CREATE TABLE col (
+        id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+        ver integer not null, dty integer not null, usn integer not null, ls integer not null,
+        conf text not null, models text not null, decks text not null, dconf text not null, tags text not null
+    );
+    CREATE TABLE notes (
+        id integer primary key, guid text not null, mid integer not null, mod integer not null,
+        usn integer not null, tags text not null, flds text not null, sfld integer not null,
+        csum integer not null, flags integer not null, data text not null
+    );
+    CREATE TABLE cards (
+        id integer primary key, nid integer not null, did integer not null, ord integer not null,
+        mod integer not null, usn integer not null, type integer not null, queue integer not null,
+        due integer not null, ivl integer not null, factor integer not null, reps integer not null,
+        lapses integer not null, left integer not null, odue integer not null, odid integer not null,
+        flags integer not null, data text not null
+    );
+    CREATE TABLE revlog (
+        id integer primary key, cid integer not null, usn integer not null, ease integer not null,
+        ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null,
+        type integer not null
+    );
+    CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+    CREATE INDEX ix_notes_usn on notes (usn);
+    CREATE INDEX ix_cards_usn on cards (usn);
+    CREATE INDEX ix_revlog_usn on revlog (usn);
+    CREATE INDEX ix_cards_nid on cards (nid);
+    CREATE INDEX ix_cards_sched on cards (did, queue, due);
+    CREATE INDEX ix_revlog_cid on revlog (cid);
+    CREATE INDEX ix_notes_csum on notes (csum);
+"#;
+
+const ANKI_CSS: &str = ".card { font-family: arial; font-size: 22px; text-align: center; color: black; background-color: white; }
+.word { font-size: 32px; font-weight: bold; }
+.ipa, .pos { color: #666; }
+.translation { margin-top: 8px; }
+.example { margin-top: 12px; font-style: italic; }";
+
+fn write_anki_collection(
+    path: &Path,
+    deck_name: &str,
+    entries: &[VocabularyEntry],
+    media: &HashMap<i64, String>,
+) -> Result<(), String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| e.to_string())?;
+    let (now_secs, now_ms) = (now.as_secs() as i64, now.as_millis() as i64);
+    let deck_id = anki_id(deck_name);
+    let model_id = anki_id("web-chat vocabulary");
+
+    let conf = serde_json::json!({
+        "activeDecks": [1], "curDeck": deck_id, "newSpread": 0, "collapseTime": 1200, "timeLim": 0,
+        "estTimes": true, "dueCounts": true, "curModel": model_id.to_string(), "nextPos": entries.len() + 1,
+        "sortType": "noteFld", "sortBackwards": false, "addToCur": true
+    });
+    let models = serde_json::json!({
+        model_id.to_string(): {
+            "id": model_id, "name": "Web Chat Vocabulary", "type": 0, "mod": now_secs, "usn": -1,
+            "sortf": 0, "did": deck_id, "tags": [], "vers": [], "css": ANKI_CSS,
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "flds": ANKI_FIELDS.iter().enumerate().map(|(ord, name)| serde_json::json!({
+                "name": name, "ord": ord, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": []
+            })).collect::<Vec<_>>(),
+            "tmpls": [{
+                "name": "Card 1", "ord": 0, "did": null, "bqfmt": "", "bafmt": "",
+                "qfmt": "<div class=\"word\">{{Word}}</div><div class=\"ipa\">{{IPA}}</div>{{Audio}}",
+                "afmt": "{{FrontSide}}<hr id=answer><div class=\"pos\">{{PartOfSpeech}}</div><div>{{Definition}}</div><div class=\"translation\">{{Translation}}</div><div class=\"example\">{{Example}}</div>"
+            }],
+            "req": [[0, "any", [0]]]
+        }
+    });
+    let deck = |id: i64, name: &str| {
+        serde_json::json!({
+            "id": id, "name": name, "desc": "", "mod": now_secs, "usn": -1, "collapsed": false,
+            "browserCollapsed": false, "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0],
+            "timeToday": [0, 0], "dyn": 0, "conf": 1, "extendNew": 10, "extendRev": 50
+        })
+    };
+    let decks = serde_json::json!({ "1": deck(1, "Default"), deck_id.to_string(): deck(deck_id, deck_name) });
+    let dconf = serde_json::json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true, "timer": 0,
+            "replayq": true, "dyn": false,
+            "new": { "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "separate": true, "order": 1,
+                     "perDay": 20, "bury": false },
+            "lapse": { "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0 },
+            "rev": { "perDay": 200, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1, "ivlFct": 1, "maxIvl": 36500,
+                     "bury": false, "hardFactor": 1.2 }
+        }
+    });
+
+    let conn = Connection::open(path).map_err(|e| format!("創建 Anki 數據庫失敗：{}", e))?;
+    let write = || -> rusqlite::Result<()> {
+        conn.execute_batch(ANKI_SCHEMA)?;
+        conn.execute(
+            "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+            params![now_secs, now_ms, conf.to_string(), models.to_string(), decks.to_string(), dconf.to_string()],
+        )?;
+        for (index, entry) in entries.iter().enumerate() {
+            let audio = media.get(&entry.id).map(|name| format!("[sound:{}]", name)).unwrap_or_default();
+            let fields = [
+                html_escape(&entry.word),
+                html_escape(&entry.ipa),
+                html_escape(&entry.part_of_speech),
+                html_escape(&entry.definition),
+                html_escape(&entry.translation),
+                html_escape(&entry.example),
+                audio,
+            ];
+            let flds = fields.join(&FIELD_SEPARATOR.to_string());
+            // 校驗和取排序字段 SHA-1 的前 4 個字節
+            let digest = sha1_smol::Sha1::from(&fields[0]).digest().bytes();
+            let checksum = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+            let tags: Vec<String> = entry.tags.iter().map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("_")).collect();
+            let tags = if tags.is_empty() { String::new() } else { format!(" {} ", tags.join(" ")) };
+            let note_id = now_ms + index as i64;
+            conn.execute(
+                "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+                params![note_id, stable_id("vocab", &entry.word), model_id, now_secs, tags, flds, fields[0], checksum],
+            )?;
+            conn.execute(
+                "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+                params![note_id, note_id, deck_id, now_secs, index as i64 + 1],
+            )?;
+        }
+        Ok(())
+    };
+    write().map_err(|e| format!("寫入 Anki 數據庫失敗：{}", e))
+}
+
+// 把生詞本導出為 Anki 牌組（.apkg 是包含 collection.anki2、media 映射和音頻文件的 zip）。
+// audio 為詞條 id 到 WAV 數據的映射，沒有音頻的詞條卡片上不帶發音
+pub fn export_anki(
+    db: &Database,
+    path: &Path,
+    deck_name: &str,
+    audio: &HashMap<i64, Vec<u8>>,
+) -> Result<AnkiExportResult, String> {
+    let mut entries = vocabulary::list_entries(db, &VocabularyFilter::default())?;
+    if entries.is_empty() {
+        return Err("生詞本為空，沒有可導出的詞".to_string());
+    }
+    entries.sort_by_key(|entry| entry.id);
+    create_parent(path)?;
+
+    let media: HashMap<i64, String> =
+        entries.iter().filter(|e| audio.contains_key(&e.id)).map(|e| (e.id, media_file_name(e.id, &e.word))).collect();
+    let collection_path = path.with_extension("anki2.tmp");
+    let _ = std::fs::remove_file(&collection_path);
+    let collection = write_anki_collection(&collection_path, deck_name, &entries, &media)
+        .and_then(|_| std::fs::read(&collection_path).map_err(|e| format!("讀取 Anki 數據庫失敗：{}", e)));
+    let _ = std::fs::remove_file(&collection_path);
+    let collection = collection?;
+
+    let file = std::fs::File::create(path).map_err(|e| format!("創建導出文件失敗：{}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let zip_error = |e: zip::result::ZipError| format!("寫入 Anki 牌組失敗：{}", e);
+    let io_error = |e: std::io::Error| format!("寫入 Anki 牌組失敗：{}", e);
+
+    zip.start_file("collection.anki2", options).map_err(zip_error)?;
+    zip.write_all(&collection).map_err(io_error)?;
+    // media 文件中記錄 zip 內編號到文件名的映射
+    let mut media_map = serde_json::Map::new();
+    for (index, entry) in entries.iter().filter(|e| media.contains_key(&e.id)).enumerate() {
+        let name = index.to_string();
+        zip.start_file(name.as_str(), options).map_err(zip_error)?;
+        zip.write_all(&audio[&entry.id]).map_err(io_error)?;
+        media_map.insert(name, serde_json::Value::String(media[&entry.id].clone()));
+    }
+    zip.start_file("media", options).map_err(zip_error)?;
+    zip.write_all(serde_json::Value::Object(media_map).to_string().as_bytes()).map_err(io_error)?;
+    zip.finish().map_err(zip_error)?;
+
+    Ok(AnkiExportResult { path: path.display().to_string(), note_count: entries.len(), audio_count: media.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gemini_service::VocabularyCandidate;
+
+    fn open_db() -> Database {
+        Database::open(&std::env::temp_dir().join(format!("web-chat-export-{:016x}", rand::random::<u64>()))).unwrap()
+    }
+
+    fn add_word(db: &Database, word: &str, translation: &str) -> VocabularyEntry {
+        let candidate = VocabularyCandidate {
+            word: word.to_string(),
+            part_of_speech: "adjective".to_string(),
+            ipa: String::new(),
+            definition: format!("meaning of {}", word),
+            translation: translation.to_string(),
+            example: String::new(),
+        };
+        vocabulary::add_candidates(db, &[candidate], "", "B1").unwrap().remove(0)
+    }
+
+    fn read_anki(path: &Path) -> (Vec<String>, serde_json::Value) {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        let collection = path.with_extension("anki2.test");
+        std::io::copy(&mut archive.by_name("collection.anki2").unwrap(), &mut std::fs::File::create(&collection).unwrap())
+            .unwrap();
+        let media: serde_json::Value = serde_json::from_reader(archive.by_name("media").unwrap()).unwrap();
+        let conn = Connection::open(&collection).unwrap();
+        let mut stmt = conn.prepare("SELECT guid FROM notes ORDER BY id").unwrap();
+        let guids = stmt.query_map([], |row| row.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap();
+        (guids, media)
+    }
+
+    #[test]
+    fn json_import_skips_records_and_words_already_present() {
+        let source = open_db();
+        source.insert_practice_record("Travel", &HashMap::from([("overall".to_string(), 82.0)]), "good", None).unwrap();
+        add_word(&source, "naïve", "天真的");
+        add_word(&source, "brisk", "輕快的");
+        let path = source.data_dir().join("export.json");
+        export_json(&source, &path).unwrap();
+
+        let target = open_db();
+        add_word(&target, "Naïve", "");
+        let first = import_json(&target, &path).unwrap();
+        assert_eq!((first.practice_records_added, first.practice_records_skipped), (1, 0));
+        assert_eq!((first.vocabulary_added, first.vocabulary_merged, first.vocabulary_skipped), (1, 1, 0));
+        // 已有的詞只補全空白字段
+        let merged = vocabulary::entry_by_key(&target, &vocabulary::word_key("naïve")).unwrap().unwrap();
+        assert_eq!((merged.word.as_str(), merged.translation.as_str()), ("Naïve", "天真的"));
+
+        // 重複導入同一文件不產生重複數據
+        let second = import_json(&target, &path).unwrap();
+        assert_eq!((second.practice_records_added, second.practice_records_skipped), (0, 1));
+        assert_eq!((second.vocabulary_added, second.vocabulary_merged, second.vocabulary_skipped), (0, 0, 2));
+        assert_eq!(collect(&target).unwrap().practice_records.len(), 1);
+        assert_eq!(collect(&target).unwrap().vocabulary.len(), 2);
+    }
+
+    #[test]
+    fn json_import_rejects_foreign_and_newer_files() {
+        let db = open_db();
+        let path = db.data_dir().join("other.json");
+        let mut data = collect(&db).unwrap();
+        data.version = EXPORT_VERSION + 1;
+        std::fs::write(&path, serde_json::to_string(&data).unwrap()).unwrap();
+        assert!(import_json(&db, &path).unwrap_err().contains("過新"));
+        data.format = "something-else".to_string();
+        std::fs::write(&path, serde_json::to_string(&data).unwrap()).unwrap();
+        assert!(import_json(&db, &path).is_err());
+    }
+
+    #[test]
+    fn anki_notes_keep_stable_guids_and_unique_media_names() {
+        let db = open_db();
+        let first = add_word(&db, "naïve", "天真的");
+        let second = add_word(&db, "na ve", "");
+        let audio = HashMap::from([(first.id, b"RIFF1".to_vec()), (second.id, b"RIFF2".to_vec())]);
+
+        // Anki 按 guid 識別筆記，再次導出同一詞條時更新而不是重複添加
+        let path = db.data_dir().join("deck.apkg");
+        let result = export_anki(&db, &path, "Vocabulary", &audio).unwrap();
+        assert_eq!((result.note_count, result.audio_count), (2, 2));
+        let (guids, media) = read_anki(&path);
+        let again = db.data_dir().join("deck-again.apkg");
+        export_anki(&db, &again, "Vocabulary", &audio).unwrap();
+        assert_eq!(read_anki(&again).0, guids);
+        assert_eq!(guids, vec![stable_id("vocab", "naïve"), stable_id("vocab", "na ve")]);
+
+        let names: Vec<&str> = media.as_object().unwrap().values().filter_map(|v| v.as_str()).collect();
+        assert_eq!(names.len(), 2);
+        assert_ne!(names[0], names[1]);
+    }
+}
@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+
+use crate::pronunciation;
+use crate::text::{is_filled_pause, is_function_word, TimedWord};
+
+// 詞間靜音超過此值記為停頓（秒）
+const SILENT_PAUSE_SECS: f64 = 0.25;
+const LONG_PAUSE_SECS: f64 = 1.0;
+// 口語中常見的自我修正標記
+const REPAIR_MARKERS: &[&[&str]] = &[&["i", "mean"], &["sorry"], &["no", "wait"], &["i", "meant"]];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FluencyMetrics {
+    pub total_duration_secs: f64,
+    pub phonation_time_secs: f64,
+    pub word_count: usize,
+    pub syllable_count: usize,
+    pub speech_rate_wpm: f64,
+    pub articulation_rate_sps: f64, // 每秒音節數，不含停頓
+    pub silent_pause_count: usize,
+    pub long_pause_count: usize,
+    pub mean_pause_secs: f64,
+    pub longest_pause_secs: f64,
+    pub mean_length_of_run: f64, // 兩次停頓之間的平均音節數
+    pub filled_pause_count: usize,
+    pub repetition_count: usize,
+    pub self_correction_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FluencyAssessment {
+    pub score: f64,
+    pub rate_score: f64,
+    pub pause_score: f64,
+    pub disfluency_score: f64,
+    pub metrics: FluencyMetrics,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TokenKind {
+    Word,
+    Filled,
+    Partial, // 說到一半中斷的詞
+}
+
+struct Token {
+    text: String,
+    kind: TokenKind,
+    pause_before: f64,
+}
+
+// 根據 ASR 詞級時間戳計算流利度指標與分數
+pub fn assess_fluency(words: &[TimedWord]) -> Option<FluencyAssessment> {
+    let mut timed: Vec<&TimedWord> = words.iter().filter(|w| !w.word.trim().is_empty()).collect();
+    timed.sort_by(|a, b| a.start.total_cmp(&b.start));
+    if timed.is_empty() {
+        return None;
+    }
+
+    let mut tokens = Vec::with_capacity(timed.len());
+    let mut pauses = Vec::new();
+    let mut previous_end = timed[0].start;
+    for word in &timed {
+        let gap = (word.start - previous_end).max(0.0);
+        if gap >= SILENT_PAUSE_SECS {
+            pauses.push(gap);
+        }
+        previous_end = previous_end.max(word.end);
+
+        let raw = word.word.trim().to_lowercase();
+        let kind = if raw.ends_with('-') {
+            TokenKind::Partial
+        } else if is_filled_pause(&normalize(&raw)) {
+            TokenKind::Filled
+        } else {
+            TokenKind::Word
+        };
+        tokens.push(Token { text: normalize(&raw), kind, pause_before: gap });
+    }
+
+    let total_duration = (previous_end - timed[0].start).max(0.01);
+    let pause_time: f64 = pauses.iter().sum();
+    let phonation_time = (total_duration - pause_time).max(0.01);
+
+    let lexical: Vec<&Token> = tokens.iter().filter(|t| t.kind == TokenKind::Word).collect();
+    let word_count = lexical.len();
+    let syllable_count: usize = lexical.iter().map(|t| pronunciation::syllable_count(&t.text)).sum();
+    let filled_pause_count = tokens.iter().filter(|t| t.kind == TokenKind::Filled).count();
+    let (repetition_count, self_correction_count) = count_repairs(&tokens);
+
+    // 以停頓或填充詞分隔的語流段
+    let mut runs = Vec::new();
+    let mut current = 0;
+    for token in &tokens {
+        if (token.pause_before >= SILENT_PAUSE_SECS || token.kind == TokenKind::Filled) && current > 0 {
+            runs.push(current);
+            current = 0;
+        }
+        if token.kind == TokenKind::Word {
+            current += pronunciation::syllable_count(&token.text);
+        }
+    }
+    if current > 0 {
+        runs.push(current);
+    }
+
+    let metrics = FluencyMetrics {
+        total_duration_secs: round2(total_duration),
+        phonation_time_secs: round2(phonation_time),
+        word_count,
+        syllable_count,
+        speech_rate_wpm: round2(word_count as f64 / total_duration * 60.0),
+        articulation_rate_sps: round2(syllable_count as f64 / phonation_time),
+        silent_pause_count: pauses.len(),
+        long_pause_count: pauses.iter().filter(|&&p| p >= LONG_PAUSE_SECS).count(),
+        mean_pause_secs: round2(if pauses.is_empty() { 0.0 } else { pause_time / pauses.len() as f64 }),
+        longest_pause_secs: round2(pauses.iter().cloned().fold(0.0, f64::max)),
+        mean_length_of_run: round2(if runs.is_empty() {
+            0.0
+        } else {
+            runs.iter().sum::<usize>() as f64 / runs.len() as f64
+        }),
+        filled_pause_count,
+        repetition_count,
+        self_correction_count,
+    };
+
+    Some(score_metrics(metrics))
+}
+
+fn score_metrics(metrics: FluencyMetrics) -> FluencyAssessment {
+    // 語速：110-170 詞/分鐘視為理想區間
+    let wpm = metrics.speech_rate_wpm;
+    let rate_score = if wpm < 110.0 {
+        100.0 * ((wpm - 50.0) / 60.0).clamp(0.0, 1.0)
+    } else if wpm > 170.0 {
+        100.0 * (1.0 - (wpm - 170.0) / 80.0).clamp(0.0, 1.0)
+    } else {
+        100.0
+    };
+
+    // 停頓：平均語流長度越長越好，每個長停頓額外扣分
+    let run_score = 20.0 + 80.0 * ((metrics.mean_length_of_run - 2.0) / 10.0).clamp(0.0, 1.0);
+    let pause_score = (run_score - 5.0 * metrics.long_pause_count as f64).max(0.0);
+
+    // 不流利現象：每百詞的填充詞、重複與自我修正次數
+    let disfluencies =
+        (metrics.filled_pause_count + metrics.repetition_count + metrics.self_correction_count) as f64;
+    let per_hundred = disfluencies / metrics.word_count.max(1) as f64 * 100.0;
+    let disfluency_score = (100.0 - 5.0 * per_hundred).max(0.0);
+
+    let score = 0.3 * rate_score + 0.4 * pause_score + 0.3 * disfluency_score;
+
+    FluencyAssessment {
+        score: round1(score),
+        rate_score: round1(rate_score),
+        pause_score: round1(pause_score),
+        disfluency_score: round1(disfluency_score),
+        metrics,
+    }
+}
+
+// 統計重複（"I I want"、"I want I want"）與自我修正（半截詞、修正標記、停頓後改口重說）
+fn count_repairs(tokens: &[Token]) -> (usize, usize) {
+    let words: Vec<&Token> = tokens.iter().filter(|t| t.kind != TokenKind::Filled).collect();
+    let mut repetitions = 0;
+    let mut corrections = words.iter().filter(|t| t.kind == TokenKind::Partial).count();
+
+    let mut i = 0;
+    while i < words.len() {
+        let text_at = |k: usize| words.get(k).map(|t| t.text.as_str());
+
+        if REPAIR_MARKERS.iter().any(|marker| {
+            marker.iter().enumerate().all(|(offset, m)| text_at(i + offset) == Some(m))
+        }) {
+            corrections += 1;
+            i += 1;
+            continue;
+        }
+
+        let mut handled = false;
+        for length in (1..=3).rev() {
+            if i + 2 * length > words.len() {
+                continue;
+            }
+            let first: Vec<&str> = (i..i + length).map(|k| words[k].text.as_str()).collect();
+            let second: Vec<&str> = (i + length..i + 2 * length).map(|k| words[k].text.as_str()).collect();
+            if first == second {
+                repetitions += 1;
+                i += length;
+                handled = true;
+                break;
+            }
+        }
+        if handled {
+            continue;
+        }
+
+        // 停頓或填充詞之後從同一個詞重新開始，但後續內容不同
+        for distance in 2..=3 {
+            let restart = i + distance;
+            let Some(next) = words.get(restart) else { break };
+            let hesitated = preceded_by_hesitation(tokens, words[restart]);
+            if next.text == words[i].text && hesitated && text_at(i + 1) != text_at(restart + 1) {
+                corrections += 1;
+                break;
+            }
+        }
+
+        // 停頓後改用同一個詞的另一種詞形（"go... goes"、"walk, um, walked"）
+        if let Some(next) = words.get(i + 1) {
+            let current = &words[i].text;
+            if !is_function_word(current)
+                && is_inflection(current, &next.text)
+                && preceded_by_hesitation(tokens, next)
+            {
+                corrections += 1;
+            }
+        }
+
+        i += 1;
+    }
+
+    (repetitions, corrections)
+}
+
+// 判斷某個詞之前是否緊接著停頓或填充詞
+fn preceded_by_hesitation(tokens: &[Token], target: &Token) -> bool {
+    let Some(position) = tokens.iter().position(|t| std::ptr::eq(t, target)) else {
+        return false;
+    };
+    target.pause_before >= SILENT_PAUSE_SECS
+        || position
+            .checked_sub(1)
+            .is_some_and(|p| tokens[p].kind == TokenKind::Filled)
+}
+
+// 一個詞是另一個詞加上 -s/-es/-ed/-ing 構成的屈折形式
+fn is_inflection(a: &str, b: &str) -> bool {
+    let inflected = |base: &str, form: &str| {
+        form.strip_prefix(base)
+            .is_some_and(|suffix| ["s", "es", "ed", "ing"].contains(&suffix))
+    };
+    a.len() >= 2 && b.len() >= 2 && (inflected(a, b) || inflected(b, a))
+}
+
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'' && c != '-')
+        .trim_end_matches('-')
+        .to_string()
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每個詞 0.4 秒，數字為該詞之前的停頓（秒）
+    fn speak(words: &[(&str, f64)]) -> Vec<TimedWord> {
+        let mut time = 0.0;
+        words
+            .iter()
+            .map(|&(word, pause)| {
+                time += pause;
+                let start = time;
+                time += 0.4;
+                TimedWord { word: word.to_string(), start, end: time }
+            })
+            .collect()
+    }
+
+    fn fluent(text: &str) -> Vec<TimedWord> {
+        speak(&text.split(' ').map(|word| (word, 0.0)).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn fluent_speech_scores_full_marks() {
+        let assessment = assess_fluency(&fluent("we went to the market to buy fresh bread")).unwrap();
+        assert_eq!(assessment.metrics.word_count, 9);
+        assert_eq!(assessment.metrics.speech_rate_wpm, 150.0);
+        assert_eq!(assessment.metrics.silent_pause_count, 0);
+        assert_eq!(assessment.rate_score, 100.0);
+        assert_eq!(assessment.disfluency_score, 100.0);
+        assert!(assess_fluency(&[]).is_none());
+    }
+
+    #[test]
+    fn pauses_and_fillers_are_counted() {
+        let words = speak(&[("I", 0.0), ("um", 0.0), ("think", 0.3), ("that", 1.2), ("uh", 0.0), ("works", 0.0)]);
+        let metrics = assess_fluency(&words).unwrap().metrics;
+        assert_eq!(metrics.filled_pause_count, 2);
+        assert_eq!(metrics.word_count, 4);
+        assert_eq!(metrics.silent_pause_count, 2);
+        assert_eq!(metrics.long_pause_count, 1);
+        assert_eq!(metrics.longest_pause_secs, 1.2);
+    }
+
+    #[test]
+    fn repetitions_are_detected() {
+        let metrics = assess_fluency(&fluent("I I want to go")).unwrap().metrics;
+        assert_eq!((metrics.repetition_count, metrics.self_correction_count), (1, 0));
+        let metrics = assess_fluency(&fluent("I want I want to go home")).unwrap().metrics;
+        assert_eq!((metrics.repetition_count, metrics.self_correction_count), (1, 0));
+    }
+
+    #[test]
+    fn self_corrections_are_detected() {
+        let metrics = assess_fluency(&fluent("she wa- wants to leave")).unwrap().metrics;
+        assert_eq!(metrics.self_correction_count, 1);
+        let metrics = assess_fluency(&fluent("turn left I mean right")).unwrap().metrics;
+        assert_eq!(metrics.self_correction_count, 1);
+        let words = speak(&[("he", 0.0), ("go", 0.0), ("um", 0.0), ("goes", 0.0), ("home", 0.0)]);
+        assert_eq!(assess_fluency(&words).unwrap().metrics.self_correction_count, 1);
+        let words = speak(&[("we", 0.0), ("walk", 0.0), ("walked", 0.5), ("home", 0.0)]);
+        assert_eq!(assess_fluency(&words).unwrap().metrics.self_correction_count, 1);
+    }
+
+    #[test]
+    fn shared_prefixes_are_not_corrections() {
+        for text in ["we continue contributing", "the president presented it", "an international interest", "walk walked"] {
+            let metrics = assess_fluency(&fluent(text)).unwrap().metrics;
+            assert_eq!(metrics.self_correction_count, 0, "{}", text);
+        }
+    }
+}
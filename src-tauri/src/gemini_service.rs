@@ -1,14 +1,106 @@
 use serde::{Deserialize, Serialize};
-use reqwest;
 use std::collections::HashMap;
 
+use crate::logging;
+use crate::pronunciation;
+use crate::text::TimedWord;
+
+// Gemini TTS 的預置音色
+const TTS_VOICES: &[&str] = &["Puck", "Charon", "Kore", "Fenrir", "Aoede"];
+pub const DEFAULT_TTS_VOICE: &str = "Kore";
+const PROBE_TIMEOUT_SECS: u64 = 10;
+
+// 無法獲取模型列表時使用的默認模型
+pub const DEFAULT_TEXT_MODEL: &str = "gemini-2.5-flash";
+pub const DEFAULT_TTS_MODEL: &str = "gemini-2.5-flash-preview-tts";
+pub const DEFAULT_AUDIO_MODEL: &str = "gemini-2.5-flash";
+// 模型列表分頁拉取，防止異常的分頁令牌導致死循環
+const MAX_MODEL_PAGES: usize = 10;
+
+// 錯誤需要能跨 await 傳遞，Tauri 的異步命令要求 Future 是 Send
+pub type ServiceResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeminiConfig {
     pub api_key: String,
     pub model: String,
+    pub tts_model: String,
+    pub audio_model: String, // 語音識別和口語回答評估等需要聽音頻的請求
     pub base_url: String,
 }
 
+// 文本、語音合成和音頻理解分別使用的模型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSelection {
+    pub text: String,
+    pub tts: String,
+    pub audio: String,
+}
+
+impl Default for ModelSelection {
+    fn default() -> Self {
+        Self {
+            text: DEFAULT_TEXT_MODEL.to_string(),
+            tts: DEFAULT_TTS_MODEL.to_string(),
+            audio: DEFAULT_AUDIO_MODEL.to_string(),
+        }
+    }
+}
+
+// models 列表接口返回的模型信息
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiModel {
+    name: String, // 帶 models/ 前綴
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    input_token_limit: u64,
+    #[serde(default)]
+    output_token_limit: u64,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListModelsResponse {
+    #[serde(default)]
+    models: Vec<ApiModel>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub name: String, // 不帶前綴，如 gemini-2.5-flash
+    pub display_name: String,
+    pub description: String,
+    pub input_token_limit: u64,
+    pub output_token_limit: u64,
+    pub supported_methods: Vec<String>,
+}
+
+impl From<ApiModel> for ModelInfo {
+    fn from(model: ApiModel) -> Self {
+        Self {
+            name: model.name.strip_prefix("models/").unwrap_or(&model.name).to_string(),
+            display_name: model.display_name,
+            description: model.description,
+            input_token_limit: model.input_token_limit,
+            output_token_limit: model.output_token_limit,
+            supported_methods: model.supported_generation_methods,
+        }
+    }
+}
+
+impl ModelInfo {
+    pub fn supports(&self, method: &str) -> bool {
+        self.supported_methods.iter().any(|m| m == method)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeminiRequest {
     pub contents: Vec<Content>,
@@ -26,7 +118,17 @@ pub struct Content {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Part {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub text: String,
+    #[serde(rename = "inlineData", default, skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<InlineData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InlineData {
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    pub data: String, // base64
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,6 +140,32 @@ pub struct GenerationConfig {
     pub top_p: f32,
     #[serde(rename = "maxOutputTokens")]
     pub max_output_tokens: i32,
+    #[serde(rename = "responseMimeType", skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    #[serde(rename = "responseSchema", skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
+    #[serde(rename = "responseModalities", skip_serializing_if = "Option::is_none")]
+    pub response_modalities: Option<Vec<String>>,
+    #[serde(rename = "speechConfig", skip_serializing_if = "Option::is_none")]
+    pub speech_config: Option<SpeechConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpeechConfig {
+    #[serde(rename = "voiceConfig")]
+    pub voice_config: VoiceConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoiceConfig {
+    #[serde(rename = "prebuiltVoiceConfig")]
+    pub prebuilt_voice_config: PrebuiltVoiceConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrebuiltVoiceConfig {
+    #[serde(rename = "voiceName")]
+    pub voice_name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,8 +186,10 @@ pub struct Candidate {
     pub content: Content,
     #[serde(rename = "finishReason")]
     pub finish_reason: Option<String>,
+    // 語音合成與 JSON 模式的響應中可能不帶這兩個字段
+    #[serde(default)]
     pub index: i32,
-    #[serde(rename = "safetyRatings")]
+    #[serde(rename = "safetyRatings", default)]
     pub safety_ratings: Vec<SafetyRating>,
 }
 
@@ -89,6 +219,60 @@ pub struct TutorFeedback {
     pub difficulty_adjustment: String, // "increase", "maintain", "decrease"
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub text: String,
+    #[serde(default)]
+    pub words: Vec<TimedWord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RubricScore {
+    pub score: f64,
+    #[serde(default)]
+    pub comment: String,
+}
+
+// 自由口語回答的評分：發音基於錄音本身（沒有參考文本），其餘維度基於轉寫文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenResponseEvaluation {
+    pub pronunciation: RubricScore,
+    pub grammar: RubricScore,
+    pub vocabulary: RubricScore,
+    pub coherence: RubricScore,
+    pub relevance: RubricScore,
+    pub corrected_answer: String,
+    #[serde(default)]
+    pub overall_comment: String,
+}
+
+// 模型返回的語法問題，只包含原文片段，位置由調用方在原文中定位
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrammarIssue {
+    pub original: String,
+    pub correction: String,
+    pub category: String,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrammarReview {
+    #[serde(default)]
+    pub issues: Vec<GrammarIssue>,
+    pub corrected_text: String,
+}
+
+// 從練習文本中提取的生詞
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyCandidate {
+    pub word: String,
+    pub part_of_speech: String,
+    pub ipa: String,
+    pub definition: String,
+    pub translation: String,
+    pub example: String,
+}
+
 pub struct GeminiService {
     config: GeminiConfig,
     client: reqwest::Client,
@@ -96,9 +280,13 @@ pub struct GeminiService {
 
 impl GeminiService {
     pub fn new(api_key: String) -> Self {
+        // 請求地址帶著密鑰，出錯時可能被記進日誌
+        logging::register_secret(&api_key);
         let config = GeminiConfig {
             api_key,
-            model: "gemini-1.5-pro".to_string(),
+            model: DEFAULT_TEXT_MODEL.to_string(),
+            tts_model: DEFAULT_TTS_MODEL.to_string(),
+            audio_model: DEFAULT_AUDIO_MODEL.to_string(),
             base_url: "https://gemini.66666618.xyz/v1beta/models".to_string(),
         };
         
@@ -106,17 +294,81 @@ impl GeminiService {
         
         Self { config, client }
     }
+
+    pub fn config(&self) -> &GeminiConfig {
+        &self.config
+    }
+
+    pub fn models(&self) -> ModelSelection {
+        ModelSelection {
+            text: self.config.model.clone(),
+            tts: self.config.tts_model.clone(),
+            audio: self.config.audio_model.clone(),
+        }
+    }
+
+    pub fn set_models(&mut self, models: ModelSelection) {
+        self.config.model = models.text;
+        self.config.tts_model = models.tts;
+        self.config.audio_model = models.audio;
+    }
+
+    // 當前密鑰可以使用的全部模型
+    pub async fn list_models(&self) -> ServiceResult<Vec<ModelInfo>> {
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+        for _ in 0..MAX_MODEL_PAGES {
+            let mut url = format!("{}?key={}&pageSize=1000", self.config.base_url, self.config.api_key);
+            if let Some(token) = &page_token {
+                url.push_str(&format!("&pageToken={}", token));
+            }
+            let response = self
+                .client
+                .get(&url)
+                .timeout(std::time::Duration::from_secs(PROBE_TIMEOUT_SECS))
+                .send()
+                .await
+                .map_err(|e| e.without_url())?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(format!("HTTP {}: {}", status.as_u16(), body.chars().take(200).collect::<String>()).into());
+            }
+            let page = response.json::<ListModelsResponse>().await.map_err(|e| e.without_url())?;
+            models.extend(page.models.into_iter().map(ModelInfo::from));
+            page_token = page.next_page_token.filter(|token| !token.is_empty());
+            if page_token.is_none() {
+                break;
+            }
+        }
+        Ok(models)
+    }
+
+    // 查詢單個模型的信息，不消耗生成配額，用於檢查連接、密鑰和模型是否可用。
+    // 返回 HTTP 狀態碼和響應正文；錯誤信息中去掉了帶密鑰的請求地址
+    pub async fn probe_model(&self, model: &str) -> ServiceResult<(u16, String)> {
+        let url = format!("{}/{}?key={}", self.config.base_url, model, self.config.api_key);
+        let response = self
+            .client
+            .get(&url)
+            .timeout(std::time::Duration::from_secs(PROBE_TIMEOUT_SECS))
+            .send()
+            .await
+            .map_err(|e| e.without_url())?;
+        let status = response.status().as_u16();
+        Ok((status, response.text().await.unwrap_or_default()))
+    }
     
     pub async fn generate_tutor_feedback(
         &self,
         user_performance: &HashMap<String, serde_json::Value>,
         practice_context: &str,
-    ) -> Result<TutorFeedback, Box<dyn std::error::Error>> {
+    ) -> ServiceResult<TutorFeedback> {
         let prompt = self.create_tutor_prompt(user_performance, practice_context);
         
         let request = GeminiRequest {
             contents: vec![Content {
-                parts: vec![Part { text: prompt }],
+                parts: vec![Part { text: prompt, inline_data: None }],
                 role: Some("user".to_string()),
             }],
             generation_config: GenerationConfig {
@@ -124,6 +376,10 @@ impl GeminiService {
                 top_k: 40,
                 top_p: 0.95,
                 max_output_tokens: 1024,
+                response_mime_type: None,
+                response_schema: None,
+                response_modalities: None,
+                speech_config: None,
             },
             safety_settings: vec![
                 SafetySetting {
@@ -172,14 +428,52 @@ impl GeminiService {
             .get("pronunciation")
             .and_then(|v| v.as_f64())
             .unwrap_or(0.0);
+        // 流利度需要語音識別結果，缺失時標記為未評估
         let fluency_score = user_performance
             .get("fluency")
             .and_then(|v| v.as_f64())
-            .unwrap_or(0.0);
+            .map(|score| format!("{:.1}分", score))
+            .unwrap_or_else(|| "未評估".to_string());
         let completeness_score = user_performance
             .get("completeness")
             .and_then(|v| v.as_f64())
             .unwrap_or(0.0);
+        // 語調分數只有在錄音可分析時才會出現
+        let prosody_line = user_performance
+            .get("prosody")
+            .and_then(|v| v.as_f64())
+            .map(|score| format!("\n- 語調與重音：{:.1}分（低分通常代表朗讀平淡、缺少起伏）", score))
+            .unwrap_or_default();
+        // 重音錯誤的詞附上詞典中的標準讀音，讓導師能具體指出重讀音節
+        let stress_details = user_performance
+            .get("prosody_analysis")
+            .and_then(|analysis| analysis.get("word_stress"))
+            .and_then(|words| words.as_array())
+            .map(|words| {
+                let mut details: Vec<String> = Vec::new();
+                let missed = words.iter().filter(|w| w["matched"] == false).filter_map(|w| w["word"].as_str());
+                for p in missed.filter_map(pronunciation::lookup).filter(|p| p.syllables.len() > 1) {
+                    let line = format!("{} /{}/（重音在第 {} 音節）", p.word, p.ipa, p.primary_stress.unwrap_or(0) + 1);
+                    if details.len() < 5 && !details.contains(&line) {
+                        details.push(line);
+                    }
+                }
+                details
+            })
+            .filter(|words| !words.is_empty())
+            .map(|words| format!("\n- 重音有誤的詞（標準讀音）：{}", words.join("、")))
+            .unwrap_or_default();
+        let fluency_details = user_performance
+            .get("fluency_analysis")
+            .and_then(|analysis| analysis.get("metrics"))
+            .map(|m| {
+                format!(
+                    "\n- 流利度細節：語速 {} 詞/分鐘，停頓 {} 次（最長 {} 秒），填充詞 {} 次，重複 {} 次，自我修正 {} 次",
+                    m["speech_rate_wpm"], m["silent_pause_count"], m["longest_pause_secs"],
+                    m["filled_pause_count"], m["repetition_count"], m["self_correction_count"]
+                )
+            })
+            .unwrap_or_default();
         
         format!(
             r#"你是一位專業的英語口語私人導師，具有豐富的教學經驗和激勵學生的能力。請根據學生的練習表現提供個性化的反饋和指導。
@@ -188,8 +482,8 @@ impl GeminiService {
 - 練習內容：{}
 - 總體得分：{:.1}分
 - 發音準確度：{:.1}分
-- 流利度：{:.1}分
-- 完整度：{:.1}分
+- 流利度：{}{}
+- 完整度：{:.1}分{}{}
 
 請以JSON格式回應，包含以下字段：
 {{
@@ -207,11 +501,11 @@ impl GeminiService {
 3. 根據分數水平調整激勵策略
 4. 像Duolingo一樣提供即時、積極的反饋
 5. 使用繁體中文回應"#,
-            practice_context, overall_score, pronunciation_score, fluency_score, completeness_score
+            practice_context, overall_score, pronunciation_score, fluency_score, fluency_details, completeness_score, prosody_line, stress_details
         )
     }
     
-    fn parse_tutor_response(&self, content: &str) -> Result<TutorFeedback, Box<dyn std::error::Error>> {
+    fn parse_tutor_response(&self, content: &str) -> ServiceResult<TutorFeedback> {
         // 嘗試從回應中提取JSON
         let json_start = content.find('{');
         let json_end = content.rfind('}');
@@ -290,7 +584,7 @@ impl GeminiService {
         topic: &str,
         difficulty_level: &str,
         user_interests: &[String],
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    ) -> ServiceResult<String> {
         let prompt = format!(
             r#"作為英語口語教學專家，請為學生生成個性化的練習內容。
 
@@ -314,7 +608,7 @@ impl GeminiService {
         
         let request = GeminiRequest {
             contents: vec![Content {
-                parts: vec![Part { text: prompt }],
+                parts: vec![Part { text: prompt, inline_data: None }],
                 role: Some("user".to_string()),
             }],
             generation_config: GenerationConfig {
@@ -322,6 +616,10 @@ impl GeminiService {
                 top_k: 40,
                 top_p: 0.95,
                 max_output_tokens: 512,
+                response_mime_type: None,
+                response_schema: None,
+                response_modalities: None,
+                speech_config: None,
             },
             safety_settings: vec![],
         };
@@ -350,8 +648,8 @@ impl GeminiService {
     pub async fn generate_speech_audio(
         &self,
         text: &str,
-        voice_config: Option<&str>,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+        _voice_config: Option<&str>,
+    ) -> ServiceResult<String> {
         // 使用 Gemini 生成更自然的語音提示文本
         let enhanced_prompt = format!(
             r#"請將以下文本轉換為適合語音合成的格式，添加適當的語調標記和停頓：
@@ -370,7 +668,7 @@ impl GeminiService {
         
         let request = GeminiRequest {
             contents: vec![Content {
-                parts: vec![Part { text: enhanced_prompt }],
+                parts: vec![Part { text: enhanced_prompt, inline_data: None }],
                 role: Some("user".to_string()),
             }],
             generation_config: GenerationConfig {
@@ -378,6 +676,10 @@ impl GeminiService {
                 top_k: 20,
                 top_p: 0.8,
                 max_output_tokens: 256,
+                response_mime_type: None,
+                response_schema: None,
+                response_modalities: None,
+                speech_config: None,
             },
             safety_settings: vec![],
         };
@@ -404,4 +706,381 @@ impl GeminiService {
             Err("No response from Gemini API".into())
         }
     }
+    
+    // 逐字轉寫錄音並返回詞級時間戳（用於流利度分析）
+    pub async fn transcribe_audio(
+        &self,
+        audio_base64: &str,
+        mime_type: &str,
+    ) -> ServiceResult<Transcript> {
+        let prompt = r#"請逐字轉寫這段英語錄音，並給出每個詞的起止時間。
+
+要求：
+1. 逐字記錄實際說出的內容，不要修正語法，不要刪除重複的詞
+2. 保留語氣填充詞（um、uh、er 等），每個作為單獨的詞
+3. 說到一半中斷的詞以 "-" 結尾（例如 "wan-"）
+4. 時間以秒為單位，從錄音開頭算起，保留兩位小數
+
+只返回JSON，格式如下：
+{"text": "完整轉寫文本", "words": [{"word": "hello", "start": 0.12, "end": 0.48}]}"#;
+
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![
+                    Part { text: prompt.to_string(), inline_data: None },
+                    Part {
+                        text: String::new(),
+                        inline_data: Some(InlineData {
+                            mime_type: mime_type.to_string(),
+                            data: audio_base64.to_string(),
+                        }),
+                    },
+                ],
+                role: Some("user".to_string()),
+            }],
+            generation_config: GenerationConfig {
+                temperature: 0.0,
+                top_k: 1,
+                top_p: 1.0,
+                max_output_tokens: 4096,
+                response_mime_type: Some("application/json".to_string()),
+                response_schema: None,
+                response_modalities: None,
+                speech_config: None,
+            },
+            safety_settings: vec![],
+        };
+        
+        let url = format!(
+            "{}/{}:generateContent?key={}",
+            self.config.base_url, self.config.audio_model, self.config.api_key
+        );
+        
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await?
+            .json::<GeminiResponse>()
+            .await?;
+        
+        let content = match response.candidates.first() {
+            Some(candidate) => &candidate.content.parts[0].text,
+            None => return Err("No response from Gemini API".into()),
+        };
+        let json_start = content.find('{').ok_or("Transcript is not valid JSON")?;
+        let json_end = content.rfind('}').ok_or("Transcript is not valid JSON")?;
+        let mut transcript: Transcript = serde_json::from_str(&content[json_start..=json_end])?;
+        
+        transcript.words.retain(|w| !w.word.trim().is_empty() && w.end >= w.start);
+        transcript.words.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Ok(transcript)
+    }
+
+    // 按評分標準評估對提問的自由回答，同時返回修改後的回答
+    pub async fn evaluate_open_response(
+        &self,
+        question: &str,
+        transcript: &str,
+        audio_base64: &str,
+        mime_type: &str,
+    ) -> ServiceResult<OpenResponseEvaluation> {
+        let prompt = format!(
+            r#"作為英語口語考官，請評估學生對下面問題的口頭回答。附帶的錄音是學生的回答，轉寫文本已經逐字給出。
+
+問題：{}
+轉寫文本：{}
+
+請按以下標準為每個維度打 0-100 分，並用繁體中文給出一句簡短評語：
+1. pronunciation（發音）：只根據錄音判斷。90 以上：清晰自然，接近母語者；70-89：個別音不準但不影響理解；50-69：多處錯誤，需要仔細聽才能理解；50 以下：大部分內容難以聽懂
+2. grammar（語法）：90 以上：基本沒有錯誤且使用了複雜句型；70-89：少量不影響理解的錯誤；50-69：錯誤較多，時態、冠詞、主謂一致等問題反覆出現；50 以下：錯誤嚴重影響理解
+3. vocabulary（詞彙）：90 以上：用詞豐富準確，有地道搭配；70-89：用詞恰當但較常見；50-69：詞彙單一或有明顯誤用；50 以下：詞彙不足以表達意思
+4. coherence（連貫性）：90 以上：條理清晰，連接詞使用自然；70-89：結構基本清楚；50-69：跳躍或重複較多；50 以下：難以理解整體意思
+5. relevance（切題程度）：90 以上：完整回答了問題並有展開；70-89：回答了問題但展開不足；50-69：部分離題；50 以下：基本沒有回答問題
+
+corrected_answer 是修改後的英語回答：保留學生的原意和大致結構，只修正錯誤並讓表達更自然。
+
+只返回JSON，格式如下：
+{{"pronunciation": {{"score": 80, "comment": "..."}}, "grammar": {{"score": 70, "comment": "..."}}, "vocabulary": {{"score": 75, "comment": "..."}}, "coherence": {{"score": 80, "comment": "..."}}, "relevance": {{"score": 90, "comment": "..."}}, "corrected_answer": "...", "overall_comment": "..."}}"#,
+            question, transcript
+        );
+
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![
+                    Part { text: prompt, inline_data: None },
+                    Part {
+                        text: String::new(),
+                        inline_data: Some(InlineData {
+                            mime_type: mime_type.to_string(),
+                            data: audio_base64.to_string(),
+                        }),
+                    },
+                ],
+                role: Some("user".to_string()),
+            }],
+            generation_config: GenerationConfig {
+                temperature: 0.2,
+                top_k: 20,
+                top_p: 0.9,
+                max_output_tokens: 2048,
+                response_mime_type: Some("application/json".to_string()),
+                response_schema: None,
+                response_modalities: None,
+                speech_config: None,
+            },
+            safety_settings: vec![],
+        };
+
+        let url = format!(
+            "{}/{}:generateContent?key={}",
+            self.config.base_url, self.config.audio_model, self.config.api_key
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await?
+            .json::<GeminiResponse>()
+            .await?;
+
+        let content = match response.candidates.first() {
+            Some(candidate) => &candidate.content.parts[0].text,
+            None => return Err("No response from Gemini API".into()),
+        };
+        let json_start = content.find('{').ok_or("Evaluation is not valid JSON")?;
+        let json_end = content.rfind('}').ok_or("Evaluation is not valid JSON")?;
+        let mut evaluation: OpenResponseEvaluation = serde_json::from_str(&content[json_start..=json_end])?;
+
+        for rubric in [
+            &mut evaluation.pronunciation,
+            &mut evaluation.grammar,
+            &mut evaluation.vocabulary,
+            &mut evaluation.coherence,
+            &mut evaluation.relevance,
+        ] {
+            rubric.score = rubric.score.clamp(0.0, 100.0);
+        }
+        Ok(evaluation)
+    }
+
+    // 檢查語法錯誤，使用 responseSchema 約束輸出結構
+    pub async fn check_grammar(&self, text: &str, categories: &[&str]) -> ServiceResult<GrammarReview> {
+        let prompt = format!(
+            r#"請檢查下面這段英語學習者的文本（可能是口語轉寫）中的語法錯誤。
+
+文本：{}
+
+要求：
+1. 只標出語法錯誤（時態、冠詞、主謂一致、介詞、語序等），不要修改拼寫、標點、大小寫或口語中正常的省略
+2. original 必須是原文中一字不差的片段，盡量短，只包含出錯的詞
+3. 錯誤按在原文中出現的順序排列
+4. explanation 用繁體中文，一句話說明錯誤原因
+5. corrected_text 是修正全部錯誤後的完整文本；沒有錯誤時 issues 為空數組"#,
+            text
+        );
+
+        let schema = serde_json::json!({
+            "type": "OBJECT",
+            "properties": {
+                "issues": {
+                    "type": "ARRAY",
+                    "items": {
+                        "type": "OBJECT",
+                        "properties": {
+                            "original": {"type": "STRING"},
+                            "correction": {"type": "STRING"},
+                            "category": {"type": "STRING", "enum": categories},
+                            "explanation": {"type": "STRING"}
+                        },
+                        "required": ["original", "correction", "category", "explanation"],
+                        "propertyOrdering": ["original", "correction", "category", "explanation"]
+                    }
+                },
+                "corrected_text": {"type": "STRING"}
+            },
+            "required": ["issues", "corrected_text"],
+            "propertyOrdering": ["issues", "corrected_text"]
+        });
+
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part { text: prompt, inline_data: None }],
+                role: Some("user".to_string()),
+            }],
+            generation_config: GenerationConfig {
+                temperature: 0.0,
+                top_k: 1,
+                top_p: 1.0,
+                max_output_tokens: 2048,
+                response_mime_type: Some("application/json".to_string()),
+                response_schema: Some(schema),
+                response_modalities: None,
+                speech_config: None,
+            },
+            safety_settings: vec![],
+        };
+
+        let url = format!(
+            "{}/{}:generateContent?key={}",
+            self.config.base_url, self.config.model, self.config.api_key
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await?
+            .json::<GeminiResponse>()
+            .await?;
+
+        let content = match response.candidates.first() {
+            Some(candidate) => &candidate.content.parts[0].text,
+            None => return Err("No response from Gemini API".into()),
+        };
+        Ok(serde_json::from_str(content.trim())?)
+    }
+
+    // 按學習者水平從練習文本中挑選生詞，並給出釋義、音標和翻譯
+    pub async fn extract_vocabulary(
+        &self,
+        passage: &str,
+        difficulty_level: &str,
+        native_language: &str,
+        max_words: usize,
+    ) -> ServiceResult<Vec<VocabularyCandidate>> {
+        let prompt = format!(
+            r#"請從下面的英語練習文本中挑選對 {} 水平學習者來說可能是生詞的詞彙或短語（最多 {} 個）。
+
+文本：{}
+
+要求：
+1. 跳過該水平學習者應該已經掌握的基礎詞彙，以及人名、地名
+2. word 使用詞典形式（動詞原形、名詞單數），固定搭配可以作為一個條目
+3. part_of_speech 使用英文縮寫（n.、v.、adj.、adv.、phr. 等）
+4. ipa 為美式發音的國際音標，不帶斜線
+5. definition 為簡明的英文釋義
+6. translation 為該詞在文中意思的{}翻譯
+7. example 優先使用文本中包含該詞的原句"#,
+            difficulty_level, max_words, passage, native_language
+        );
+
+        let schema = serde_json::json!({
+            "type": "ARRAY",
+            "items": {
+                "type": "OBJECT",
+                "properties": {
+                    "word": {"type": "STRING"},
+                    "part_of_speech": {"type": "STRING"},
+                    "ipa": {"type": "STRING"},
+                    "definition": {"type": "STRING"},
+                    "translation": {"type": "STRING"},
+                    "example": {"type": "STRING"}
+                },
+                "required": ["word", "part_of_speech", "ipa", "definition", "translation", "example"],
+                "propertyOrdering": ["word", "part_of_speech", "ipa", "definition", "translation", "example"]
+            }
+        });
+
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part { text: prompt, inline_data: None }],
+                role: Some("user".to_string()),
+            }],
+            generation_config: GenerationConfig {
+                temperature: 0.2,
+                top_k: 20,
+                top_p: 0.9,
+                max_output_tokens: 2048,
+                response_mime_type: Some("application/json".to_string()),
+                response_schema: Some(schema),
+                response_modalities: None,
+                speech_config: None,
+            },
+            safety_settings: vec![],
+        };
+
+        let url = format!(
+            "{}/{}:generateContent?key={}",
+            self.config.base_url, self.config.model, self.config.api_key
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await?
+            .json::<GeminiResponse>()
+            .await?;
+
+        let content = match response.candidates.first() {
+            Some(candidate) => &candidate.content.parts[0].text,
+            None => return Err("No response from Gemini API".into()),
+        };
+        let mut candidates: Vec<VocabularyCandidate> = serde_json::from_str(content.trim())?;
+        candidates.retain(|c| !c.word.trim().is_empty());
+        candidates.truncate(max_words);
+        Ok(candidates)
+    }
+
+    // 使用 Gemini TTS 模型合成語音，返回音頻數據（通常是 24 kHz 的 16-bit PCM）
+    pub async fn synthesize_speech(
+        &self,
+        text: &str,
+        voice: Option<&str>,
+    ) -> ServiceResult<InlineData> {
+        let voice_name = voice
+            .filter(|v| TTS_VOICES.contains(v))
+            .unwrap_or(DEFAULT_TTS_VOICE);
+
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part { text: text.to_string(), inline_data: None }],
+                role: Some("user".to_string()),
+            }],
+            generation_config: GenerationConfig {
+                temperature: 1.0,
+                top_k: 40,
+                top_p: 0.95,
+                max_output_tokens: 8192,
+                response_mime_type: None,
+                response_schema: None,
+                response_modalities: Some(vec!["AUDIO".to_string()]),
+                speech_config: Some(SpeechConfig {
+                    voice_config: VoiceConfig {
+                        prebuilt_voice_config: PrebuiltVoiceConfig {
+                            voice_name: voice_name.to_string(),
+                        },
+                    },
+                }),
+            },
+            safety_settings: vec![],
+        };
+        
+        let url = format!(
+            "{}/{}:generateContent?key={}",
+            self.config.base_url, self.config.tts_model, self.config.api_key
+        );
+        
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await?
+            .json::<GeminiResponse>()
+            .await?;
+        
+        response
+            .candidates
+            .into_iter()
+            .next()
+            .and_then(|candidate| candidate.content.parts.into_iter().find_map(|part| part.inline_data))
+            .ok_or_else(|| "No audio in Gemini TTS response".into())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,423 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::database::Database;
+
+const GOALS_SETTING_KEY: &str = "practice_goals";
+
+// 每連續達標 7 天獲得一天凍結，最多保留 2 天；漏練的一天自動消耗凍結，連續天數不中斷
+const DAYS_PER_FREEZE: u32 = 7;
+const MAX_FREEZES: u32 = 2;
+// 計算進步幅度時比較的練習次數
+const IMPROVEMENT_WINDOW: u32 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PracticeGoals {
+    pub minutes_per_day: u32,
+    pub sessions_per_week: u32,
+    pub reminders_enabled: bool,
+    pub reminder_times: Vec<String>, // 本地時間 "HH:MM"
+}
+
+impl Default for PracticeGoals {
+    fn default() -> Self {
+        Self {
+            minutes_per_day: 15,
+            sessions_per_week: 5,
+            reminders_enabled: false,
+            reminder_times: vec!["20:00".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub goals: PracticeGoals,
+    pub date: String,
+    pub today_minutes: f64,
+    pub daily_goal_met: bool,
+    pub week_sessions: u32,
+    pub weekly_goal_met: bool,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub freezes_available: u32,
+    pub frozen_days: Vec<String>, // 當前連續記錄中用凍結補上的日期
+}
+
+// 練習頁的總體統計
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningStats {
+    pub total_sessions: u32,
+    pub total_minutes: f64,
+    pub average_score: Option<f64>,
+    // 最近幾次練習的平均分相對之前同樣次數的變化（百分比）
+    pub improvement_rate: Option<f64>,
+    pub current_streak: u32,
+}
+
+pub struct Reminder {
+    pub title: String,
+    pub body: String,
+}
+
+pub fn load_goals(db: &Database) -> Result<PracticeGoals, String> {
+    Ok(db.get_setting(GOALS_SETTING_KEY)?.unwrap_or_default())
+}
+
+pub fn save_goals(db: &Database, goals: &PracticeGoals) -> Result<PracticeGoals, String> {
+    let mut times = Vec::new();
+    for time in &goals.reminder_times {
+        let normalized = parse_time(time).ok_or_else(|| format!("提醒時間格式錯誤：{}（應為 HH:MM）", time))?;
+        if !times.contains(&normalized) {
+            times.push(normalized);
+        }
+    }
+    times.sort();
+    let goals = PracticeGoals {
+        minutes_per_day: goals.minutes_per_day.clamp(1, 240),
+        sessions_per_week: goals.sessions_per_week.min(50),
+        reminder_times: times,
+        ..goals.clone()
+    };
+    db.set_setting(GOALS_SETTING_KEY, &goals)?;
+    Ok(goals)
+}
+
+fn parse_time(time: &str) -> Option<String> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+    (hour < 24 && minute < 60).then(|| format!("{:02}:{:02}", hour, minute))
+}
+
+// 記錄一段練習時間；plan_activity_id 用於取消計劃活動完成狀態時一併刪除
+pub fn record_practice_time(
+    db: &Database,
+    seconds: f64,
+    activity: &str,
+    plan_activity_id: Option<i64>,
+) -> Result<(), String> {
+    if seconds.is_nan() || seconds <= 0.0 {
+        return Err("練習時長必須大於 0".to_string());
+    }
+    db.conn()
+        .execute(
+            "INSERT INTO practice_time (activity, seconds, plan_activity_id) VALUES (?1, ?2, ?3)",
+            params![activity, seconds.min(24.0 * 3600.0), plan_activity_id],
+        )
+        .map_err(|e| format!("保存練習時間失敗：{}", e))?;
+    Ok(())
+}
+
+// 按已保存錄音的時長記錄練習時間。評分時已計入的錄音在保存練習記錄時不再重複計算
+pub fn record_recording_time(db: &Database, recording_id: i64, activity: &str) -> Result<(), String> {
+    db.conn()
+        .execute(
+            "INSERT INTO practice_time (activity, seconds, recording_id)
+             SELECT ?2, MIN(duration_secs, 86400), id FROM recordings WHERE id = ?1 AND duration_secs > 0
+             ON CONFLICT (recording_id) DO NOTHING",
+            params![recording_id, activity],
+        )
+        .map_err(|e| format!("保存練習時間失敗：{}", e))?;
+    Ok(())
+}
+
+// 公曆日期與天數互換（1970-01-01 為第 0 天），用於逐日計算連續天數
+pub fn day_number(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+pub fn date_string(day: i64) -> String {
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+struct Streak {
+    current: u32,
+    longest: u32,
+    freezes: u32,
+    frozen_days: Vec<i64>,
+}
+
+// 從第一個達標日逐日推算。今天還沒達標不算中斷，達標後計入
+fn compute_streak(met_days: &HashSet<i64>, today: i64) -> Streak {
+    let mut streak = Streak { current: 0, longest: 0, freezes: 0, frozen_days: Vec::new() };
+    let Some(&first) = met_days.iter().min() else {
+        return streak;
+    };
+    let mut since_freeze = 0;
+    for day in first..=today {
+        if met_days.contains(&day) {
+            streak.current += 1;
+            since_freeze += 1;
+            if since_freeze == DAYS_PER_FREEZE {
+                since_freeze = 0;
+                streak.freezes = (streak.freezes + 1).min(MAX_FREEZES);
+            }
+        } else if day == today {
+            break;
+        } else if streak.current > 0 && streak.freezes > 0 {
+            streak.freezes -= 1;
+            streak.frozen_days.push(day);
+        } else {
+            // 中斷後重新累積，之前攢下的凍結一併清零
+            streak.current = 0;
+            streak.freezes = 0;
+            since_freeze = 0;
+            streak.frozen_days.clear();
+        }
+        streak.longest = streak.longest.max(streak.current);
+    }
+    streak
+}
+
+pub fn goal_progress(db: &Database) -> Result<GoalProgress, String> {
+    let goals = load_goals(db)?;
+    let (today, week_start): (String, String) = db
+        .conn()
+        .query_row(
+            // 週一為一週的第一天
+            "SELECT date('now', 'localtime'), date('now', 'localtime', '-6 days', 'weekday 1')",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut daily_minutes: HashMap<String, f64> = HashMap::new();
+    {
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT date(created_at, 'localtime') AS day, SUM(seconds) / 60.0 FROM practice_time
+                 GROUP BY day",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+            .map_err(|e| format!("查詢練習時間失敗：{}", e))?;
+        for row in rows {
+            let (day, minutes) = row.map_err(|e| e.to_string())?;
+            daily_minutes.insert(day, minutes);
+        }
+    }
+
+    let week_sessions: u32 = db
+        .conn()
+        .query_row(
+            "SELECT COUNT(*) FROM practice_records WHERE date(created_at, 'localtime') >= ?1",
+            [&week_start],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("查詢練習記錄失敗：{}", e))?;
+
+    let goal_minutes = goals.minutes_per_day as f64;
+    let met_days: HashSet<i64> = daily_minutes
+        .iter()
+        .filter(|(_, &minutes)| minutes >= goal_minutes)
+        .filter_map(|(day, _)| day_number(day))
+        .collect();
+    let today_number = day_number(&today).ok_or("日期格式錯誤")?;
+    let streak = compute_streak(&met_days, today_number);
+    let today_minutes = daily_minutes.get(&today).copied().unwrap_or_default();
+
+    Ok(GoalProgress {
+        date: today,
+        today_minutes: (today_minutes * 10.0).round() / 10.0,
+        daily_goal_met: today_minutes >= goal_minutes,
+        week_sessions,
+        weekly_goal_met: week_sessions >= goals.sessions_per_week,
+        current_streak: streak.current,
+        longest_streak: streak.longest,
+        freezes_available: streak.freezes,
+        frozen_days: streak.frozen_days.into_iter().map(date_string).collect(),
+        goals,
+    })
+}
+
+pub fn learning_stats(db: &Database) -> Result<LearningStats, String> {
+    let (total_sessions, average_score): (u32, Option<f64>) = db
+        .conn()
+        .query_row("SELECT COUNT(*), AVG(json_extract(scores, '$.overall')) FROM practice_records", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| format!("查詢練習記錄失敗：{}", e))?;
+    let total_minutes: f64 = db
+        .conn()
+        .query_row("SELECT COALESCE(SUM(seconds), 0) / 60.0 FROM practice_time", [], |row| row.get(0))
+        .map_err(|e| format!("查詢練習時間失敗：{}", e))?;
+
+    let window_average = |offset: u32| -> Result<Option<f64>, String> {
+        db.conn()
+            .query_row(
+                "SELECT AVG(overall) FROM (
+                     SELECT json_extract(scores, '$.overall') AS overall FROM practice_records
+                     ORDER BY created_at DESC, id DESC LIMIT ?1 OFFSET ?2
+                 )",
+                params![IMPROVEMENT_WINDOW, offset],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("查詢練習記錄失敗：{}", e))
+    };
+    let improvement_rate = match (window_average(0)?, window_average(IMPROVEMENT_WINDOW)?) {
+        (Some(recent), Some(previous)) if previous > 0.0 => {
+            Some(((recent - previous) / previous * 1000.0).round() / 10.0)
+        }
+        _ => None,
+    };
+
+    Ok(LearningStats {
+        total_sessions,
+        total_minutes: (total_minutes * 10.0).round() / 10.0,
+        average_score: average_score.map(|score| (score * 10.0).round() / 10.0),
+        improvement_rate,
+        current_streak: goal_progress(db)?.current_streak,
+    })
+}
+
+// 到了提醒時間且今天還沒達標時返回提醒內容；fired 記錄今天已處理過的「日期 時間」，
+// 同一時間點只處理一次，之前日期的記錄隨之清除
+pub fn due_reminder(db: &Database, fired: &mut HashSet<String>) -> Result<Option<Reminder>, String> {
+    let goals = load_goals(db)?;
+    if !goals.reminders_enabled {
+        return Ok(None);
+    }
+    let (date, time): (String, String) = db
+        .conn()
+        .query_row("SELECT date('now', 'localtime'), strftime('%H:%M', 'now', 'localtime')", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    fired.retain(|key| key.starts_with(&date));
+    if !goals.reminder_times.contains(&time) || !fired.insert(format!("{} {}", date, time)) {
+        return Ok(None);
+    }
+
+    let progress = goal_progress(db)?;
+    if progress.daily_goal_met {
+        return Ok(None);
+    }
+    let remaining = (progress.goals.minutes_per_day as f64 - progress.today_minutes).max(1.0).ceil();
+    let body = if progress.current_streak > 0 {
+        format!("再練 {} 分鐘就能保持 {} 天的連續記錄！", remaining, progress.current_streak)
+    } else {
+        format!("今天還差 {} 分鐘達成目標，現在開始吧！", remaining)
+    };
+    Ok(Some(Reminder { title: "該練習口語了".to_string(), body }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioClip;
+    use crate::recordings;
+
+    fn open_db() -> Database {
+        Database::open(&std::env::temp_dir().join(format!("web-chat-goals-{:016x}", rand::random::<u64>()))).unwrap()
+    }
+
+    fn days(range: std::ops::Range<i64>) -> HashSet<i64> {
+        range.collect()
+    }
+
+    #[test]
+    fn converts_dates_to_day_numbers() {
+        assert_eq!(day_number("1970-01-01"), Some(0));
+        assert_eq!(day_number("2024-03-01"), Some(day_number("2024-02-29").unwrap() + 1));
+        assert_eq!(date_string(day_number("2000-12-31").unwrap()), "2000-12-31");
+        assert_eq!(day_number("2024-xx-01"), None);
+    }
+
+    #[test]
+    fn counts_consecutive_days_and_ignores_unfinished_today() {
+        let streak = compute_streak(&days(10..15), 15);
+        assert_eq!((streak.current, streak.longest), (5, 5));
+        let streak = compute_streak(&days(10..16), 15);
+        assert_eq!(streak.current, 6);
+        assert_eq!(compute_streak(&days(10..14), 15).current, 0);
+        assert_eq!(compute_streak(&HashSet::new(), 15).current, 0);
+    }
+
+    #[test]
+    fn freezes_cover_missed_days() {
+        // 連續 7 天獲得一天凍結，漏練的第 8 天由凍結補上
+        let mut met = days(0..7);
+        met.extend(8..10);
+        let streak = compute_streak(&met, 10);
+        assert_eq!(streak.current, 9);
+        assert_eq!(streak.freezes, 0);
+        assert_eq!(streak.frozen_days, vec![7]);
+
+        let streak = compute_streak(&days(0..21), 21);
+        assert_eq!(streak.freezes, MAX_FREEZES);
+    }
+
+    #[test]
+    fn broken_streak_resets_freezes() {
+        // 攢了兩天凍結後連續漏練三天，中斷後不再保留之前的凍結
+        let mut met = days(0..14);
+        met.extend(17..20);
+        let streak = compute_streak(&met, 20);
+        assert_eq!(streak.current, 3);
+        assert_eq!(streak.longest, 14);
+        assert_eq!(streak.freezes, 0);
+        assert!(streak.frozen_days.is_empty());
+
+        // 重新累積 7 天後才再次獲得凍結
+        met.extend(20..24);
+        assert_eq!(compute_streak(&met, 24).freezes, 1);
+    }
+
+    #[test]
+    fn day_meeting_goal_counts_toward_progress() {
+        let db = open_db();
+        save_goals(&db, &PracticeGoals { minutes_per_day: 10, ..PracticeGoals::default() }).unwrap();
+        record_practice_time(&db, 300.0, "pronunciation", None).unwrap();
+        let progress = goal_progress(&db).unwrap();
+        assert!(!progress.daily_goal_met);
+        assert_eq!(progress.current_streak, 0);
+
+        record_practice_time(&db, 330.0, "shadowing", None).unwrap();
+        let progress = goal_progress(&db).unwrap();
+        assert!(progress.daily_goal_met);
+        assert_eq!(progress.today_minutes, 10.5);
+        assert_eq!((progress.current_streak, progress.longest_streak), (1, 1));
+        assert!(record_practice_time(&db, 0.0, "pronunciation", None).is_err());
+    }
+
+    #[test]
+    fn recordings_count_once() {
+        let db = open_db();
+        let clip = AudioClip::new(vec![0.1; 16_000 * 90], 16_000);
+        let id = recordings::save_recording(&db, "hello", &clip, Some(80.0)).unwrap();
+        record_recording_time(&db, id, "pronunciation").unwrap();
+        record_recording_time(&db, id, "practice").unwrap();
+        record_recording_time(&db, id + 1, "practice").unwrap();
+        assert_eq!(goal_progress(&db).unwrap().today_minutes, 1.5);
+    }
+
+    #[test]
+    fn reminders_forget_previous_days() {
+        let db = open_db();
+        save_goals(&db, &PracticeGoals { reminders_enabled: true, reminder_times: vec![], ..PracticeGoals::default() })
+            .unwrap();
+        let mut fired: HashSet<String> = ["2000-01-01 20:00".to_string()].into_iter().collect();
+        assert!(due_reminder(&db, &mut fired).unwrap().is_none());
+        assert!(fired.is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::gemini_service::GrammarReview;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrammarCategory {
+    Tense,
+    Article,
+    Agreement,
+    Preposition,
+    WordOrder,
+    Other,
+}
+
+impl GrammarCategory {
+    pub const ALL: [GrammarCategory; 6] = [
+        GrammarCategory::Tense,
+        GrammarCategory::Article,
+        GrammarCategory::Agreement,
+        GrammarCategory::Preposition,
+        GrammarCategory::WordOrder,
+        GrammarCategory::Other,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            GrammarCategory::Tense => "tense",
+            GrammarCategory::Article => "article",
+            GrammarCategory::Agreement => "agreement",
+            GrammarCategory::Preposition => "preposition",
+            GrammarCategory::WordOrder => "word_order",
+            GrammarCategory::Other => "other",
+        }
+    }
+
+    fn from_code(code: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.code() == code.trim().to_lowercase().replace([' ', '-'], "_"))
+            .unwrap_or(GrammarCategory::Other)
+    }
+}
+
+// start/end 為 UTF-16 偏移，與前端 JavaScript 字符串下標一致，可直接用於高亮
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrammarError {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub category: GrammarCategory,
+    pub correction: String,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrammarCheck {
+    pub text: String,
+    pub errors: Vec<GrammarError>,
+    pub corrected_text: String,
+}
+
+// 在原文中定位模型給出的錯誤片段。模型返回的偏移不可靠，這裡按順序逐個查找原文片段，
+// 找不到的錯誤（模型改寫了原文）丟棄，不在錯誤位置上高亮
+pub fn locate_errors(text: &str, review: GrammarReview) -> GrammarCheck {
+    let mut errors: Vec<GrammarError> = Vec::new();
+    let mut cursor = 0;
+    for issue in review.issues {
+        let original = issue.original.trim();
+        if original.is_empty() || original == issue.correction.trim() {
+            continue;
+        }
+        // 先從上一個錯誤之後找，模型沒有按順序返回時再從頭找
+        let Some((start, end)) = find_word(text, original, cursor).or_else(|| find_word(text, original, 0)) else {
+            tracing::warn!(original = %original, "Grammar issue not found in text");
+            continue;
+        };
+        if errors.iter().any(|e| overlaps(text, e, start, end)) {
+            continue;
+        }
+        cursor = end;
+        errors.push(GrammarError {
+            start: utf16_offset(text, start),
+            end: utf16_offset(text, end),
+            text: text[start..end].to_string(),
+            category: GrammarCategory::from_code(&issue.category),
+            correction: issue.correction.trim().to_string(),
+            explanation: issue.explanation,
+        });
+    }
+    errors.sort_by_key(|e| e.start);
+
+    GrammarCheck { text: text.to_string(), errors, corrected_text: review.corrected_text }
+}
+
+// 查找完整詞的匹配（避免 "a" 匹配到 "cat" 中的字母），忽略大小寫，返回原文中的字節範圍。
+// 逐字符比較小寫形式，小寫後長度改變的字符（如 "İ"）也能得到原文中正確的偏移
+fn find_word(text: &str, needle: &str, from: usize) -> Option<(usize, usize)> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    if needle.is_empty() {
+        return None;
+    }
+    text.get(from..)?.char_indices().find_map(|(offset, _)| {
+        let start = from + offset;
+        let end = match_lowercase(text, start, &needle)?;
+        let boundary_before = text[..start].chars().next_back().is_none_or(|c| !c.is_alphanumeric());
+        let boundary_after = text[end..].chars().next().is_none_or(|c| !c.is_alphanumeric());
+        (boundary_before && boundary_after).then_some((start, end))
+    })
+}
+
+// 從 start 開始的原文小寫後以 needle 開頭時，返回匹配結束處的字節偏移
+fn match_lowercase(text: &str, start: usize, needle: &[char]) -> Option<usize> {
+    let mut matched = 0;
+    for (offset, c) in text[start..].char_indices() {
+        for lower in c.to_lowercase() {
+            if needle.get(matched) != Some(&lower) {
+                return None;
+            }
+            matched += 1;
+        }
+        if matched == needle.len() {
+            return Some(start + offset + c.len_utf8());
+        }
+    }
+    None
+}
+
+fn overlaps(text: &str, error: &GrammarError, start: usize, end: usize) -> bool {
+    let (start, end) = (utf16_offset(text, start), utf16_offset(text, end));
+    start < error.end && error.start < end
+}
+
+fn utf16_offset(text: &str, byte_offset: usize) -> usize {
+    text[..byte_offset].encode_utf16().count()
+}
+
+// 檢查結果按原文緩存，同一段文本不重複調用模型
+pub fn cached_check(db: &Database, text: &str) -> Result<Option<GrammarCheck>, String> {
+    let result: Option<String> = db
+        .conn()
+        .query_row("SELECT result FROM grammar_checks WHERE text = ?1", [text], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("讀取語法檢查緩存失敗：{}", e))?;
+    Ok(result.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+pub fn cache_check(db: &Database, check: &GrammarCheck) -> Result<(), String> {
+    let json = serde_json::to_string(check).map_err(|e| e.to_string())?;
+    db.conn()
+        .execute(
+            "INSERT INTO grammar_checks (text, result) VALUES (?1, ?2)
+             ON CONFLICT (text) DO UPDATE SET result = excluded.result, created_at = datetime('now')",
+            params![check.text, json],
+        )
+        .map_err(|e| format!("保存語法檢查緩存失敗：{}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gemini_service::GrammarIssue;
+
+    fn review(issues: &[(&str, &str, &str)]) -> GrammarReview {
+        GrammarReview {
+            issues: issues
+                .iter()
+                .map(|(original, correction, category)| GrammarIssue {
+                    original: original.to_string(),
+                    correction: correction.to_string(),
+                    category: category.to_string(),
+                    explanation: String::new(),
+                })
+                .collect(),
+            corrected_text: String::new(),
+        }
+    }
+
+    fn spans(check: &GrammarCheck) -> Vec<(usize, usize, &str)> {
+        check.errors.iter().map(|e| (e.start, e.end, e.text.as_str())).collect()
+    }
+
+    #[test]
+    fn matches_whole_words_ignoring_case() {
+        assert_eq!(find_word("The cat ate a fish", "a", 0), Some((12, 13)));
+        assert_eq!(find_word("He Go home", "go", 0), Some((3, 5)));
+        assert_eq!(find_word("go, go", "go", 1), Some((4, 6)));
+        assert_eq!(find_word("gone", "go", 0), None);
+        assert_eq!(find_word("text", "", 0), None);
+    }
+
+    #[test]
+    fn handles_characters_whose_lowercase_changes_length() {
+        // "İ" 小寫後是兩個字符、三個字節，偏移仍按原文計算
+        let text = "İstanbul: He Go home.";
+        assert_eq!(find_word(text, "İSTANBUL", 0), Some((0, 9)));
+        assert_eq!(find_word(text, "go", 0), Some((14, 16)));
+        assert_eq!(&text[14..16], "Go");
+        assert_eq!(find_word("Kİ", "ki", 0), None);
+    }
+
+    #[test]
+    fn locates_errors_with_utf16_offsets() {
+        let text = "我說 He go to school yesterday and buyed 🍎 apple.";
+        let check = locate_errors(
+            text,
+            review(&[("buyed", "bought", "tense"), ("go", "went", "Tense"), ("🍎 apple", "an apple", "article")]),
+        );
+        assert_eq!(spans(&check), vec![(6, 8, "go"), (33, 38, "buyed"), (39, 47, "🍎 apple")]);
+        assert_eq!(check.errors[0].category, GrammarCategory::Tense);
+        assert_eq!(check.errors[2].category, GrammarCategory::Article);
+    }
+
+    #[test]
+    fn drops_unlocatable_unchanged_and_overlapping_issues() {
+        let check = locate_errors(
+            "She don't like the apples.",
+            review(&[
+                ("She don't", "She doesn't", "agreement"),
+                ("don't like", "doesn't like", "agreement"),
+                ("the", "the", "article"),
+                ("apple pie", "apples", "other"),
+                ("apples", "apple", "word order"),
+            ]),
+        );
+        assert_eq!(spans(&check), vec![(0, 9, "She don't"), (19, 25, "apples")]);
+        assert_eq!(check.errors[1].category, GrammarCategory::WordOrder);
+    }
+
+    #[test]
+    fn caches_checks_by_text() {
+        let db = Database::open(&std::env::temp_dir().join(format!("web-chat-grammar-{:016x}", rand::random::<u64>()))).unwrap();
+        assert!(cached_check(&db, "He go.").unwrap().is_none());
+        cache_check(&db, &locate_errors("He go.", review(&[("go", "goes", "agreement")]))).unwrap();
+        let cached = cached_check(&db, "He go.").unwrap().unwrap();
+        assert_eq!(spans(&cached), vec![(3, 5, "go")]);
+    }
+}
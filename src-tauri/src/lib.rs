@@ -1,60 +1,206 @@
 use std::collections::HashMap;
+use serde::Serialize;
 use serde_json::Value;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
 use tokio::sync::Mutex;
+use tracing::{error, info, instrument, warn};
 
+mod achievements;
+mod assignments;
+mod audio;
+mod database;
+mod diagnostics;
+mod drills;
+mod export;
+mod fluency;
 mod gemini_service;
-use gemini_service::{GeminiService, TutorFeedback};
+mod goals;
+mod grammar;
+mod logging;
+mod minimal_pairs;
+mod models;
+mod pdf;
+mod planner;
+mod preprocess;
+mod profiles;
+mod pronunciation;
+mod prosody;
+mod recordings;
+mod report;
+mod segmentation;
+mod shadowing;
+mod sync;
+mod text;
+mod vad;
+mod vocabulary;
+use achievements::Achievement;
+use assignments::{
+    AssignmentBundle, AssignmentDraft, AssignmentProgress, AssignmentResults, AssignmentSummary, ImportedAssignment,
+    PassageProgress, ResultBundle, ResultImport, SigningIdentity,
+};
+use audio::{AudioClip, AudioFormat, ANALYSIS_SAMPLE_RATE};
+use database::Database;
+use diagnostics::{ConnectionTest, DiagnosticsBundle, DiagnosticsReport};
+use drills::{DrillSessionInfo, DrillSessionResult, SegmentAudio};
+use export::{AnkiExportResult, ImportSummary};
+use fluency::FluencyAssessment;
+use goals::{GoalProgress, LearningStats, PracticeGoals};
+use logging::LogEntry;
+use grammar::{GrammarCategory, GrammarCheck};
+use models::{GeminiSettings, ModelCache, ModelCatalog, ModelPurpose};
+use minimal_pairs::{ContrastProgress, ContrastSummary, MinimalPairAnswer, MinimalPairAudio, MinimalPairDrill};
+use gemini_service::{GeminiService, OpenResponseEvaluation, Transcript, TutorFeedback, DEFAULT_TTS_VOICE};
+use planner::{DailyPlan, LearningGoals};
+use preprocess::{PreparedRecording, QualityReport};
+use profiles::{Profile, ProfileList, ProfileStore, ProfileUpdate};
+use report::{GeneratedReport, ReportLocale, ReportPeriod};
+use pronunciation::WordPronunciation;
+use prosody::ProsodyAnalysis;
+use recordings::{RecordingAudio, RecordingComparison, RecordingInfo, RetentionPolicy};
+use segmentation::SegmentedText;
+use sync::{SyncConfig, SyncReport, SyncStatus};
+use shadowing::{ShadowingSegmentResult, ShadowingSession, ShadowingSessionInfo, ShadowingSummary};
+use text::TimedWord;
+use vad::{EndpointStatus, Endpointer, VadConfig, VadResult};
+use vocabulary::{VocabularyEntry, VocabularyFilter, VocabularyUpdate};
 
 // 全局狀態管理
 struct AppState {
     gemini_service: Mutex<Option<GeminiService>>,
+    // 最近一次轉寫結果（按錄音內容哈希），避免同一錄音重複調用 ASR
+    last_transcript: Mutex<Option<(u64, Transcript)>>,
+    // 當前錄音的實時端點檢測狀態
+    recording: Mutex<Option<Endpointer>>,
+    // 當前檔案的本地數據庫，打開失敗時為 None，相關命令返回錯誤；切換檔案時整個替換
+    database: Mutex<Option<Database>>,
+    // 當前的跟讀練習
+    shadowing: Mutex<Option<ShadowingSession>>,
+    // 學習者檔案列表
+    profiles: Mutex<Option<ProfileStore>>,
+    // 最近一次拉取的模型列表
+    model_cache: Mutex<Option<ModelCache>>,
+    // 同步進行中時持有，後台定時同步和手動同步不會同時運行
+    sync_guard: Mutex<()>,
+}
+
+// 打開檔案的數據庫，並按保留策略清理一次過期錄音
+fn open_database(dir: &std::path::Path) -> Result<Database, String> {
+    let db = Database::open(dir)?;
+    if let Err(e) = recordings::retention_policy(&db).and_then(|policy| recordings::apply_retention(&db, &policy)) {
+        error!(error = %e, "Recording retention failed");
+    }
+    Ok(db)
+}
+
+// 檔案的程度同時寫入學習目標，生成每日計劃時使用
+fn apply_profile_level(db: &Database, level: Option<String>) -> Result<(), String> {
+    let goals = LearningGoals { level, ..planner::load_goals(db)? };
+    planner::save_goals(db, &goals)
 }
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
+#[instrument(skip_all)]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+// 用新密鑰創建服務並替換當前服務。模型列表拿不到時（如離線）仍允許替換，只是不校驗指定的模型
+async fn activate_gemini_service(
+    api_key: String,
+    text_model: Option<String>,
+    tts_model: Option<String>,
+    audio_model: Option<String>,
+    state: &AppState,
+) -> Result<(), String> {
+    let mut service = GeminiService::new(api_key.clone());
+    let catalog = match models::catalog(&mut *state.model_cache.lock().await, &service, false).await {
+        Ok(catalog) => Some(catalog),
+        Err(e) => {
+            warn!(error = %e, "Model validation skipped");
+            None
+        }
+    };
+    let selection = models::resolve(catalog.as_ref(), text_model, tts_model, audio_model)?;
+    info!(text = %selection.text, tts = %selection.tts, audio = %selection.audio, "Gemini service initialized");
+    // 密鑰和模型保存在當前檔案中，下次啟動或切換回來時恢復
+    let mut gemini_service = state.gemini_service.lock().await;
+    if let Some(db) = state.database.lock().await.as_ref() {
+        models::save_settings(db, &GeminiSettings { api_key, models: selection.clone() })?;
+    }
+    service.set_models(selection);
+    *gemini_service = Some(service);
+    Ok(())
+}
+
 #[tauri::command]
+#[instrument(skip_all, err)]
 async fn initialize_gemini_service(
     api_key: String,
+    text_model: Option<String>,
+    tts_model: Option<String>,
+    audio_model: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let service = GeminiService::new(api_key);
-    let mut gemini_service = state.gemini_service.lock().await;
-    *gemini_service = Some(service);
+    activate_gemini_service(api_key, text_model, tts_model, audio_model, &state).await?;
     Ok("Gemini service initialized successfully".to_string())
 }
 
+// 當前密鑰可用的模型及各用途的推薦模型。傳入 api_key 時用該密鑰查詢，便於初始化前選擇模型
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn list_gemini_models(
+    api_key: Option<String>,
+    refresh: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ModelCatalog, String> {
+    let refresh = refresh.unwrap_or(false);
+    if let Some(api_key) = api_key {
+        let service = GeminiService::new(api_key);
+        return models::catalog(&mut *state.model_cache.lock().await, &service, refresh).await;
+    }
+    let gemini_service = state.gemini_service.lock().await;
+    let service = gemini_service
+        .as_ref()
+        .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+    let catalog = models::catalog(&mut *state.model_cache.lock().await, service, refresh).await?;
+    Ok(ModelCatalog { active: Some(service.models()), ..catalog })
+}
+
+// 只查詢模型信息來測試密鑰，不消耗配額；默認不影響當前服務，activate 為 true 且測試通過時才切換到這個密鑰
 #[tauri::command]
+#[instrument(skip_all, err)]
 async fn test_gemini_connection(
     api_key: String,
+    model: Option<String>,
+    activate: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    // 創建臨時服務進行測試
-    let test_service = GeminiService::new(api_key.clone());
-    
-    // 測試簡單的內容生成
-    match test_service.generate_practice_content(
-        "daily",
-        "beginner", 
-        &vec!["測試".to_string()]
-    ).await {
-        Ok(content) => {
-            // 測試成功，初始化服務
-            let mut gemini_service = state.gemini_service.lock().await;
-            *gemini_service = Some(test_service);
-            Ok(format!("連接測試成功！生成的測試內容：{}", content.chars().take(50).collect::<String>()))
-        },
-        Err(e) => {
-            Err(format!("連接測試失敗：{}", e))
-        }
+) -> Result<ConnectionTest, String> {
+    let service = GeminiService::new(api_key.clone());
+    let model = model.map(|model| model.trim().trim_start_matches("models/").to_string()).filter(|model| !model.is_empty());
+    let probe_model = model.clone().unwrap_or_else(|| service.config().model.clone());
+    let mut result = diagnostics::test_connection(&service, &probe_model).await;
+    info!(
+        success = result.success,
+        failure = ?result.failure,
+        latency_ms = result.latency_ms,
+        "Gemini connection tested"
+    );
+    if result.success && activate.unwrap_or(false) {
+        // 測試的模型放進與其能力對應的槽位，其餘槽位用推薦值
+        let (text_model, tts_model) = match models::slot_for(&probe_model) {
+            ModelPurpose::Tts => (None, model),
+            ModelPurpose::Text | ModelPurpose::Audio => (model, None),
+        };
+        activate_gemini_service(api_key, text_model, tts_model, None, &state).await?;
+        result.activated = true;
     }
+    Ok(result)
 }
 
 #[tauri::command]
+#[instrument(skip_all, err)]
 async fn get_ai_tutor_feedback(
     user_performance: HashMap<String, Value>,
     practice_context: String,
@@ -66,7 +212,7 @@ async fn get_ai_tutor_feedback(
         match service.generate_tutor_feedback(&user_performance, &practice_context).await {
             Ok(feedback) => Ok(feedback),
             Err(e) => {
-                eprintln!("Gemini API error: {}", e);
+                error!(error = %e, "Gemini API error");
                 // 提供備用反饋
                 Ok(create_fallback_feedback(&user_performance))
             }
@@ -77,6 +223,7 @@ async fn get_ai_tutor_feedback(
 }
 
 #[tauri::command]
+#[instrument(skip_all, err)]
 async fn generate_practice_content(
     topic: String,
     difficulty_level: String,
@@ -89,7 +236,7 @@ async fn generate_practice_content(
         match service.generate_practice_content(&topic, &difficulty_level, &user_interests).await {
             Ok(content) => Ok(content),
             Err(e) => {
-                eprintln!("Gemini API error: {}", e);
+                error!(error = %e, "Gemini API error");
                 // 提供備用內容
                 Ok(create_fallback_content(&topic, &difficulty_level))
             }
@@ -100,6 +247,7 @@ async fn generate_practice_content(
 }
 
 #[tauri::command]
+#[instrument(skip_all, err)]
 async fn gemini_text_to_speech(
     text: String,
     voice_config: Option<String>,
@@ -111,7 +259,7 @@ async fn gemini_text_to_speech(
         match service.generate_speech_audio(&text, voice_config.as_deref()).await {
             Ok(audio_data) => Ok(audio_data),
             Err(e) => {
-                eprintln!("Gemini TTS error: {}", e);
+                error!(error = %e, "Gemini TTS error");
                 Err(format!("Gemini語音合成失敗: {}", e))
             }
         }
@@ -120,17 +268,61 @@ async fn gemini_text_to_speech(
     }
 }
 
-// 模擬語音識別（實際項目中應該集成真實的ASR服務）
+// 使用 Gemini 轉寫錄音；服務未初始化時返回 Ok(None)
+// 上傳給 ASR 的是解碼後統一格式的 WAV，避免不同平台的容器格式影響識別結果
+async fn transcribe_recording(
+    state: &AppState,
+    audio_data: &str,
+    clip: &AudioClip,
+) -> Result<Option<Transcript>, String> {
+    let fingerprint = {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(audio_data, &mut hasher);
+        std::hash::Hasher::finish(&hasher)
+    };
+    if let Some((cached_fingerprint, transcript)) = state.last_transcript.lock().await.as_ref() {
+        if *cached_fingerprint == fingerprint {
+            return Ok(Some(transcript.clone()));
+        }
+    }
+
+    let gemini_service = state.gemini_service.lock().await;
+    let Some(service) = gemini_service.as_ref() else {
+        return Ok(None);
+    };
+
+    let wav_base64 = audio::encode_base64(&clip.to_wav_bytes()?);
+    match service.transcribe_audio(&wav_base64, AudioFormat::Wav.mime_type()).await {
+        Ok(transcript) => {
+            *state.last_transcript.lock().await = Some((fingerprint, transcript.clone()));
+            Ok(Some(transcript))
+        }
+        Err(e) => {
+            error!(error = %e, "Gemini ASR error");
+            Err(format!("語音識別失敗: {}", e))
+        }
+    }
+}
+
+// 語音識別：已配置 Gemini 時使用真實轉寫，否則返回模擬結果
 #[tauri::command]
-async fn speech_to_text(audio_data: String) -> Result<String, String> {
-    // 這裡應該集成實際的語音識別服務
-    // 目前返回模擬結果
+#[instrument(skip_all, err)]
+async fn speech_to_text(
+    audio_data: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let clip = AudioClip::from_base64(&audio_data)?;
+    if let Some(transcript) = transcribe_recording(&state, &audio_data, &clip).await? {
+        return Ok(transcript.text);
+    }
+
     tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
     Ok("This is a simulated speech recognition result.".to_string())
 }
 
 // 模擬語音合成（實際項目中應該集成真實的TTS服務）
 #[tauri::command]
+#[instrument(skip_all, err)]
 async fn text_to_speech(text: String) -> Result<String, String> {
     // 這裡應該集成實際的語音合成服務
     // 目前返回模擬結果
@@ -138,54 +330,1582 @@ async fn text_to_speech(text: String) -> Result<String, String> {
     Ok(format!("Generated audio for: {}", text))
 }
 
-// 模擬發音評分（實際項目中應該集成真實的發音評估服務）
+// 發音評分結果：分數表保持扁平結構，韻律與流利度分析作為附加字段
+#[derive(Debug, Serialize)]
+struct PronunciationResult {
+    #[serde(flatten)]
+    scores: HashMap<String, f64>,
+    prosody_analysis: Option<ProsodyAnalysis>,
+    fluency_analysis: Option<FluencyAssessment>,
+    voice_activity: Option<VadResult>,
+    recording_quality: QualityReport,
+    recording_id: Option<i64>,
+}
+
+// 評分前的預處理；質量不合格的錄音不評分，錯誤以原因代碼開頭（如 "too_noisy: ..."），前端據此提示用戶
+async fn prepare_for_scoring(learner: &AudioClip) -> Result<PreparedRecording, String> {
+    let clip = learner.clone();
+    let prepared = tokio::task::spawn_blocking(move || preprocess::prepare_recording(&clip, &VadConfig::default()))
+        .await
+        .map_err(|e| format!("錄音預處理失敗：{}", e))?;
+    if let Some(issue) = prepared.quality.issue {
+        return Err(format!("{}: {}", issue.code(), issue.message()));
+    }
+    Ok(prepared)
+}
+
+// 發音評分：有 ASR 轉寫時按詞對齊計算準確度與完整度，否則沿用模擬分數
 #[tauri::command]
+#[instrument(skip_all, err)]
 async fn pronunciation_score(
     audio_data: String,
     reference_text: String,
-) -> Result<HashMap<String, f64>, String> {
-    // 這裡應該集成實際的發音評分服務
-    tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
-    
+    reference_audio: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<PronunciationResult, String> {
+    let reference = match reference_audio.as_deref().map(AudioClip::from_base64) {
+        Some(Ok(clip)) => Some(clip),
+        Some(Err(e)) => {
+            warn!(error = %e, "Reference audio decode error");
+            None
+        }
+        None => None,
+    };
+    assess_pronunciation(&state, &audio_data, &reference_text, reference, "pronunciation").await
+}
+
+// 評分後按錄音時長計入每日練習時間；計時失敗不影響評分結果
+fn record_scored_time(db: &Database, recording_id: Option<i64>, clip: &AudioClip, activity: &str) {
+    let recorded = match recording_id {
+        Some(id) => goals::record_recording_time(db, id, activity),
+        None => {
+            let seconds = clip.samples.len() as f64 / clip.sample_rate.max(1) as f64;
+            goals::record_practice_time(db, seconds, activity, None)
+        }
+    };
+    if let Err(e) = recorded {
+        error!(error = %e, "Failed to record practice time");
+    }
+}
+
+// 完整的評分流程：解碼、質量檢查、轉寫、流利度與韻律分析，最後保存錄音並計入練習時間
+async fn assess_pronunciation(
+    state: &AppState,
+    audio_data: &str,
+    reference_text: &str,
+    reference: Option<AudioClip>,
+    practice_activity: &str,
+) -> Result<PronunciationResult, String> {
+    // 無法解碼的錄音直接報錯，而不是返回缺少分析結果的分數
+    let learner = AudioClip::from_base64(audio_data)?;
+
+    let prepared = prepare_for_scoring(&learner).await?;
+
+    let transcript = match transcribe_recording(state, audio_data, &learner).await {
+        Ok(transcript) => transcript,
+        Err(e) => {
+            warn!(error = %e, "Transcription skipped");
+            None
+        }
+    };
+
     let mut scores = HashMap::new();
-    scores.insert("overall".to_string(), 75.0 + rand::random::<f64>() * 20.0);
-    scores.insert("pronunciation".to_string(), 70.0 + rand::random::<f64>() * 25.0);
-    scores.insert("fluency".to_string(), 65.0 + rand::random::<f64>() * 30.0);
-    scores.insert("completeness".to_string(), 80.0 + rand::random::<f64>() * 15.0);
-    
-    Ok(scores)
+    let mut phoneme_errors = Vec::new();
+    match &transcript {
+        Some(transcript) => {
+            let expected = text::tokenize_words(reference_text);
+            let spoken: Vec<String> = text::tokenize_words(&transcript.text)
+                .into_iter()
+                .filter(|w| !text::is_filled_pause(w))
+                .collect();
+            let matched = text::matched_word_count(&expected, &spoken) as f64;
+            // 讀錯的詞按詞典讀音找出具體的音位替換，供最小對立練習挑選弱項
+            phoneme_errors = minimal_pairs::phoneme_errors(&expected, &spoken);
+            // 多讀的詞同樣拉低準確度
+            let accuracy = 100.0 * matched / expected.len().max(spoken.len()).max(1) as f64;
+            let completeness = 100.0 * matched / expected.len().max(1) as f64;
+            scores.insert("pronunciation".to_string(), (accuracy * 10.0).round() / 10.0);
+            scores.insert("completeness".to_string(), (completeness * 10.0).round() / 10.0);
+        }
+        None => {
+            scores.insert("overall".to_string(), 75.0 + rand::random::<f64>() * 20.0);
+            scores.insert("pronunciation".to_string(), 70.0 + rand::random::<f64>() * 25.0);
+            scores.insert("completeness".to_string(), 80.0 + rand::random::<f64>() * 15.0);
+        }
+    }
+
+    // 流利度基於 ASR 詞級時間戳計算；沒有轉寫結果時不給出流利度分數
+    let fluency_analysis = transcript
+        .as_ref()
+        .and_then(|t| fluency::assess_fluency(&t.words));
+    if let Some(analysis) = &fluency_analysis {
+        scores.insert("fluency".to_string(), analysis.score);
+    }
+
+    // 先裁掉首尾靜音，再做語調與重音分析（參考音頻通常是同一文本的 TTS 音頻）
+    let words = transcript.as_ref().map(|t| t.words.clone());
+    let recording_quality = prepared.quality.clone();
+    let prosody_text = reference_text.to_string();
+    let (activity, analysis) = tokio::task::spawn_blocking(move || {
+        let vad_config = VadConfig::default();
+        let activity = prepared.activity;
+        let (trimmed, offset) = vad::trim_silence(&prepared.clip, &activity, &vad_config);
+        // ASR 時間戳基於原始錄音，需要按裁剪偏移校正
+        let word_timings: Option<Vec<TimedWord>> = words.map(|words| {
+            words
+                .into_iter()
+                .map(|w| TimedWord { start: w.start - offset, end: w.end - offset, ..w })
+                .collect()
+        });
+        let analysis = prosody::analyze_prosody(
+            &trimmed,
+            reference.as_ref(),
+            &prosody_text,
+            word_timings.as_deref(),
+        );
+        (activity, analysis)
+    })
+    .await
+    .map_err(|e| format!("韻律分析失敗：{}", e))?;
+    scores.insert("prosody".to_string(), analysis.prosody_score);
+
+    // 總分按各維度加權，缺少的維度不參與計算
+    if transcript.is_some() {
+        let weighted: Vec<(f64, f64)> = [("pronunciation", 0.4), ("completeness", 0.2), ("fluency", 0.2), ("prosody", 0.2)]
+            .iter()
+            .filter_map(|(key, weight)| scores.get(*key).map(|score| (score * weight, *weight)))
+            .collect();
+        let total_weight: f64 = weighted.iter().map(|(_, w)| w).sum();
+        let overall = weighted.iter().map(|(s, _)| s).sum::<f64>() / total_weight;
+        scores.insert("overall".to_string(), (overall * 10.0).round() / 10.0);
+    }
+
+    // 保存原始錄音，便於日後回放對比；保存失敗不影響評分結果
+    let recording_id = match state.database.lock().await.as_ref() {
+        Some(db) => {
+            if let Err(e) = minimal_pairs::record_errors(db, &phoneme_errors) {
+                error!(error = %e, "Failed to save phoneme errors");
+            }
+            let recording_id = recordings::save_recording(db, reference_text, &learner, scores.get("overall").copied())
+                .map_err(|e| error!(error = %e, "Failed to save recording"))
+                .ok();
+            record_scored_time(db, recording_id, &learner, practice_activity);
+            recording_id
+        }
+        None => None,
+    };
+
+    Ok(PronunciationResult {
+        scores,
+        prosody_analysis: Some(analysis),
+        fluency_analysis,
+        voice_activity: Some(activity),
+        recording_quality,
+        recording_id,
+    })
 }
 
+// 評分前檢查錄音質量，供前端在提交前提示用戶重新錄音
 #[tauri::command]
-async fn start_recording() -> Result<String, String> {
+#[instrument(skip_all, err)]
+async fn check_recording_quality(audio_data: String) -> Result<QualityReport, String> {
+    let clip = AudioClip::from_base64(&audio_data)?;
+    tokio::task::spawn_blocking(move || preprocess::prepare_recording(&clip, &VadConfig::default()).quality)
+        .await
+        .map_err(|e| format!("錄音質量檢查失敗：{}", e))
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn start_recording(
+    sample_rate: Option<u32>,
+    vad_config: Option<VadConfig>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let endpointer = Endpointer::new(
+        vad_config.unwrap_or_default().validated()?,
+        sample_rate.unwrap_or(ANALYSIS_SAMPLE_RATE),
+    );
+    *state.recording.lock().await = Some(endpointer);
     Ok("Recording started".to_string())
 }
 
+// 錄音過程中前端持續推送 PCM 數據；檢測到說完話後發出 recording-auto-stopped 事件
 #[tauri::command]
-async fn stop_recording() -> Result<String, String> {
+#[instrument(skip_all, level = "debug", err)]
+async fn push_recording_audio(
+    pcm_data: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<EndpointStatus, String> {
+    let samples = audio::pcm16_from_base64(&pcm_data)?;
+    let mut recording = state.recording.lock().await;
+    let endpointer = recording.as_mut().ok_or("Recording not started")?;
+
+    let already_stopped = endpointer.status().stopped.is_some();
+    let status = endpointer.push(&samples).clone();
+    if !already_stopped && status.stopped.is_some() {
+        app.emit("recording-auto-stopped", &status)
+            .map_err(|e| format!("發送自動停止事件失敗: {}", e))?;
+    }
+    Ok(status)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn stop_recording(state: State<'_, AppState>) -> Result<String, String> {
+    state.recording.lock().await.take();
     Ok("Recording stopped".to_string())
 }
 
 #[tauri::command]
+#[instrument(skip_all, level = "debug", err)]
+async fn detect_voice_activity(
+    audio_data: String,
+    vad_config: Option<VadConfig>,
+) -> Result<VadResult, String> {
+    let config = vad_config.unwrap_or_default().validated()?;
+    let clip = AudioClip::from_base64(&audio_data)?;
+    Ok(vad::detect_speech(&clip, &config))
+}
+
+// 開始跟讀練習：切分參考文本，為每個片段合成參考音頻
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn start_shadowing_session(
+    reference_text: String,
+    voice: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ShadowingSessionInfo, String> {
+    let phrases = text::split_phrases(&reference_text, shadowing::MAX_SEGMENT_WORDS);
+    if phrases.is_empty() {
+        return Err("參考文本為空".to_string());
+    }
+
+    let mut segments = Vec::with_capacity(phrases.len());
+    {
+        let gemini_service = state.gemini_service.lock().await;
+        let service = gemini_service
+            .as_ref()
+            .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+        for phrase in phrases {
+            let audio = service.synthesize_speech(&phrase, voice.as_deref()).await.map_err(|e| {
+                error!(error = %e, "Gemini TTS error");
+                format!("Gemini語音合成失敗: {}", e)
+            })?;
+            let clip = AudioClip::from_inline_audio(&audio.mime_type, &audio.data)?;
+            segments.push((phrase, clip));
+        }
+    }
+
+    let session = ShadowingSession::new(segments);
+    let info = session.info()?;
+    *state.shadowing.lock().await = Some(session);
+    Ok(info)
+}
+
+// 評估一個片段的跟讀錄音
+// playback_offset_ms：參考音頻開始播放的時刻，以錄音開始為零點（先錄音後播放時為正數）
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn score_shadowing_segment(
+    session_id: String,
+    segment_index: usize,
+    audio_data: String,
+    playback_offset_ms: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<ShadowingSegmentResult, String> {
+    let (text, reference) = {
+        let shadowing = state.shadowing.lock().await;
+        let session = shadowing
+            .as_ref()
+            .filter(|s| s.id == session_id)
+            .ok_or("跟讀練習不存在或已結束")?;
+        let segment = session.segments.get(segment_index).ok_or("片段序號無效")?;
+        (segment.text.clone(), segment.reference.clone())
+    };
+
+    let learner = AudioClip::from_base64(&audio_data)?;
+    let prepared = prepare_for_scoring(&learner).await?;
+
+    let transcript = match transcribe_recording(&state, &audio_data, &learner).await {
+        Ok(transcript) => transcript,
+        Err(e) => {
+            warn!(error = %e, "Shadowing transcription skipped");
+            None
+        }
+    };
+
+    let offset_secs = playback_offset_ms.unwrap_or(0.0) / 1000.0;
+    let result = tokio::task::spawn_blocking(move || {
+        shadowing::score_segment(
+            segment_index,
+            &text,
+            &reference,
+            &prepared.clip,
+            &prepared.activity,
+            offset_secs,
+            transcript.as_ref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("跟讀評分失敗：{}", e))??;
+
+    if let Some(db) = state.database.lock().await.as_ref() {
+        record_scored_time(db, None, &learner, "shadowing");
+    }
+    let mut shadowing = state.shadowing.lock().await;
+    if let Some(session) = shadowing.as_mut().filter(|s| s.id == session_id) {
+        session.segments[segment_index].result = Some(result.clone());
+    }
+    Ok(result)
+}
+
+// 結束跟讀練習並返回匯總結果
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn finish_shadowing_session(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<ShadowingSummary, String> {
+    let mut shadowing = state.shadowing.lock().await;
+    if shadowing.as_ref().is_none_or(|s| s.id != session_id) {
+        return Err("跟讀練習不存在或已結束".to_string());
+    }
+    let session = shadowing.take().ok_or("跟讀練習不存在或已結束")?;
+    Ok(session.summary())
+}
+
+// 自由口語評分結果：分數表與發音評分一致保持扁平，附帶各維度評語與修改後的回答
+#[derive(Debug, Serialize)]
+struct OpenResponseResult {
+    #[serde(flatten)]
+    scores: HashMap<String, f64>,
+    transcript: String,
+    corrected_answer: String,
+    evaluation: OpenResponseEvaluation,
+    prosody_analysis: Option<ProsodyAnalysis>,
+    fluency_analysis: Option<FluencyAssessment>,
+    recording_quality: QualityReport,
+    recording_id: Option<i64>,
+}
+
+// 評估對提問的自由回答：沒有參考文本，發音由模型直接根據錄音判斷，
+// 語法、詞彙、連貫性和切題程度按評分標準基於轉寫文本評估
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn evaluate_open_response(
+    question: String,
+    audio_data: String,
+    state: State<'_, AppState>,
+) -> Result<OpenResponseResult, String> {
+    let learner = AudioClip::from_base64(&audio_data)?;
+    let prepared = prepare_for_scoring(&learner).await?;
+
+    let transcript = transcribe_recording(&state, &audio_data, &learner)
+        .await?
+        .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+    if text::tokenize_words(&transcript.text).iter().all(|w| text::is_filled_pause(w)) {
+        return Err("no_speech: 沒有識別到有效的回答內容，請重新錄音".to_string());
+    }
+
+    let evaluation = {
+        let gemini_service = state.gemini_service.lock().await;
+        let service = gemini_service
+            .as_ref()
+            .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+        let wav_base64 = audio::encode_base64(&learner.to_wav_bytes()?);
+        service
+            .evaluate_open_response(&question, &transcript.text, &wav_base64, AudioFormat::Wav.mime_type())
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Gemini evaluation error");
+                format!("回答評估失敗: {}", e)
+            })?
+    };
+
+    let fluency_analysis = fluency::assess_fluency(&transcript.words);
+
+    // 沒有參考音頻，韻律分析只看學習者自身的語調變化，重音按轉寫文本判斷
+    let recording_quality = prepared.quality.clone();
+    let transcript_text = transcript.text.clone();
+    let words = transcript.words.clone();
+    let prosody_analysis = tokio::task::spawn_blocking(move || {
+        let (trimmed, offset) = vad::trim_silence(&prepared.clip, &prepared.activity, &VadConfig::default());
+        let word_timings: Vec<TimedWord> = words
+            .into_iter()
+            .map(|w| TimedWord { start: w.start - offset, end: w.end - offset, ..w })
+            .collect();
+        prosody::analyze_prosody(&trimmed, None, &transcript_text, Some(&word_timings))
+    })
+    .await
+    .map_err(|e| format!("韻律分析失敗：{}", e))?;
+
+    let mut scores = HashMap::new();
+    scores.insert("pronunciation".to_string(), evaluation.pronunciation.score);
+    scores.insert("grammar".to_string(), evaluation.grammar.score);
+    scores.insert("vocabulary".to_string(), evaluation.vocabulary.score);
+    scores.insert("coherence".to_string(), evaluation.coherence.score);
+    scores.insert("relevance".to_string(), evaluation.relevance.score);
+    scores.insert("prosody".to_string(), prosody_analysis.prosody_score);
+    if let Some(analysis) = &fluency_analysis {
+        scores.insert("fluency".to_string(), analysis.score);
+    }
+    let overall = scores.values().sum::<f64>() / scores.len() as f64;
+    scores.insert("overall".to_string(), (overall * 10.0).round() / 10.0);
+
+    // 按問題歸檔錄音，同一問題的歷次回答可以回放對比
+    let recording_id = match state.database.lock().await.as_ref() {
+        Some(db) => {
+            let recording_id = recordings::save_recording(db, &question, &learner, scores.get("overall").copied())
+                .map_err(|e| error!(error = %e, "Failed to save recording"))
+                .ok();
+            record_scored_time(db, recording_id, &learner, "open_response");
+            recording_id
+        }
+        None => None,
+    };
+
+    Ok(OpenResponseResult {
+        scores,
+        transcript: transcript.text,
+        corrected_answer: evaluation.corrected_answer.clone(),
+        evaluation,
+        prosody_analysis: Some(prosody_analysis),
+        fluency_analysis,
+        recording_quality,
+        recording_id,
+    })
+}
+
+// 語法檢查：返回錯誤在原文中的位置、類別、修改建議和解釋，結果按原文緩存
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn check_grammar(text: String, state: State<'_, AppState>) -> Result<GrammarCheck, String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("文本為空".to_string());
+    }
+    if let Some(db) = state.database.lock().await.as_ref() {
+        if let Some(check) = grammar::cached_check(db, &text)? {
+            return Ok(check);
+        }
+    }
+
+    let review = {
+        let gemini_service = state.gemini_service.lock().await;
+        let service = gemini_service
+            .as_ref()
+            .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+        let categories: Vec<&str> = GrammarCategory::ALL.iter().map(|c| c.code()).collect();
+        service.check_grammar(&text, &categories).await.map_err(|e| {
+            error!(error = %e, "Gemini grammar check error");
+            format!("語法檢查失敗: {}", e)
+        })?
+    };
+    let check = grammar::locate_errors(&text, review);
+
+    if let Some(db) = state.database.lock().await.as_ref() {
+        if let Err(e) = grammar::cache_check(db, &check) {
+            warn!(error = %e, "Failed to cache grammar check");
+        }
+    }
+    Ok(check)
+}
+
+// 查詢文本中各詞的標準讀音（IPA、音節、重音），完全離線：詞典未收錄的詞按拼寫規則推測
+#[tauri::command]
+#[instrument(skip_all)]
+fn lookup_pronunciation(text: String) -> Vec<WordPronunciation> {
+    pronunciation::pronounce_text(&text)
+}
+
+// 將練習內容切分為句子和短語塊，id 由文本內容決定，同一內容每次切分結果相同
+#[tauri::command]
+#[instrument(skip_all)]
+fn segment_practice_content(text: String) -> SegmentedText {
+    segmentation::segment_text(&text)
+}
+
+// 開始逐句練習，練習與各片段的成績保存在數據庫中
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn start_drill_session(
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<DrillSessionInfo, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    drills::start_session(db, &text)
+}
+
+// 片段的參考音頻，已合成過的直接讀取緩存
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn synthesize_segment(
+    session_id: String,
+    segment_id: String,
+    voice: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<SegmentAudio, String> {
+    let voice = voice.unwrap_or_else(|| DEFAULT_TTS_VOICE.to_string());
+    let (text, cached) = {
+        let database = state.database.lock().await;
+        let db = database.as_ref().ok_or("數據庫未初始化")?;
+        let content = drills::load_session(db, &session_id)?;
+        let text = content.find_segment(&segment_id).ok_or("片段不存在")?.to_string();
+        let cached = drills::cached_segment_audio(db, &text, &voice);
+        (text, cached)
+    };
+
+    let clip = match cached {
+        Some(clip) => clip,
+        None => synthesize_and_cache(&state, &text, &voice).await?,
+    };
+
+    Ok(SegmentAudio {
+        segment_id,
+        text,
+        duration_secs: clip.samples.len() as f64 / clip.sample_rate as f64,
+        mime_type: AudioFormat::Wav.mime_type().to_string(),
+        audio_data: audio::encode_base64(&clip.to_wav_bytes()?),
+    })
+}
+
+// 合成一段文本並寫入 TTS 緩存，下次練習同一文本時直接讀取
+async fn synthesize_and_cache(state: &AppState, text: &str, voice: &str) -> Result<AudioClip, String> {
+    let audio = {
+        let gemini_service = state.gemini_service.lock().await;
+        let service = gemini_service
+            .as_ref()
+            .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+        service.synthesize_speech(text, Some(voice)).await.map_err(|e| {
+            error!(error = %e, "Gemini TTS error");
+            format!("Gemini語音合成失敗: {}", e)
+        })?
+    };
+    let clip = AudioClip::from_inline_audio(&audio.mime_type, &audio.data)?;
+    if let Some(db) = state.database.lock().await.as_ref() {
+        if let Err(e) = drills::cache_segment_audio(db, text, voice, &clip) {
+            warn!(error = %e, "Failed to cache segment audio");
+        }
+    }
+    Ok(clip)
+}
+
+// 逐句練習中單個片段的評分結果
+#[derive(Debug, Serialize)]
+struct SegmentAttemptResult {
+    segment_id: String,
+    text: String,
+    attempt_id: i64,
+    passed: bool,
+    #[serde(flatten)]
+    result: PronunciationResult,
+}
+
+// 評估一個句子或短語塊的錄音；已合成的片段音頻作為韻律分析的參考
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn score_segment_attempt(
+    session_id: String,
+    segment_id: String,
+    audio_data: String,
+    voice: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<SegmentAttemptResult, String> {
+    let voice = voice.unwrap_or_else(|| DEFAULT_TTS_VOICE.to_string());
+    let (text, reference) = {
+        let database = state.database.lock().await;
+        let db = database.as_ref().ok_or("數據庫未初始化")?;
+        let content = drills::load_session(db, &session_id)?;
+        let text = content.find_segment(&segment_id).ok_or("片段不存在")?.to_string();
+        let reference = drills::cached_segment_audio(db, &text, &voice);
+        (text, reference)
+    };
+
+    let result = assess_pronunciation(&state, &audio_data, &text, reference, "drill").await?;
+    let overall = result.scores.get("overall").copied().unwrap_or_default();
+
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let attempt_id = drills::record_attempt(db, &session_id, &segment_id, &text, &result.scores, result.recording_id)?;
+    Ok(SegmentAttemptResult {
+        segment_id,
+        text,
+        attempt_id,
+        passed: overall >= drills::PASS_THRESHOLD,
+        result,
+    })
+}
+
+// 逐句練習的結果：各句子和短語塊的最近得分，以及需要重練的片段
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_drill_session_result(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<DrillSessionResult, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    drills::session_result(db, &session_id)
+}
+
+// 各音位對比的弱項排名，依據評分中的音位替換和最小對立練習的答題記錄
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_weak_contrasts(state: State<'_, AppState>) -> Result<Vec<ContrastSummary>, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    minimal_pairs::weak_contrasts(db)
+}
+
+// 生成最小對立練習；不指定對比時選擇學習者最弱的一項
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn generate_minimal_pair_drill(
+    contrast: Option<String>,
+    pair_count: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<MinimalPairDrill, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let contrast = match contrast {
+        Some(id) => minimal_pairs::find_contrast(&id).ok_or_else(|| format!("未知的音位對比：{}", id))?,
+        None => {
+            let weakest = minimal_pairs::weak_contrasts(db)?;
+            let id = weakest.first().map(|c| c.contrast.id.clone()).unwrap_or_default();
+            minimal_pairs::find_contrast(&id).unwrap_or(&minimal_pairs::CONTRASTS[0])
+        }
+    };
+    minimal_pairs::generate_drill(db, contrast, pair_count.unwrap_or(6).clamp(2, 12))
+}
+
+// 題目的示範音頻：聽辨題為隨機選中的目標詞，讀詞題為要讀的詞
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn synthesize_minimal_pair(
+    item_id: String,
+    voice: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<MinimalPairAudio, String> {
+    let voice = voice.unwrap_or_else(|| DEFAULT_TTS_VOICE.to_string());
+    let (target, cached) = {
+        let database = state.database.lock().await;
+        let db = database.as_ref().ok_or("數據庫未初始化")?;
+        let item = minimal_pairs::load_item(db, &item_id)?;
+        let cached = drills::cached_segment_audio(db, &item.target, &voice);
+        (item.target, cached)
+    };
+
+    let clip = match cached {
+        Some(clip) => clip,
+        None => synthesize_and_cache(&state, &target, &voice).await?,
+    };
+    Ok(MinimalPairAudio {
+        item_id,
+        duration_secs: clip.samples.len() as f64 / clip.sample_rate as f64,
+        mime_type: AudioFormat::Wav.mime_type().to_string(),
+        audio_data: audio::encode_base64(&clip.to_wav_bytes()?),
+    })
+}
+
+// 提交聽辨題的選擇
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn answer_minimal_pair(
+    item_id: String,
+    choice: String,
+    state: State<'_, AppState>,
+) -> Result<MinimalPairAnswer, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    minimal_pairs::answer_listening(db, &item_id, &choice)
+}
+
+// 讀詞題的評分結果：識別出的詞與目標詞一致才算讀對
+#[derive(Debug, Serialize)]
+struct MinimalPairSpeakResult {
+    item_id: String,
+    target: String,
+    heard: Option<String>,
+    correct: bool,
+    contrast_accuracy: Option<f64>,
+    #[serde(flatten)]
+    result: PronunciationResult,
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn score_minimal_pair(
+    item_id: String,
+    audio_data: String,
+    voice: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<MinimalPairSpeakResult, String> {
+    let voice = voice.unwrap_or_else(|| DEFAULT_TTS_VOICE.to_string());
+    let (item, reference) = {
+        let database = state.database.lock().await;
+        let db = database.as_ref().ok_or("數據庫未初始化")?;
+        let item = minimal_pairs::load_item(db, &item_id)?;
+        if item.kind != minimal_pairs::ExerciseKind::Speak {
+            return Err("這道題需要選擇答案".to_string());
+        }
+        let reference = drills::cached_segment_audio(db, &item.target, &voice);
+        (item, reference)
+    };
+
+    // 是否讀成了對比詞只能由識別結果判斷，沒有語音識別時無法評分
+    let clip = AudioClip::from_base64(&audio_data)?;
+    let transcript = transcribe_recording(&state, &audio_data, &clip)
+        .await?
+        .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+    let spoken = text::tokenize_words(&transcript.text);
+    let heard = spoken
+        .iter()
+        .find(|w| item.words.contains(w))
+        .or_else(|| spoken.iter().find(|w| !text::is_filled_pause(w)))
+        .cloned();
+    let correct = heard.as_deref() == Some(item.target.as_str());
+
+    // 轉寫結果已緩存，評分時不會重複識別
+    let result = assess_pronunciation(&state, &audio_data, &item.target, reference, "minimal_pairs").await?;
+
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let response = heard.clone().unwrap_or_default();
+    minimal_pairs::record_attempt(db, &item_id, &item, &response, correct, result.scores.get("overall").copied())?;
+    Ok(MinimalPairSpeakResult {
+        item_id,
+        target: item.target.clone(),
+        heard,
+        correct,
+        contrast_accuracy: minimal_pairs::contrast_progress(db, item.contrast)?.accuracy,
+        result,
+    })
+}
+
+// 某個音位對比的正確率變化，按天匯總
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_contrast_progress(
+    contrast: String,
+    state: State<'_, AppState>,
+) -> Result<ContrastProgress, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let contrast = minimal_pairs::find_contrast(&contrast).ok_or_else(|| format!("未知的音位對比：{}", contrast))?;
+    minimal_pairs::contrast_progress(db, contrast)
+}
+
+// 生成今天的學習計劃：到期複習、薄弱音位、未過關的句子、當前程度的新內容和一次口語問答，
+// 按時間預算取捨。傳入 goals 時同時保存為新的學習目標
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn generate_daily_plan(
+    time_budget_minutes: u32,
+    goals: Option<LearningGoals>,
+    regenerate: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<DailyPlan, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let goals = match goals {
+        Some(goals) => {
+            planner::save_goals(db, &goals)?;
+            goals
+        }
+        None => planner::load_goals(db)?,
+    };
+    planner::daily_plan(db, time_budget_minutes.clamp(5, 240), &goals, regenerate.unwrap_or(false))
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_learning_goals(state: State<'_, AppState>) -> Result<LearningGoals, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    planner::load_goals(db)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn complete_plan_activity(
+    activity_id: i64,
+    completed: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<DailyPlan, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    planner::set_activity_completed(db, activity_id, completed.unwrap_or(true))
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_practice_goals(state: State<'_, AppState>) -> Result<PracticeGoals, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    goals::load_goals(db)
+}
+
+// 保存每日時長、每週次數目標與提醒時間，返回規範化後的設置
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn set_practice_goals(goals: PracticeGoals, state: State<'_, AppState>) -> Result<PracticeGoals, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    goals::save_goals(db, &goals)
+}
+
+// 前端在一段練習結束時上報時長，計入每日目標
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn record_practice_time(
+    seconds: f64,
+    activity: String,
+    state: State<'_, AppState>,
+) -> Result<GoalProgress, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    goals::record_practice_time(db, seconds, &activity, None)?;
+    goals::goal_progress(db)
+}
+
+// 今日進度、本週次數與連續天數（含凍結）
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_goal_progress(state: State<'_, AppState>) -> Result<GoalProgress, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    goals::goal_progress(db)
+}
+
+// 後台每半分鐘檢查一次提醒時間；今天已達標時跳過提醒
+fn spawn_reminder_loop(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut fired = std::collections::HashSet::new();
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+            let state = app.state::<AppState>();
+            let reminder = match state.database.lock().await.as_ref() {
+                Some(db) => goals::due_reminder(db, &mut fired),
+                None => continue,
+            };
+            match reminder {
+                Ok(Some(reminder)) => {
+                    if let Err(e) = app.notification().builder().title(reminder.title).body(reminder.body).show() {
+                        error!(error = %e, "Failed to show reminder");
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => error!(error = %e, "Reminder check failed"),
+            }
+        }
+    });
+}
+
+// 後台按設置的間隔自動同步；失敗（如離線）後按指數退避重試，恢復後自動推送離線期間的修改
+fn spawn_sync_loop(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut next_attempt = tokio::time::Instant::now();
+        let mut backoff_secs = 30;
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+            let state = app.state::<AppState>();
+            let config = match state.database.lock().await.as_ref() {
+                Some(db) => sync::load_config(db),
+                None => continue,
+            };
+            let config = match config {
+                Ok(config) if config.enabled => config,
+                Ok(_) => continue,
+                Err(e) => {
+                    error!(error = %e, "Failed to load sync config");
+                    continue;
+                }
+            };
+            if tokio::time::Instant::now() < next_attempt {
+                continue;
+            }
+            // 手動同步正在進行時跳過這一輪
+            let Ok(_guard) = state.sync_guard.try_lock() else {
+                continue;
+            };
+            match sync::sync_now(&state.database).await {
+                Ok(report) => {
+                    backoff_secs = 30;
+                    next_attempt = tokio::time::Instant::now() + tokio::time::Duration::from_secs(config.interval_secs);
+                    if report.applied > 0 {
+                        if let Err(e) = app.emit("sync-completed", &report) {
+                            error!(error = %e, "Failed to emit sync event");
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "Sync failed");
+                    next_attempt = tokio::time::Instant::now() + tokio::time::Duration::from_secs(backoff_secs);
+                    backoff_secs = (backoff_secs * 2).min(1800);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_sync_config(state: State<'_, AppState>) -> Result<SyncConfig, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    sync::load_config(db)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn set_sync_config(config: SyncConfig, state: State<'_, AppState>) -> Result<SyncConfig, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    sync::save_config(db, &config)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_sync_status(state: State<'_, AppState>) -> Result<SyncStatus, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    sync::status(db)
+}
+
+// 立即同步一次，未啟用自動同步時也可以手動觸發
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn sync_now(state: State<'_, AppState>) -> Result<SyncReport, String> {
+    let _guard = state.sync_guard.try_lock().map_err(|_| "同步正在進行中".to_string())?;
+    sync::sync_now(&state.database).await
+}
+
+// 保存練習記錄；scores 中的非數值字段（詳細分析結果）不入庫
+#[tauri::command]
+#[instrument(skip_all, err)]
 async fn save_practice_record(
     topic: String,
-    scores: HashMap<String, f64>,
+    scores: HashMap<String, Value>,
     feedback: String,
+    recording_id: Option<i64>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<i64, String> {
+    let numeric_scores: HashMap<String, f64> = scores
+        .iter()
+        .filter(|(key, _)| key.as_str() != "recording_id")
+        .filter_map(|(key, value)| value.as_f64().map(|v| (key.clone(), v)))
+        .collect();
+    let recording_id = recording_id.or_else(|| scores.get("recording_id").and_then(Value::as_i64));
+
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let id = db.insert_practice_record(&topic, &numeric_scores, &feedback, recording_id)?;
+    // 評分時已計入的錄音不會重複計算
+    if let Some(recording_id) = recording_id {
+        if let Err(e) = goals::record_recording_time(db, recording_id, "practice") {
+            error!(error = %e, "Failed to record practice time");
+        }
+    }
+
+    // 成就檢查失敗不影響保存結果
+    match achievements::evaluate(db) {
+        Ok(earned) => {
+            for achievement in earned {
+                if let Err(e) = app.emit("achievement-unlocked", &achievement) {
+                    error!(error = %e, "Failed to emit achievement event");
+                }
+            }
+        }
+        Err(e) => error!(error = %e, "Achievement evaluation failed"),
+    }
+    Ok(id)
+}
+
+// 全部徽章及解鎖進度
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_achievements(state: State<'_, AppState>) -> Result<Vec<Achievement>, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    achievements::list(db)
+}
+
+// 同一參考文本的歷次錄音
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn list_recording_attempts(
+    reference_text: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<RecordingInfo>, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    recordings::list_attempts(db, &reference_text)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_recording(id: i64, state: State<'_, AppState>) -> Result<RecordingAudio, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    recordings::load_recording(db, id)
+}
+
+// 返回兩次錄音供並排回放
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn compare_recordings(
+    first_id: i64,
+    second_id: i64,
+    state: State<'_, AppState>,
+) -> Result<RecordingComparison, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    recordings::compare_recordings(db, first_id, second_id)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn delete_recording(id: i64, state: State<'_, AppState>) -> Result<String, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    recordings::delete_recording(db, id)?;
+    Ok("Recording deleted".to_string())
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_retention_policy(state: State<'_, AppState>) -> Result<RetentionPolicy, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    recordings::retention_policy(db)
+}
+
+// 更新保留策略並立即執行，返回刪除的錄音數量
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn set_retention_policy(
+    policy: RetentionPolicy,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    recordings::set_retention_policy(db, &policy)
+}
+
+// 從練習文本中提取生詞存入生詞本，返回新加入的詞條（已有的詞不重複添加）
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn extract_vocabulary(
+    passage: String,
+    difficulty_level: String,
+    native_language: Option<String>,
+    max_words: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<VocabularyEntry>, String> {
+    let candidates = {
+        let gemini_service = state.gemini_service.lock().await;
+        let service = gemini_service
+            .as_ref()
+            .ok_or("Gemini service not initialized. Please set up your API key first.")?;
+        service
+            .extract_vocabulary(
+                &passage,
+                &difficulty_level,
+                native_language.as_deref().unwrap_or("繁體中文"),
+                max_words.unwrap_or(8).clamp(1, 20),
+            )
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Gemini vocabulary error");
+                format!("生詞提取失敗: {}", e)
+            })?
+    };
+
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    vocabulary::add_candidates(db, &candidates, passage.trim(), &difficulty_level)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn list_vocabulary(
+    filter: Option<VocabularyFilter>,
+    state: State<'_, AppState>,
+) -> Result<Vec<VocabularyEntry>, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    vocabulary::list_entries(db, &filter.unwrap_or_default())
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn tag_vocabulary(
+    id: i64,
+    tags: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<VocabularyEntry, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    vocabulary::set_tags(db, id, &tags)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn update_vocabulary(
+    id: i64,
+    update: VocabularyUpdate,
+    state: State<'_, AppState>,
+) -> Result<VocabularyEntry, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    vocabulary::update_entry(db, id, &update)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn delete_vocabulary(id: i64, state: State<'_, AppState>) -> Result<String, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    vocabulary::delete_entry(db, id)?;
+    Ok("Vocabulary entry deleted".to_string())
+}
+
+// 記錄一次複習（quality 為 0-5 的自評），返回更新了下次複習時間的詞條
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn review_vocabulary(
+    id: i64,
+    quality: u8,
+    state: State<'_, AppState>,
+) -> Result<VocabularyEntry, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    vocabulary::review_entry(db, id, quality)
+}
+
+// 導出練習記錄、評分歷史和生詞本。json 寫單個文件，csv 寫到一個目錄下的多個文件；返回寫入的文件路徑
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn export_learning_data(
+    format: String,
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let paths = match format.as_str() {
+        "json" => {
+            let path = match path {
+                Some(path) => path.into(),
+                None => export::default_export_path(db, "learning-data", "json")?,
+            };
+            export::export_json(db, &path)?;
+            vec![path]
+        }
+        "csv" => {
+            let dir = match path {
+                Some(path) => path.into(),
+                None => export::default_export_path(db, "learning-data", "")?,
+            };
+            export::export_csv(db, &dir)?
+        }
+        other => return Err(format!("不支持的導出格式：{}", other)),
+    };
+    Ok(paths.iter().map(|path| path.display().to_string()).collect())
+}
+
+// 把生詞本導出為 Anki 牌組，已合成過的發音一併打包；synthesize_missing 為 true 時先合成缺少的發音
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn export_anki_deck(
+    path: Option<String>,
+    deck_name: Option<String>,
+    synthesize_missing: Option<bool>,
+    voice: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<AnkiExportResult, String> {
+    let voice = voice.unwrap_or_else(|| DEFAULT_TTS_VOICE.to_string());
+    let (words, mut audio) = {
+        let database = state.database.lock().await;
+        let db = database.as_ref().ok_or("數據庫未初始化")?;
+        let entries = vocabulary::list_entries(db, &VocabularyFilter::default())?;
+        let mut audio = HashMap::new();
+        for entry in &entries {
+            if let Some(clip) = drills::cached_segment_audio(db, &entry.word, &voice) {
+                audio.insert(entry.id, clip.to_wav_bytes()?);
+            }
+        }
+        let words: Vec<(i64, String)> = entries.into_iter().map(|entry| (entry.id, entry.word)).collect();
+        (words, audio)
+    };
+
+    // 合成期間不持有數據庫鎖，單個詞合成失敗不影響導出
+    if synthesize_missing.unwrap_or(false) {
+        let missing: Vec<&(i64, String)> = words.iter().filter(|(id, _)| !audio.contains_key(id)).collect();
+        for (id, word) in missing {
+            match synthesize_and_cache(&state, word, &voice).await {
+                Ok(clip) => {
+                    audio.insert(*id, clip.to_wav_bytes()?);
+                }
+                Err(e) => warn!(error = %e, word = %word, "Failed to synthesize audio"),
+            }
+        }
+    }
+
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let path = match path {
+        Some(path) => path.into(),
+        None => export::default_export_path(db, "vocabulary", "apkg")?,
+    };
+    let deck_name = deck_name.filter(|name| !name.trim().is_empty()).unwrap_or_else(|| "Web Chat 生詞本".to_string());
+    export::export_anki(db, &path, deck_name.trim(), &audio)
+}
+
+// 導入 JSON 導出文件，已存在的記錄不會重複添加
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn import_learning_data(path: String, state: State<'_, AppState>) -> Result<ImportSummary, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    export::import_json(db, std::path::Path::new(&path))
+}
+
+// 生成週報或月報，覆蓋包含 date 的那一週或那個月（默認本週、本月）。format 為 html（默認）或 pdf；
+// locale 如 zh-TW、zh-CN、en，默認繁體中文
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn generate_progress_report(
+    period: String,
+    date: Option<String>,
+    format: Option<String>,
+    locale: Option<String>,
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<GeneratedReport, String> {
+    let period = ReportPeriod::parse(&period)?;
+    let format = format.unwrap_or_else(|| "html".to_string());
+    let locale = ReportLocale::parse(locale.as_deref());
+    let learner = {
+        let profiles = state.profiles.lock().await;
+        profiles.as_ref().and_then(|store| store.get(store.active_id()).ok()).map(|profile| profile.name)
+    };
+
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let report = report::build(db, period, date.as_deref(), learner)?;
+    let path = match path {
+        Some(path) => path.into(),
+        None => export::default_export_path(db, &format!("{}-report", report.start_date), &format)?,
+    };
+    report::write(&report, locale, &format, &path)?;
+    Ok(GeneratedReport { path: path.display().to_string(), report })
+}
+
+// 當前檔案的簽名公鑰及指紋，師生可據此核對作業包和結果包的來源
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_signing_identity(state: State<'_, AppState>) -> Result<SigningIdentity, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::identity(db)
+}
+
+// 老師編寫作業並寫出簽名的作業包
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn create_assignment(
+    draft: AssignmentDraft,
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<AssignmentBundle, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let path = match path {
+        Some(path) => path.into(),
+        None => export::default_export_path(db, "assignment", "wcassign")?,
+    };
+    let assignment = assignments::create(db, &draft, &path)?;
+    Ok(AssignmentBundle { path: path.display().to_string(), assignment })
+}
+
+// 重新導出自己佈置的作業包
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn export_assignment_bundle(
+    assignment_id: String,
+    path: Option<String>,
+    state: State<'_, AppState>,
 ) -> Result<String, String> {
-    // 這裡應該保存到數據庫
-    println!("Saving practice record: topic={}, scores={:?}", topic, scores);
-    Ok("Practice record saved successfully".to_string())
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let path = match path {
+        Some(path) => path.into(),
+        None => export::default_export_path(db, "assignment", "wcassign")?,
+    };
+    assignments::export_bundle(db, &assignment_id, &path)?;
+    Ok(path.display().to_string())
 }
 
 #[tauri::command]
-async fn get_learning_stats() -> Result<HashMap<String, Value>, String> {
-    // 這裡應該從數據庫獲取學習統計
-    let mut stats = HashMap::new();
-    stats.insert("total_sessions".to_string(), Value::Number(serde_json::Number::from(42)));
-    stats.insert("average_score".to_string(), Value::Number(serde_json::Number::from_f64(78.5).unwrap()));
-    stats.insert("improvement_rate".to_string(), Value::Number(serde_json::Number::from_f64(12.3).unwrap()));
-    
-    Ok(stats)
+#[instrument(skip_all, err)]
+async fn import_assignment_bundle(path: String, state: State<'_, AppState>) -> Result<ImportedAssignment, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::import_assignment(db, std::path::Path::new(&path))
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn list_assignments(state: State<'_, AppState>) -> Result<Vec<AssignmentSummary>, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::list(db)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn delete_assignment(assignment_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::delete(db, &assignment_id)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_assignment_progress(assignment_id: String, state: State<'_, AppState>) -> Result<AssignmentProgress, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::progress(db, &assignment_id)
+}
+
+// 學生完成作業中某段的一次練習；scores 與 save_practice_record 相同，非數值項忽略
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn record_assignment_attempt(
+    assignment_id: String,
+    passage_id: String,
+    scores: HashMap<String, Value>,
+    transcript: Option<String>,
+    recording_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<PassageProgress, String> {
+    let numeric_scores = scores.iter().filter_map(|(key, value)| value.as_f64().map(|v| (key.clone(), v))).collect();
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::record_attempt(
+        db,
+        &assignment_id,
+        &passage_id,
+        &numeric_scores,
+        transcript.as_deref().unwrap_or_default(),
+        recording_id,
+    )
+}
+
+// 學生生成結果包交給老師；student_name 默認用當前檔案的名稱
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn export_assignment_result(
+    assignment_id: String,
+    student_name: Option<String>,
+    include_recordings: Option<bool>,
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ResultBundle, String> {
+    let student_name = match student_name {
+        Some(name) => name,
+        None => {
+            let profiles = state.profiles.lock().await;
+            let store = profiles.as_ref().ok_or("檔案列表未初始化")?;
+            store.get(store.active_id())?.name
+        }
+    };
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    let path = match path {
+        Some(path) => path.into(),
+        None => export::default_export_path(db, "assignment-result", "wcresult")?,
+    };
+    let result = assignments::export_result(db, &assignment_id, &student_name, include_recordings.unwrap_or(true), &path)?;
+    Ok(ResultBundle { path: path.display().to_string(), result })
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn import_assignment_result(path: String, state: State<'_, AppState>) -> Result<ResultImport, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::import_result(db, std::path::Path::new(&path))
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_assignment_results(assignment_id: String, state: State<'_, AppState>) -> Result<AssignmentResults, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    assignments::results(db, &assignment_id)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn list_profiles(state: State<'_, AppState>) -> Result<ProfileList, String> {
+    let profiles = state.profiles.lock().await;
+    Ok(profiles.as_ref().ok_or("檔案列表未初始化")?.list())
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn create_profile(
+    name: String,
+    level: Option<String>,
+    pin: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Profile, String> {
+    let mut profiles = state.profiles.lock().await;
+    let store = profiles.as_mut().ok_or("檔案列表未初始化")?;
+    let profile = store.create(&name, level.as_deref(), pin.as_deref())?;
+    let db = Database::open(&store.profile_dir(&profile.id))?;
+    apply_profile_level(&db, profile.level.clone())?;
+    Ok(profile)
+}
+
+// 切換到另一個檔案（或解鎖啟動時鎖定的當前檔案）：驗證 PIN 後替換數據庫和該檔案的 Gemini 服務，
+// 並清空上一個檔案的模型列表、錄音和跟讀狀態
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn switch_profile(id: String, pin: Option<String>, state: State<'_, AppState>) -> Result<Profile, String> {
+    let mut profiles = state.profiles.lock().await;
+    let store = profiles.as_mut().ok_or("檔案列表未初始化")?;
+    store.verify_pin(&id, pin.as_deref())?;
+    let db = open_database(&store.profile_dir(&id))?;
+    let service = models::restore_service(&db)?;
+    store.set_active(&id)?;
+
+    {
+        let mut gemini_service = state.gemini_service.lock().await;
+        *state.database.lock().await = Some(db);
+        *gemini_service = service;
+    }
+    *state.model_cache.lock().await = None;
+    *state.last_transcript.lock().await = None;
+    *state.recording.lock().await = None;
+    *state.shadowing.lock().await = None;
+    store.get(&id)
+}
+
+// 修改檔案名稱、程度或 PIN；設置了 PIN 的檔案需要提供當前 PIN
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn update_profile(
+    id: String,
+    update: ProfileUpdate,
+    current_pin: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Profile, String> {
+    let mut profiles = state.profiles.lock().await;
+    let store = profiles.as_mut().ok_or("檔案列表未初始化")?;
+    store.verify_pin(&id, current_pin.as_deref())?;
+    let profile = store.update(&id, &update)?;
+    if update.level.is_some() {
+        if id == store.active_id() {
+            let database = state.database.lock().await;
+            apply_profile_level(database.as_ref().ok_or("數據庫未初始化")?, profile.level.clone())?;
+        } else {
+            apply_profile_level(&Database::open(&store.profile_dir(&id))?, profile.level.clone())?;
+        }
+    }
+    Ok(profile)
+}
+
+// 刪除檔案及其全部練習數據
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn delete_profile(id: String, pin: Option<String>, state: State<'_, AppState>) -> Result<(), String> {
+    let mut profiles = state.profiles.lock().await;
+    let store = profiles.as_mut().ok_or("檔案列表未初始化")?;
+    store.verify_pin(&id, pin.as_deref())?;
+    store.delete(&id)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn get_learning_stats(state: State<'_, AppState>) -> Result<LearningStats, String> {
+    let database = state.database.lock().await;
+    let db = database.as_ref().ok_or("數據庫未初始化")?;
+    goals::learning_stats(db)
+}
+
+// 應用內調試面板：最近的日誌，level 為最低級別
+#[tauri::command]
+#[instrument(skip_all, level = "debug", err)]
+fn get_recent_logs(level: Option<String>, limit: Option<usize>) -> Result<Vec<LogEntry>, String> {
+    logging::recent(level.as_deref(), limit)
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+fn get_log_level() -> Result<String, String> {
+    logging::level()
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+fn set_log_level(level: String) -> Result<String, String> {
+    let level = logging::set_level(&level)?;
+    info!(level = %level, "Log level changed");
+    Ok(level)
+}
+
+// 逐項檢查各子系統。audio_sample 為前端錄下的一小段麥克風音頻（base64），用於檢查輸入設備
+async fn collect_diagnostics(
+    audio_sample: Option<&str>,
+    state: &State<'_, AppState>,
+    app: &AppHandle,
+) -> Result<(DiagnosticsReport, std::path::PathBuf), String> {
+    // 網絡檢查可能要等好幾秒，期間不佔用數據庫
+    let mut checks = {
+        let gemini_service = state.gemini_service.lock().await;
+        diagnostics::check_gemini(gemini_service.as_ref()).await
+    };
+    let database = state.database.lock().await;
+    let data_dir = match database.as_ref() {
+        Some(db) => db.data_dir().to_path_buf(),
+        None => app.path().app_data_dir().map_err(|e| e.to_string())?,
+    };
+    checks.extend(diagnostics::check_local(database.as_ref(), &data_dir, audio_sample));
+    Ok((diagnostics::report(checks), data_dir))
+}
+
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn diagnostics(
+    audio_sample: Option<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<DiagnosticsReport, String> {
+    let (report, _) = collect_diagnostics(audio_sample.as_deref(), &state, &app).await?;
+    info!(status = ?report.status, "Diagnostics completed");
+    Ok(report)
+}
+
+// 診斷結果連同最近的日誌打包成 zip，密鑰和音頻數據已脫敏，可直接附在問題反饋中
+#[tauri::command]
+#[instrument(skip_all, err)]
+async fn export_diagnostics(
+    audio_sample: Option<String>,
+    path: Option<String>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<DiagnosticsBundle, String> {
+    let (report, data_dir) = collect_diagnostics(audio_sample.as_deref(), &state, &app).await?;
+    let path = path.map(std::path::PathBuf::from).unwrap_or_else(|| diagnostics::default_bundle_path(&data_dir));
+    diagnostics::export_bundle(&report, &path)?;
+    Ok(DiagnosticsBundle { path: path.to_string_lossy().to_string(), report })
 }
 
 // 備用反饋生成函數
@@ -230,16 +1950,73 @@ fn create_fallback_content(topic: &str, difficulty_level: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let app_state = AppState {
-        gemini_service: Mutex::new(None),
-    };
-    
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .manage(app_state)
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            // 日誌最先初始化，後面的錯誤才能記錄下來；失敗時只能輸出到終端
+            match app.path().app_log_dir() {
+                Ok(log_dir) => {
+                    if let Err(e) = logging::init(&log_dir) {
+                        eprintln!("Logging unavailable: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Logging unavailable: {}", e),
+            }
+            info!(version = env!("CARGO_PKG_VERSION"), "Application starting");
+            let data_dir = app.path().app_data_dir()?;
+            let profiles = match ProfileStore::open(&data_dir) {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    error!(error = %e, "Profile store error");
+                    None
+                }
+            };
+            // 設置了 PIN 的檔案啟動時保持鎖定，前端通過 switch_profile 輸入 PIN 後才打開數據庫
+            let database = match profiles.as_ref() {
+                Some(store) if !store.is_unlocked() => {
+                    info!("Active profile is locked until its PIN is entered");
+                    None
+                }
+                Some(store) => match open_database(&store.active_dir()) {
+                    Ok(db) => Some(db),
+                    Err(e) => {
+                        error!(error = %e, "Database error");
+                        None
+                    }
+                },
+                None => None,
+            };
+            let gemini_service = match database.as_ref().map(models::restore_service) {
+                Some(Ok(service)) => service,
+                Some(Err(e)) => {
+                    error!(error = %e, "Failed to restore Gemini settings");
+                    None
+                }
+                None => None,
+            };
+
+            // 發音詞典較大，在後台預先解析，避免首次評分時卡頓
+            std::thread::spawn(pronunciation::preload);
+
+            app.manage(AppState {
+                gemini_service: Mutex::new(gemini_service),
+                last_transcript: Mutex::new(None),
+                recording: Mutex::new(None),
+                database: Mutex::new(database),
+                shadowing: Mutex::new(None),
+                profiles: Mutex::new(profiles),
+                model_cache: Mutex::new(None),
+                sync_guard: Mutex::new(()),
+            });
+            spawn_reminder_loop(app.handle().clone());
+            spawn_sync_loop(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             initialize_gemini_service,
+            list_gemini_models,
             test_gemini_connection,
             get_ai_tutor_feedback,
             generate_practice_content,
@@ -248,9 +2025,78 @@ pub fn run() {
             text_to_speech,
             pronunciation_score,
             start_recording,
+            push_recording_audio,
             stop_recording,
+            detect_voice_activity,
+            check_recording_quality,
+            start_shadowing_session,
+            score_shadowing_segment,
+            finish_shadowing_session,
+            evaluate_open_response,
+            check_grammar,
+            lookup_pronunciation,
+            segment_practice_content,
+            start_drill_session,
+            synthesize_segment,
+            score_segment_attempt,
+            get_drill_session_result,
+            get_weak_contrasts,
+            generate_minimal_pair_drill,
+            synthesize_minimal_pair,
+            answer_minimal_pair,
+            score_minimal_pair,
+            get_contrast_progress,
+            generate_daily_plan,
+            get_learning_goals,
+            complete_plan_activity,
+            get_practice_goals,
+            set_practice_goals,
+            record_practice_time,
+            get_goal_progress,
             save_practice_record,
-            get_learning_stats
+            get_achievements,
+            list_recording_attempts,
+            get_recording,
+            compare_recordings,
+            delete_recording,
+            get_retention_policy,
+            set_retention_policy,
+            extract_vocabulary,
+            list_vocabulary,
+            tag_vocabulary,
+            update_vocabulary,
+            delete_vocabulary,
+            review_vocabulary,
+            export_learning_data,
+            export_anki_deck,
+            import_learning_data,
+            generate_progress_report,
+            get_signing_identity,
+            create_assignment,
+            export_assignment_bundle,
+            import_assignment_bundle,
+            list_assignments,
+            delete_assignment,
+            get_assignment_progress,
+            record_assignment_attempt,
+            export_assignment_result,
+            import_assignment_result,
+            get_assignment_results,
+            get_sync_config,
+            set_sync_config,
+            get_sync_status,
+            sync_now,
+            list_profiles,
+            create_profile,
+            switch_profile,
+            update_profile,
+            delete_profile,
+            get_learning_stats,
+            get_recent_logs,
+            get_log_level,
+            set_log_level,
+            diagnostics,
+            export_diagnostics
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
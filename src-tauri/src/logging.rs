@@ -0,0 +1,386 @@
+// 結構化日誌：JSON 格式寫入應用日誌目錄，按天輪換並只保留最近幾天；開發時同時輸出到終端。
+// 所有輸出在寫出前統一脫敏，隱藏 API 密鑰等憑證並省略音頻之類的大段 base64 數據
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+const FILE_PREFIX: &str = "web-chat";
+const FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+// 日誌級別保存在日誌目錄中，與學習者檔案無關，也不參與同步
+const LEVEL_FILE: &str = "log-level";
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+const DEFAULT_ENTRIES: usize = 200;
+const MAX_ENTRIES: usize = 2000;
+// 連續超過這個長度的 base64 字符視為音頻等二進制數據
+const PAYLOAD_THRESHOLD: usize = 256;
+const REDACTED: &str = "[已隱藏]";
+
+// 參數名或字段名為這些詞時，其值一律隱藏
+const SENSITIVE_KEYS: &[&str] = &[
+    "key",
+    "api_key",
+    "apikey",
+    "x-goog-api-key",
+    "token",
+    "access_token",
+    "password",
+    "passphrase",
+    "secret",
+    "authorization",
+    "bearer",
+];
+
+struct Logger {
+    dir: PathBuf,
+    filter: reload::Handle<Targets, Registry>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+// 運行時登記的憑證（如用戶填入的 API 密鑰），原樣出現時直接替換
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    // 所在的命令或操作，即當前 span 的名稱
+    pub span: Option<String>,
+    pub fields: Map<String, Value>,
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level.trim()).map_err(|_| format!("無效的日誌級別：{}", level))
+}
+
+// 本應用按所選級別記錄，依賴庫（HTTP 客戶端等）最多記錄到 warn，避免刷屏
+fn targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+        .with_default(level.min(LevelFilter::WARN))
+}
+
+fn saved_level(dir: &Path) -> LevelFilter {
+    std::fs::read_to_string(dir.join(LEVEL_FILE))
+        .ok()
+        .and_then(|level| parse_level(&level).ok())
+        .unwrap_or(DEFAULT_LEVEL)
+}
+
+pub fn init(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("創建日誌目錄失敗：{}", e))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix(FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)
+        .map_err(|e| format!("創建日誌文件失敗：{}", e))?;
+    let (filter, handle) = reload::Layer::new(targets(saved_level(dir)));
+    let file_layer = tracing_subscriber::fmt::layer()
+        .json()
+        .with_ansi(false)
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(Redacting(appender));
+    let console_layer = tracing_subscriber::fmt::layer().with_writer(Redacting(io::stderr));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(console_layer)
+        .try_init()
+        .map_err(|e| format!("初始化日誌失敗：{}", e))?;
+    LOGGER
+        .set(Logger { dir: dir.to_path_buf(), filter: handle })
+        .map_err(|_| "日誌已初始化".to_string())
+}
+
+fn logger() -> Result<&'static Logger, String> {
+    LOGGER.get().ok_or_else(|| "日誌未初始化".to_string())
+}
+
+pub fn level() -> Result<String, String> {
+    Ok(saved_level(&logger()?.dir).to_string().to_lowercase())
+}
+
+// 立即生效並保存，下次啟動沿用
+pub fn set_level(level: &str) -> Result<String, String> {
+    let logger = logger()?;
+    let level = parse_level(level)?;
+    logger.filter.reload(targets(level)).map_err(|e| format!("設置日誌級別失敗：{}", e))?;
+    std::fs::write(logger.dir.join(LEVEL_FILE), level.to_string())
+        .map_err(|e| format!("保存日誌級別失敗：{}", e))?;
+    Ok(level.to_string().to_lowercase())
+}
+
+pub fn register_secret(secret: &str) {
+    let secret = secret.trim();
+    // 太短的值替換起來容易誤傷正常內容
+    if secret.len() < 8 {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap_or_else(|e| e.into_inner());
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+fn is_base64_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '_' | '-')
+}
+
+// 值的分隔符：查詢參數和 tracing 字段用 `=`，JSON 和 Debug 輸出用 `:`，也可能帶（轉義的）引號
+fn value_start(rest: &str) -> Option<usize> {
+    let mut chars = rest.char_indices().peekable();
+    let mut index = 0;
+    let mut separator = false;
+    while let Some(&(i, c)) = chars.peek() {
+        match c {
+            '"' | '\\' => {}
+            '=' | ':' if !separator => separator = true,
+            ' ' if separator => {}
+            _ => {
+                index = i;
+                break;
+            }
+        }
+        index = i + c.len_utf8();
+        chars.next();
+    }
+    separator.then_some(index)
+}
+
+fn redact_fields(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(is_word_char) {
+        let end = rest[start..].find(|c| !is_word_char(c)).map_or(rest.len(), |i| start + i);
+        output.push_str(&rest[..end]);
+        let word = rest[start..end].to_ascii_lowercase();
+        rest = &rest[end..];
+        if !SENSITIVE_KEYS.contains(&word.as_str()) {
+            continue;
+        }
+        // Bearer 後面直接跟憑證
+        let value = if word == "bearer" { rest.strip_prefix(' ').map(|_| 1) } else { value_start(rest) };
+        let Some(mut offset) = value else {
+            continue;
+        };
+        // Authorization 頭隱藏的是認證方式後面的憑證
+        if let Some(scheme) = ["Bearer ", "Basic "].iter().find(|scheme| rest[offset..].starts_with(*scheme)) {
+            offset += scheme.len();
+        }
+        let length = rest[offset..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\\' | '&' | ',' | '}' | ')' | ']'))
+            .unwrap_or(rest.len() - offset);
+        if length == 0 {
+            continue;
+        }
+        output.push_str(&rest[..offset]);
+        output.push_str(REDACTED);
+        rest = &rest[offset + length..];
+    }
+    output.push_str(rest);
+    output
+}
+
+// Google API 密鑰有固定前綴；過長的 base64 串按音頻等二進制數據省略
+fn redact_payloads(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(is_base64_char) {
+        let end = rest[start..].find(|c| !is_base64_char(c)).map_or(rest.len(), |i| start + i);
+        output.push_str(&rest[..start]);
+        let run = &rest[start..end];
+        if run.starts_with("AIza") && run.len() >= 39 {
+            output.push_str(REDACTED);
+        } else if run.len() >= PAYLOAD_THRESHOLD {
+            output.push_str(&format!("[已省略 {} 字節數據]", run.len() * 3 / 4));
+        } else {
+            output.push_str(run);
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+pub fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    for secret in SECRETS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        if text.contains(secret.as_str()) {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+    }
+    redact_payloads(&redact_fields(&text))
+}
+
+// 包裝日誌輸出：格式化好的一條記錄先緩存，脫敏後再整體寫出
+struct Redacting<M>(M);
+
+struct RedactingWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter { inner: self.0.make_writer(), buffer: Vec::new() }
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            let text = redact(&String::from_utf8_lossy(&self.buffer));
+            self.buffer.clear();
+            self.inner.write_all(text.as_bytes())?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn parse_entry(line: &str) -> Option<LogEntry> {
+    let Value::Object(mut entry) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    let mut text = |key: &str| match entry.remove(key) {
+        Some(Value::String(value)) => value,
+        _ => String::new(),
+    };
+    let (timestamp, level, target) = (text("timestamp"), text("level"), text("target"));
+    let mut fields = match entry.remove("fields") {
+        Some(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    let message = match fields.remove("message") {
+        Some(Value::String(message)) => message,
+        _ => String::new(),
+    };
+    let span = entry.get("span").and_then(|span| span["name"].as_str()).map(str::to_string);
+    Some(LogEntry { timestamp, level, target, message, span, fields })
+}
+
+// 最近的日誌，新的在前。level 為最低級別，默認 info
+pub fn recent(level: Option<&str>, limit: Option<usize>) -> Result<Vec<LogEntry>, String> {
+    let logger = logger()?;
+    let minimum = level.map(parse_level).transpose()?.unwrap_or(DEFAULT_LEVEL);
+    let limit = limit.unwrap_or(DEFAULT_ENTRIES).clamp(1, MAX_ENTRIES);
+
+    // 文件名帶日期（web-chat.2026-10-19.log），按名稱倒序即從新到舊
+    let mut files = std::fs::read_dir(&logger.dir)
+        .map_err(|e| format!("讀取日誌目錄失敗：{}", e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+                name.starts_with(FILE_PREFIX) && name.ends_with(&format!(".{}", FILE_SUFFIX))
+            })
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| b.cmp(a));
+
+    let mut entries = Vec::new();
+    for path in files {
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::warn!(error = %e, file = %path.display(), "Failed to read log file");
+                continue;
+            }
+        };
+        for entry in content.lines().rev().filter_map(parse_entry) {
+            if Level::from_str(&entry.level).is_ok_and(|entry_level| entry_level <= minimum) {
+                entries.push(entry);
+                if entries.len() >= limit {
+                    return Ok(entries);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_sensitive_fields() {
+        assert_eq!(
+            redact("GET https://example.com/v1/models?key=abc123def&alt=sse"),
+            "GET https://example.com/v1/models?key=[已隱藏]&alt=sse"
+        );
+        assert_eq!(redact(r#"{"api_key":"abc123def","model":"gemini"}"#), r#"{"api_key":"[已隱藏]","model":"gemini"}"#);
+        // 日誌中的 JSON 字符串帶轉義引號
+        assert_eq!(redact(r#"body="{\"password\": \"hunter22\"}""#), r#"body="{\"password\": \"[已隱藏]\"}""#);
+        assert_eq!(redact("Authorization: Bearer ya29.token"), "Authorization: Bearer [已隱藏]");
+        assert_eq!(redact("token=abc error=timeout"), "token=[已隱藏] error=timeout");
+    }
+
+    #[test]
+    fn leaves_ordinary_text_alone() {
+        for text in ["the key point is timing", "monkey: banana", "keyboard=qwerty", "key=", "score: 92.5"] {
+            assert_eq!(redact(text), text);
+        }
+    }
+
+    #[test]
+    fn redacts_api_keys_and_payloads() {
+        let key = format!("AIza{}", "x".repeat(35));
+        assert_eq!(redact(&format!("using {} now", key)), "using [已隱藏] now");
+
+        let payload = "QUJD".repeat(100);
+        assert_eq!(redact(&format!(r#"{{"audio_data":"{}"}}"#, payload)), r#"{"audio_data":"[已省略 300 字節數據]"}"#);
+        let short = "QUJD".repeat(10);
+        assert_eq!(redact(&short), short);
+    }
+
+    #[test]
+    fn redacts_registered_secrets() {
+        register_secret("short");
+        register_secret("sk-live-0123456789");
+        assert_eq!(redact("short value sk-live-0123456789!"), "short value [已隱藏]!");
+    }
+
+    #[test]
+    fn parses_json_log_lines() {
+        let line = r#"{"timestamp":"2026-10-19T08:00:00Z","level":"WARN","target":"app","fields":{"message":"Slow","elapsed":3},"span":{"name":"sync_now"}}"#;
+        let entry = parse_entry(line).unwrap();
+        assert_eq!((entry.level.as_str(), entry.message.as_str()), ("WARN", "Slow"));
+        assert_eq!(entry.span.as_deref(), Some("sync_now"));
+        assert_eq!(entry.fields["elapsed"], 3);
+        assert!(parse_entry("not json").is_none());
+    }
+}
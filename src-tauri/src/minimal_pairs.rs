@@ -0,0 +1,589 @@
+use rand::seq::SliceRandom;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::database::Database;
+use crate::pronunciation;
+
+// 統計弱項時只看最近一段時間的記錄
+const RECENT_DAYS: u32 = 30;
+
+// 常見的易混淆音位對比。pairs 為人工挑選的常用詞對，運行時按詞典校驗，不夠用時再從詞典中補充
+pub struct Contrast {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub phonemes: [&'static str; 2], // ARPAbet，不含重音標記
+    pairs: &'static [(&'static str, &'static str)],
+}
+
+pub const CONTRASTS: &[Contrast] = &[
+    Contrast {
+        id: "l-n",
+        label: "/l/–/n/",
+        phonemes: ["L", "N"],
+        pairs: &[("light", "night"), ("low", "no"), ("lead", "need"), ("lot", "not"), ("line", "nine"), ("lap", "nap"), ("lame", "name"), ("lice", "nice")],
+    },
+    Contrast {
+        id: "l-r",
+        label: "/l/–/r/",
+        phonemes: ["L", "R"],
+        pairs: &[("light", "right"), ("lead", "read"), ("long", "wrong"), ("lock", "rock"), ("collect", "correct"), ("glass", "grass"), ("play", "pray"), ("fly", "fry")],
+    },
+    Contrast {
+        id: "iy-ih",
+        label: "/iː/–/ɪ/",
+        phonemes: ["IY", "IH"],
+        pairs: &[("sheep", "ship"), ("leave", "live"), ("seat", "sit"), ("feet", "fit"), ("heat", "hit"), ("beat", "bit"), ("eat", "it"), ("deep", "dip")],
+    },
+    Contrast {
+        id: "v-w",
+        label: "/v/–/w/",
+        phonemes: ["V", "W"],
+        pairs: &[("vest", "west"), ("vine", "wine"), ("vet", "wet"), ("verse", "worse"), ("vent", "went"), ("vow", "wow"), ("veil", "whale"), ("viper", "wiper")],
+    },
+    Contrast {
+        id: "th-s",
+        label: "/θ/–/s/",
+        phonemes: ["TH", "S"],
+        pairs: &[("think", "sink"), ("thick", "sick"), ("thing", "sing"), ("math", "mass"), ("path", "pass"), ("thank", "sank"), ("mouth", "mouse"), ("thumb", "sum")],
+    },
+    Contrast {
+        id: "dh-d",
+        label: "/ð/–/d/",
+        phonemes: ["DH", "D"],
+        pairs: &[("they", "day"), ("then", "den"), ("those", "doze"), ("though", "dough"), ("there", "dare"), ("breathe", "breed")],
+    },
+    Contrast {
+        id: "ae-eh",
+        label: "/æ/–/e/",
+        phonemes: ["AE", "EH"],
+        pairs: &[("bad", "bed"), ("man", "men"), ("pan", "pen"), ("sad", "said"), ("had", "head"), ("band", "bend"), ("sat", "set"), ("bat", "bet")],
+    },
+    Contrast {
+        id: "b-v",
+        label: "/b/–/v/",
+        phonemes: ["B", "V"],
+        pairs: &[("berry", "very"), ("ban", "van"), ("best", "vest"), ("boat", "vote"), ("bet", "vet"), ("curb", "curve")],
+    },
+    Contrast {
+        id: "s-sh",
+        label: "/s/–/ʃ/",
+        phonemes: ["S", "SH"],
+        pairs: &[("see", "she"), ("sip", "ship"), ("sell", "shell"), ("seat", "sheet"), ("sort", "short"), ("mass", "mash"), ("save", "shave"), ("sign", "shine")],
+    },
+    Contrast {
+        id: "uw-uh",
+        label: "/uː/–/ʊ/",
+        phonemes: ["UW", "UH"],
+        pairs: &[("pool", "pull"), ("fool", "full"), ("suit", "soot"), ("luke", "look"), ("stewed", "stood"), ("who'd", "hood")],
+    },
+    Contrast {
+        id: "n-ng",
+        label: "/n/–/ŋ/",
+        phonemes: ["N", "NG"],
+        pairs: &[("sin", "sing"), ("thin", "thing"), ("win", "wing"), ("ban", "bang"), ("ran", "rang"), ("kin", "king")],
+    },
+    Contrast {
+        id: "ch-sh",
+        label: "/tʃ/–/ʃ/",
+        phonemes: ["CH", "SH"],
+        pairs: &[("chip", "ship"), ("cheap", "sheep"), ("chair", "share"), ("watch", "wash"), ("match", "mash"), ("chin", "shin"), ("choose", "shoes")],
+    },
+];
+
+pub fn find_contrast(id: &str) -> Option<&'static Contrast> {
+    CONTRASTS.iter().find(|c| c.id == id)
+}
+
+// 音位對屬於哪一個對比（不分方向）
+fn contrast_of(expected: &str, produced: &str) -> Option<&'static Contrast> {
+    CONTRASTS.iter().find(|c| {
+        let [a, b] = c.phonemes;
+        (expected == a && produced == b) || (expected == b && produced == a)
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContrastInfo {
+    pub id: String,
+    pub label: String,
+    pub phonemes: [String; 2],
+}
+
+impl From<&Contrast> for ContrastInfo {
+    fn from(contrast: &Contrast) -> Self {
+        ContrastInfo {
+            id: contrast.id.to_string(),
+            label: contrast.label.to_string(),
+            phonemes: contrast.phonemes.map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExerciseKind {
+    Listen, // 聽音辨詞：播放其中一個詞，學習者選出聽到的詞
+    Speak,  // 讀詞評分：讀出指定的詞，按識別結果判斷是否讀成了對比詞
+}
+
+impl ExerciseKind {
+    fn code(self) -> &'static str {
+        match self {
+            ExerciseKind::Listen => "listen",
+            ExerciseKind::Speak => "speak",
+        }
+    }
+}
+
+// 聽辨題不返回答案，target 只在讀詞題中給出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinimalPairItem {
+    pub id: String,
+    pub kind: ExerciseKind,
+    pub words: [String; 2],
+    pub ipa: [String; 2],
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinimalPairDrill {
+    pub drill_id: String,
+    pub contrast: ContrastInfo,
+    pub items: Vec<MinimalPairItem>,
+}
+
+// 已保存的題目，作答與評分時按 id 讀取
+pub struct StoredItem {
+    pub contrast: &'static Contrast,
+    pub kind: ExerciseKind,
+    pub words: [String; 2],
+    pub target: String,
+}
+
+// 題目的示範音頻；聽辨題不返回文本，避免洩露答案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinimalPairAudio {
+    pub item_id: String,
+    pub duration_secs: f64,
+    pub mime_type: String,
+    pub audio_data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinimalPairAnswer {
+    pub item_id: String,
+    pub correct: bool,
+    pub choice: String,
+    pub target: String,
+    pub contrast_accuracy: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContrastSummary {
+    #[serde(flatten)]
+    pub contrast: ContrastInfo,
+    pub error_count: usize, // 最近評分中出現的音位替換次數
+    pub attempts: usize,
+    pub correct: usize,
+    pub accuracy: Option<f64>,
+    pub weakness: f64, // 0-1，越高越需要練習
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyAccuracy {
+    pub date: String,
+    pub attempts: usize,
+    pub accuracy: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContrastProgress {
+    pub contrast: ContrastInfo,
+    pub attempts: usize,
+    pub accuracy: Option<f64>,
+    pub listen_accuracy: Option<f64>,
+    pub speak_accuracy: Option<f64>,
+    pub daily: Vec<DailyAccuracy>,
+}
+
+// 按編輯距離對齊兩個序列，返回 (參考位置, 實際位置) 對；插入或刪除的一側為 None
+fn align<T: PartialEq>(expected: &[T], actual: &[T]) -> Vec<(Option<usize>, Option<usize>)> {
+    let (n, m) = (expected.len(), actual.len());
+    let mut cost = vec![vec![0usize; m + 1]; n + 1];
+    for (i, row) in cost.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    cost[0] = (0..=m).collect();
+    for i in 1..=n {
+        for j in 1..=m {
+            let substitution = cost[i - 1][j - 1] + usize::from(expected[i - 1] != actual[j - 1]);
+            cost[i][j] = substitution.min(cost[i - 1][j] + 1).min(cost[i][j - 1] + 1);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && cost[i][j] == cost[i - 1][j - 1] + usize::from(expected[i - 1] != actual[j - 1]) {
+            pairs.push((Some(i - 1), Some(j - 1)));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && cost[i][j] == cost[i - 1][j] + 1 {
+            pairs.push((Some(i - 1), None));
+            i -= 1;
+        } else {
+            pairs.push((None, Some(j - 1)));
+            j -= 1;
+        }
+    }
+    pairs.reverse();
+    pairs
+}
+
+// 一次音位替換：參考詞中的 expected 被讀成了 produced
+#[derive(Debug, Clone)]
+pub struct PhonemeError {
+    pub word: String,
+    pub heard: String,
+    pub expected: String,
+    pub produced: String,
+}
+
+// 從參考文本與識別結果中找出音位替換。先按詞對齊，再對讀錯的詞按詞典讀音逐音位對齊；
+// 讀音相差太大的詞（多半是識別錯誤或換了詞）不計入
+pub fn phoneme_errors(expected: &[String], spoken: &[String]) -> Vec<PhonemeError> {
+    let mut errors = Vec::new();
+    for (i, j) in align(expected, spoken) {
+        let (Some(i), Some(j)) = (i, j) else {
+            continue;
+        };
+        if expected[i] == spoken[j] {
+            continue;
+        }
+        let (Some(target), Some(heard)) =
+            (pronunciation::dictionary_phonemes(&expected[i]), pronunciation::dictionary_phonemes(&spoken[j]))
+        else {
+            continue;
+        };
+        let pairs = align(&target, &heard);
+        let differences = pairs
+            .iter()
+            .filter(|(a, b)| match (a, b) {
+                (Some(a), Some(b)) => target[*a] != heard[*b],
+                _ => true,
+            })
+            .count();
+        if target.len() < 2 || differences > 2 {
+            continue;
+        }
+        for (a, b) in pairs {
+            if let (Some(a), Some(b)) = (a, b) {
+                if target[a] != heard[b] {
+                    errors.push(PhonemeError {
+                        word: expected[i].clone(),
+                        heard: spoken[j].clone(),
+                        expected: target[a].to_string(),
+                        produced: heard[b].to_string(),
+                    });
+                }
+            }
+        }
+    }
+    errors
+}
+
+pub fn record_errors(db: &Database, errors: &[PhonemeError]) -> Result<(), String> {
+    for error in errors {
+        db.conn()
+            .execute(
+                "INSERT INTO phoneme_errors (word, heard, expected, produced) VALUES (?1, ?2, ?3, ?4)",
+                params![error.word, error.heard, error.expected, error.produced],
+            )
+            .map_err(|e| format!("保存音位錯誤失敗：{}", e))?;
+    }
+    Ok(())
+}
+
+// 兩個詞的詞典讀音是否只在對比的音位上不同
+fn is_minimal_pair(contrast: &Contrast, first: &str, second: &str) -> bool {
+    let (Some(a), Some(b)) = (pronunciation::dictionary_phonemes(first), pronunciation::dictionary_phonemes(second))
+    else {
+        return false;
+    };
+    let [pa, pb] = contrast.phonemes;
+    a.len() == b.len() && {
+        let differences: Vec<(&str, &str)> = a.iter().zip(&b).filter(|(x, y)| x != y).map(|(x, y)| (*x, *y)).collect();
+        differences == [(pa, pb)]
+    }
+}
+
+// 候選詞對：人工詞對在前（打亂順序），詞典中找到的詞對在後
+fn candidate_pairs(contrast: &Contrast) -> Vec<(String, String)> {
+    let mut rng = rand::thread_rng();
+    let mut curated: Vec<(String, String)> = contrast
+        .pairs
+        .iter()
+        .filter(|(a, b)| is_minimal_pair(contrast, a, b))
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+    curated.shuffle(&mut rng);
+
+    let [a, b] = contrast.phonemes;
+    let mut discovered: Vec<(String, String)> = pronunciation::minimal_pairs(a, b)
+        .into_iter()
+        .filter(|pair| !curated.contains(pair))
+        .collect();
+    discovered.shuffle(&mut rng);
+    curated.extend(discovered);
+    curated
+}
+
+// 生成一組練習：每個詞對出一道聽辨題和一道讀詞題，聽辨題在前
+pub fn generate_drill(db: &Database, contrast: &'static Contrast, pair_count: usize) -> Result<MinimalPairDrill, String> {
+    let pairs: Vec<(String, String)> = candidate_pairs(contrast).into_iter().take(pair_count).collect();
+    if pairs.is_empty() {
+        return Err(format!("找不到 {} 的最小對立詞", contrast.label));
+    }
+
+    let drill_id = format!("{:016x}", rand::random::<u64>());
+    let mut items = Vec::new();
+    for kind in [ExerciseKind::Listen, ExerciseKind::Speak] {
+        for (first, second) in &pairs {
+            let target = if rand::random::<bool>() { first } else { second };
+            let id = format!("{:016x}", rand::random::<u64>());
+            db.conn()
+                .execute(
+                    "INSERT INTO minimal_pair_items (id, drill_id, contrast, kind, word_a, word_b, target)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![id, drill_id, contrast.id, kind.code(), first, second, target],
+                )
+                .map_err(|e| format!("保存練習題失敗：{}", e))?;
+            let ipa = |word: &str| pronunciation::lookup(word).map(|p| p.ipa).unwrap_or_default();
+            items.push(MinimalPairItem {
+                id,
+                kind,
+                words: [first.clone(), second.clone()],
+                ipa: [ipa(first), ipa(second)],
+                target: (kind == ExerciseKind::Speak).then(|| target.clone()),
+            });
+        }
+    }
+    Ok(MinimalPairDrill { drill_id, contrast: contrast.into(), items })
+}
+
+pub fn load_item(db: &Database, item_id: &str) -> Result<StoredItem, String> {
+    let row: Option<(String, String, String, String, String)> = db
+        .conn()
+        .query_row(
+            "SELECT contrast, kind, word_a, word_b, target FROM minimal_pair_items WHERE id = ?1",
+            [item_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .optional()
+        .map_err(|e| format!("查詢練習題失敗：{}", e))?;
+    let (contrast, kind, word_a, word_b, target) = row.ok_or("練習題不存在")?;
+    Ok(StoredItem {
+        contrast: find_contrast(&contrast).ok_or_else(|| format!("未知的音位對比：{}", contrast))?,
+        kind: if kind == "speak" { ExerciseKind::Speak } else { ExerciseKind::Listen },
+        words: [word_a, word_b],
+        target,
+    })
+}
+
+pub fn record_attempt(
+    db: &Database,
+    item_id: &str,
+    item: &StoredItem,
+    response: &str,
+    correct: bool,
+    score: Option<f64>,
+) -> Result<(), String> {
+    db.conn()
+        .execute(
+            "INSERT INTO contrast_attempts (item_id, contrast, kind, response, correct, score)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![item_id, item.contrast.id, item.kind.code(), response, correct, score],
+        )
+        .map_err(|e| format!("保存練習結果失敗：{}", e))?;
+    Ok(())
+}
+
+fn is_answered(db: &Database, item_id: &str) -> Result<bool, String> {
+    db.conn()
+        .query_row("SELECT EXISTS (SELECT 1 FROM contrast_attempts WHERE item_id = ?1)", [item_id], |row| row.get(0))
+        .map_err(|e| format!("查詢練習結果失敗：{}", e))
+}
+
+pub fn answer_listening(db: &Database, item_id: &str, choice: &str) -> Result<MinimalPairAnswer, String> {
+    let item = load_item(db, item_id)?;
+    if item.kind != ExerciseKind::Listen {
+        return Err("這道題需要錄音作答".to_string());
+    }
+    let choice = choice.trim().to_lowercase();
+    if !item.words.contains(&choice) {
+        return Err(format!("\"{}\" 不是這道題的選項", choice));
+    }
+    // 作答後答案已經公開，重複提交會虛增正確率
+    if is_answered(db, item_id)? {
+        return Err("這道題已經作答過".to_string());
+    }
+    let correct = choice == item.target;
+    record_attempt(db, item_id, &item, &choice, correct, None)?;
+    Ok(MinimalPairAnswer {
+        item_id: item_id.to_string(),
+        correct,
+        choice,
+        target: item.target,
+        contrast_accuracy: contrast_progress(db, item.contrast)?.accuracy,
+    })
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// 各音位對比的弱項程度：評分中的音位替換與練習答錯都算失誤，答對算成功，
+// 取平滑後的失誤率。沒有任何記錄的對比為 0.5
+pub fn weak_contrasts(db: &Database) -> Result<Vec<ContrastSummary>, String> {
+    let since = format!("-{} days", RECENT_DAYS);
+    let mut errors: HashMap<&'static str, usize> = HashMap::new();
+    {
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT expected, produced, COUNT(*) FROM phoneme_errors
+                 WHERE created_at >= datetime('now', ?1) GROUP BY expected, produced",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&since], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, usize>(2)?)))
+            .map_err(|e| format!("查詢音位錯誤失敗：{}", e))?;
+        for row in rows {
+            let (expected, produced, count) = row.map_err(|e| e.to_string())?;
+            if let Some(contrast) = contrast_of(&expected, &produced) {
+                *errors.entry(contrast.id).or_default() += count;
+            }
+        }
+    }
+
+    let mut attempts: HashMap<String, (usize, usize)> = HashMap::new();
+    {
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT contrast, COUNT(*), SUM(correct) FROM contrast_attempts
+                 WHERE created_at >= datetime('now', ?1) GROUP BY contrast",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&since], |row| Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?, row.get::<_, usize>(2)?)))
+            .map_err(|e| format!("查詢練習結果失敗：{}", e))?;
+        for row in rows {
+            let (contrast, total, correct) = row.map_err(|e| e.to_string())?;
+            attempts.insert(contrast, (total, correct));
+        }
+    }
+
+    let mut summaries: Vec<ContrastSummary> = CONTRASTS
+        .iter()
+        .map(|contrast| {
+            let error_count = errors.get(contrast.id).copied().unwrap_or_default();
+            let (total, correct) = attempts.get(contrast.id).copied().unwrap_or_default();
+            let failures = error_count + total - correct;
+            ContrastSummary {
+                contrast: contrast.into(),
+                error_count,
+                attempts: total,
+                correct,
+                accuracy: (total > 0).then(|| round1(100.0 * correct as f64 / total as f64)),
+                weakness: round2((failures as f64 + 1.0) / ((failures + correct) as f64 + 2.0)),
+            }
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.weakness.total_cmp(&a.weakness).then(b.error_count.cmp(&a.error_count)));
+    Ok(summaries)
+}
+
+pub fn contrast_progress(db: &Database, contrast: &Contrast) -> Result<ContrastProgress, String> {
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT date(created_at), kind, COUNT(*), SUM(correct) FROM contrast_attempts
+             WHERE contrast = ?1 GROUP BY date(created_at), kind ORDER BY date(created_at)",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([contrast.id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, usize>(2)?, row.get::<_, usize>(3)?))
+        })
+        .map_err(|e| format!("查詢練習結果失敗：{}", e))?;
+
+    let accuracy = |correct: usize, total: usize| (total > 0).then(|| round1(100.0 * correct as f64 / total as f64));
+    let mut daily: Vec<(String, usize, usize)> = Vec::new();
+    let mut by_kind: HashMap<String, (usize, usize)> = HashMap::new();
+    for row in rows {
+        let (date, kind, total, correct) = row.map_err(|e| e.to_string())?;
+        let entry = by_kind.entry(kind).or_default();
+        entry.0 += total;
+        entry.1 += correct;
+        match daily.last_mut() {
+            Some(last) if last.0 == date => {
+                last.1 += total;
+                last.2 += correct;
+            }
+            _ => daily.push((date, total, correct)),
+        }
+    }
+
+    let kind_accuracy = |kind: ExerciseKind| by_kind.get(kind.code()).and_then(|&(total, correct)| accuracy(correct, total));
+    let total: usize = daily.iter().map(|d| d.1).sum();
+    let correct: usize = daily.iter().map(|d| d.2).sum();
+    Ok(ContrastProgress {
+        contrast: contrast.into(),
+        attempts: total,
+        accuracy: accuracy(correct, total),
+        listen_accuracy: kind_accuracy(ExerciseKind::Listen),
+        speak_accuracy: kind_accuracy(ExerciseKind::Speak),
+        daily: daily
+            .into_iter()
+            .map(|(date, attempts, correct)| DailyAccuracy {
+                date,
+                attempts,
+                accuracy: accuracy(correct, attempts).unwrap_or_default(),
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listening_items_can_only_be_answered_once() {
+        let db = Database::open(&std::env::temp_dir().join(format!("web-chat-pairs-{:016x}", rand::random::<u64>()))).unwrap();
+        let contrast = find_contrast("iy-ih").unwrap();
+        let drill = generate_drill(&db, contrast, 2).unwrap();
+        let listen = drill.items.iter().find(|item| item.kind == ExerciseKind::Listen).unwrap();
+        let speak = drill.items.iter().find(|item| item.kind == ExerciseKind::Speak).unwrap();
+        assert!(listen.target.is_none());
+        assert!(speak.target.is_some());
+
+        let target = load_item(&db, &listen.id).unwrap().target;
+        assert!(answer_listening(&db, &listen.id, "banana").is_err());
+        let answer = answer_listening(&db, &listen.id, &target.to_uppercase()).unwrap();
+        assert!(answer.correct);
+        assert_eq!(answer.contrast_accuracy, Some(100.0));
+
+        // 重複提交不再計入
+        assert!(answer_listening(&db, &listen.id, &target).is_err());
+        assert!(answer_listening(&db, &speak.id, &target).is_err());
+        assert_eq!(contrast_progress(&db, contrast).unwrap().attempts, 1);
+    }
+}
@@ -0,0 +1,243 @@
+// 模型發現：通過 models 列表接口查看當前密鑰可用的模型，按用途推薦默認模型，並校驗用戶指定的模型
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use crate::database::Database;
+use crate::gemini_service::{GeminiService, ModelInfo, ModelSelection};
+
+// 文本、語音合成和音頻理解都通過 generateContent 調用
+const GENERATE_METHOD: &str = "generateContent";
+const CACHE_TTL: Duration = Duration::from_secs(6 * 3600);
+// 每個檔案各自的密鑰和模型選擇；local_ 開頭的設置不參與同步
+const SETTINGS_KEY: &str = "local_gemini_settings";
+// 名稱中帶這些詞的是專用模型（圖像生成、向量、實時對話等），不適合作為通用默認
+const SPECIALISED: &[&str] =
+    &["image", "embedding", "live", "native-audio", "thinking", "learnlm", "computer-use", "robotics", "aqa", "gemma"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelPurpose {
+    Text,
+    Tts,
+    Audio,
+}
+
+impl ModelPurpose {
+    const ALL: [ModelPurpose; 3] = [ModelPurpose::Text, ModelPurpose::Tts, ModelPurpose::Audio];
+
+    fn label(self) -> &'static str {
+        match self {
+            ModelPurpose::Text => "文本生成",
+            ModelPurpose::Tts => "語音合成",
+            ModelPurpose::Audio => "音頻理解",
+        }
+    }
+
+    // 同一代模型中的偏好：文本和音頻優先 flash（快且配額多），lite 作為後備
+    fn tier_rank(self, name: &str) -> u8 {
+        let tier = if name.contains("flash-lite") {
+            "lite"
+        } else if name.contains("flash") {
+            "flash"
+        } else if name.contains("pro") {
+            "pro"
+        } else {
+            ""
+        };
+        match (self, tier) {
+            (_, "flash") => 0,
+            (ModelPurpose::Audio, "lite") | (ModelPurpose::Text, "pro") | (ModelPurpose::Tts, "pro") => 1,
+            (_, "pro") | (_, "lite") => 2,
+            _ => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogModel {
+    #[serde(flatten)]
+    pub info: ModelInfo,
+    pub purposes: Vec<ModelPurpose>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCatalog {
+    pub models: Vec<CatalogModel>,
+    pub recommended: ModelSelection,
+    pub active: Option<ModelSelection>, // 當前服務正在使用的模型
+    pub age_secs: u64, // 距離上次從服務端拉取的時間
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiSettings {
+    pub api_key: String,
+    pub models: ModelSelection,
+}
+
+// 按密鑰和服務地址緩存，換了密鑰自動失效
+pub struct ModelCache {
+    key: u64,
+    fetched: Instant,
+    models: Vec<ModelInfo>,
+}
+
+fn is_tts(name: &str) -> bool {
+    name.contains("-tts")
+}
+
+// 單獨指定一個模型時它應佔用的槽位：語音合成模型只能用於 TTS，其餘按文本模型處理
+pub fn slot_for(name: &str) -> ModelPurpose {
+    if is_tts(name) { ModelPurpose::Tts } else { ModelPurpose::Text }
+}
+
+// 預覽版和實驗版排在正式版之後
+fn is_unstable(name: &str) -> bool {
+    name.contains("preview") || name.contains("exp")
+}
+
+// gemini-2.5-flash → 25，用於比較模型代數
+fn generation(name: &str) -> u32 {
+    let version = name.strip_prefix("gemini-").and_then(|rest| rest.split('-').next()).unwrap_or("");
+    let mut parts = version.split('.');
+    let major = parts.next().and_then(|p| p.parse::<u32>().ok()).unwrap_or(0);
+    let minor = parts.next().and_then(|p| p.parse::<u32>().ok()).unwrap_or(0);
+    major * 10 + minor
+}
+
+fn suits(model: &ModelInfo, purpose: ModelPurpose) -> bool {
+    if !model.supports(GENERATE_METHOD) || !model.name.starts_with("gemini-") {
+        return false;
+    }
+    match purpose {
+        ModelPurpose::Tts => is_tts(&model.name),
+        ModelPurpose::Text | ModelPurpose::Audio => {
+            !is_tts(&model.name) && !SPECIALISED.iter().any(|word| model.name.contains(word))
+        }
+    }
+}
+
+// 正式版優先，其次代數新的，再按用途偏好；同名的帶日期或版本號的快照排在別名之後
+fn recommend(models: &[ModelInfo], purpose: ModelPurpose) -> Option<String> {
+    models
+        .iter()
+        .filter(|model| suits(model, purpose))
+        .min_by_key(|model| {
+            let name = model.name.as_str();
+            (is_unstable(name), std::cmp::Reverse(generation(name)), purpose.tier_rank(name), name.len())
+        })
+        .map(|model| model.name.clone())
+}
+
+fn recommended(models: &[ModelInfo]) -> ModelSelection {
+    let defaults = ModelSelection::default();
+    ModelSelection {
+        text: recommend(models, ModelPurpose::Text).unwrap_or(defaults.text),
+        tts: recommend(models, ModelPurpose::Tts).unwrap_or(defaults.tts),
+        audio: recommend(models, ModelPurpose::Audio).unwrap_or(defaults.audio),
+    }
+}
+
+fn cache_key(service: &GeminiService) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    service.config().api_key.hash(&mut hasher);
+    service.config().base_url.hash(&mut hasher);
+    hasher.finish()
+}
+
+// 優先使用緩存，過期、換了密鑰或要求刷新時重新拉取
+pub async fn catalog(cache: &mut Option<ModelCache>, service: &GeminiService, refresh: bool) -> Result<ModelCatalog, String> {
+    let key = cache_key(service);
+    let fresh = cache.as_ref().is_some_and(|c| c.key == key && c.fetched.elapsed() < CACHE_TTL);
+    if refresh || !fresh {
+        let models = service.list_models().await.map_err(|e| format!("獲取模型列表失敗：{}", e))?;
+        tracing::info!(count = models.len(), "Gemini model list fetched");
+        *cache = Some(ModelCache { key, fetched: Instant::now(), models });
+    }
+    let cached = cache.as_ref().ok_or("模型列表為空")?;
+    let models = cached
+        .models
+        .iter()
+        .map(|info| CatalogModel {
+            info: info.clone(),
+            purposes: ModelPurpose::ALL.into_iter().filter(|purpose| suits(info, *purpose)).collect(),
+        })
+        .collect();
+    Ok(ModelCatalog {
+        models,
+        recommended: recommended(&cached.models),
+        active: None,
+        age_secs: cached.fetched.elapsed().as_secs(),
+    })
+}
+
+fn validate(models: &[ModelInfo], name: &str, purpose: ModelPurpose) -> Result<(), String> {
+    let model = models
+        .iter()
+        .find(|model| model.name == name)
+        .ok_or_else(|| format!("模型 {} 不存在或當前密鑰無權使用", name))?;
+    if !model.supports(GENERATE_METHOD) {
+        return Err(format!("模型 {} 不支持 {}，不能用於{}", name, GENERATE_METHOD, purpose.label()));
+    }
+    if purpose == ModelPurpose::Tts && !is_tts(name) {
+        return Err(format!("模型 {} 不能輸出語音，不能用於{}", name, purpose.label()));
+    }
+    if purpose != ModelPurpose::Tts && is_tts(name) {
+        return Err(format!("模型 {} 只能用於語音合成，不能用於{}", name, purpose.label()));
+    }
+    Ok(())
+}
+
+fn normalize(name: Option<String>) -> Option<String> {
+    name.map(|name| name.trim().trim_start_matches("models/").to_string()).filter(|name| !name.is_empty())
+}
+
+// 確定初始化時使用的模型：用戶指定的必須在列表中且支持對應方法，未指定的用推薦值。
+// 拿不到模型列表時（如離線）不做校驗，未指定的用內置默認值
+pub fn resolve(
+    catalog: Option<&ModelCatalog>,
+    text: Option<String>,
+    tts: Option<String>,
+    audio: Option<String>,
+) -> Result<ModelSelection, String> {
+    let (text, tts, audio) = (normalize(text), normalize(tts), normalize(audio));
+    let Some(catalog) = catalog else {
+        let defaults = ModelSelection::default();
+        return Ok(ModelSelection {
+            text: text.unwrap_or(defaults.text),
+            tts: tts.unwrap_or(defaults.tts),
+            audio: audio.unwrap_or(defaults.audio),
+        });
+    };
+    let models: Vec<ModelInfo> = catalog.models.iter().map(|model| model.info.clone()).collect();
+    let mut selection = catalog.recommended.clone();
+    for (requested, purpose, slot) in [
+        (text, ModelPurpose::Text, &mut selection.text),
+        (tts, ModelPurpose::Tts, &mut selection.tts),
+        (audio, ModelPurpose::Audio, &mut selection.audio),
+    ] {
+        if let Some(name) = requested {
+            validate(&models, &name, purpose)?;
+            *slot = name;
+        }
+    }
+    Ok(selection)
+}
+
+pub fn load_settings(db: &Database) -> Result<Option<GeminiSettings>, String> {
+    db.get_setting(SETTINGS_KEY)
+}
+
+pub fn save_settings(db: &Database, settings: &GeminiSettings) -> Result<(), String> {
+    db.set_setting(SETTINGS_KEY, settings)
+}
+
+// 用檔案保存的設置創建服務；模型在保存時已經校驗過，這裡不再請求模型列表
+pub fn restore_service(db: &Database) -> Result<Option<GeminiService>, String> {
+    Ok(load_settings(db)?.map(|settings| {
+        let mut service = GeminiService::new(settings.api_key);
+        service.set_models(settings.models);
+        service
+    }))
+}
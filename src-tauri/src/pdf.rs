@@ -0,0 +1,168 @@
+// 極簡的 PDF 生成，只支持文字、折線和矩形，夠畫報告用。不嵌入字體文件：
+// 拉丁文字用標準的 Helvetica，中文用閱讀器自帶的 Adobe CJK 字體（MSung-Light / STSong-Light）
+
+pub const PAGE_WIDTH: f64 = 595.0; // A4，單位為點
+pub const PAGE_HEIGHT: f64 = 842.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CjkFont {
+    Traditional,
+    Simplified,
+}
+
+impl CjkFont {
+    // 字體名、編碼、字符集、字符集版本
+    fn spec(self) -> (&'static str, &'static str, &'static str, u32) {
+        match self {
+            CjkFont::Traditional => ("MSung-Light", "UniCNS-UCS2-H", "CNS1", 0),
+            CjkFont::Simplified => ("STSong-Light", "UniGB-UCS2-H", "GB1", 2),
+        }
+    }
+}
+
+pub type Color = (f64, f64, f64);
+
+pub struct PdfWriter {
+    font: CjkFont,
+    pages: Vec<String>,
+}
+
+// 估算文字寬度。Helvetica 按平均字寬算，CJK 字體中半角字符寬 500、全角字符寬 1000
+pub fn text_width(text: &str, size: f64) -> f64 {
+    if text.is_ascii() {
+        return text.len() as f64 * size * 0.55;
+    }
+    text.chars().map(|c| if c.is_ascii() { 0.5 } else { 1.0 }).sum::<f64>() * size
+}
+
+fn escape_literal(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars().filter(|c| !c.is_ascii_control()) {
+        if matches!(c, '(' | ')' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// CJK 字體用 UCS-2 編碼，超出基本平面的字符（如 emoji）換成問號
+fn encode_ucs2(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .map(|c| format!("{:04X}", if (c as u32) <= 0xFFFF { c as u32 } else { '?' as u32 }))
+        .collect()
+}
+
+impl PdfWriter {
+    pub fn new(font: CjkFont) -> Self {
+        Self { font, pages: vec![String::new()] }
+    }
+
+    pub fn new_page(&mut self) {
+        self.pages.push(String::new());
+    }
+
+    fn content(&mut self) -> &mut String {
+        self.pages.last_mut().expect("至少有一頁")
+    }
+
+    // 坐標以頁面左上角為原點，y 向下；y 為文字基線的位置
+    pub fn text(&mut self, x: f64, y: f64, size: f64, color: Color, text: &str) {
+        let (font, encoded) = if text.is_ascii() {
+            ("F1", format!("({})", escape_literal(text)))
+        } else {
+            ("F2", format!("<{}>", encode_ucs2(text)))
+        };
+        let command = format!(
+            "BT {:.3} {:.3} {:.3} rg /{} {:.1} Tf {:.2} {:.2} Td {} Tj ET\n",
+            color.0,
+            color.1,
+            color.2,
+            font,
+            size,
+            x,
+            PAGE_HEIGHT - y,
+            encoded
+        );
+        self.content().push_str(&command);
+    }
+
+    pub fn polyline(&mut self, points: &[(f64, f64)], width: f64, color: Color) {
+        let Some((first, rest)) = points.split_first() else {
+            return;
+        };
+        let mut command = format!("{:.3} {:.3} {:.3} RG {:.2} w ", color.0, color.1, color.2, width);
+        command.push_str(&format!("{:.2} {:.2} m ", first.0, PAGE_HEIGHT - first.1));
+        for point in rest {
+            command.push_str(&format!("{:.2} {:.2} l ", point.0, PAGE_HEIGHT - point.1));
+        }
+        command.push_str("S\n");
+        self.content().push_str(&command);
+    }
+
+    // (x, y) 為矩形左上角
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: Color) {
+        let command = format!(
+            "{:.3} {:.3} {:.3} rg {:.2} {:.2} {:.2} {:.2} re f\n",
+            color.0,
+            color.1,
+            color.2,
+            x,
+            PAGE_HEIGHT - y - height,
+            width,
+            height
+        );
+        self.content().push_str(&command);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        let (base_font, encoding, ordering, supplement) = self.font.spec();
+        // 對象編號：1 目錄，2 頁面樹，3-6 字體，之後每頁兩個對象（頁面和內容流）
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            String::new(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+            format!("<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /{} /DescendantFonts [5 0 R] >>", base_font, encoding),
+            format!(
+                "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering ({}) \
+                 /Supplement {} >> /FontDescriptor 6 0 R /DW 1000 /W [1 95 500] >>",
+                base_font, ordering, supplement
+            ),
+            format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 6 /FontBBox [-160 -249 1015 1071] /ItalicAngle 0 \
+                 /Ascent 880 /Descent -120 /CapHeight 880 /StemV 93 >>",
+                base_font
+            ),
+        ];
+        let mut kids = Vec::new();
+        for content in &self.pages {
+            let page_id = objects.len() + 1;
+            kids.push(format!("{} 0 R", page_id));
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> \
+                 /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                page_id + 1
+            ));
+            objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+        }
+        objects[1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), self.pages.len());
+
+        // 內容只含 ASCII（中文已轉為十六進制），字節偏移即字符串長度
+        let mut output = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(output.len());
+            output.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+        }
+        let xref = output.len();
+        output.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+        for offset in offsets {
+            output.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        output.push_str(&format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref));
+        output.into_bytes()
+    }
+}
@@ -0,0 +1,438 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::database::Database;
+use crate::drills;
+use crate::goals;
+use crate::minimal_pairs;
+
+const GOALS_SETTING_KEY: &str = "learning_goals";
+
+// 各類活動的時間估算（分鐘）
+const MINUTES_PER_REVIEW: f64 = 0.5;
+const MINUTES_PER_SEGMENT: u32 = 1;
+const MINIMAL_PAIR_MINUTES: u32 = 5;
+const CONVERSATION_MINUTES: u32 = 5;
+const NEW_CONTENT_MINUTES: (u32, u32) = (5, 12);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusArea {
+    Pronunciation,
+    Vocabulary,
+    Fluency,
+    Conversation,
+}
+
+// 學習目標，保存在設置中，不傳時沿用上次的目標
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LearningGoals {
+    pub focus: Vec<FocusArea>,
+    pub level: Option<String>, // 不設置時按近期得分推斷
+    pub topics: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    VocabularyReview,
+    MinimalPairs,
+    SentenceReview,
+    NewContent,
+    Conversation,
+}
+
+impl ActivityKind {
+    fn code(self) -> &'static str {
+        match self {
+            ActivityKind::VocabularyReview => "vocabulary_review",
+            ActivityKind::MinimalPairs => "minimal_pairs",
+            ActivityKind::SentenceReview => "sentence_review",
+            ActivityKind::NewContent => "new_content",
+            ActivityKind::Conversation => "conversation",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        [
+            ActivityKind::VocabularyReview,
+            ActivityKind::MinimalPairs,
+            ActivityKind::SentenceReview,
+            ActivityKind::NewContent,
+            ActivityKind::Conversation,
+        ]
+        .into_iter()
+        .find(|kind| kind.code() == code)
+    }
+}
+
+// details 中是前端開始活動所需的參數（對比 id、待重練片段、題目等）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanActivity {
+    pub id: i64,
+    pub kind: ActivityKind,
+    pub title: String,
+    pub minutes: u32,
+    pub details: serde_json::Value,
+    pub completed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyPlan {
+    pub date: String,
+    pub time_budget: u32,
+    pub level: String,
+    pub planned_minutes: u32,
+    pub completed_minutes: u32,
+    pub completed: bool,
+    pub activities: Vec<PlanActivity>,
+}
+
+// 候選活動：先按優先級以最短時長排入，剩餘時間再按優先級延長到理想時長
+struct Candidate {
+    kind: ActivityKind,
+    priority: u32,
+    min_minutes: u32,
+    ideal_minutes: u32,
+    details: serde_json::Value,
+}
+
+const CONVERSATION_QUESTIONS: &[(&str, &[&str])] = &[
+    ("beginner", &[
+        "What do you usually do on weekends?",
+        "Tell me about your favourite food.",
+        "Describe your home and the room you like most.",
+        "What did you do yesterday?",
+        "Who is your best friend, and what do you do together?",
+    ]),
+    ("intermediate", &[
+        "Describe a trip that you remember well. Why was it special?",
+        "What is a skill you would like to learn, and why?",
+        "Talk about a book, film or series you enjoyed recently.",
+        "How has technology changed the way you study or work?",
+        "Describe a challenge you faced and how you dealt with it.",
+    ]),
+    ("advanced", &[
+        "Should cities ban private cars from their centres? Give reasons.",
+        "What makes a good leader? Use examples from your experience.",
+        "How should schools prepare students for jobs that do not exist yet?",
+        "Is remote work better for society in the long run? Explain your view.",
+        "Describe a decision you changed your mind about and what persuaded you.",
+    ]),
+];
+
+pub fn load_goals(db: &Database) -> Result<LearningGoals, String> {
+    Ok(db.get_setting(GOALS_SETTING_KEY)?.unwrap_or_default())
+}
+
+pub fn save_goals(db: &Database, goals: &LearningGoals) -> Result<(), String> {
+    db.set_setting(GOALS_SETTING_KEY, goals)
+}
+
+// 按最近兩週的評分推斷程度
+fn current_level(db: &Database, goals: &LearningGoals) -> Result<String, String> {
+    if let Some(level) = goals.level.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        return Ok(level.to_string());
+    }
+    let average: Option<f64> = db
+        .conn()
+        .query_row(
+            "SELECT AVG(overall_score) FROM recordings
+             WHERE overall_score IS NOT NULL AND created_at >= datetime('now', '-14 days')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("查詢練習成績失敗：{}", e))?;
+    Ok(match average {
+        Some(score) if score >= 85.0 => "advanced",
+        Some(score) if score >= 65.0 => "intermediate",
+        _ => "beginner",
+    }
+    .to_string())
+}
+
+fn today(db: &Database) -> Result<String, String> {
+    db.conn()
+        .query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn gather_candidates(db: &Database, goals: &LearningGoals, level: &str, date: &str) -> Result<Vec<Candidate>, String> {
+    let focused = |area: FocusArea| goals.focus.contains(&area);
+    let boost = |area: FocusArea, priority: u32| if focused(area) { priority.saturating_sub(10) } else { priority };
+    let mut candidates = Vec::new();
+
+    // 到期的生詞不複習會被遺忘，優先安排
+    let due: u32 = db
+        .conn()
+        .query_row("SELECT COUNT(*) FROM vocabulary WHERE due_at <= datetime('now')", [], |row| row.get(0))
+        .map_err(|e| format!("查詢待複習生詞失敗：{}", e))?;
+    if due > 0 {
+        candidates.push(Candidate {
+            kind: ActivityKind::VocabularyReview,
+            priority: boost(FocusArea::Vocabulary, 10),
+            min_minutes: 2.min((due as f64 * MINUTES_PER_REVIEW).ceil() as u32).max(1),
+            ideal_minutes: ((due as f64 * MINUTES_PER_REVIEW).ceil() as u32).min(15),
+            details: json!({ "due_count": due }),
+        });
+    }
+
+    // 有失誤記錄的音位對比，最多安排兩項
+    let weak: Vec<_> = minimal_pairs::weak_contrasts(db)?
+        .into_iter()
+        .filter(|c| c.weakness > 0.5 || (focused(FocusArea::Pronunciation) && c.attempts == 0))
+        .take(if focused(FocusArea::Pronunciation) { 2 } else { 1 })
+        .collect();
+    for (index, contrast) in weak.into_iter().enumerate() {
+        candidates.push(Candidate {
+            kind: ActivityKind::MinimalPairs,
+            priority: boost(FocusArea::Pronunciation, 30 + index as u32 * 20),
+            min_minutes: MINIMAL_PAIR_MINUTES,
+            ideal_minutes: MINIMAL_PAIR_MINUTES,
+            details: json!({ "contrast": contrast.contrast.id, "label": contrast.contrast.label }),
+        });
+    }
+
+    // 最近一次逐句練習中還沒過關的片段
+    let session_id: Option<String> = db
+        .conn()
+        .query_row("SELECT id FROM drill_sessions ORDER BY created_at DESC, rowid DESC LIMIT 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("查詢練習失敗：{}", e))?;
+    if let Some(session_id) = session_id {
+        let result = drills::session_result(db, &session_id)?;
+        let count = result.needs_practice.len() as u32;
+        if count > 0 {
+            candidates.push(Candidate {
+                kind: ActivityKind::SentenceReview,
+                priority: boost(FocusArea::Fluency, 40),
+                min_minutes: MINUTES_PER_SEGMENT.max(count.min(2) * MINUTES_PER_SEGMENT),
+                ideal_minutes: (count * MINUTES_PER_SEGMENT).min(8),
+                details: json!({ "session_id": session_id, "segment_ids": result.needs_practice }),
+            });
+        }
+    }
+
+    // 每天換一個話題和問題，同一天重新生成計劃時保持不變
+    let day_number: usize = date.bytes().filter(u8::is_ascii_digit).fold(0, |n, b| n * 10 + (b - b'0') as usize);
+    let topics: Vec<&str> = if goals.topics.is_empty() {
+        vec!["daily", "business"]
+    } else {
+        goals.topics.iter().map(String::as_str).collect()
+    };
+    candidates.push(Candidate {
+        kind: ActivityKind::NewContent,
+        priority: 20,
+        min_minutes: NEW_CONTENT_MINUTES.0,
+        ideal_minutes: NEW_CONTENT_MINUTES.1,
+        details: json!({ "topic": topics[day_number % topics.len()], "difficulty_level": level }),
+    });
+
+    let questions = CONVERSATION_QUESTIONS
+        .iter()
+        .find(|(l, _)| *l == level)
+        .map_or(CONVERSATION_QUESTIONS[0].1, |(_, q)| *q);
+    candidates.push(Candidate {
+        kind: ActivityKind::Conversation,
+        priority: boost(FocusArea::Conversation, 25),
+        min_minutes: CONVERSATION_MINUTES,
+        ideal_minutes: CONVERSATION_MINUTES,
+        details: json!({ "question": questions[day_number % questions.len()] }),
+    });
+
+    Ok(candidates)
+}
+
+// 在時間預算內選擇活動並分配時長，結果按練習順序排列：複習熱身、弱項、新內容，最後是對話
+fn fit_to_budget(mut candidates: Vec<Candidate>, budget: u32) -> Vec<(Candidate, u32)> {
+    candidates.sort_by_key(|c| c.priority);
+    let mut remaining = budget;
+    let mut chosen: Vec<(Candidate, u32)> = Vec::new();
+    for candidate in candidates {
+        if candidate.min_minutes <= remaining {
+            remaining -= candidate.min_minutes;
+            let minutes = candidate.min_minutes;
+            chosen.push((candidate, minutes));
+        }
+    }
+    for (candidate, minutes) in &mut chosen {
+        let extra = candidate.ideal_minutes.saturating_sub(*minutes).min(remaining);
+        *minutes += extra;
+        remaining -= extra;
+    }
+    // 仍有剩餘時間時都用在新內容上
+    if let Some((_, minutes)) = chosen.iter_mut().find(|(c, _)| c.kind == ActivityKind::NewContent) {
+        *minutes += remaining;
+    }
+    chosen.sort_by_key(|(c, _)| c.kind as u8);
+    chosen
+}
+
+fn activity_title(kind: ActivityKind, minutes: u32, details: &serde_json::Value) -> String {
+    match kind {
+        ActivityKind::VocabularyReview => {
+            let count = details["review_count"].as_u64().unwrap_or_default();
+            format!("複習到期生詞（{} 個）", count)
+        }
+        ActivityKind::MinimalPairs => format!("最小對立練習：{}", details["label"].as_str().unwrap_or_default()),
+        ActivityKind::SentenceReview => {
+            let count = details["segment_ids"].as_array().map_or(0, Vec::len);
+            format!("重練未過關的句子（{} 段）", count)
+        }
+        ActivityKind::NewContent => format!("新內容跟讀（{} 分鐘）", minutes),
+        ActivityKind::Conversation => format!("口語問答：{}", details["question"].as_str().unwrap_or_default()),
+    }
+}
+
+// 生成今天的學習計劃。今天已有計劃時直接返回；regenerate 時保留已完成的活動，
+// 其餘活動按剩餘時間重新安排
+pub fn daily_plan(db: &Database, time_budget: u32, goals: &LearningGoals, regenerate: bool) -> Result<DailyPlan, String> {
+    let date = today(db)?;
+    let exists: bool = db
+        .conn()
+        .query_row("SELECT COUNT(*) > 0 FROM daily_plans WHERE date = ?1", [&date], |row| row.get(0))
+        .map_err(|e| format!("查詢學習計劃失敗：{}", e))?;
+    if exists && !regenerate {
+        return load_plan(db, &date);
+    }
+
+    let level = current_level(db, goals)?;
+    db.conn()
+        .execute(
+            "INSERT INTO daily_plans (date, time_budget, level) VALUES (?1, ?2, ?3)
+             ON CONFLICT (date) DO UPDATE SET time_budget = excluded.time_budget, level = excluded.level",
+            params![date, time_budget, level],
+        )
+        .map_err(|e| format!("保存學習計劃失敗：{}", e))?;
+    db.conn()
+        .execute("DELETE FROM plan_activities WHERE plan_date = ?1 AND completed_at IS NULL", [&date])
+        .map_err(|e| format!("保存學習計劃失敗：{}", e))?;
+
+    let (completed_count, completed_minutes): (u32, u32) = db
+        .conn()
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(minutes), 0) FROM plan_activities WHERE plan_date = ?1",
+            [&date],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("查詢學習計劃失敗：{}", e))?;
+
+    let candidates = gather_candidates(db, goals, &level, &date)?;
+    let activities = fit_to_budget(candidates, time_budget.saturating_sub(completed_minutes));
+    for (index, (candidate, minutes)) in activities.into_iter().enumerate() {
+        let mut details = candidate.details;
+        if candidate.kind == ActivityKind::VocabularyReview {
+            let due = details["due_count"].as_u64().unwrap_or_default();
+            let count = ((minutes as f64 / MINUTES_PER_REVIEW) as u64).min(due);
+            details["review_count"] = json!(count);
+        }
+        db.conn()
+            .execute(
+                "INSERT INTO plan_activities (plan_date, position, kind, title, details, minutes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    date,
+                    completed_count as usize + index,
+                    candidate.kind.code(),
+                    activity_title(candidate.kind, minutes, &details),
+                    details.to_string(),
+                    minutes,
+                ],
+            )
+            .map_err(|e| format!("保存學習計劃失敗：{}", e))?;
+    }
+    load_plan(db, &date)
+}
+
+pub fn load_plan(db: &Database, date: &str) -> Result<DailyPlan, String> {
+    let (time_budget, level): (u32, String) = db
+        .conn()
+        .query_row("SELECT time_budget, level FROM daily_plans WHERE date = ?1", [date], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()
+        .map_err(|e| format!("查詢學習計劃失敗：{}", e))?
+        .ok_or_else(|| format!("{} 沒有學習計劃", date))?;
+
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT id, kind, title, minutes, details, completed_at FROM plan_activities
+             WHERE plan_date = ?1 ORDER BY position, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([date], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, u32>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })
+        .map_err(|e| format!("查詢學習計劃失敗：{}", e))?;
+
+    let mut activities = Vec::new();
+    for row in rows {
+        let (id, kind, title, minutes, details, completed_at) = row.map_err(|e| e.to_string())?;
+        let Some(kind) = ActivityKind::from_code(&kind) else {
+            continue;
+        };
+        activities.push(PlanActivity {
+            id,
+            kind,
+            title,
+            minutes,
+            details: serde_json::from_str(&details).unwrap_or_default(),
+            completed_at,
+        });
+    }
+
+    let planned_minutes = activities.iter().map(|a| a.minutes).sum();
+    let completed_minutes = activities.iter().filter(|a| a.completed_at.is_some()).map(|a| a.minutes).sum();
+    Ok(DailyPlan {
+        date: date.to_string(),
+        time_budget,
+        level,
+        planned_minutes,
+        completed_minutes,
+        completed: !activities.is_empty() && activities.iter().all(|a| a.completed_at.is_some()),
+        activities,
+    })
+}
+
+// 標記活動完成或取消完成，返回更新後的整個計劃。完成的活動按計劃時長計入當天的練習時間
+pub fn set_activity_completed(db: &Database, activity_id: i64, completed: bool) -> Result<DailyPlan, String> {
+    let (date, kind, minutes, was_completed): (String, String, u32, bool) = db
+        .conn()
+        .query_row(
+            "SELECT plan_date, kind, minutes, completed_at IS NOT NULL FROM plan_activities WHERE id = ?1",
+            [activity_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| format!("查詢學習計劃失敗：{}", e))?
+        .ok_or("計劃中沒有這項活動")?;
+    if completed == was_completed {
+        return load_plan(db, &date);
+    }
+
+    db.conn()
+        .execute(
+            "UPDATE plan_activities SET completed_at = CASE WHEN ?1 THEN datetime('now') END WHERE id = ?2",
+            params![completed, activity_id],
+        )
+        .map_err(|e| format!("更新學習計劃失敗：{}", e))?;
+    if completed {
+        goals::record_practice_time(db, minutes as f64 * 60.0, &kind, Some(activity_id))?;
+    } else {
+        db.conn()
+            .execute("DELETE FROM practice_time WHERE plan_activity_id = ?1", [activity_id])
+            .map_err(|e| format!("更新練習時間失敗：{}", e))?;
+    }
+    load_plan(db, &date)
+}
@@ -0,0 +1,391 @@
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{frame_energy_db, AudioClip};
+use crate::vad::{self, VadConfig, VadResult};
+
+// 錄音質量門檻
+const MIN_SPEECH_SECS: f64 = 0.3;
+const MIN_SPEECH_LEVEL_DB: f64 = -45.0; // 語音段平均電平（dBFS）
+const MAX_CLIPPING_RATIO: f64 = 0.01; // 語音段中削波樣本的比例
+const MIN_SNR_DB: f64 = 10.0;
+const NOISY_FLOOR_DB: f64 = -35.0; // 噪聲底高於此值時，檢測不到語音多半是被噪聲淹沒
+const CLIP_LEVEL: f32 = 0.98;
+
+// 響度歸一化：語音段平均電平對齊到 -20 dBFS，峰值不超過 -1 dBFS
+const TARGET_LEVEL_DB: f64 = -20.0;
+const MAX_GAIN_DB: f64 = 30.0;
+const PEAK_LIMIT: f32 = 0.89;
+
+// 譜減法降噪參數
+const FFT_SIZE: usize = 512;
+const FFT_HOP: usize = FFT_SIZE / 2;
+const OVER_SUBTRACTION: f32 = 2.0;
+const SPECTRAL_FLOOR: f32 = 0.02;
+const MIN_NOISE_FRAMES: usize = 5;
+
+// 錄音不合格的原因，前端根據代碼顯示對應提示
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityIssue {
+    NoSpeech,
+    TooQuiet,
+    Clipped,
+    TooNoisy,
+}
+
+impl QualityIssue {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::NoSpeech => "no_speech",
+            Self::TooQuiet => "too_quiet",
+            Self::Clipped => "clipped",
+            Self::TooNoisy => "too_noisy",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::NoSpeech => "沒有檢測到說話聲，請靠近麥克風完整朗讀一遍",
+            Self::TooQuiet => "錄音音量太小，請靠近麥克風或提高輸入音量",
+            Self::Clipped => "錄音音量過大導致失真，請離麥克風遠一點或降低輸入音量",
+            Self::TooNoisy => "環境噪音太大，請移到更安靜的地方再錄音",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub passed: bool,
+    pub issue: Option<QualityIssue>,
+    pub message: Option<String>,
+    pub snr_db: f64,
+    pub speech_level_db: f64,
+    pub noise_level_db: f64,
+    pub peak_db: f64,
+    pub clipping_ratio: f64,
+    pub dc_offset: f64,
+    pub speech_secs: f64,
+}
+
+// 預處理後的錄音：去直流、降噪並歸一化響度，附帶語音段與質量評估
+pub struct PreparedRecording {
+    pub clip: AudioClip,
+    pub activity: VadResult,
+    pub quality: QualityReport,
+}
+
+pub fn prepare_recording(clip: &AudioClip, vad_config: &VadConfig) -> PreparedRecording {
+    let dc_offset = mean(&clip.samples);
+    let centered = AudioClip::new(remove_dc(&clip.samples), clip.sample_rate);
+
+    // 質量評估基於降噪前的信號，反映真實的錄音環境
+    let activity = vad::detect_speech(&centered, vad_config);
+    let quality = assess_quality(&centered, &activity, vad_config, dc_offset);
+
+    let speech_mask = speech_mask(&activity, centered.samples.len(), centered.sample_rate);
+    let denoised = spectral_subtract(&centered.samples, &speech_mask);
+    let samples = normalize_loudness(&denoised, &speech_mask);
+
+    PreparedRecording {
+        clip: AudioClip::new(samples, clip.sample_rate),
+        activity,
+        quality,
+    }
+}
+
+fn assess_quality(clip: &AudioClip, activity: &VadResult, config: &VadConfig, dc_offset: f32) -> QualityReport {
+    let frame_len = (clip.sample_rate as usize * config.frame_ms as usize / 1000).max(1);
+    let energy = frame_energy_db(&clip.samples, frame_len, frame_len);
+    let frame_secs = frame_len as f64 / clip.sample_rate.max(1) as f64;
+    let in_speech = |frame: usize| {
+        let t = (frame as f64 + 0.5) * frame_secs;
+        activity.segments.iter().any(|s| t >= s.start_secs && t < s.end_secs)
+    };
+
+    let mut speech_frames = Vec::new();
+    let mut noise_frames = Vec::new();
+    for (i, &db) in energy.iter().enumerate() {
+        if in_speech(i) {
+            speech_frames.push(db);
+        } else {
+            noise_frames.push(db);
+        }
+    }
+    let speech_level_db = power_mean_db(&speech_frames);
+    // 整段都是語音時沒有純噪聲幀，退回用 VAD 估計的噪聲底
+    let noise_level_db = if noise_frames.len() >= MIN_NOISE_FRAMES {
+        power_mean_db(&noise_frames)
+    } else {
+        activity.noise_floor_db
+    };
+    let loudest_db = energy.iter().cloned().fold(-100.0f32, f32::max) as f64;
+
+    let peak = clip.samples.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+    let clipped = clip.samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+    let speech_samples = (activity.speech_secs * clip.sample_rate as f64).max(1.0);
+    let clipping_ratio = clipped as f64 / speech_samples;
+    let snr_db = if speech_frames.is_empty() { 0.0 } else { speech_level_db - noise_level_db };
+
+    let issue = if loudest_db < MIN_SPEECH_LEVEL_DB {
+        Some(QualityIssue::TooQuiet)
+    } else if activity.speech_secs < MIN_SPEECH_SECS && activity.noise_floor_db > NOISY_FLOOR_DB {
+        Some(QualityIssue::TooNoisy)
+    } else if activity.speech_secs < MIN_SPEECH_SECS {
+        Some(QualityIssue::NoSpeech)
+    } else if clipping_ratio > MAX_CLIPPING_RATIO {
+        Some(QualityIssue::Clipped)
+    } else if speech_level_db < MIN_SPEECH_LEVEL_DB {
+        Some(QualityIssue::TooQuiet)
+    } else if snr_db < MIN_SNR_DB {
+        Some(QualityIssue::TooNoisy)
+    } else {
+        None
+    };
+
+    QualityReport {
+        passed: issue.is_none(),
+        issue,
+        message: issue.map(|i| i.message().to_string()),
+        snr_db: round1(snr_db),
+        speech_level_db: round1(speech_level_db),
+        noise_level_db: round1(noise_level_db),
+        peak_db: round1(20.0 * (peak as f64 + 1e-10).log10()),
+        clipping_ratio: (clipping_ratio * 10_000.0).round() / 10_000.0,
+        dc_offset: (dc_offset as f64 * 10_000.0).round() / 10_000.0,
+        speech_secs: activity.speech_secs,
+    }
+}
+
+// 先減去均值，再用一階高通（約 13 Hz）去掉緩慢漂移
+fn remove_dc(samples: &[f32]) -> Vec<f32> {
+    let offset = mean(samples);
+    let mut previous_input = 0.0f32;
+    let mut previous_output = 0.0f32;
+    samples
+        .iter()
+        .map(|&s| {
+            let x = s - offset;
+            let y = x - previous_input + 0.995 * previous_output;
+            previous_input = x;
+            previous_output = y;
+            y
+        })
+        .collect()
+}
+
+// 每個樣本是否位於語音段內
+fn speech_mask(activity: &VadResult, len: usize, sample_rate: u32) -> Vec<bool> {
+    let mut mask = vec![false; len];
+    for segment in &activity.segments {
+        let start = ((segment.start_secs * sample_rate as f64) as usize).min(len);
+        let end = ((segment.end_secs * sample_rate as f64) as usize).clamp(start, len);
+        mask[start..end].iter_mut().for_each(|m| *m = true);
+    }
+    mask
+}
+
+// 譜減法降噪：噪聲譜取非語音幀的平均功率譜，語音幀按增益函數衰減噪聲成分
+fn spectral_subtract(samples: &[f32], speech_mask: &[bool]) -> Vec<f32> {
+    if samples.len() < FFT_SIZE {
+        return samples.to_vec();
+    }
+
+    let mut planner = RealFftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(FFT_SIZE);
+    let inverse = planner.plan_fft_inverse(FFT_SIZE);
+    // 平方根漢寧窗用於分析與合成，50% 重疊時可完美重建
+    let window: Vec<f32> = (0..FFT_SIZE)
+        .map(|i| (std::f32::consts::PI * i as f32 / FFT_SIZE as f32).sin())
+        .collect();
+
+    // 前後補零，保證首尾樣本也被完整覆蓋
+    let mut padded = vec![0.0f32; FFT_SIZE];
+    padded.extend_from_slice(samples);
+    padded.resize(padded.len() + 2 * FFT_SIZE, 0.0);
+    let frame_starts: Vec<usize> = (0..=padded.len() - FFT_SIZE).step_by(FFT_HOP).collect();
+
+    let mut input = forward.make_input_vec();
+    let mut spectra = Vec::with_capacity(frame_starts.len());
+    for &start in &frame_starts {
+        for (i, value) in input.iter_mut().enumerate() {
+            *value = padded[start + i] * window[i];
+        }
+        let mut spectrum = forward.make_output_vec();
+        if forward.process(&mut input, &mut spectrum).is_err() {
+            return samples.to_vec();
+        }
+        spectra.push(spectrum);
+    }
+
+    let powers: Vec<Vec<f32>> = spectra
+        .iter()
+        .map(|spectrum| spectrum.iter().map(|c| c.norm_sqr()).collect())
+        .collect();
+    let is_noise_frame = |start: usize| {
+        let center = (start + FFT_SIZE / 2).checked_sub(FFT_SIZE);
+        center.is_some_and(|c| c < samples.len() && !speech_mask[c])
+    };
+    let mut noise_frames: Vec<usize> =
+        (0..frame_starts.len()).filter(|&f| is_noise_frame(frame_starts[f])).collect();
+    // 非語音幀太少時，取能量最低的 10% 幀估計噪聲
+    if noise_frames.len() < MIN_NOISE_FRAMES {
+        let mut by_energy: Vec<(usize, f32)> =
+            powers.iter().map(|p| p.iter().sum::<f32>()).enumerate().collect();
+        by_energy.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let count = (by_energy.len() / 10).max(1);
+        noise_frames = by_energy.iter().take(count).map(|&(f, _)| f).collect();
+    }
+    let bins = FFT_SIZE / 2 + 1;
+    let mut noise = vec![0.0f32; bins];
+    for &f in &noise_frames {
+        for (n, p) in noise.iter_mut().zip(&powers[f]) {
+            *n += p / noise_frames.len() as f32;
+        }
+    }
+
+    let mut output = vec![0.0f32; padded.len()];
+    let mut frame_out = inverse.make_output_vec();
+    let mut previous_gain = vec![1.0f32; bins];
+    for (f, mut spectrum) in spectra.into_iter().enumerate() {
+        for bin in 0..bins {
+            let power = powers[f][bin].max(1e-12);
+            let gain = (1.0 - OVER_SUBTRACTION * noise[bin] / power).max(SPECTRAL_FLOOR).sqrt();
+            // 增益在時間上平滑，減少「音樂噪聲」
+            let smoothed = 0.7 * gain + 0.3 * previous_gain[bin];
+            previous_gain[bin] = smoothed;
+            spectrum[bin] *= smoothed;
+        }
+        // 實數逆變換要求直流與奈奎斯特分量的虛部為零
+        spectrum[0].im = 0.0;
+        spectrum[bins - 1].im = 0.0;
+        if inverse.process(&mut spectrum, &mut frame_out).is_err() {
+            return samples.to_vec();
+        }
+        let start = frame_starts[f];
+        for i in 0..FFT_SIZE {
+            output[start + i] += frame_out[i] * window[i] / FFT_SIZE as f32;
+        }
+    }
+
+    output[FFT_SIZE..FFT_SIZE + samples.len()].to_vec()
+}
+
+// 按語音段平均電平計算增益，並限制峰值
+fn normalize_loudness(samples: &[f32], speech_mask: &[bool]) -> Vec<f32> {
+    let speech: Vec<f32> = samples
+        .iter()
+        .zip(speech_mask)
+        .filter(|(_, &speech)| speech)
+        .map(|(&s, _)| s)
+        .collect();
+    let reference = if speech.is_empty() { samples } else { &speech[..] };
+    let mean_square = reference.iter().map(|s| (s * s) as f64).sum::<f64>() / reference.len().max(1) as f64;
+    if mean_square <= 1e-12 {
+        return samples.to_vec();
+    }
+
+    let level_db = 10.0 * mean_square.log10();
+    let gain_db = (TARGET_LEVEL_DB - level_db).clamp(-MAX_GAIN_DB, MAX_GAIN_DB);
+    let mut gain = 10f64.powf(gain_db / 20.0) as f32;
+    let peak = samples.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+    if peak * gain > PEAK_LIMIT {
+        gain = PEAK_LIMIT / peak;
+    }
+    samples.iter().map(|s| s * gain).collect()
+}
+
+// 按功率（而非 dB）平均多個幀的能量
+fn power_mean_db(levels: &[f32]) -> f64 {
+    if levels.is_empty() {
+        return -100.0;
+    }
+    let sum: f64 = levels.iter().map(|&db| 10f64.powf(db as f64 / 10.0)).sum();
+    10.0 * (sum / levels.len() as f64 + 1e-10).log10()
+}
+
+fn mean(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64) as f32
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    // 可重複的偽隨機白噪聲
+    fn noise(count: usize, amplitude: f32) -> Vec<f32> {
+        let mut state = 0x2545_f491u32;
+        (0..count)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                amplitude * ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0)
+            })
+            .collect()
+    }
+
+    // 靜音 0.5 秒 + 語音 1 秒 + 靜音 0.5 秒，疊加背景噪聲
+    fn recording(speech_amplitude: f32, noise_amplitude: f32) -> AudioClip {
+        let mut samples = noise(2 * RATE as usize, noise_amplitude);
+        for (i, sample) in samples.iter_mut().enumerate().skip(RATE as usize / 2).take(RATE as usize) {
+            *sample += speech_amplitude * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / RATE as f32).sin();
+        }
+        AudioClip::new(samples, RATE)
+    }
+
+    fn rms_db(samples: &[f32]) -> f64 {
+        10.0 * (samples.iter().map(|s| (s * s) as f64).sum::<f64>() / samples.len() as f64).log10()
+    }
+
+    #[test]
+    fn clean_recording_passes_and_is_normalized() {
+        let prepared = prepare_recording(&recording(0.05, 0.001), &VadConfig::default());
+        assert!(prepared.quality.passed, "{:?}", prepared.quality);
+        assert!(prepared.quality.snr_db > 20.0);
+        let speech = &prepared.clip.samples[RATE as usize * 3 / 4..RATE as usize * 5 / 4];
+        assert!((rms_db(speech) - TARGET_LEVEL_DB).abs() < 2.0, "{}", rms_db(speech));
+    }
+
+    #[test]
+    fn detects_quality_issues() {
+        let config = VadConfig::default();
+        let silent = prepare_recording(&AudioClip::new(vec![0.0; 2 * RATE as usize], RATE), &config);
+        assert_eq!(silent.quality.issue, Some(QualityIssue::TooQuiet));
+
+        let clipped = recording(0.5, 0.001);
+        let clipped = AudioClip::new(clipped.samples.iter().map(|s| (s * 4.0).clamp(-1.0, 1.0)).collect(), RATE);
+        assert_eq!(prepare_recording(&clipped, &config).quality.issue, Some(QualityIssue::Clipped));
+
+        let noisy = prepare_recording(&AudioClip::new(noise(2 * RATE as usize, 0.3), RATE), &config);
+        assert_eq!(noisy.quality.issue, Some(QualityIssue::TooNoisy));
+        assert_eq!(noisy.quality.message.as_deref(), Some(QualityIssue::TooNoisy.message()));
+    }
+
+    #[test]
+    fn removes_dc_offset() {
+        let shifted: Vec<f32> = recording(0.1, 0.001).samples.iter().map(|s| s + 0.2).collect();
+        let prepared = prepare_recording(&AudioClip::new(shifted, RATE), &VadConfig::default());
+        assert!((prepared.quality.dc_offset - 0.2).abs() < 1e-3);
+        assert!(mean(&prepared.clip.samples).abs() < 0.01);
+    }
+
+    #[test]
+    fn spectral_subtraction_reduces_background_noise() {
+        let clip = recording(0.1, 0.02);
+        let activity = vad::detect_speech(&clip, &VadConfig::default());
+        let mask = speech_mask(&activity, clip.samples.len(), RATE);
+        let denoised = spectral_subtract(&clip.samples, &mask);
+        let quiet = ..RATE as usize * 2 / 5;
+        assert!(rms_db(&denoised[quiet]) < rms_db(&clip.samples[quiet]) - 6.0);
+        let speech = RATE as usize * 3 / 4..RATE as usize * 5 / 4;
+        assert!((rms_db(&denoised[speech.clone()]) - rms_db(&clip.samples[speech])).abs() < 1.5);
+    }
+}
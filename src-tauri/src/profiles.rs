@@ -0,0 +1,312 @@
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::goals;
+
+const PROFILES_FILE: &str = "profiles.json";
+const PIN_ITERATIONS: u32 = 100_000;
+
+// 連續輸錯 PIN 達到次數後暫時鎖定
+const MAX_PIN_FAILURES: u32 = 5;
+const PIN_LOCKOUT: Duration = Duration::from_secs(60);
+
+// 舊版本直接保存在數據目錄下的文件，升級時移入默認檔案的目錄
+const LEGACY_ENTRIES: [&str; 7] =
+    ["practice.db", "practice.db-wal", "practice.db-shm", "practice.db-journal", "recordings", "tts", "exports"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfileRecord {
+    id: String,
+    name: String,
+    level: Option<String>,
+    pin_hash: Option<String>, // "迭代次數$鹽$哈希"，均為十六進制
+    created_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    active: String,
+    profiles: Vec<ProfileRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub level: Option<String>,
+    pub has_pin: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileList {
+    pub active: String,
+    pub locked: bool, // 當前檔案設置了 PIN 且本次啟動後尚未驗證，需要先調用 switch_profile
+    pub profiles: Vec<Profile>,
+}
+
+// 修改檔案，只更新提供了的字段；pin 傳空字符串表示取消 PIN
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileUpdate {
+    pub name: Option<String>,
+    pub level: Option<String>,
+    pub pin: Option<String>,
+}
+
+impl From<&ProfileRecord> for Profile {
+    fn from(record: &ProfileRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            name: record.name.clone(),
+            level: record.level.clone(),
+            has_pin: record.pin_hash.is_some(),
+            created_at: record.created_at.clone(),
+        }
+    }
+}
+
+// 學習者檔案列表，保存在應用數據目錄的 profiles.json；每個檔案的數據庫、錄音和緩存
+// 放在 profiles/<id>/ 下，互不影響
+pub struct ProfileStore {
+    root: PathBuf,
+    file: ProfilesFile,
+    pin_failures: HashMap<String, (u32, Instant)>,
+    // 當前檔案是否已解鎖；啟動時設置了 PIN 的檔案處於鎖定狀態
+    unlocked: bool,
+}
+
+fn now_string() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (day, time) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    format!("{} {:02}:{:02}:{:02}", goals::date_string(day), time / 3600, time % 3600 / 60, time % 60)
+}
+
+fn random_hex(bytes: usize) -> String {
+    (0..bytes).map(|_| format!("{:02x}", rand::random::<u8>())).collect()
+}
+
+fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("檔案名稱不能為空".to_string());
+    }
+    if name.chars().count() > 32 {
+        return Err("檔案名稱不能超過 32 個字".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn normalize_level(level: Option<&str>) -> Option<String> {
+    level.map(str::trim).filter(|l| !l.is_empty()).map(str::to_string)
+}
+
+fn hash_pin(pin: &str) -> Result<String, String> {
+    if pin.len() < 4 || pin.len() > 12 || !pin.chars().all(|c| c.is_ascii_digit()) {
+        return Err("PIN 必須是 4 到 12 位數字".to_string());
+    }
+    let salt = random_hex(16);
+    let mut hash = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(pin.as_bytes(), salt.as_bytes(), PIN_ITERATIONS, &mut hash);
+    let hash: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}${}${}", PIN_ITERATIONS, salt, hash))
+}
+
+fn pin_matches(stored: &str, pin: &str) -> bool {
+    let mut parts = stored.splitn(3, '$');
+    let (Some(iterations), Some(salt), Some(expected)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    let Ok(iterations) = iterations.parse::<u32>() else {
+        return false;
+    };
+    let mut hash = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(pin.as_bytes(), salt.as_bytes(), iterations, &mut hash);
+    let actual: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+    // 逐字節比較全部內容，耗時與匹配位置無關
+    actual.len() == expected.len() && actual.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+impl ProfileStore {
+    pub fn open(root: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(root).map_err(|e| format!("無法創建數據目錄：{}", e))?;
+        let path = root.join(PROFILES_FILE);
+        let file = if path.exists() {
+            let content = std::fs::read_to_string(&path).map_err(|e| format!("讀取檔案列表失敗：{}", e))?;
+            serde_json::from_str(&content).map_err(|e| format!("檔案列表格式錯誤：{}", e))?
+        } else {
+            ProfilesFile::default()
+        };
+        let mut store = Self { root: root.to_path_buf(), file, pin_failures: HashMap::new(), unlocked: false };
+
+        if store.file.profiles.is_empty() {
+            let profile = store.create("學習者", None, None)?;
+            store.migrate_legacy(&profile.id)?;
+            store.file.active = profile.id;
+            store.save()?;
+        } else if store.find(&store.file.active).is_none() {
+            store.file.active = store.file.profiles[0].id.clone();
+            store.save()?;
+        }
+        store.unlocked = store.find(&store.file.active).is_some_and(|p| p.pin_hash.is_none());
+        Ok(store)
+    }
+
+    // 把舊版本的數據移入第一個檔案，升級後歷史記錄仍然可見
+    fn migrate_legacy(&self, id: &str) -> Result<(), String> {
+        let dir = self.profile_dir(id);
+        for entry in LEGACY_ENTRIES {
+            let source = self.root.join(entry);
+            if source.exists() {
+                std::fs::rename(&source, dir.join(entry)).map_err(|e| format!("遷移舊數據失敗：{}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.file).map_err(|e| e.to_string())?;
+        // 先寫臨時文件再替換，避免寫到一半時崩潰導致列表損壞
+        let temp = self.root.join(format!("{}.tmp", PROFILES_FILE));
+        std::fs::write(&temp, json).map_err(|e| format!("保存檔案列表失敗：{}", e))?;
+        std::fs::rename(&temp, self.root.join(PROFILES_FILE)).map_err(|e| format!("保存檔案列表失敗：{}", e))
+    }
+
+    fn find(&self, id: &str) -> Option<&ProfileRecord> {
+        self.file.profiles.iter().find(|p| p.id == id)
+    }
+
+    fn find_mut(&mut self, id: &str) -> Result<&mut ProfileRecord, String> {
+        self.file.profiles.iter_mut().find(|p| p.id == id).ok_or_else(|| "檔案不存在".to_string())
+    }
+
+    pub fn profile_dir(&self, id: &str) -> PathBuf {
+        self.root.join("profiles").join(id)
+    }
+
+    pub fn active_id(&self) -> &str {
+        &self.file.active
+    }
+
+    pub fn active_dir(&self) -> PathBuf {
+        self.profile_dir(&self.file.active)
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked
+    }
+
+    pub fn get(&self, id: &str) -> Result<Profile, String> {
+        self.find(id).map(Profile::from).ok_or_else(|| "檔案不存在".to_string())
+    }
+
+    pub fn list(&self) -> ProfileList {
+        ProfileList {
+            active: self.file.active.clone(),
+            locked: !self.unlocked,
+            profiles: self.file.profiles.iter().map(Profile::from).collect(),
+        }
+    }
+
+    pub fn create(&mut self, name: &str, level: Option<&str>, pin: Option<&str>) -> Result<Profile, String> {
+        let name = normalize_name(name)?;
+        if self.file.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("已存在名為「{}」的檔案", name));
+        }
+        let record = ProfileRecord {
+            id: random_hex(8),
+            name,
+            level: normalize_level(level),
+            pin_hash: pin.filter(|p| !p.is_empty()).map(hash_pin).transpose()?,
+            created_at: now_string(),
+        };
+        std::fs::create_dir_all(self.profile_dir(&record.id)).map_err(|e| format!("無法創建檔案目錄：{}", e))?;
+        let profile = Profile::from(&record);
+        self.file.profiles.push(record);
+        self.save()?;
+        Ok(profile)
+    }
+
+    // 檢查 PIN；沒有設置 PIN 的檔案直接通過
+    pub fn verify_pin(&mut self, id: &str, pin: Option<&str>) -> Result<(), String> {
+        let record = self.find(id).ok_or("檔案不存在")?;
+        let Some(stored) = record.pin_hash.clone() else {
+            return Ok(());
+        };
+        if let Some((failures, since)) = self.pin_failures.get(id) {
+            if *failures >= MAX_PIN_FAILURES && since.elapsed() < PIN_LOCKOUT {
+                let wait = (PIN_LOCKOUT - since.elapsed()).as_secs() + 1;
+                return Err(format!("PIN 錯誤次數過多，請 {} 秒後再試", wait));
+            }
+        }
+        if pin.is_some_and(|pin| pin_matches(&stored, pin)) {
+            self.pin_failures.remove(id);
+            return Ok(());
+        }
+        let entry = self.pin_failures.entry(id.to_string()).or_insert((0, Instant::now()));
+        if entry.0 >= MAX_PIN_FAILURES {
+            *entry = (0, Instant::now());
+        }
+        entry.0 += 1;
+        entry.1 = Instant::now();
+        Err(if pin.is_some() { "PIN 不正確" } else { "該檔案需要輸入 PIN" }.to_string())
+    }
+
+    // 調用前需先通過 verify_pin
+    pub fn set_active(&mut self, id: &str) -> Result<(), String> {
+        self.find(id).ok_or("檔案不存在")?;
+        self.file.active = id.to_string();
+        self.unlocked = true;
+        self.save()
+    }
+
+    pub fn update(&mut self, id: &str, update: &ProfileUpdate) -> Result<Profile, String> {
+        let name = update.name.as_deref().map(normalize_name).transpose()?;
+        if let Some(name) = &name {
+            if self.file.profiles.iter().any(|p| &p.name == name && p.id != id) {
+                return Err(format!("已存在名為「{}」的檔案", name));
+            }
+        }
+        let pin_hash = match update.pin.as_deref() {
+            Some("") => Some(None),
+            Some(pin) => Some(Some(hash_pin(pin)?)),
+            None => None,
+        };
+        let record = self.find_mut(id)?;
+        if let Some(name) = name {
+            record.name = name;
+        }
+        if let Some(level) = &update.level {
+            record.level = normalize_level(Some(level));
+        }
+        if let Some(pin_hash) = pin_hash {
+            record.pin_hash = pin_hash;
+        }
+        let profile = Profile::from(&*record);
+        self.save()?;
+        Ok(profile)
+    }
+
+    // 刪除檔案及其全部數據；不能刪除正在使用的檔案
+    pub fn delete(&mut self, id: &str) -> Result<(), String> {
+        if id == self.file.active {
+            return Err("不能刪除正在使用的檔案，請先切換到其他檔案".to_string());
+        }
+        self.find(id).ok_or("檔案不存在")?;
+        self.file.profiles.retain(|p| p.id != id);
+        self.save()?;
+        self.pin_failures.remove(id);
+        let dir = self.profile_dir(id);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).map_err(|e| format!("刪除檔案數據失敗：{}", e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,560 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::text::tokenize_words;
+
+// 內置的 CMU 發音詞典（ARPAbet 音標，元音帶 0/1/2 重音標記），首次查詢時解析
+const CMUDICT: &str = include_str!("../data/cmudict-0.7b");
+
+static DICTIONARY: OnceLock<HashMap<String, Vec<Vec<&'static str>>>> = OnceLock::new();
+
+// 合法的詞首輔音組合，用於按「最大首音」原則劃分音節
+const ONSETS: &[&[&str]] = &[
+    &["P", "R"], &["P", "L"], &["B", "R"], &["B", "L"], &["T", "R"], &["D", "R"], &["K", "R"],
+    &["K", "L"], &["G", "R"], &["G", "L"], &["F", "R"], &["F", "L"], &["TH", "R"], &["SH", "R"],
+    &["S", "P"], &["S", "T"], &["S", "K"], &["S", "M"], &["S", "N"], &["S", "L"], &["S", "W"],
+    &["S", "F"], &["T", "W"], &["D", "W"], &["K", "W"], &["G", "W"], &["TH", "W"], &["P", "Y"],
+    &["B", "Y"], &["F", "Y"], &["V", "Y"], &["K", "Y"], &["M", "Y"], &["HH", "Y"], &["S", "P", "R"],
+    &["S", "P", "L"], &["S", "T", "R"], &["S", "K", "R"], &["S", "K", "W"], &["S", "K", "Y"],
+    &["S", "P", "Y"],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PronunciationSource {
+    Dictionary,
+    Derived, // 詞典中有詞幹，按詞尾規則推導（plays、googled）
+    Rules,   // 按拼寫規則推測，準確度有限
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Syllable {
+    pub phonemes: Vec<String>,
+    pub ipa: String,
+    pub stress: u8, // 0 無重音，1 主重音，2 次重音
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordPronunciation {
+    pub word: String,
+    pub source: PronunciationSource,
+    pub arpabet: Vec<String>,
+    pub ipa: String,
+    pub syllables: Vec<Syllable>,
+    pub stress_pattern: String, // 每個音節一位，如 "102"
+    pub primary_stress: Option<usize>,
+    pub variants: Vec<String>, // 其他讀音的 IPA
+}
+
+fn dictionary() -> &'static HashMap<String, Vec<Vec<&'static str>>> {
+    DICTIONARY.get_or_init(|| {
+        let mut entries: HashMap<String, Vec<Vec<&'static str>>> = HashMap::with_capacity(135_000);
+        for line in CMUDICT.lines() {
+            if line.starts_with(";;;") {
+                continue;
+            }
+            let Some((head, phones)) = line.split_once("  ") else {
+                continue;
+            };
+            // 變體讀音寫作 WORD(1)、WORD(2)
+            let word = head.split('(').next().unwrap_or(head).to_lowercase();
+            entries.entry(word).or_default().push(phones.split_whitespace().collect());
+        }
+        entries
+    })
+}
+
+// 提前解析詞典，避免首次評分時的延遲
+pub fn preload() {
+    dictionary();
+}
+
+pub fn dictionary_size() -> usize {
+    dictionary().len()
+}
+
+pub fn lookup(word: &str) -> Option<WordPronunciation> {
+    let word = word.trim().to_lowercase().replace('’', "'");
+    if !word.chars().any(|c| c.is_alphabetic()) {
+        return None;
+    }
+
+    if let Some(variants) = dictionary().get(&word) {
+        let mut pronunciations = variants.iter().map(|v| v.iter().map(|p| p.to_string()).collect());
+        let first = pronunciations.next()?;
+        let variants = pronunciations.map(|p: Vec<String>| to_ipa(&syllabify(&p))).collect();
+        return Some(build(word, PronunciationSource::Dictionary, first, variants));
+    }
+    if let Some(phonemes) = derive_from_stem(&word) {
+        return Some(build(word, PronunciationSource::Derived, phonemes, Vec::new()));
+    }
+    let phonemes = letters_to_phonemes(&word);
+    if phonemes.is_empty() {
+        return None;
+    }
+    Some(build(word, PronunciationSource::Rules, phonemes, Vec::new()))
+}
+
+pub fn pronounce_text(text: &str) -> Vec<WordPronunciation> {
+    tokenize_words(text).iter().filter_map(|w| lookup(w)).collect()
+}
+
+pub fn syllable_count(word: &str) -> usize {
+    lookup(word).map_or(1, |p| p.syllables.len().max(1))
+}
+
+// 詞典中的第一個讀音，去掉重音標記；未收錄時為空
+pub fn dictionary_phonemes(word: &str) -> Option<Vec<&'static str>> {
+    let entry = dictionary().get(&word.trim().to_lowercase())?.first()?;
+    Some(entry.iter().map(|p| split_stress(p).0).collect())
+}
+
+// 詞典裡大量是人名地名，以收錄了幾種規則的派生形式（複數、過去式、進行式等）粗略判斷是否為常用詞
+fn is_common_word(word: &str) -> bool {
+    let entries = dictionary();
+    let has = |suffixes: &[&str]| suffixes.iter().any(|suffix| entries.contains_key(&format!("{}{}", word, suffix)));
+    let forms = [&["s", "es"][..], &["ed", "d"], &["ing"], &["er", "r"], &["ly"]];
+    forms.iter().filter(|suffixes| has(suffixes)).count() >= 2
+}
+
+// 在詞典中查找只在一個音位上不同、分別含 a 和 b 的最小對立詞對（ship/sheep）。
+// 只取只有一種讀音、長度適中的常用純字母詞
+pub fn minimal_pairs(a: &str, b: &str) -> Vec<(String, String)> {
+    let candidates = || {
+        dictionary().iter().filter(|(word, variants)| {
+            variants.len() == 1
+                && (3..=7).contains(&word.len())
+                && word.chars().all(|c| c.is_ascii_lowercase())
+                && is_common_word(word)
+        })
+    };
+    // 把目標音位替換為通配符作為鍵，兩邊鍵相同即構成最小對立
+    let keys = |word_phonemes: &[&'static str], target: &str| -> Vec<Vec<&'static str>> {
+        let base: Vec<&'static str> = word_phonemes.iter().map(|p| split_stress(p).0).collect();
+        (0..base.len())
+            .filter(|&i| base[i] == target)
+            .map(|i| {
+                let mut key = base.clone();
+                key[i] = "*";
+                key
+            })
+            .collect()
+    };
+
+    let mut with_a: HashMap<Vec<&'static str>, Vec<&str>> = HashMap::new();
+    for (word, variants) in candidates() {
+        for key in keys(&variants[0], a) {
+            with_a.entry(key).or_default().push(word);
+        }
+    }
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (word, variants) in candidates() {
+        for key in keys(&variants[0], b) {
+            for partner in with_a.get(&key).into_iter().flatten() {
+                pairs.push((partner.to_string(), word.clone()));
+            }
+        }
+    }
+    pairs.sort();
+    pairs.dedup();
+    pairs
+}
+
+fn build(word: String, source: PronunciationSource, arpabet: Vec<String>, variants: Vec<String>) -> WordPronunciation {
+    let syllables = syllabify(&arpabet);
+    WordPronunciation {
+        word,
+        source,
+        ipa: to_ipa(&syllables),
+        stress_pattern: syllables.iter().map(|s| char::from(b'0' + s.stress)).collect(),
+        primary_stress: syllables.iter().position(|s| s.stress == 1),
+        syllables,
+        arpabet,
+        variants,
+    }
+}
+
+fn is_vowel(phoneme: &str) -> bool {
+    phoneme.ends_with(['0', '1', '2'])
+}
+
+fn split_stress(phoneme: &str) -> (&str, u8) {
+    match phoneme.char_indices().last() {
+        Some((i, c)) if c.is_ascii_digit() => (&phoneme[..i], c as u8 - b'0'),
+        _ => (phoneme, 0),
+    }
+}
+
+// 按最大首音原則劃分音節：兩個元音之間的輔音盡量歸入後一個音節
+fn syllabify(phonemes: &[String]) -> Vec<Syllable> {
+    let nuclei: Vec<usize> = (0..phonemes.len()).filter(|&i| is_vowel(&phonemes[i])).collect();
+    if nuclei.is_empty() {
+        return Vec::new();
+    }
+
+    let mut boundaries = vec![0];
+    for pair in nuclei.windows(2) {
+        let cluster: Vec<&str> = phonemes[pair[0] + 1..pair[1]].iter().map(String::as_str).collect();
+        let onset_len = (0..=cluster.len())
+            .rev()
+            .find(|&n| is_legal_onset(&cluster[cluster.len() - n..]))
+            .unwrap_or(0);
+        boundaries.push(pair[1] - onset_len);
+    }
+    boundaries.push(phonemes.len());
+
+    boundaries
+        .windows(2)
+        .map(|range| {
+            let part = &phonemes[range[0]..range[1]];
+            let stress = part.iter().find(|p| is_vowel(p)).map_or(0, |p| split_stress(p).1);
+            Syllable {
+                phonemes: part.iter().map(|p| split_stress(p).0.to_string()).collect(),
+                ipa: part.iter().map(|p| phoneme_ipa(p)).collect(),
+                stress,
+            }
+        })
+        .collect()
+}
+
+fn is_legal_onset(cluster: &[&str]) -> bool {
+    match cluster {
+        [] => true,
+        [single] => *single != "NG",
+        _ => ONSETS.contains(&cluster),
+    }
+}
+
+// 多音節詞在重讀音節前標 ˈ（主重音）或 ˌ（次重音）
+fn to_ipa(syllables: &[Syllable]) -> String {
+    let mark = syllables.len() > 1;
+    syllables
+        .iter()
+        .map(|s| match s.stress {
+            1 if mark => format!("ˈ{}", s.ipa),
+            2 if mark => format!("ˌ{}", s.ipa),
+            _ => s.ipa.clone(),
+        })
+        .collect()
+}
+
+pub fn phoneme_ipa(phoneme: &str) -> &'static str {
+    let (base, stress) = split_stress(phoneme);
+    match base {
+        "AA" => "ɑ",
+        "AE" => "æ",
+        "AH" if stress == 0 => "ə",
+        "AH" => "ʌ",
+        "AO" => "ɔ",
+        "AW" => "aʊ",
+        "AY" => "aɪ",
+        "EH" => "ɛ",
+        "ER" if stress == 0 => "ɚ",
+        "ER" => "ɝ",
+        "EY" => "eɪ",
+        "IH" => "ɪ",
+        "IY" => "i",
+        "OW" => "oʊ",
+        "OY" => "ɔɪ",
+        "UH" => "ʊ",
+        "UW" => "u",
+        "B" => "b",
+        "CH" => "tʃ",
+        "D" => "d",
+        "DH" => "ð",
+        "F" => "f",
+        "G" => "ɡ",
+        "HH" => "h",
+        "JH" => "dʒ",
+        "K" => "k",
+        "L" => "l",
+        "M" => "m",
+        "N" => "n",
+        "NG" => "ŋ",
+        "P" => "p",
+        "R" => "ɹ",
+        "S" => "s",
+        "SH" => "ʃ",
+        "T" => "t",
+        "TH" => "θ",
+        "V" => "v",
+        "W" => "w",
+        "Y" => "j",
+        "Z" => "z",
+        "ZH" => "ʒ",
+        _ => "",
+    }
+}
+
+// 詞典沒有收錄的屈折形式：找到詞幹後按詞尾的發音規則補上後綴
+fn derive_from_stem(word: &str) -> Option<Vec<String>> {
+    let stem_phonemes = |stem: &str| -> Option<Vec<String>> {
+        let entry = dictionary().get(stem)?.first()?;
+        Some(entry.iter().map(|p| p.to_string()).collect())
+    };
+
+    for (suffix, kind) in [("'s", "s"), ("s", "s"), ("es", "s"), ("ed", "ed"), ("d", "ed"), ("ing", "ing"), ("ly", "ly"), ("er", "er"), ("est", "est")] {
+        let Some(stem) = word.strip_suffix(suffix).filter(|s| s.len() >= 2) else {
+            continue;
+        };
+        // making → make，stopped → stop，tried → try
+        let mut candidates = vec![stem.to_string(), format!("{}e", stem)];
+        let chars: Vec<char> = stem.chars().collect();
+        if chars.len() >= 3 && chars[chars.len() - 1] == chars[chars.len() - 2] {
+            candidates.push(chars[..chars.len() - 1].iter().collect());
+        }
+        if let Some(base) = stem.strip_suffix('i') {
+            candidates.push(format!("{}y", base));
+        }
+
+        let Some(mut phonemes) = candidates.iter().find_map(|stem| stem_phonemes(stem)) else {
+            continue;
+        };
+        let last = phonemes.last().map(|p| split_stress(p).0.to_string()).unwrap_or_default();
+        let suffix_phonemes: &[&str] = match kind {
+            "s" if matches!(last.as_str(), "S" | "Z" | "SH" | "ZH" | "CH" | "JH") => &["IH0", "Z"],
+            "s" if matches!(last.as_str(), "P" | "T" | "K" | "F" | "TH") => &["S"],
+            "s" => &["Z"],
+            "ed" if matches!(last.as_str(), "T" | "D") => &["IH0", "D"],
+            "ed" if matches!(last.as_str(), "P" | "K" | "F" | "TH" | "S" | "SH" | "CH") => &["T"],
+            "ed" => &["D"],
+            "ing" => &["IH0", "NG"],
+            "ly" => &["L", "IY0"],
+            "er" => &["ER0"],
+            _ => &["AH0", "S", "T"],
+        };
+        phonemes.extend(suffix_phonemes.iter().map(|p| p.to_string()));
+        return Some(phonemes);
+    }
+    None
+}
+
+// 字母到音素的規則轉換，用於詞典和詞幹推導都無法處理的詞（新詞、專有名詞、拼寫錯誤）
+fn letters_to_phonemes(word: &str) -> Vec<String> {
+    let letters: Vec<char> = word.to_lowercase().chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    let n = letters.len();
+    let is_vowel_letter = |i: usize| i < n && matches!(letters[i], 'a' | 'e' | 'i' | 'o' | 'u');
+    let rest = |i: usize| letters[i..].iter().collect::<String>();
+
+    let mut phonemes: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < n {
+        let tail = rest(i);
+        let at_start = i == 0;
+        let at_end = |len: usize| i + len == n;
+
+        // 多字母組合，較長的優先
+        let rule: Option<(usize, &[&str])> = if tail.starts_with("tion") {
+            Some((4, &["SH", "AH0", "N"]))
+        } else if tail.starts_with("sion") {
+            Some((4, if i > 0 && is_vowel_letter(i - 1) { &["ZH", "AH0", "N"] } else { &["SH", "AH0", "N"] }))
+        } else if tail.starts_with("ture") {
+            Some((4, &["CH", "ER0"]))
+        } else if tail.starts_with("eigh") {
+            Some((4, &["EY1"]))
+        } else if tail.starts_with("ough") || tail.starts_with("augh") {
+            Some((4, &["AO1"]))
+        } else if tail.starts_with("igh") {
+            Some((3, &["AY1"]))
+        } else if tail.starts_with("tch") {
+            Some((3, &["CH"]))
+        } else if tail.starts_with("dge") {
+            Some((3, &["JH"]))
+        } else if tail.starts_with("sch") {
+            Some((3, &["S", "K"]))
+        } else if tail.starts_with("le") && at_end(2) && i > 0 && !is_vowel_letter(i - 1) {
+            Some((2, &["AH0", "L"]))
+        } else if tail.starts_with("ed") && at_end(2) && i > 1 {
+            let before = letters[i - 1];
+            Some((2, match before {
+                't' | 'd' => &["IH0", "D"],
+                'p' | 'k' | 'f' | 's' | 'x' | 'c' | 'h' => &["T"],
+                _ => &["D"],
+            }))
+        } else if tail.starts_with("es") && at_end(2) && i > 1 && matches!(letters[i - 1], 's' | 'x' | 'z' | 'h') {
+            Some((2, &["IH0", "Z"]))
+        } else if tail.starts_with("kn") && at_start {
+            Some((2, &["N"]))
+        } else if tail.starts_with("wr") && at_start {
+            Some((2, &["R"]))
+        } else if tail.starts_with("gh") {
+            Some((2, if at_start { &["G"] } else { &[] }))
+        } else {
+            let digraph: Option<&[&str]> = match tail.get(..2).unwrap_or("") {
+                "ph" => Some(&["F"]),
+                "sh" => Some(&["SH"]),
+                "ch" => Some(&["CH"]),
+                "th" => Some(&["TH"]),
+                "wh" => Some(&["W"]),
+                "ck" => Some(&["K"]),
+                "ng" => Some(&["NG"]),
+                "qu" => Some(&["K", "W"]),
+                "ee" | "ea" | "ie" | "ei" => Some(&["IY1"]),
+                "ey" if at_end(2) => Some(&["IY0"]),
+                "oo" => Some(&["UW1"]),
+                "ou" => Some(&["AW1"]),
+                "ow" if at_end(2) => Some(&["OW1"]),
+                "ow" => Some(&["AW1"]),
+                "ai" | "ay" => Some(&["EY1"]),
+                "oi" | "oy" => Some(&["OY1"]),
+                "au" | "aw" => Some(&["AO1"]),
+                "ew" | "ue" => Some(&["UW1"]),
+                "oa" => Some(&["OW1"]),
+                "ar" if !is_vowel_letter(i + 2) => Some(&["AA1", "R"]),
+                "or" if !is_vowel_letter(i + 2) => Some(&["AO1", "R"]),
+                "er" | "ir" | "ur" if !is_vowel_letter(i + 2) => Some(&["ER1"]),
+                _ => None,
+            };
+            digraph.map(|p| (2, p))
+        };
+
+        if let Some((len, output)) = rule {
+            phonemes.extend_from_slice(output);
+            i += len;
+            continue;
+        }
+
+        let c = letters[i];
+        let next = letters.get(i + 1).copied();
+        // 元音 + 單個輔音 + 詞尾 e（make、time）讀長音
+        let magic_e = i + 3 == n && letters[n - 1] == 'e' && !is_vowel_letter(i + 1);
+        let output: &[&str] = match c {
+            'a' if magic_e => &["EY1"],
+            'e' if magic_e => &["IY1"],
+            'i' if magic_e => &["AY1"],
+            'o' if magic_e => &["OW1"],
+            'u' if magic_e => &["UW1"],
+            // 詞尾不發音的 e
+            'e' if i + 1 == n && n > 2 => &[],
+            'a' => &["AE1"],
+            'e' => &["EH1"],
+            'i' => &["IH1"],
+            'o' => &["AA1"],
+            'u' => &["AH1"],
+            'y' if at_start => &["Y"],
+            'y' if i + 1 == n && phonemes.iter().any(|p| is_vowel(p)) => &["IY0"],
+            'y' if i + 1 == n => &["AY1"],
+            'y' => &["IH1"],
+            'c' if matches!(next, Some('e' | 'i' | 'y')) => &["S"],
+            'c' | 'k' | 'q' => &["K"],
+            'g' if matches!(next, Some('e' | 'i' | 'y')) => &["JH"],
+            'g' => &["G"],
+            'x' => &["K", "S"],
+            's' if i + 1 == n && i > 0 && !matches!(letters[i - 1], 'p' | 't' | 'k' | 'f' | 's') => &["Z"],
+            'b' => &["B"],
+            'd' => &["D"],
+            'f' => &["F"],
+            'h' => &["HH"],
+            'j' => &["JH"],
+            'l' => &["L"],
+            'm' => &["M"],
+            'n' => &["N"],
+            'p' => &["P"],
+            'r' => &["R"],
+            's' => &["S"],
+            't' => &["T"],
+            'v' => &["V"],
+            'w' => &["W"],
+            'z' => &["Z"],
+            _ => &[],
+        };
+        phonemes.extend_from_slice(output);
+        // 雙寫輔音只發一次音
+        i += if next == Some(c) && !is_vowel_letter(i) { 2 } else { 1 };
+    }
+
+    assign_stress(phonemes.into_iter().map(str::to_string).collect(), word)
+}
+
+// 規則轉換的重音：默認重讀第一個音節，-tion、-ic、-ity 等後綴重讀其前一個音節；
+// 非重讀的 AH 弱化為 ə
+fn assign_stress(mut phonemes: Vec<String>, word: &str) -> Vec<String> {
+    let vowels: Vec<usize> = (0..phonemes.len()).filter(|&i| is_vowel(&phonemes[i])).collect();
+    if vowels.is_empty() {
+        return phonemes;
+    }
+    let stressed = if vowels.len() >= 2 && ["tion", "sion", "ic", "ical"].iter().any(|s| word.ends_with(s)) {
+        vowels[vowels.len() - 2]
+    } else if vowels.len() >= 3 && word.ends_with("ity") {
+        vowels[vowels.len() - 3]
+    } else {
+        vowels[0]
+    };
+    for &v in &vowels {
+        let (base, _) = split_stress(&phonemes[v]);
+        let base = if v != stressed && matches!(base, "AE" | "AA") { "AH" } else { base };
+        phonemes[v] = format!("{}{}", base, if v == stressed { 1 } else { 0 });
+    }
+    phonemes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syllable_ipa(word: &WordPronunciation) -> Vec<&str> {
+        word.syllables.iter().map(|s| s.ipa.as_str()).collect()
+    }
+
+    #[test]
+    fn looks_up_dictionary_words_with_syllables_and_stress() {
+        let banana = lookup("Banana").unwrap();
+        assert_eq!(banana.source, PronunciationSource::Dictionary);
+        assert_eq!(banana.ipa, "bəˈnænə");
+        assert_eq!(syllable_ipa(&banana), vec!["bə", "næ", "nə"]);
+        assert_eq!(banana.stress_pattern, "010");
+        assert_eq!(banana.primary_stress, Some(1));
+
+        // 最大首音：kstr 中只有 str 是合法詞首
+        let extra = lookup("extra").unwrap();
+        assert_eq!(syllable_ipa(&extra), vec!["ɛk", "stɹə"]);
+        assert_eq!(extra.syllables[1].phonemes, vec!["S", "T", "R", "AH"]);
+
+        // 單音節詞不標重音符號，其他讀音作為變體
+        let read = lookup("read").unwrap();
+        assert_eq!(read.ipa, "ɹɛd");
+        assert_eq!(read.variants, vec!["ɹid"]);
+        assert!(lookup("123").is_none());
+    }
+
+    #[test]
+    fn derives_inflections_from_stems() {
+        let vibing = lookup("vibing").unwrap();
+        assert_eq!(vibing.source, PronunciationSource::Derived);
+        assert_eq!(vibing.arpabet, vec!["V", "AY1", "B", "IH0", "NG"]);
+
+        let catted = lookup("catted").unwrap();
+        assert_eq!(catted.arpabet, vec!["K", "AE1", "T", "IH0", "D"]);
+        assert_eq!(catted.ipa, "ˈkætɪd");
+    }
+
+    #[test]
+    fn falls_back_to_spelling_rules() {
+        let word = lookup("zorbanation").unwrap();
+        assert_eq!(word.source, PronunciationSource::Rules);
+        assert!(word.syllables.len() >= 3);
+        // -tion 重讀其前一個音節
+        assert_eq!(word.primary_stress, Some(word.syllables.len() - 2));
+        assert_eq!(word.stress_pattern.matches('1').count(), 1);
+    }
+
+    #[test]
+    fn counts_syllables_and_maps_phonemes() {
+        assert_eq!(syllable_count("photographs"), 3);
+        assert_eq!(syllable_count("nation"), 2);
+        assert_eq!(syllable_count("!!"), 1);
+        assert_eq!(pronounce_text("Try the banana!").len(), 3);
+        assert_eq!(dictionary_phonemes("Wish"), Some(vec!["W", "IH", "SH"]));
+        assert_eq!(phoneme_ipa("AH1"), "ʌ");
+        assert_eq!(phoneme_ipa("AH0"), "ə");
+        assert_eq!(phoneme_ipa("XX"), "");
+    }
+
+    #[test]
+    fn finds_minimal_pairs() {
+        let pairs = minimal_pairs("IH", "IY");
+        assert!(pairs.contains(&("bit".to_string(), "beat".to_string())));
+        assert!(pairs.iter().all(|(a, b)| a != b));
+    }
+}
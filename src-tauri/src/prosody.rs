@@ -0,0 +1,689 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{frame_energy_db, AudioClip, ANALYSIS_SAMPLE_RATE};
+use crate::pronunciation::syllable_count;
+use crate::text::{is_filled_pause, tokenize_words, TimedWord};
+
+const FRAME_SECS: f32 = 0.04;
+const HOP_SECS: f32 = 0.01;
+const MIN_F0_HZ: f32 = 65.0;
+const MAX_F0_HZ: f32 = 450.0;
+const YIN_THRESHOLD: f32 = 0.15;
+// 音高標準差低於此值（半音）視為平調朗讀
+const MONOTONE_SEMITONES: f64 = 1.5;
+// 時間對齊與輸出曲線的降採樣步長（幀）
+const CONTOUR_STEP: usize = 2;
+const MAX_DTW_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordStress {
+    pub word: String,
+    pub index: usize,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub expected_stress: bool,
+    pub learner_stress: bool,
+    pub matched: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProsodyAnalysis {
+    pub prosody_score: f64,
+    pub intonation_score: f64,
+    pub stress_score: Option<f64>,
+    pub contour_similarity: Option<f64>, // 僅在提供參考音頻時計算
+    pub mean_pitch_hz: f64,
+    pub pitch_range_semitones: f64,
+    pub is_monotone: bool,
+    pub word_stress: Vec<WordStress>,
+    pub contour_step_ms: u32,
+    pub pitch_contour: Vec<f32>,                   // 0 表示無聲幀
+    pub reference_pitch_contour: Option<Vec<f32>>, // 已對齊到學習者的時間軸
+}
+
+// 逐幀的音高與能量
+struct ProsodyTrack {
+    pitch_hz: Vec<f32>, // 0 表示無聲
+    energy_db: Vec<f32>,
+    semitones: Vec<Option<f32>>, // 相對說話人中位音高
+    median_hz: f32,
+}
+
+impl ProsodyTrack {
+    fn extract(clip: &AudioClip) -> Self {
+        let clip = clip.resampled(ANALYSIS_SAMPLE_RATE);
+        let sample_rate = clip.sample_rate as f32;
+        let frame_len = (FRAME_SECS * sample_rate) as usize;
+        let hop = (HOP_SECS * sample_rate) as usize;
+
+        let energy_db = frame_energy_db(&clip.samples, frame_len, hop);
+        let peak_db = energy_db.iter().cloned().fold(f32::MIN, f32::max);
+        let voiced_floor = (peak_db - 35.0).max(-55.0);
+
+        let raw_pitch: Vec<f32> = energy_db
+            .iter()
+            .enumerate()
+            .map(|(i, &db)| {
+                if db < voiced_floor {
+                    return 0.0;
+                }
+                let start = i * hop;
+                yin_pitch(&clip.samples[start..start + frame_len], sample_rate).unwrap_or(0.0)
+            })
+            .collect();
+        let pitch_hz = median_smooth(&raw_pitch);
+
+        let voiced: Vec<f32> = pitch_hz.iter().cloned().filter(|&p| p > 0.0).collect();
+        let median_hz = median(&voiced).unwrap_or(0.0);
+        let semitones = pitch_hz
+            .iter()
+            .map(|&p| {
+                if p > 0.0 && median_hz > 0.0 {
+                    Some(12.0 * (p / median_hz).log2())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Self { pitch_hz, energy_db, semitones, median_hz }
+    }
+
+    fn len(&self) -> usize {
+        self.energy_db.len()
+    }
+
+    // 去掉首尾靜音後的語音範圍 [start, end)
+    fn speech_bounds(&self) -> Option<(usize, usize)> {
+        let peak = self.energy_db.iter().cloned().fold(f32::MIN, f32::max);
+        let threshold = (peak - 30.0).max(-60.0);
+        let first = self.energy_db.iter().position(|&db| db >= threshold)?;
+        let last = self.energy_db.iter().rposition(|&db| db >= threshold)?;
+        Some((first, last + 1))
+    }
+
+    fn semitone_spread(&self) -> f64 {
+        let values: Vec<f32> = self.semitones.iter().flatten().cloned().collect();
+        std_dev(&values) as f64
+    }
+
+    // DTW 使用的特徵：插值後的半音曲線與歸一化能量
+    fn features(&self, bounds: (usize, usize), step: usize) -> Vec<[f32; 2]> {
+        let contour = interpolate_gaps(&self.semitones[bounds.0..bounds.1]);
+        let energy = &self.energy_db[bounds.0..bounds.1];
+        let mean = energy.iter().sum::<f32>() / energy.len() as f32;
+        let spread = std_dev(energy).max(1.0);
+        (0..energy.len())
+            .step_by(step)
+            .map(|i| [contour[i] / 3.0, (energy[i] - mean) / spread])
+            .collect()
+    }
+
+    // 詞的突顯度：能量、音高峰值與每音節時長的標準分之和
+    fn word_prominence(&self, spans: &[(usize, usize)], words: &[String]) -> Vec<f32> {
+        let energy: Vec<f32> = spans
+            .iter()
+            .map(|&(s, e)| mean(&self.energy_db[s..e]).unwrap_or(-90.0))
+            .collect();
+        let pitch: Vec<Option<f32>> = spans
+            .iter()
+            .map(|&(s, e)| {
+                self.semitones[s..e]
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .fold(None, |acc: Option<f32>, v| Some(acc.map_or(v, |a| a.max(v))))
+            })
+            .collect();
+        let duration: Vec<f32> = spans
+            .iter()
+            .zip(words)
+            .map(|(&(s, e), w)| (e - s) as f32 / syllable_count(w) as f32)
+            .collect();
+
+        let energy_z = z_scores(&energy);
+        let duration_z = z_scores(&duration);
+        let present: Vec<f32> = pitch.iter().flatten().cloned().collect();
+        let pitch_mean = mean(&present).unwrap_or(0.0);
+        let pitch_spread = std_dev(&present).max(0.5);
+
+        (0..spans.len())
+            .map(|i| {
+                let pitch_z = pitch[i].map_or(0.0, |p| (p - pitch_mean) / pitch_spread);
+                energy_z[i] + pitch_z + 0.5 * duration_z[i]
+            })
+            .collect()
+    }
+}
+
+// 分析學習者錄音的語調與重音；有參考音頻（TTS）時做時間對齊比較
+pub fn analyze_prosody(
+    learner: &AudioClip,
+    reference: Option<&AudioClip>,
+    reference_text: &str,
+    word_timings: Option<&[TimedWord]>,
+) -> ProsodyAnalysis {
+    let track = ProsodyTrack::extract(learner);
+    let spread = track.semitone_spread();
+    let pitch_contour: Vec<f32> = track.pitch_hz.iter().step_by(CONTOUR_STEP).cloned().collect();
+    let contour_step_ms = (HOP_SECS * 1000.0) as u32 * CONTOUR_STEP as u32;
+
+    let bounds = match track.speech_bounds() {
+        Some(b) if track.median_hz > 0.0 => b,
+        _ => {
+            // 沒有檢測到有聲語音
+            return ProsodyAnalysis {
+                prosody_score: 0.0,
+                intonation_score: 0.0,
+                stress_score: None,
+                contour_similarity: None,
+                mean_pitch_hz: 0.0,
+                pitch_range_semitones: 0.0,
+                is_monotone: true,
+                word_stress: Vec::new(),
+                contour_step_ms,
+                pitch_contour,
+                reference_pitch_contour: None,
+            };
+        }
+    };
+
+    let words = tokenize_words(reference_text);
+    let spans = word_timings
+        .and_then(|timings| spans_from_timings(&words, timings, track.len()))
+        .unwrap_or_else(|| estimate_word_spans(&words, bounds));
+    let learner_prominence = track.word_prominence(&spans, &words);
+
+    let reference_track = reference.map(ProsodyTrack::extract);
+    let alignment = reference_track.as_ref().and_then(|r| {
+        let ref_bounds = r.speech_bounds().filter(|_| r.median_hz > 0.0)?;
+        Some((r, ref_bounds, align(&track, bounds, r, ref_bounds)))
+    });
+
+    let (intonation_score, contour_similarity, expected_prominence, reference_pitch_contour, is_monotone) =
+        match &alignment {
+            Some((reference_track, ref_bounds, alignment)) => {
+                let similarity = contour_correlation(&track, reference_track, alignment);
+                let range_ratio = spread / reference_track.semitone_spread().max(0.1);
+                let intonation =
+                    100.0 * (0.7 * similarity.unwrap_or(0.0).max(0.0) + 0.3 * range_ratio.min(1.0));
+
+                // 將學習者的詞邊界映射到參考音頻上
+                let ref_spans: Vec<(usize, usize)> = spans
+                    .iter()
+                    .map(|&(s, e)| {
+                        let rs = alignment.map_frame(s);
+                        let re = alignment.map_frame(e.saturating_sub(1)).max(rs) + 1;
+                        (rs, re.min(ref_bounds.1))
+                    })
+                    .collect();
+                let expected = reference_track.word_prominence(&ref_spans, &words);
+
+                let aligned_contour = (0..track.len())
+                    .step_by(CONTOUR_STEP)
+                    .map(|f| {
+                        if f < bounds.0 || f >= bounds.1 {
+                            0.0
+                        } else {
+                            reference_track.pitch_hz[alignment.map_frame(f)]
+                        }
+                    })
+                    .collect();
+
+                let monotone = spread < MONOTONE_SEMITONES || range_ratio < 0.5;
+                (intonation, similarity, Some(expected), Some(aligned_contour), monotone)
+            }
+            None => {
+                let intonation =
+                    40.0 + 60.0 * ((spread - 1.0) / 2.0).clamp(0.0, 1.0);
+                (intonation, None, None, None, spread < MONOTONE_SEMITONES)
+            }
+        };
+
+    let word_stress = if words.len() >= 2 {
+        let learner_flags = stressed_flags(&learner_prominence);
+        let expected_flags = match &expected_prominence {
+            Some(p) => stressed_flags(p),
+            // 沒有參考音頻時，預期實詞重讀、功能詞弱讀
+            None => words.iter().map(|w| !crate::text::is_function_word(w)).collect(),
+        };
+        let frame_secs = HOP_SECS as f64;
+        words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| WordStress {
+                word: w.clone(),
+                index: i,
+                start_secs: spans[i].0 as f64 * frame_secs,
+                end_secs: spans[i].1 as f64 * frame_secs,
+                expected_stress: expected_flags[i],
+                learner_stress: learner_flags[i],
+                matched: expected_flags[i] == learner_flags[i],
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let stress_score = if word_stress.is_empty() {
+        None
+    } else {
+        let matched = word_stress.iter().filter(|w| w.matched).count();
+        Some(100.0 * matched as f64 / word_stress.len() as f64)
+    };
+
+    let prosody_score = match stress_score {
+        Some(stress) => 0.6 * intonation_score + 0.4 * stress,
+        None => intonation_score,
+    };
+
+    ProsodyAnalysis {
+        prosody_score: round1(prosody_score),
+        intonation_score: round1(intonation_score),
+        stress_score: stress_score.map(round1),
+        contour_similarity: contour_similarity.map(|c| (c * 1000.0).round() / 1000.0),
+        mean_pitch_hz: round1(track.median_hz as f64),
+        pitch_range_semitones: round1(spread),
+        is_monotone,
+        word_stress,
+        contour_step_ms,
+        pitch_contour,
+        reference_pitch_contour,
+    }
+}
+
+// 學習者幀到參考幀的映射（基於 DTW 路徑）
+struct Alignment {
+    learner_start: usize,
+    reference_start: usize,
+    step: usize,
+    path: Vec<(usize, usize)>,
+    learner_to_reference: Vec<usize>,
+}
+
+impl Alignment {
+    fn map_frame(&self, frame: usize) -> usize {
+        let i = frame.saturating_sub(self.learner_start) / self.step;
+        let j = self.learner_to_reference[i.min(self.learner_to_reference.len() - 1)];
+        self.reference_start + j * self.step
+    }
+}
+
+fn align(
+    learner: &ProsodyTrack,
+    learner_bounds: (usize, usize),
+    reference: &ProsodyTrack,
+    reference_bounds: (usize, usize),
+) -> Alignment {
+    let n = learner_bounds.1 - learner_bounds.0;
+    let m = reference_bounds.1 - reference_bounds.0;
+    let mut step = CONTOUR_STEP;
+    while (n / step + 1) * (m / step + 1) > MAX_DTW_CELLS {
+        step += 1;
+    }
+
+    let a = learner.features(learner_bounds, step);
+    let b = reference.features(reference_bounds, step);
+    let path = dtw_path(&a, &b, |x, y| (x[0] - y[0]).abs() + (x[1] - y[1]).abs());
+
+    // 路徑單調遞增，同一學習者幀對應多個參考幀時取第一個
+    let mut learner_to_reference = vec![usize::MAX; a.len()];
+    for &(i, j) in &path {
+        if learner_to_reference[i] == usize::MAX {
+            learner_to_reference[i] = j;
+        }
+    }
+
+    Alignment {
+        learner_start: learner_bounds.0,
+        reference_start: reference_bounds.0,
+        step,
+        path,
+        learner_to_reference,
+    }
+}
+
+// 帶 Sakoe-Chiba 帶寬限制的動態時間規整
+pub fn dtw_path<T>(a: &[T], b: &[T], frame_distance: impl Fn(&T, &T) -> f32) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    if n == 0 || m == 0 {
+        return Vec::new();
+    }
+    let band = (n.max(m) / 4).max(10) as isize;
+    let mut cost = vec![f32::INFINITY; n * m];
+    let distance = |i: usize, j: usize| frame_distance(&a[i], &b[j]);
+
+    for i in 0..n {
+        let diagonal = (i * m / n) as isize;
+        let j_start = (diagonal - band).max(0) as usize;
+        let j_end = ((diagonal + band) as usize).min(m - 1);
+        for j in j_start..=j_end {
+            let best_previous = if i == 0 && j == 0 {
+                0.0
+            } else {
+                let mut best = f32::INFINITY;
+                if i > 0 && j > 0 {
+                    best = best.min(cost[(i - 1) * m + j - 1]);
+                }
+                if i > 0 {
+                    best = best.min(cost[(i - 1) * m + j]);
+                }
+                if j > 0 {
+                    best = best.min(cost[i * m + j - 1]);
+                }
+                best
+            };
+            cost[i * m + j] = distance(i, j) + best_previous;
+        }
+    }
+
+    // 從終點回溯
+    let (mut i, mut j) = (n - 1, m - 1);
+    let mut path = vec![(i, j)];
+    while i > 0 || j > 0 {
+        let candidates = [
+            (i.checked_sub(1), j.checked_sub(1)),
+            (i.checked_sub(1), Some(j)),
+            (Some(i), j.checked_sub(1)),
+        ];
+        let (next_i, next_j) = candidates
+            .iter()
+            .filter_map(|&(ci, cj)| Some((ci?, cj?)))
+            .min_by(|x, y| cost[x.0 * m + x.1].total_cmp(&cost[y.0 * m + y.1]))
+            .unwrap_or((0, 0));
+        i = next_i;
+        j = next_j;
+        path.push((i, j));
+    }
+    path.reverse();
+    path
+}
+
+// 對齊路徑上雙方都有聲的幀之間的半音曲線相關係數
+fn contour_correlation(learner: &ProsodyTrack, reference: &ProsodyTrack, alignment: &Alignment) -> Option<f64> {
+    let mut pairs = Vec::new();
+    for &(i, j) in &alignment.path {
+        let lf = alignment.learner_start + i * alignment.step;
+        let rf = alignment.reference_start + j * alignment.step;
+        if let (Some(Some(l)), Some(Some(r))) = (learner.semitones.get(lf), reference.semitones.get(rf)) {
+            pairs.push((*l as f64, *r as f64));
+        }
+    }
+    if pairs.len() < 10 {
+        return None;
+    }
+
+    let n = pairs.len() as f64;
+    let mean_l = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_r = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut cov, mut var_l, mut var_r) = (0.0, 0.0, 0.0);
+    for &(l, r) in &pairs {
+        cov += (l - mean_l) * (r - mean_r);
+        var_l += (l - mean_l).powi(2);
+        var_r += (r - mean_r).powi(2);
+    }
+    if var_l <= f64::EPSILON || var_r <= f64::EPSILON {
+        return Some(0.0);
+    }
+    Some(cov / (var_l.sqrt() * var_r.sqrt()))
+}
+
+// 識別結果與參考文本逐詞一致時，直接使用 ASR 的詞邊界
+fn spans_from_timings(words: &[String], timings: &[TimedWord], frame_count: usize) -> Option<Vec<(usize, usize)>> {
+    let spoken: Vec<(String, &TimedWord)> = timings
+        .iter()
+        .filter_map(|t| {
+            let tokens = tokenize_words(&t.word);
+            match tokens.as_slice() {
+                [token] if !is_filled_pause(token) => Some((token.clone(), t)),
+                _ => None,
+            }
+        })
+        .collect();
+    if frame_count == 0 || spoken.len() != words.len() || spoken.iter().zip(words).any(|((s, _), w)| s != w) {
+        return None;
+    }
+
+    let to_frame = |secs: f64| ((secs / HOP_SECS as f64).round().max(0.0) as usize).min(frame_count - 1);
+    Some(
+        spoken
+            .iter()
+            .map(|(_, t)| {
+                let start = to_frame(t.start);
+                (start, to_frame(t.end).max(start + 1).min(frame_count))
+            })
+            .collect(),
+    )
+}
+
+// 沒有詞級時間戳時，按音節數在語音範圍內按比例分配詞邊界
+fn estimate_word_spans(words: &[String], bounds: (usize, usize)) -> Vec<(usize, usize)> {
+    let weights: Vec<usize> = words.iter().map(|w| syllable_count(w)).collect();
+    let total = weights.iter().sum::<usize>().max(1);
+    let length = bounds.1 - bounds.0;
+    let mut consumed = 0;
+    weights
+        .iter()
+        .map(|&w| {
+            let start = bounds.0 + length * consumed / total;
+            consumed += w;
+            let end = bounds.0 + length * consumed / total;
+            (start, end.max(start + 1))
+        })
+        .collect()
+}
+
+fn stressed_flags(prominence: &[f32]) -> Vec<bool> {
+    let average = mean(prominence).unwrap_or(0.0);
+    prominence.iter().map(|&p| p > average).collect()
+}
+
+// YIN 基頻估計，返回 None 表示無聲幀
+fn yin_pitch(frame: &[f32], sample_rate: f32) -> Option<f32> {
+    let tau_min = (sample_rate / MAX_F0_HZ).floor() as usize;
+    let tau_max = ((sample_rate / MIN_F0_HZ).ceil() as usize).min(frame.len() / 2);
+    if tau_min < 2 || tau_max <= tau_min + 1 {
+        return None;
+    }
+
+    let window = frame.len() - tau_max;
+    let mut cmnd = vec![1.0f32; tau_max + 1];
+    let mut running = 0.0f32;
+    for tau in 1..=tau_max {
+        let mut sum = 0.0f32;
+        for j in 0..window {
+            let d = frame[j] - frame[j + tau];
+            sum += d * d;
+        }
+        running += sum;
+        cmnd[tau] = if running > 0.0 { sum * tau as f32 / running } else { 1.0 };
+    }
+
+    let mut tau = tau_min;
+    while tau < tau_max {
+        if cmnd[tau] < YIN_THRESHOLD {
+            while tau + 1 < tau_max && cmnd[tau + 1] < cmnd[tau] {
+                tau += 1;
+            }
+            // 拋物線插值求更精確的週期
+            let (s0, s1, s2) = (cmnd[tau - 1], cmnd[tau], cmnd[tau + 1]);
+            let denominator = s0 - 2.0 * s1 + s2;
+            let refined = if denominator.abs() > f32::EPSILON {
+                tau as f32 + (s0 - s2) / (2.0 * denominator)
+            } else {
+                tau as f32
+            };
+            return Some(sample_rate / refined);
+        }
+        tau += 1;
+    }
+    None
+}
+
+// 對有聲幀做中值濾波，並去掉過短的孤立有聲段
+fn median_smooth(pitch: &[f32]) -> Vec<f32> {
+    let mut smoothed: Vec<f32> = (0..pitch.len())
+        .map(|i| {
+            if pitch[i] <= 0.0 {
+                return 0.0;
+            }
+            let start = i.saturating_sub(2);
+            let end = (i + 3).min(pitch.len());
+            let neighbours: Vec<f32> = pitch[start..end].iter().cloned().filter(|&p| p > 0.0).collect();
+            median(&neighbours).unwrap_or(0.0)
+        })
+        .collect();
+
+    let mut i = 0;
+    while i < smoothed.len() {
+        if smoothed[i] > 0.0 {
+            let run_start = i;
+            while i < smoothed.len() && smoothed[i] > 0.0 {
+                i += 1;
+            }
+            if i - run_start < 3 {
+                smoothed[run_start..i].iter_mut().for_each(|p| *p = 0.0);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    smoothed
+}
+
+// 無聲幀的半音值用前後有聲幀線性插值
+fn interpolate_gaps(values: &[Option<f32>]) -> Vec<f32> {
+    let known: Vec<(usize, f32)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.map(|v| (i, v)))
+        .collect();
+    if known.is_empty() {
+        return vec![0.0; values.len()];
+    }
+
+    let mut result = Vec::with_capacity(values.len());
+    let mut next = 0;
+    for i in 0..values.len() {
+        while next < known.len() && known[next].0 < i {
+            next += 1;
+        }
+        let value = match (next.checked_sub(1).map(|p| known[p]), known.get(next)) {
+            (_, Some(&(k, v))) if k == i => v,
+            (Some((pi, pv)), Some(&(ni, nv))) => pv + (nv - pv) * (i - pi) as f32 / (ni - pi) as f32,
+            (Some((_, pv)), None) => pv,
+            (None, Some(&(_, nv))) => nv,
+            (None, None) => 0.0,
+        };
+        result.push(value);
+    }
+    result
+}
+
+fn median(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Some(sorted[sorted.len() / 2])
+}
+
+fn mean(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f32>() / values.len() as f32)
+    }
+}
+
+fn std_dev(values: &[f32]) -> f32 {
+    let Some(average) = mean(values) else {
+        return 0.0;
+    };
+    (values.iter().map(|v| (v - average).powi(2)).sum::<f32>() / values.len() as f32).sqrt()
+}
+
+fn z_scores(values: &[f32]) -> Vec<f32> {
+    let average = mean(values).unwrap_or(0.0);
+    let spread = std_dev(values).max(1e-3);
+    values.iter().map(|v| (v - average) / spread).collect()
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按給定的頻率曲線合成正弦波，頻率隨時間線性變化
+    fn glide(start_hz: f32, end_hz: f32, secs: f32) -> AudioClip {
+        let rate = ANALYSIS_SAMPLE_RATE as f32;
+        let count = (secs * rate) as usize;
+        let mut phase = 0.0f32;
+        let samples = (0..count)
+            .map(|i| {
+                let hz = start_hz + (end_hz - start_hz) * i as f32 / count as f32;
+                phase += 2.0 * std::f32::consts::PI * hz / rate;
+                0.5 * phase.sin()
+            })
+            .collect();
+        AudioClip::new(samples, ANALYSIS_SAMPLE_RATE)
+    }
+
+    #[test]
+    fn yin_finds_sine_frequency() {
+        let clip = glide(220.0, 220.0, 0.1);
+        let frame_len = (FRAME_SECS * ANALYSIS_SAMPLE_RATE as f32) as usize;
+        let pitch = yin_pitch(&clip.samples[..frame_len], ANALYSIS_SAMPLE_RATE as f32).unwrap();
+        assert!((pitch - 220.0).abs() < 2.0, "{}", pitch);
+        assert_eq!(yin_pitch(&vec![0.0; frame_len], ANALYSIS_SAMPLE_RATE as f32), None);
+    }
+
+    #[test]
+    fn dtw_follows_time_stretch() {
+        let a: Vec<f32> = (0..20).map(|i| i as f32).collect();
+        assert_eq!(dtw_path(&a, &a, |x, y| (x - y).abs()), (0..20).map(|i| (i, i)).collect::<Vec<_>>());
+
+        // b 的每個值重複兩次，a 的每一幀應對應到 b 中相同的值
+        let b: Vec<f32> = a.iter().flat_map(|&v| [v, v]).collect();
+        let path = dtw_path(&a, &b, |x, y| (x - y).abs());
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(19, 39)));
+        assert!(path.iter().all(|&(i, j)| a[i] == b[j]));
+        assert!(path.windows(2).all(|w| w[1].0 >= w[0].0 && w[1].1 >= w[0].1));
+        assert!(dtw_path::<f32>(&[], &b, |x, y| (x - y).abs()).is_empty());
+    }
+
+    #[test]
+    fn flat_pitch_is_monotone() {
+        let analysis = analyze_prosody(&glide(180.0, 180.0, 1.5), None, "hello there friend", None);
+        assert!(analysis.is_monotone);
+        assert!((analysis.mean_pitch_hz - 180.0).abs() < 3.0);
+        assert!(analysis.pitch_range_semitones < 0.5);
+        assert_eq!(analysis.word_stress.len(), 3);
+    }
+
+    #[test]
+    fn matching_reference_contour_scores_high() {
+        let learner = glide(140.0, 260.0, 1.5);
+        let reference = glide(150.0, 280.0, 1.2);
+        let analysis = analyze_prosody(&learner, Some(&reference), "rising question", None);
+        assert!(!analysis.is_monotone);
+        assert!(analysis.contour_similarity.unwrap() > 0.9, "{:?}", analysis.contour_similarity);
+
+        // 和反方向的音調比較，相關係數為負
+        let falling = glide(260.0, 140.0, 1.5);
+        let analysis = analyze_prosody(&falling, Some(&reference), "rising question", None);
+        assert!(analysis.contour_similarity.unwrap() < 0.0, "{:?}", analysis.contour_similarity);
+    }
+
+    #[test]
+    fn silence_scores_zero() {
+        let analysis = analyze_prosody(&AudioClip::new(vec![0.0; 16_000], ANALYSIS_SAMPLE_RATE), None, "hello", None);
+        assert_eq!(analysis.prosody_score, 0.0);
+        assert!(analysis.word_stress.is_empty());
+    }
+}
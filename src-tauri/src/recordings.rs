@@ -0,0 +1,340 @@
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::audio::{self, AudioClip, AudioFormat};
+use crate::database::Database;
+use crate::text::tokenize_words;
+
+const RETENTION_SETTING_KEY: &str = "recording_retention";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    pub id: i64,
+    pub reference_text: String,
+    pub duration_secs: f64,
+    pub overall_score: Option<f64>,
+    pub created_at: String,
+}
+
+// 帶音頻數據的錄音，供前端直接播放
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingAudio {
+    #[serde(flatten)]
+    pub info: RecordingInfo,
+    pub mime_type: String,
+    pub audio_data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingComparison {
+    pub earlier: RecordingAudio,
+    pub later: RecordingAudio,
+    pub score_change: Option<f64>,
+    pub days_apart: f64,
+}
+
+// 錄音保留策略：兩項條件都可以不設置，設置後每次保存錄音時自動執行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub keep_last_per_text: Option<u32>,
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self { keep_last_per_text: Some(20), max_age_days: None }
+    }
+}
+
+// 同一參考文本的不同寫法（大小寫、標點）視為同一練習內容
+fn text_key(reference_text: &str) -> String {
+    tokenize_words(reference_text).join(" ")
+}
+
+fn recordings_dir(db: &Database) -> PathBuf {
+    db.data_dir().join("recordings")
+}
+
+fn row_to_info(row: &Row) -> rusqlite::Result<RecordingInfo> {
+    Ok(RecordingInfo {
+        id: row.get("id")?,
+        reference_text: row.get("reference_text")?,
+        duration_secs: row.get("duration_secs")?,
+        overall_score: row.get("overall_score")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+// 錄音統一保存為 WAV，保證各平台的 webview 都能播放
+pub fn save_recording(
+    db: &Database,
+    reference_text: &str,
+    clip: &AudioClip,
+    overall_score: Option<f64>,
+) -> Result<i64, String> {
+    let dir = recordings_dir(db);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("無法創建錄音目錄：{}", e))?;
+
+    let file_name = format!(
+        "{}-{:08x}.wav",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default(),
+        rand::random::<u32>()
+    );
+    std::fs::write(dir.join(&file_name), clip.to_wav_bytes()?)
+        .map_err(|e| format!("保存錄音文件失敗：{}", e))?;
+
+    let duration_secs = clip.samples.len() as f64 / clip.sample_rate.max(1) as f64;
+    let inserted = db.conn().execute(
+        "INSERT INTO recordings (reference_text, text_key, file_name, duration_secs, overall_score)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![reference_text, text_key(reference_text), file_name, duration_secs, overall_score],
+    );
+    if let Err(e) = inserted {
+        let _ = std::fs::remove_file(dir.join(&file_name));
+        return Err(format!("保存錄音記錄失敗：{}", e));
+    }
+    let id = db.conn().last_insert_rowid();
+
+    if let Err(e) = apply_retention(db, &retention_policy(db)?) {
+        tracing::error!(error = %e, "Recording retention failed");
+    }
+    Ok(id)
+}
+
+// 同一參考文本的所有錄音，按時間從新到舊排列
+pub fn list_attempts(db: &Database, reference_text: &str) -> Result<Vec<RecordingInfo>, String> {
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT id, reference_text, duration_secs, overall_score, created_at FROM recordings
+             WHERE text_key = ?1 ORDER BY created_at DESC, id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([text_key(reference_text)], row_to_info)
+        .map_err(|e| format!("查詢錄音失敗：{}", e))?;
+    rows.collect::<Result<_, _>>().map_err(|e| format!("查詢錄音失敗：{}", e))
+}
+
+fn find_recording(db: &Database, id: i64) -> Result<(RecordingInfo, PathBuf), String> {
+    let (info, file_name) = db
+        .conn()
+        .query_row(
+            "SELECT id, reference_text, duration_secs, overall_score, created_at, file_name
+             FROM recordings WHERE id = ?1",
+            [id],
+            |row| Ok((row_to_info(row)?, row.get::<_, String>("file_name")?)),
+        )
+        .optional()
+        .map_err(|e| format!("查詢錄音失敗：{}", e))?
+        .ok_or_else(|| format!("錄音 {} 不存在", id))?;
+    Ok((info, recordings_dir(db).join(file_name)))
+}
+
+// 錄音的 WAV 文件內容
+pub fn recording_bytes(db: &Database, id: i64) -> Result<Vec<u8>, String> {
+    let (_, path) = find_recording(db, id)?;
+    std::fs::read(path).map_err(|e| format!("讀取錄音文件失敗：{}", e))
+}
+
+pub fn load_recording(db: &Database, id: i64) -> Result<RecordingAudio, String> {
+    let (info, path) = find_recording(db, id)?;
+    let bytes = std::fs::read(path).map_err(|e| format!("讀取錄音文件失敗：{}", e))?;
+    Ok(RecordingAudio {
+        info,
+        mime_type: AudioFormat::Wav.mime_type().to_string(),
+        audio_data: audio::encode_base64(&bytes),
+    })
+}
+
+// 取出兩次錄音用於並排回放，按錄製時間排序
+pub fn compare_recordings(db: &Database, first_id: i64, second_id: i64) -> Result<RecordingComparison, String> {
+    let first = load_recording(db, first_id)?;
+    let second = load_recording(db, second_id)?;
+    let (earlier, later) = if (&first.info.created_at, first.info.id) <= (&second.info.created_at, second.info.id) {
+        (first, second)
+    } else {
+        (second, first)
+    };
+
+    let score_change = match (earlier.info.overall_score, later.info.overall_score) {
+        (Some(a), Some(b)) => Some(((b - a) * 10.0).round() / 10.0),
+        _ => None,
+    };
+    let days_apart: f64 = db
+        .conn()
+        .query_row(
+            "SELECT julianday(?2) - julianday(?1)",
+            params![earlier.info.created_at, later.info.created_at],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(RecordingComparison {
+        earlier,
+        later,
+        score_change,
+        days_apart: (days_apart * 10.0).round() / 10.0,
+    })
+}
+
+pub fn delete_recording(db: &Database, id: i64) -> Result<(), String> {
+    let file_name: Option<String> = db
+        .conn()
+        .query_row("SELECT file_name FROM recordings WHERE id = ?1", [id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("查詢錄音失敗：{}", e))?;
+    let file_name = file_name.ok_or_else(|| format!("錄音 {} 不存在", id))?;
+
+    db.conn()
+        .execute("DELETE FROM recordings WHERE id = ?1", [id])
+        .map_err(|e| format!("刪除錄音失敗：{}", e))?;
+    remove_file(db, &file_name);
+    Ok(())
+}
+
+pub fn retention_policy(db: &Database) -> Result<RetentionPolicy, String> {
+    Ok(db.get_setting(RETENTION_SETTING_KEY)?.unwrap_or_default())
+}
+
+pub fn set_retention_policy(db: &Database, policy: &RetentionPolicy) -> Result<usize, String> {
+    db.set_setting(RETENTION_SETTING_KEY, policy)?;
+    apply_retention(db, policy)
+}
+
+// 按保留策略刪除過期錄音，返回刪除的數量。作業練習引用的錄音要隨結果包提交，不自動刪除
+pub fn apply_retention(db: &Database, policy: &RetentionPolicy) -> Result<usize, String> {
+    let mut expired: Vec<(i64, String)> = Vec::new();
+    let mut collect = |sql: &str, param: i64| -> Result<(), String> {
+        let mut stmt = db.conn().prepare(sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([param], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("查詢過期錄音失敗：{}", e))?;
+        for row in rows {
+            let row = row.map_err(|e| e.to_string())?;
+            if !expired.iter().any(|(id, _)| *id == row.0) {
+                expired.push(row);
+            }
+        }
+        Ok(())
+    };
+
+    if let Some(days) = policy.max_age_days {
+        collect(
+            "SELECT id, file_name FROM recordings WHERE created_at < datetime('now', '-' || ?1 || ' days')
+             AND id NOT IN (SELECT recording_id FROM assignment_attempts WHERE recording_id IS NOT NULL)",
+            days as i64,
+        )?;
+    }
+    if let Some(keep) = policy.keep_last_per_text.filter(|&keep| keep > 0) {
+        collect(
+            "SELECT id, file_name FROM (
+                 SELECT id, file_name, ROW_NUMBER() OVER (
+                     PARTITION BY text_key ORDER BY created_at DESC, id DESC
+                 ) AS position FROM recordings
+             ) WHERE position > ?1
+             AND id NOT IN (SELECT recording_id FROM assignment_attempts WHERE recording_id IS NOT NULL)",
+            keep as i64,
+        )?;
+    }
+
+    for (id, file_name) in &expired {
+        db.conn()
+            .execute("DELETE FROM recordings WHERE id = ?1", [id])
+            .map_err(|e| format!("刪除過期錄音失敗：{}", e))?;
+        remove_file(db, file_name);
+    }
+    Ok(expired.len())
+}
+
+// 文件已不存在時忽略錯誤，數據庫記錄已經刪除
+fn remove_file(db: &Database, file_name: &str) {
+    if let Err(e) = std::fs::remove_file(recordings_dir(db).join(file_name)) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!(error = %e, file = file_name, "Failed to remove recording file");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_db() -> Database {
+        Database::open(&std::env::temp_dir().join(format!("web-chat-recordings-{:016x}", rand::random::<u64>()))).unwrap()
+    }
+
+    fn save(db: &Database, text: &str, score: f64) -> i64 {
+        save_recording(db, text, &AudioClip::new(vec![0.1; 1600], 16_000), Some(score)).unwrap()
+    }
+
+    // 把錄音時間往前挪，模擬較早的練習
+    fn age(db: &Database, id: i64, days: u32) {
+        db.conn()
+            .execute(
+                "UPDATE recordings SET created_at = datetime('now', '-' || ?2 || ' days') WHERE id = ?1",
+                params![id, days],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn groups_attempts_by_normalized_text() {
+        let db = open_db();
+        let first = save(&db, "Hello, world!", 60.0);
+        let second = save(&db, "hello world", 70.0);
+        save(&db, "Something else", 80.0);
+
+        let attempts = list_attempts(&db, "HELLO WORLD.").unwrap();
+        assert_eq!(attempts.iter().map(|a| a.id).collect::<Vec<_>>(), vec![second, first]);
+        assert!((attempts[0].duration_secs - 0.1).abs() < 1e-9);
+        assert!(!load_recording(&db, first).unwrap().audio_data.is_empty());
+    }
+
+    #[test]
+    fn compares_in_chronological_order() {
+        let db = open_db();
+        let first = save(&db, "hello", 60.0);
+        let second = save(&db, "hello", 75.5);
+        age(&db, first, 3);
+
+        let comparison = compare_recordings(&db, second, first).unwrap();
+        assert_eq!(comparison.earlier.info.id, first);
+        assert_eq!(comparison.score_change, Some(15.5));
+        assert!((comparison.days_apart - 3.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn retention_keeps_latest_per_text_and_drops_old() {
+        let db = open_db();
+        set_retention_policy(&db, &RetentionPolicy { keep_last_per_text: None, max_age_days: None }).unwrap();
+        let ids: Vec<i64> = (0..4).map(|i| save(&db, "hello", i as f64)).collect();
+        let other = save(&db, "goodbye", 50.0);
+        age(&db, other, 40);
+
+        let policy = RetentionPolicy { keep_last_per_text: Some(2), max_age_days: Some(30) };
+        assert_eq!(set_retention_policy(&db, &policy).unwrap(), 3);
+        assert_eq!(list_attempts(&db, "hello").unwrap().iter().map(|a| a.id).collect::<Vec<_>>(), vec![ids[3], ids[2]]);
+        assert!(list_attempts(&db, "goodbye").unwrap().is_empty());
+        assert!(recording_bytes(&db, ids[0]).is_err());
+
+        // 新保存的錄音會自動套用策略
+        save(&db, "hello", 90.0);
+        assert_eq!(list_attempts(&db, "hello").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn delete_removes_row_and_file() {
+        let db = open_db();
+        let id = save(&db, "hello", 60.0);
+        assert!(recording_bytes(&db, id).unwrap().starts_with(b"RIFF"));
+        delete_recording(&db, id).unwrap();
+        assert!(recording_bytes(&db, id).is_err());
+        assert!(delete_recording(&db, id).is_err());
+    }
+}
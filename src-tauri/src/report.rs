@@ -0,0 +1,1404 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::achievements::{self, Achievement};
+use crate::database::Database;
+use crate::gemini_service::TutorFeedback;
+use crate::goals;
+use crate::minimal_pairs;
+use crate::pdf::{self, CjkFont, Color, PdfWriter};
+use crate::pronunciation;
+
+// 對比練習兩期都至少練了這麼多次才比較正確率，次數太少時波動沒有意義
+const MIN_CONTRAST_ATTEMPTS: u32 = 5;
+const MAX_IMPROVED: usize = 3;
+const MAX_WEAK_PHONEMES: usize = 5;
+const MAX_NEW_WORDS: usize = 30;
+const MAX_HIGHLIGHTS: usize = 3;
+
+// 各項評分的顯示順序，其餘按名稱排在後面
+const METRIC_ORDER: [&str; 6] = ["overall", "pronunciation", "accuracy", "fluency", "completeness", "prosody"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Week,
+    Month,
+}
+
+impl ReportPeriod {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "week" | "weekly" => Ok(ReportPeriod::Week),
+            "month" | "monthly" => Ok(ReportPeriod::Month),
+            other => Err(format!("不支持的報告週期：{}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportLocale {
+    ZhHant,
+    ZhHans,
+    En,
+}
+
+impl ReportLocale {
+    // 接受 zh-TW、zh-Hans-CN、en-US 這類語言標記，無法識別時用繁體中文
+    pub fn parse(tag: Option<&str>) -> Self {
+        let tag = tag.unwrap_or_default().to_ascii_lowercase().replace('_', "-");
+        if tag.starts_with("en") {
+            ReportLocale::En
+        } else if tag.starts_with("zh") && ["hans", "cn", "sg", "my"].iter().any(|part| tag.contains(part)) {
+            ReportLocale::ZhHans
+        } else {
+            ReportLocale::ZhHant
+        }
+    }
+
+    fn html_lang(self) -> &'static str {
+        match self {
+            ReportLocale::ZhHant => "zh-Hant",
+            ReportLocale::ZhHans => "zh-Hans",
+            ReportLocale::En => "en",
+        }
+    }
+
+    fn strings(self) -> &'static Strings {
+        match self {
+            ReportLocale::ZhHant => &ZH_HANT,
+            ReportLocale::ZhHans => &ZH_HANS,
+            ReportLocale::En => &EN,
+        }
+    }
+
+    fn cjk_font(self) -> CjkFont {
+        match self {
+            ReportLocale::ZhHans => CjkFont::Simplified,
+            _ => CjkFont::Traditional,
+        }
+    }
+
+    fn date(self, date: &str) -> String {
+        let mut parts = date.splitn(3, '-').map(|p| p.parse::<u32>().unwrap_or_default());
+        let (y, m, d) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+        match self {
+            ReportLocale::En => {
+                const MONTHS: [&str; 12] =
+                    ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+                let month = MONTHS.get(m.saturating_sub(1) as usize).copied().unwrap_or_default();
+                format!("{} {}, {}", month, d, y)
+            }
+            _ => format!("{}年{}月{}日", y, m, d),
+        }
+    }
+
+    fn colon(self) -> &'static str {
+        match self {
+            ReportLocale::En => ": ",
+            _ => "：",
+        }
+    }
+
+    fn duration(self, minutes: f64) -> String {
+        let total = minutes.round() as u64;
+        let (hours, minutes) = (total / 60, total % 60);
+        let s = self.strings();
+        if hours == 0 {
+            format!("{} {}", minutes, s.minutes_unit)
+        } else {
+            format!("{} {} {} {}", hours, s.hours_unit, minutes, s.minutes_unit)
+        }
+    }
+}
+
+// 報告中的固定文字
+struct Strings {
+    title_week: &'static str,
+    title_month: &'static str,
+    learner: &'static str,
+    generated_at: &'static str,
+    summary: &'static str,
+    sessions: &'static str,
+    practice_time: &'static str,
+    active_days: &'static str,
+    average_score: &'static str,
+    words_learned: &'static str,
+    vs_previous: &'static str,
+    score_trend: &'static str,
+    daily_minutes: &'static str,
+    metric_breakdown: &'static str,
+    metric: &'static str,
+    this_period: &'static str,
+    previous_period: &'static str,
+    change: &'static str,
+    improved: &'static str,
+    weakest: &'static str,
+    accuracy: &'static str,
+    errors: &'static str,
+    heard_as: &'static str,
+    examples: &'static str,
+    new_words: &'static str,
+    more_words: &'static str, // {} 為剩餘詞數
+    highlights: &'static str,
+    achievements: &'static str,
+    no_data: &'static str,
+    minutes_unit: &'static str,
+    hours_unit: &'static str,
+    metrics: [(&'static str, &'static str); 6],
+}
+
+const ZH_HANT: Strings = Strings {
+    title_week: "每週學習報告",
+    title_month: "每月學習報告",
+    learner: "學習者",
+    generated_at: "生成於",
+    summary: "概覽",
+    sessions: "練習次數",
+    practice_time: "練習時間",
+    active_days: "練習天數",
+    average_score: "平均分",
+    words_learned: "新學詞彙",
+    vs_previous: "較上期",
+    score_trend: "分數趨勢",
+    daily_minutes: "每日練習時間",
+    metric_breakdown: "各項評分",
+    metric: "項目",
+    this_period: "本期",
+    previous_period: "上期",
+    change: "變化",
+    improved: "進步最多的發音",
+    weakest: "最需加強的發音",
+    accuracy: "正確率",
+    errors: "錯誤次數",
+    heard_as: "常讀成",
+    examples: "例詞",
+    new_words: "本期新學詞彙",
+    more_words: "另有 {} 個詞",
+    highlights: "導師點評摘錄",
+    achievements: "本期獲得的成就",
+    no_data: "本期沒有相關記錄",
+    minutes_unit: "分鐘",
+    hours_unit: "小時",
+    metrics: [
+        ("overall", "綜合"),
+        ("pronunciation", "發音"),
+        ("accuracy", "準確度"),
+        ("fluency", "流利度"),
+        ("completeness", "完整度"),
+        ("prosody", "韻律"),
+    ],
+};
+
+const ZH_HANS: Strings = Strings {
+    title_week: "每周学习报告",
+    title_month: "每月学习报告",
+    learner: "学习者",
+    generated_at: "生成于",
+    summary: "概览",
+    sessions: "练习次数",
+    practice_time: "练习时间",
+    active_days: "练习天数",
+    average_score: "平均分",
+    words_learned: "新学词汇",
+    vs_previous: "较上期",
+    score_trend: "分数趋势",
+    daily_minutes: "每日练习时间",
+    metric_breakdown: "各项评分",
+    metric: "项目",
+    this_period: "本期",
+    previous_period: "上期",
+    change: "变化",
+    improved: "进步最多的发音",
+    weakest: "最需加强的发音",
+    accuracy: "正确率",
+    errors: "错误次数",
+    heard_as: "常读成",
+    examples: "例词",
+    new_words: "本期新学词汇",
+    more_words: "另有 {} 个词",
+    highlights: "导师点评摘录",
+    achievements: "本期获得的成就",
+    no_data: "本期没有相关记录",
+    minutes_unit: "分钟",
+    hours_unit: "小时",
+    metrics: [
+        ("overall", "综合"),
+        ("pronunciation", "发音"),
+        ("accuracy", "准确度"),
+        ("fluency", "流利度"),
+        ("completeness", "完整度"),
+        ("prosody", "韵律"),
+    ],
+};
+
+const EN: Strings = Strings {
+    title_week: "Weekly Progress Report",
+    title_month: "Monthly Progress Report",
+    learner: "Learner",
+    generated_at: "Generated",
+    summary: "Summary",
+    sessions: "Sessions",
+    practice_time: "Time practised",
+    active_days: "Active days",
+    average_score: "Average score",
+    words_learned: "Words learned",
+    vs_previous: "vs. previous",
+    score_trend: "Score trend",
+    daily_minutes: "Daily practice time",
+    metric_breakdown: "Score breakdown",
+    metric: "Metric",
+    this_period: "This period",
+    previous_period: "Previous",
+    change: "Change",
+    improved: "Most improved sounds",
+    weakest: "Sounds to work on",
+    accuracy: "Accuracy",
+    errors: "Errors",
+    heard_as: "Often said as",
+    examples: "Examples",
+    new_words: "New vocabulary",
+    more_words: "and {} more",
+    highlights: "Tutor highlights",
+    achievements: "Achievements unlocked",
+    no_data: "No records in this period",
+    minutes_unit: "min",
+    hours_unit: "h",
+    metrics: [
+        ("overall", "Overall"),
+        ("pronunciation", "Pronunciation"),
+        ("accuracy", "Accuracy"),
+        ("fluency", "Fluency"),
+        ("completeness", "Completeness"),
+        ("prosody", "Prosody"),
+    ],
+};
+
+impl Strings {
+    fn metric_name<'a>(&self, key: &'a str) -> &'a str {
+        self.metrics.iter().find(|(k, _)| *k == key).map(|(_, name)| *name).unwrap_or(key)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySummary {
+    pub date: String,
+    pub sessions: u32,
+    pub average_score: Option<f64>,
+    pub minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSummary {
+    pub metric: String,
+    pub average: f64,
+    pub previous: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContrastChange {
+    pub contrast: String,
+    pub label: String,
+    pub attempts: u32,
+    pub accuracy: f64, // 百分比
+    pub previous_accuracy: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeakPhoneme {
+    pub phoneme: String, // ARPAbet，不含重音標記
+    pub ipa: String,
+    pub errors: u32,
+    pub previous_errors: u32,
+    pub heard_as: Option<String>, // 最常被替換成的音（IPA）
+    pub examples: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedWord {
+    pub word: String,
+    pub ipa: String,
+    pub translation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TutorHighlight {
+    pub date: String,
+    pub topic: String,
+    pub score: Option<f64>,
+    pub feedback: String,
+    pub tip: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressReport {
+    pub period: ReportPeriod,
+    pub start_date: String,
+    pub end_date: String,
+    pub learner: Option<String>,
+    pub generated_at: String,
+    pub sessions: u32,
+    pub previous_sessions: u32,
+    pub practice_minutes: f64,
+    pub previous_practice_minutes: f64,
+    pub active_days: u32,
+    pub average_score: Option<f64>,
+    pub previous_average_score: Option<f64>,
+    pub metrics: Vec<MetricSummary>,
+    pub daily: Vec<DailySummary>,
+    pub improved_contrasts: Vec<ContrastChange>,
+    pub weak_phonemes: Vec<WeakPhoneme>,
+    pub words_learned: u32,
+    pub new_words: Vec<LearnedWord>,
+    pub highlights: Vec<TutorHighlight>,
+    pub achievements: Vec<Achievement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedReport {
+    pub path: String,
+    pub report: ProgressReport,
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+// 報告覆蓋的日期範圍（本地日期，含首尾），以天數表示
+#[derive(Debug, Clone, Copy)]
+struct DayRange {
+    start: i64,
+    end: i64,
+}
+
+impl DayRange {
+    fn bounds(self) -> (String, String) {
+        (goals::date_string(self.start), goals::date_string(self.end))
+    }
+}
+
+fn month_start(year: i64, month: i64) -> Result<i64, String> {
+    let (year, month) = if month < 1 { (year - 1, month + 12) } else if month > 12 { (year + 1, month - 12) } else { (year, month) };
+    goals::day_number(&format!("{:04}-{:02}-01", year, month)).ok_or_else(|| "日期格式錯誤".to_string())
+}
+
+// 返回包含該日期的自然週（週一開始）或自然月，以及上一期
+fn period_ranges(period: ReportPeriod, date: &str) -> Result<(DayRange, DayRange), String> {
+    let day = goals::day_number(date).ok_or("日期格式錯誤，應為 YYYY-MM-DD")?;
+    Ok(match period {
+        ReportPeriod::Week => {
+            // 1970-01-01 是週四
+            let start = day - (day + 3).rem_euclid(7);
+            (DayRange { start, end: start + 6 }, DayRange { start: start - 7, end: start - 1 })
+        }
+        ReportPeriod::Month => {
+            let mut parts = date.splitn(3, '-').map(|p| p.parse::<i64>().unwrap_or_default());
+            let (year, month) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+            let start = month_start(year, month)?;
+            let current = DayRange { start, end: month_start(year, month + 1)? - 1 };
+            (current, DayRange { start: month_start(year, month - 1)?, end: start - 1 })
+        }
+    })
+}
+
+fn session_stats(db: &Database, range: DayRange) -> Result<(u32, Option<f64>), String> {
+    let (start, end) = range.bounds();
+    db.conn()
+        .query_row(
+            "SELECT COUNT(*), AVG(json_extract(scores, '$.overall')) FROM practice_records
+             WHERE date(created_at, 'localtime') BETWEEN ?1 AND ?2",
+            params![start, end],
+            |row| Ok((row.get(0)?, row.get::<_, Option<f64>>(1)?.map(round1))),
+        )
+        .map_err(|e| format!("查詢練習記錄失敗：{}", e))
+}
+
+fn practice_minutes(db: &Database, range: DayRange) -> Result<f64, String> {
+    let (start, end) = range.bounds();
+    db.conn()
+        .query_row(
+            "SELECT COALESCE(SUM(seconds), 0) / 60.0 FROM practice_time WHERE date(created_at, 'localtime') BETWEEN ?1 AND ?2",
+            params![start, end],
+            |row| row.get(0),
+        )
+        .map(round1)
+        .map_err(|e| format!("查詢練習時間失敗：{}", e))
+}
+
+fn daily_summaries(db: &Database, range: DayRange) -> Result<Vec<DailySummary>, String> {
+    let (start, end) = range.bounds();
+    let mut scores: HashMap<String, (u32, Option<f64>)> = HashMap::new();
+    {
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT date(created_at, 'localtime') AS day, COUNT(*), AVG(json_extract(scores, '$.overall'))
+                 FROM practice_records WHERE day BETWEEN ?1 AND ?2 GROUP BY day",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![start, end], |row| Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| format!("查詢練習記錄失敗：{}", e))?;
+        for row in rows {
+            let (day, stats) = row.map_err(|e| e.to_string())?;
+            scores.insert(day, stats);
+        }
+    }
+    let mut minutes: HashMap<String, f64> = HashMap::new();
+    {
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT date(created_at, 'localtime') AS day, SUM(seconds) / 60.0 FROM practice_time
+                 WHERE day BETWEEN ?1 AND ?2 GROUP BY day",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![start, end], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+            .map_err(|e| format!("查詢練習時間失敗：{}", e))?;
+        for row in rows {
+            let (day, value) = row.map_err(|e| e.to_string())?;
+            minutes.insert(day, value);
+        }
+    }
+    Ok((range.start..=range.end)
+        .map(|day| {
+            let date = goals::date_string(day);
+            let (sessions, average) = scores.get(&date).copied().unwrap_or((0, None));
+            DailySummary {
+                sessions,
+                average_score: average.map(round1),
+                minutes: round1(minutes.get(&date).copied().unwrap_or_default()),
+                date,
+            }
+        })
+        .collect())
+}
+
+fn metric_averages(db: &Database, range: DayRange) -> Result<HashMap<String, f64>, String> {
+    let (start, end) = range.bounds();
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT each.key, AVG(each.value) FROM practice_records, json_each(practice_records.scores) AS each
+             WHERE date(practice_records.created_at, 'localtime') BETWEEN ?1 AND ?2
+               AND each.type IN ('integer', 'real')
+             GROUP BY each.key",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("查詢評分失敗：{}", e))?;
+    rows.collect::<Result<_, _>>().map_err(|e| format!("查詢評分失敗：{}", e))
+}
+
+fn metric_summaries(db: &Database, current: DayRange, previous: DayRange) -> Result<Vec<MetricSummary>, String> {
+    let averages = metric_averages(db, current)?;
+    let previous = metric_averages(db, previous)?;
+    let mut metrics: Vec<MetricSummary> = averages
+        .into_iter()
+        .map(|(metric, average)| MetricSummary {
+            average: round1(average),
+            previous: previous.get(&metric).copied().map(round1),
+            metric,
+        })
+        .collect();
+    metrics.sort_by_cached_key(|m| {
+        (METRIC_ORDER.iter().position(|key| *key == m.metric).unwrap_or(METRIC_ORDER.len()), m.metric.clone())
+    });
+    Ok(metrics)
+}
+
+fn contrast_accuracy(db: &Database, range: DayRange) -> Result<HashMap<String, (u32, f64)>, String> {
+    let (start, end) = range.bounds();
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT contrast, COUNT(*), AVG(correct) FROM contrast_attempts
+             WHERE date(created_at, 'localtime') BETWEEN ?1 AND ?2 GROUP BY contrast",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start, end], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+        .map_err(|e| format!("查詢對比練習失敗：{}", e))?;
+    rows.collect::<Result<_, _>>().map_err(|e| format!("查詢對比練習失敗：{}", e))
+}
+
+// 正確率比上期提高最多的對立音
+fn improved_contrasts(db: &Database, current: DayRange, previous: DayRange) -> Result<Vec<ContrastChange>, String> {
+    let accuracy = contrast_accuracy(db, current)?;
+    let previous = contrast_accuracy(db, previous)?;
+    let mut changes: Vec<ContrastChange> = accuracy
+        .into_iter()
+        .filter_map(|(contrast, (attempts, accuracy))| {
+            let &(previous_attempts, previous_accuracy) = previous.get(&contrast)?;
+            if attempts < MIN_CONTRAST_ATTEMPTS || previous_attempts < MIN_CONTRAST_ATTEMPTS || accuracy <= previous_accuracy {
+                return None;
+            }
+            let label = minimal_pairs::find_contrast(&contrast).map(|c| c.label.to_string()).unwrap_or_else(|| contrast.clone());
+            Some(ContrastChange {
+                contrast,
+                label,
+                attempts,
+                accuracy: (accuracy * 100.0).round(),
+                previous_accuracy: (previous_accuracy * 100.0).round(),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| (b.accuracy - b.previous_accuracy).total_cmp(&(a.accuracy - a.previous_accuracy)));
+    changes.truncate(MAX_IMPROVED);
+    Ok(changes)
+}
+
+fn phoneme_error_rows(db: &Database, range: DayRange) -> Result<Vec<(String, String, String)>, String> {
+    let (start, end) = range.bounds();
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT expected, produced, word FROM phoneme_errors
+             WHERE date(created_at, 'localtime') BETWEEN ?1 AND ?2 ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| format!("查詢音位錯誤失敗：{}", e))?;
+    rows.collect::<Result<_, _>>().map_err(|e| format!("查詢音位錯誤失敗：{}", e))
+}
+
+// 本期讀錯次數最多的音位。按 IPA 歸類，同一個音帶不同重音標記時算作一個
+fn weak_phonemes(db: &Database, current: DayRange, previous: DayRange) -> Result<Vec<WeakPhoneme>, String> {
+    let mut phonemes: Vec<WeakPhoneme> = Vec::new();
+    let mut substitutions: HashMap<String, HashMap<String, u32>> = HashMap::new();
+    for (expected, produced, word) in phoneme_error_rows(db, current)? {
+        let ipa = pronunciation::phoneme_ipa(&expected).to_string();
+        let position = match phonemes.iter().position(|p| p.ipa == ipa) {
+            Some(position) => position,
+            None => {
+                phonemes.push(WeakPhoneme {
+                    phoneme: expected.trim_end_matches(|c: char| c.is_ascii_digit()).to_string(),
+                    ipa: ipa.clone(),
+                    errors: 0,
+                    previous_errors: 0,
+                    heard_as: None,
+                    examples: Vec::new(),
+                });
+                phonemes.len() - 1
+            }
+        };
+        let entry = &mut phonemes[position];
+        entry.errors += 1;
+        let word = word.to_lowercase();
+        if entry.examples.len() < 3 && !entry.examples.contains(&word) {
+            entry.examples.push(word);
+        }
+        *substitutions.entry(ipa).or_default().entry(pronunciation::phoneme_ipa(&produced).to_string()).or_default() += 1;
+    }
+    for (expected, _, _) in phoneme_error_rows(db, previous)? {
+        let ipa = pronunciation::phoneme_ipa(&expected);
+        if let Some(entry) = phonemes.iter_mut().find(|p| p.ipa == ipa) {
+            entry.previous_errors += 1;
+        }
+    }
+    for entry in &mut phonemes {
+        entry.heard_as = substitutions
+            .get(&entry.ipa)
+            .and_then(|counts| counts.iter().max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0))))
+            .map(|(ipa, _)| ipa.clone());
+    }
+    phonemes.sort_by(|a, b| b.errors.cmp(&a.errors).then_with(|| a.ipa.cmp(&b.ipa)));
+    phonemes.truncate(MAX_WEAK_PHONEMES);
+    Ok(phonemes)
+}
+
+fn learned_words(db: &Database, range: DayRange) -> Result<(u32, Vec<LearnedWord>), String> {
+    let (start, end) = range.bounds();
+    let count: u32 = db
+        .conn()
+        .query_row(
+            "SELECT COUNT(*) FROM vocabulary WHERE date(created_at, 'localtime') BETWEEN ?1 AND ?2",
+            params![start, end],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("查詢生詞失敗：{}", e))?;
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT word, ipa, translation FROM vocabulary WHERE date(created_at, 'localtime') BETWEEN ?1 AND ?2
+             ORDER BY created_at, id LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start, end, MAX_NEW_WORDS as i64], |row| {
+            Ok(LearnedWord { word: row.get(0)?, ipa: row.get(1)?, translation: row.get(2)? })
+        })
+        .map_err(|e| format!("查詢生詞失敗：{}", e))?;
+    let words = rows.collect::<Result<_, _>>().map_err(|e| format!("查詢生詞失敗：{}", e))?;
+    Ok((count, words))
+}
+
+// 取本期得分最高的幾次練習的導師點評。點評由前端以 TutorFeedback 的 JSON 保存，解析不了時按純文本處理
+fn tutor_highlights(db: &Database, range: DayRange) -> Result<Vec<TutorHighlight>, String> {
+    let (start, end) = range.bounds();
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT date(created_at, 'localtime'), topic, json_extract(scores, '$.overall') AS overall, feedback
+             FROM practice_records WHERE date(created_at, 'localtime') BETWEEN ?1 AND ?2 AND feedback != ''
+             ORDER BY overall DESC NULLS LAST, created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<f64>>(2)?, row.get::<_, String>(3)?))
+        })
+        .map_err(|e| format!("查詢導師點評失敗：{}", e))?;
+
+    let mut highlights: Vec<TutorHighlight> = Vec::new();
+    for row in rows {
+        let (date, topic, score, feedback) = row.map_err(|e| e.to_string())?;
+        let (text, tip) = match serde_json::from_str::<TutorFeedback>(&feedback) {
+            Ok(parsed) => {
+                let text = if parsed.specific_feedback.trim().is_empty() { parsed.encouragement } else { parsed.specific_feedback };
+                (text, parsed.improvement_tips.into_iter().find(|tip| !tip.trim().is_empty()))
+            }
+            Err(_) if feedback.trim_start().starts_with('{') => continue,
+            Err(_) => (feedback, None),
+        };
+        let text = text.trim().to_string();
+        if text.is_empty() || highlights.iter().any(|h| h.feedback == text) {
+            continue;
+        }
+        highlights.push(TutorHighlight { date, topic, score: score.map(round1), feedback: text, tip });
+        if highlights.len() >= MAX_HIGHLIGHTS {
+            break;
+        }
+    }
+    Ok(highlights)
+}
+
+fn unlocked_achievements(db: &Database, range: DayRange) -> Result<Vec<Achievement>, String> {
+    let (start, end) = range.bounds();
+    let ids: HashSet<String> = {
+        let mut stmt = db
+            .conn()
+            .prepare("SELECT badge_id FROM achievements WHERE date(unlocked_at, 'localtime') BETWEEN ?1 AND ?2")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![start, end], |row| row.get(0))
+            .map_err(|e| format!("查詢成就失敗：{}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| format!("查詢成就失敗：{}", e))?
+    };
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut unlocked: Vec<Achievement> = achievements::list(db)?.into_iter().filter(|a| ids.contains(&a.id)).collect();
+    unlocked.sort_by(|a, b| a.unlocked_at.cmp(&b.unlocked_at));
+    Ok(unlocked)
+}
+
+// 匯總包含 date 的那一週或那個月的學習數據；date 為空時用今天
+pub fn build(db: &Database, period: ReportPeriod, date: Option<&str>, learner: Option<String>) -> Result<ProgressReport, String> {
+    let (today, generated_at): (String, String) = db
+        .conn()
+        .query_row("SELECT date('now', 'localtime'), datetime('now', 'localtime')", [], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    let (current, previous) = period_ranges(period, date.unwrap_or(&today))?;
+    let (sessions, average_score) = session_stats(db, current)?;
+    let (previous_sessions, previous_average_score) = session_stats(db, previous)?;
+    let daily = daily_summaries(db, current)?;
+    let (words_learned, new_words) = learned_words(db, current)?;
+    let (start_date, end_date) = current.bounds();
+
+    Ok(ProgressReport {
+        period,
+        start_date,
+        end_date,
+        learner,
+        generated_at,
+        sessions,
+        previous_sessions,
+        practice_minutes: practice_minutes(db, current)?,
+        previous_practice_minutes: practice_minutes(db, previous)?,
+        active_days: daily.iter().filter(|d| d.sessions > 0 || d.minutes > 0.0).count() as u32,
+        average_score,
+        previous_average_score,
+        metrics: metric_summaries(db, current, previous)?,
+        daily,
+        improved_contrasts: improved_contrasts(db, current, previous)?,
+        weak_phonemes: weak_phonemes(db, current, previous)?,
+        words_learned,
+        new_words,
+        highlights: tutor_highlights(db, current)?,
+        achievements: unlocked_achievements(db, current)?,
+    })
+}
+
+// 圖表的繪圖區域，HTML 和 PDF 共用同一套坐標換算；x 按天等分，y 從 0 到 max
+struct Plot {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+    max: f64,
+}
+
+impl Plot {
+    fn slot(&self, count: usize) -> f64 {
+        self.width / count.max(1) as f64
+    }
+
+    fn x(&self, index: usize, count: usize) -> f64 {
+        self.left + self.slot(count) * (index as f64 + 0.5)
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        self.top + self.height * (1.0 - (value / self.max).clamp(0.0, 1.0))
+    }
+
+    fn bottom(&self) -> f64 {
+        self.top + self.height
+    }
+}
+
+// 月報的日期標籤每 5 天標一個，避免擠在一起
+fn label_step(count: usize) -> usize {
+    if count > 10 { 5 } else { 1 }
+}
+
+fn short_date(date: &str) -> String {
+    let mut parts = date.splitn(3, '-').skip(1).map(|p| p.trim_start_matches('0').to_string());
+    format!("{}/{}", parts.next().unwrap_or_default(), parts.next().unwrap_or_default())
+}
+
+// 分鐘圖的縱軸上限取整到 10 或 30 的倍數
+fn minutes_axis_max(daily: &[DailySummary]) -> f64 {
+    let max = daily.iter().map(|d| d.minutes).fold(0.0, f64::max);
+    let step = if max > 60.0 { 30.0 } else { 10.0 };
+    ((max / step).ceil() * step).max(step)
+}
+
+// 有分數的日子依次連線，沒練習的日子直接跳過，隔天練習時也能看出趨勢
+fn score_points(plot: &Plot, daily: &[DailySummary]) -> Vec<(f64, f64)> {
+    daily
+        .iter()
+        .enumerate()
+        .filter_map(|(i, day)| day.average_score.map(|score| (plot.x(i, daily.len()), plot.y(score))))
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn format_delta(current: f64, previous: f64, digits: usize) -> (String, &'static str) {
+    let delta = current - previous;
+    let class = if delta > 0.05 { "up" } else if delta < -0.05 { "down" } else { "flat" };
+    let sign = if delta > 0.05 { "+" } else if delta < -0.05 { "-" } else { "" };
+    (format!("{}{:.*}", sign, digits, delta.abs()), class)
+}
+
+// unit 為數值後的單位，沒有時傳空字符串
+fn html_delta(s: &Strings, current: f64, previous: f64, digits: usize, unit: &str) -> String {
+    let (text, class) = format_delta(current, previous, digits);
+    format!("<div class=\"delta {}\">{} {}</div>", class, s.vs_previous, format!("{} {}", text, unit).trim_end())
+}
+
+const CHART_WIDTH: f64 = 700.0;
+const CHART_HEIGHT: f64 = 200.0;
+
+fn svg_axis_labels(svg: &mut String, plot: &Plot, daily: &[DailySummary]) {
+    let step = label_step(daily.len());
+    for (i, day) in daily.iter().enumerate().filter(|(i, _)| i % step == 0) {
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            plot.x(i, daily.len()),
+            plot.bottom() + 16.0,
+            short_date(&day.date)
+        ));
+    }
+}
+
+fn svg_grid(svg: &mut String, plot: &Plot, ticks: &[f64]) {
+    for &tick in ticks {
+        let y = plot.y(tick);
+        svg.push_str(&format!(
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#e4e7eb\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            plot.left,
+            y,
+            plot.left + plot.width,
+            y,
+            plot.left - 6.0,
+            y + 4.0,
+            tick
+        ));
+    }
+}
+
+fn svg_score_chart(daily: &[DailySummary]) -> String {
+    let plot = Plot { left: 36.0, top: 10.0, width: CHART_WIDTH - 46.0, height: CHART_HEIGHT - 36.0, max: 100.0 };
+    let mut svg = format!("<svg viewBox=\"0 0 {} {}\" role=\"img\">", CHART_WIDTH, CHART_HEIGHT);
+    svg_grid(&mut svg, &plot, &[0.0, 25.0, 50.0, 75.0, 100.0]);
+    let points: Vec<String> = score_points(&plot, daily).iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+    svg.push_str(&format!(
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"#4c6ef5\" stroke-width=\"2.5\"/>",
+        points.join(" ")
+    ));
+    for (i, day) in daily.iter().enumerate() {
+        if let Some(score) = day.average_score {
+            svg.push_str(&format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3.5\" fill=\"#4c6ef5\"><title>{} · {:.1}</title></circle>",
+                plot.x(i, daily.len()),
+                plot.y(score),
+                day.date,
+                score
+            ));
+        }
+    }
+    svg_axis_labels(&mut svg, &plot, daily);
+    svg.push_str("</svg>");
+    svg
+}
+
+fn svg_minutes_chart(daily: &[DailySummary]) -> String {
+    let max = minutes_axis_max(daily);
+    let plot = Plot { left: 36.0, top: 10.0, width: CHART_WIDTH - 46.0, height: CHART_HEIGHT - 36.0, max };
+    let mut svg = format!("<svg viewBox=\"0 0 {} {}\" role=\"img\">", CHART_WIDTH, CHART_HEIGHT);
+    svg_grid(&mut svg, &plot, &[0.0, max / 2.0, max]);
+    let bar = plot.slot(daily.len()) * 0.6;
+    for (i, day) in daily.iter().enumerate().filter(|(_, day)| day.minutes > 0.0) {
+        let y = plot.y(day.minutes);
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"2\" fill=\"#20c997\"><title>{} · {:.1}</title></rect>",
+            plot.x(i, daily.len()) - bar / 2.0,
+            y,
+            bar,
+            plot.bottom() - y,
+            day.date,
+            day.minutes
+        ));
+    }
+    svg_axis_labels(&mut svg, &plot, daily);
+    svg.push_str("</svg>");
+    svg
+}
+
+const HTML_STYLE: &str = "
+body { margin: 0; background: #f5f7fa; color: #1f2933; font-family: -apple-system, 'Segoe UI', 'PingFang TC', 'PingFang SC', 'Microsoft JhengHei', 'Microsoft YaHei', sans-serif; line-height: 1.5; }
+main { max-width: 760px; margin: 0 auto; padding: 32px 24px; }
+h1 { font-size: 26px; margin: 0 0 4px; }
+h2 { font-size: 17px; margin: 0 0 12px; }
+.subtitle { color: #616e7c; margin: 0 0 24px; }
+section { background: #fff; border-radius: 10px; padding: 20px 24px; margin-bottom: 16px; box-shadow: 0 1px 3px rgba(0, 0, 0, 0.08); }
+.cards { display: grid; grid-template-columns: repeat(auto-fit, minmax(130px, 1fr)); gap: 12px; }
+.card { background: #f5f7fa; border-radius: 8px; padding: 12px; }
+.card .value { font-size: 22px; font-weight: 600; }
+.card .label { color: #616e7c; font-size: 13px; }
+.delta { font-size: 12px; color: #616e7c; }
+.up { color: #2f9e44; } .down { color: #e03131; }
+table { width: 100%; border-collapse: collapse; font-size: 14px; }
+th, td { padding: 6px 8px; border-bottom: 1px solid #e4e7eb; text-align: left; }
+th { color: #616e7c; font-weight: 500; }
+svg { width: 100%; height: auto; }
+svg text { font-size: 11px; fill: #616e7c; }
+.empty { color: #9aa5b1; }
+.ipa { font-family: 'Charis SIL', 'Doulos SIL', 'Lucida Sans Unicode', serif; }
+blockquote { margin: 0 0 12px; padding: 8px 12px; border-left: 3px solid #4c6ef5; background: #f5f7fa; }
+blockquote .meta { color: #616e7c; font-size: 12px; }
+blockquote .tip { color: #3b5bdb; font-size: 13px; margin-top: 4px; }
+ul.badges { list-style: none; padding: 0; margin: 0; }
+ul.badges li { padding: 4px 0; }
+@media print { body { background: #fff; } section { box-shadow: none; border: 1px solid #e4e7eb; break-inside: avoid; } }
+";
+
+fn html_empty(s: &Strings) -> String {
+    format!("<p class=\"empty\">{}</p>", s.no_data)
+}
+
+pub fn render_html(report: &ProgressReport, locale: ReportLocale) -> String {
+    let s = locale.strings();
+    let title = match report.period {
+        ReportPeriod::Week => s.title_week,
+        ReportPeriod::Month => s.title_month,
+    };
+    let mut subtitle = format!("{} – {}", locale.date(&report.start_date), locale.date(&report.end_date));
+    if let Some(learner) = &report.learner {
+        subtitle.push_str(&format!(" · {}{}{}", s.learner, locale.colon(), escape(learner)));
+    }
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{} · {}</title>\n<style>{}</style>\n</head>\n\
+         <body>\n<main>\n<h1>{}</h1>\n<p class=\"subtitle\">{}</p>\n",
+        locale.html_lang(),
+        title,
+        report.start_date,
+        HTML_STYLE,
+        title,
+        subtitle
+    );
+
+    // 概覽
+    let score_card = match report.average_score {
+        Some(score) => format!(
+            "<div class=\"value\">{:.1}</div>{}",
+            score,
+            report.previous_average_score.map(|previous| html_delta(s, score, previous, 1, "")).unwrap_or_default()
+        ),
+        None => "<div class=\"value\">–</div>".to_string(),
+    };
+    html.push_str(&format!(
+        "<section>\n<h2>{}</h2>\n<div class=\"cards\">\
+         <div class=\"card\"><div class=\"label\">{}</div>{}</div>\
+         <div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div>{}</div>\
+         <div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div>{}</div>\
+         <div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{} / {}</div></div>\
+         <div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div></div>\
+         </div>\n</section>\n",
+        s.summary,
+        s.average_score,
+        score_card,
+        s.sessions,
+        report.sessions,
+        html_delta(s, report.sessions as f64, report.previous_sessions as f64, 0, ""),
+        s.practice_time,
+        locale.duration(report.practice_minutes),
+        html_delta(s, report.practice_minutes, report.previous_practice_minutes, 0, s.minutes_unit),
+        s.active_days,
+        report.active_days,
+        report.daily.len(),
+        s.words_learned,
+        report.words_learned
+    ));
+
+    // 分數趨勢和每日練習時間
+    html.push_str(&format!("<section>\n<h2>{}</h2>\n", s.score_trend));
+    if report.daily.iter().any(|d| d.average_score.is_some()) {
+        html.push_str(&svg_score_chart(&report.daily));
+        if !report.metrics.is_empty() {
+            html.push_str(&format!(
+                "<h2 style=\"margin-top:16px\">{}</h2>\n<table><tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+                s.metric_breakdown, s.metric, s.this_period, s.previous_period, s.change
+            ));
+            for metric in &report.metrics {
+                let (previous, change) = match metric.previous {
+                    Some(previous) => {
+                        let (text, class) = format_delta(metric.average, previous, 1);
+                        (format!("{:.1}", previous), format!("<span class=\"{}\">{}</span>", class, text))
+                    }
+                    None => ("–".to_string(), String::new()),
+                };
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{:.1}</td><td>{}</td><td>{}</td></tr>",
+                    escape(s.metric_name(&metric.metric)),
+                    metric.average,
+                    previous,
+                    change
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+    } else {
+        html.push_str(&html_empty(s));
+    }
+    html.push_str("</section>\n");
+
+    html.push_str(&format!("<section>\n<h2>{}</h2>\n", s.daily_minutes));
+    if report.practice_minutes > 0.0 {
+        html.push_str(&svg_minutes_chart(&report.daily));
+    } else {
+        html.push_str(&html_empty(s));
+    }
+    html.push_str("</section>\n");
+
+    // 發音
+    html.push_str(&format!("<section>\n<h2>{}</h2>\n", s.improved));
+    if report.improved_contrasts.is_empty() {
+        html.push_str(&html_empty(s));
+    } else {
+        html.push_str(&format!("<table><tr><th></th><th>{}</th><th>{}</th></tr>", s.accuracy, s.change));
+        for change in &report.improved_contrasts {
+            html.push_str(&format!(
+                "<tr><td class=\"ipa\">{}</td><td>{:.0}% → {:.0}%</td><td class=\"up\">+{:.0}%</td></tr>",
+                escape(&change.label),
+                change.previous_accuracy,
+                change.accuracy,
+                change.accuracy - change.previous_accuracy
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str(&format!("<h2 style=\"margin-top:16px\">{}</h2>\n", s.weakest));
+    if report.weak_phonemes.is_empty() {
+        html.push_str(&html_empty(s));
+    } else {
+        html.push_str(&format!(
+            "<table><tr><th></th><th>{}</th><th>{}</th><th>{}</th></tr>",
+            s.errors, s.heard_as, s.examples
+        ));
+        for phoneme in &report.weak_phonemes {
+            let (change, class) = format_delta(phoneme.errors as f64, phoneme.previous_errors as f64, 0);
+            // 錯誤次數增加是壞事，顏色與分數相反
+            let class = match class {
+                "up" => "down",
+                "down" => "up",
+                other => other,
+            };
+            html.push_str(&format!(
+                "<tr><td class=\"ipa\">/{}/</td><td>{} <span class=\"delta {}\">({} {})</span></td><td class=\"ipa\">{}</td><td>{}</td></tr>",
+                escape(&phoneme.ipa),
+                phoneme.errors,
+                class,
+                s.vs_previous,
+                change,
+                phoneme.heard_as.as_deref().map(|ipa| format!("/{}/", escape(ipa))).unwrap_or_default(),
+                escape(&phoneme.examples.join(", "))
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</section>\n");
+
+    // 詞彙
+    html.push_str(&format!("<section>\n<h2>{}</h2>\n", s.new_words));
+    if report.new_words.is_empty() {
+        html.push_str(&html_empty(s));
+    } else {
+        html.push_str("<table>");
+        for word in &report.new_words {
+            html.push_str(&format!(
+                "<tr><td><strong>{}</strong></td><td class=\"ipa\">{}</td><td>{}</td></tr>",
+                escape(&word.word),
+                escape(&word.ipa),
+                escape(&word.translation)
+            ));
+        }
+        html.push_str("</table>\n");
+        let more = report.words_learned as usize - report.new_words.len().min(report.words_learned as usize);
+        if more > 0 {
+            html.push_str(&format!("<p class=\"empty\">{}</p>\n", s.more_words.replace("{}", &more.to_string())));
+        }
+    }
+    html.push_str("</section>\n");
+
+    // 導師點評
+    html.push_str(&format!("<section>\n<h2>{}</h2>\n", s.highlights));
+    if report.highlights.is_empty() {
+        html.push_str(&html_empty(s));
+    }
+    for highlight in &report.highlights {
+        let score = highlight.score.map(|score| format!(" · {:.1}", score)).unwrap_or_default();
+        html.push_str(&format!(
+            "<blockquote><div class=\"meta\">{} · {}{}</div><div>{}</div>{}</blockquote>\n",
+            locale.date(&highlight.date),
+            escape(&highlight.topic),
+            score,
+            escape(&highlight.feedback),
+            highlight.tip.as_deref().map(|tip| format!("<div class=\"tip\">💡 {}</div>", escape(tip))).unwrap_or_default()
+        ));
+    }
+    html.push_str("</section>\n");
+
+    if !report.achievements.is_empty() {
+        html.push_str(&format!("<section>\n<h2>{}</h2>\n<ul class=\"badges\">", s.achievements));
+        for achievement in &report.achievements {
+            html.push_str(&format!(
+                "<li>{} <strong>{}</strong> · {}</li>",
+                achievement.icon,
+                escape(&achievement.title),
+                escape(&achievement.description)
+            ));
+        }
+        html.push_str("</ul>\n</section>\n");
+    }
+
+    html.push_str(&format!(
+        "<p class=\"empty\">{} {}</p>\n</main>\n</body>\n</html>\n",
+        s.generated_at, report.generated_at
+    ));
+    html
+}
+
+const MARGIN: f64 = 50.0;
+const TEXT_COLOR: Color = (0.12, 0.16, 0.2);
+const MUTED_COLOR: Color = (0.38, 0.43, 0.49);
+const GRID_COLOR: Color = (0.89, 0.91, 0.92);
+const SCORE_COLOR: Color = (0.3, 0.43, 0.96);
+const MINUTES_COLOR: Color = (0.13, 0.79, 0.59);
+
+// 按寬度折行：英文盡量在空格處斷開，中文逐字斷開
+fn wrap(text: &str, size: f64, max_width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for c in paragraph.chars() {
+            line.push(c);
+            if pdf::text_width(&line, size) <= max_width {
+                continue;
+            }
+            let split = match line.rfind(' ') {
+                Some(space) if c.is_ascii_alphanumeric() && space > 0 => space,
+                _ => line.len() - c.len_utf8(),
+            };
+            let rest = line.split_off(split);
+            lines.push(line.trim_end().to_string());
+            line = rest.trim_start().to_string();
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// PDF 的逐行排版，放不下時換頁
+struct PdfLayout {
+    pdf: PdfWriter,
+    y: f64,
+}
+
+impl PdfLayout {
+    fn ensure(&mut self, height: f64) {
+        if self.y + height > pdf::PAGE_HEIGHT - MARGIN {
+            self.pdf.new_page();
+            self.y = MARGIN;
+        }
+    }
+
+    fn line(&mut self, size: f64, color: Color, text: &str) {
+        self.line_at(MARGIN, size, color, text);
+    }
+
+    fn line_at(&mut self, x: f64, size: f64, color: Color, text: &str) {
+        for line in wrap(text, size, pdf::PAGE_WIDTH - MARGIN - x) {
+            self.ensure(size * 1.5);
+            self.y += size * 1.5;
+            self.pdf.text(x, self.y, size, color, &line);
+        }
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.ensure(60.0);
+        self.y += 14.0;
+        self.line(14.0, TEXT_COLOR, text);
+        self.y += 4.0;
+    }
+
+    fn axis_labels(&mut self, plot: &Plot, daily: &[DailySummary]) {
+        let step = label_step(daily.len());
+        for (i, day) in daily.iter().enumerate().filter(|(i, _)| i % step == 0) {
+            let label = short_date(&day.date);
+            let x = plot.x(i, daily.len()) - pdf::text_width(&label, 8.0) / 2.0;
+            self.pdf.text(x, plot.bottom() + 12.0, 8.0, MUTED_COLOR, &label);
+        }
+    }
+
+    fn grid(&mut self, plot: &Plot, ticks: &[f64]) {
+        for &tick in ticks {
+            let y = plot.y(tick);
+            self.pdf.polyline(&[(plot.left, y), (plot.left + plot.width, y)], 0.5, GRID_COLOR);
+            let label = format!("{}", tick);
+            self.pdf.text(plot.left - 6.0 - pdf::text_width(&label, 8.0), y + 3.0, 8.0, MUTED_COLOR, &label);
+        }
+    }
+
+    fn chart_area(&mut self) -> Plot {
+        let height = 120.0;
+        self.ensure(height + 30.0);
+        let plot = Plot { left: MARGIN + 24.0, top: self.y + 8.0, width: pdf::PAGE_WIDTH - 2.0 * MARGIN - 24.0, height, max: 1.0 };
+        self.y += height + 26.0;
+        plot
+    }
+
+    fn score_chart(&mut self, daily: &[DailySummary]) {
+        let plot = Plot { max: 100.0, ..self.chart_area() };
+        self.grid(&plot, &[0.0, 50.0, 100.0]);
+        let points = score_points(&plot, daily);
+        self.pdf.polyline(&points, 2.0, SCORE_COLOR);
+        for (x, y) in points {
+            self.pdf.rect(x - 2.0, y - 2.0, 4.0, 4.0, SCORE_COLOR);
+        }
+        self.axis_labels(&plot, daily);
+    }
+
+    fn minutes_chart(&mut self, daily: &[DailySummary]) {
+        let max = minutes_axis_max(daily);
+        let plot = Plot { max, ..self.chart_area() };
+        self.grid(&plot, &[0.0, max / 2.0, max]);
+        let bar = plot.slot(daily.len()) * 0.6;
+        for (i, day) in daily.iter().enumerate().filter(|(_, day)| day.minutes > 0.0) {
+            let y = plot.y(day.minutes);
+            self.pdf.rect(plot.x(i, daily.len()) - bar / 2.0, y, bar, plot.bottom() - y, MINUTES_COLOR);
+        }
+        self.axis_labels(&plot, daily);
+    }
+}
+
+fn delta_text(locale: ReportLocale, current: f64, previous: f64, digits: usize, unit: &str) -> String {
+    let vs_previous = locale.strings().vs_previous;
+    let change = format!("{} {}", format_delta(current, previous, digits).0, unit);
+    match locale {
+        ReportLocale::En => format!(" ({} {})", vs_previous, change.trim_end()),
+        _ => format!("（{} {}）", vs_previous, change.trim_end()),
+    }
+}
+
+// PDF 版內容與 HTML 相同，但只用 CJK 字體裡有的字符：不用 emoji，音位用 ARPAbet 代替 IPA
+pub fn render_pdf(report: &ProgressReport, locale: ReportLocale) -> Vec<u8> {
+    let s = locale.strings();
+    let mut layout = PdfLayout { pdf: PdfWriter::new(locale.cjk_font()), y: MARGIN };
+    let title = match report.period {
+        ReportPeriod::Week => s.title_week,
+        ReportPeriod::Month => s.title_month,
+    };
+    layout.line(22.0, TEXT_COLOR, title);
+    let mut subtitle = format!("{} - {}", locale.date(&report.start_date), locale.date(&report.end_date));
+    if let Some(learner) = &report.learner {
+        subtitle.push_str(&format!("  {}{}{}", s.learner, locale.colon(), learner));
+    }
+    layout.line(10.0, MUTED_COLOR, &subtitle);
+
+    layout.heading(s.summary);
+    let average = match report.average_score {
+        Some(score) => format!(
+            "{:.1}{}",
+            score,
+            report.previous_average_score.map(|previous| delta_text(locale, score, previous, 1, "")).unwrap_or_default()
+        ),
+        None => "-".to_string(),
+    };
+    let summary = [
+        (s.average_score, average),
+        (
+            s.sessions,
+            format!(
+                "{}{}",
+                report.sessions,
+                delta_text(locale, report.sessions as f64, report.previous_sessions as f64, 0, "")
+            ),
+        ),
+        (
+            s.practice_time,
+            format!(
+                "{}{}",
+                locale.duration(report.practice_minutes),
+                delta_text(locale, report.practice_minutes, report.previous_practice_minutes, 0, s.minutes_unit)
+            ),
+        ),
+        (s.active_days, format!("{} / {}", report.active_days, report.daily.len())),
+        (s.words_learned, report.words_learned.to_string()),
+    ];
+    for (label, value) in summary {
+        layout.line(11.0, TEXT_COLOR, &format!("{}{}{}", label, locale.colon(), value));
+    }
+
+    layout.heading(s.score_trend);
+    if report.daily.iter().any(|d| d.average_score.is_some()) {
+        layout.score_chart(&report.daily);
+        for metric in &report.metrics {
+            let change = metric
+                .previous
+                .map(|previous| delta_text(locale, metric.average, previous, 1, ""))
+                .unwrap_or_default();
+            layout.line(10.0, TEXT_COLOR, &format!("{}{}{:.1}{}", s.metric_name(&metric.metric), locale.colon(), metric.average, change));
+        }
+    } else {
+        layout.line(10.0, MUTED_COLOR, s.no_data);
+    }
+
+    layout.heading(s.daily_minutes);
+    if report.practice_minutes > 0.0 {
+        layout.minutes_chart(&report.daily);
+    } else {
+        layout.line(10.0, MUTED_COLOR, s.no_data);
+    }
+
+    layout.heading(s.improved);
+    if report.improved_contrasts.is_empty() {
+        layout.line(10.0, MUTED_COLOR, s.no_data);
+    }
+    for change in &report.improved_contrasts {
+        let name = minimal_pairs::find_contrast(&change.contrast)
+            .map(|c| c.phonemes.join(" / "))
+            .unwrap_or_else(|| change.contrast.clone());
+        layout.line(
+            10.0,
+            TEXT_COLOR,
+            &format!("{}  {}{}{:.0}% -> {:.0}%", name, s.accuracy, locale.colon(), change.previous_accuracy, change.accuracy),
+        );
+    }
+
+    layout.heading(s.weakest);
+    if report.weak_phonemes.is_empty() {
+        layout.line(10.0, MUTED_COLOR, s.no_data);
+    }
+    for phoneme in &report.weak_phonemes {
+        layout.line(
+            10.0,
+            TEXT_COLOR,
+            &format!(
+                "{}  {}{}{}{}  {}{}{}",
+                phoneme.phoneme,
+                s.errors,
+                locale.colon(),
+                phoneme.errors,
+                delta_text(locale, phoneme.errors as f64, phoneme.previous_errors as f64, 0, ""),
+                s.examples,
+                locale.colon(),
+                phoneme.examples.join(", ")
+            ),
+        );
+    }
+
+    layout.heading(s.new_words);
+    if report.new_words.is_empty() {
+        layout.line(10.0, MUTED_COLOR, s.no_data);
+    }
+    for word in &report.new_words {
+        let line = if word.translation.is_empty() { word.word.clone() } else { format!("{}  {}", word.word, word.translation) };
+        layout.line(10.0, TEXT_COLOR, &line);
+    }
+    let more = report.words_learned as usize - report.new_words.len().min(report.words_learned as usize);
+    if more > 0 {
+        layout.line(10.0, MUTED_COLOR, &s.more_words.replace("{}", &more.to_string()));
+    }
+
+    layout.heading(s.highlights);
+    if report.highlights.is_empty() {
+        layout.line(10.0, MUTED_COLOR, s.no_data);
+    }
+    for highlight in &report.highlights {
+        let score = highlight.score.map(|score| format!("  {:.1}", score)).unwrap_or_default();
+        layout.ensure(50.0);
+        layout.line(9.0, MUTED_COLOR, &format!("{}  {}{}", locale.date(&highlight.date), highlight.topic, score));
+        layout.line_at(MARGIN + 10.0, 10.0, TEXT_COLOR, &highlight.feedback);
+        if let Some(tip) = &highlight.tip {
+            layout.line_at(MARGIN + 10.0, 10.0, SCORE_COLOR, tip);
+        }
+        layout.y += 6.0;
+    }
+
+    if !report.achievements.is_empty() {
+        layout.heading(s.achievements);
+        for achievement in &report.achievements {
+            layout.line(10.0, TEXT_COLOR, &format!("{}{}{}", achievement.title, locale.colon(), achievement.description));
+        }
+    }
+
+    layout.y += 10.0;
+    layout.line(8.0, MUTED_COLOR, &format!("{} {}", s.generated_at, report.generated_at));
+    layout.pdf.finish()
+}
+
+// 按格式寫出報告文件；HTML 自帶樣式和圖表，不引用外部資源
+pub fn write(report: &ProgressReport, locale: ReportLocale, format: &str, path: &Path) -> Result<(), String> {
+    let content = match format {
+        "html" => render_html(report, locale).into_bytes(),
+        "pdf" => render_pdf(report, locale),
+        other => return Err(format!("不支持的報告格式：{}", other)),
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("無法創建導出目錄：{}", e))?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("保存報告失敗：{}", e))
+}
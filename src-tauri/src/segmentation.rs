@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::text::{split_phrases, split_sentences, tokenize_words};
+
+// 句內短語塊的最大詞數
+pub const MAX_CHUNK_WORDS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    pub id: String,
+    pub index: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sentence {
+    pub id: String,
+    pub index: usize,
+    pub text: String,
+    pub chunks: Vec<Chunk>,
+}
+
+// 練習內容按句子、短語塊兩級切分的結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentedText {
+    pub content_id: String,
+    pub text: String,
+    pub sentences: Vec<Sentence>,
+}
+
+impl SegmentedText {
+    // 按 id 查找句子或短語塊的文本
+    pub fn find_segment(&self, segment_id: &str) -> Option<&str> {
+        self.sentences.iter().find_map(|sentence| {
+            if sentence.id == segment_id {
+                return Some(sentence.text.as_str());
+            }
+            sentence.chunks.iter().find(|c| c.id == segment_id).map(|c| c.text.as_str())
+        })
+    }
+}
+
+// 切分練習內容。id 只取決於文本內容（忽略大小寫和標點），同一段內容每次切分得到相同的 id，
+// 歷史成績因此可以按片段累積
+pub fn segment_text(text: &str) -> SegmentedText {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut unique_id = |prefix: &str, text: &str| {
+        let id = stable_id(prefix, text);
+        let count = seen.entry(id.clone()).or_insert(0);
+        *count += 1;
+        // 同一內容中重複出現的句子按出現次數區分
+        if *count == 1 { id } else { format!("{}-{}", id, count) }
+    };
+
+    let sentences = split_sentences(text)
+        .into_iter()
+        .enumerate()
+        .map(|(index, sentence)| {
+            let phrases = split_phrases(&sentence, MAX_CHUNK_WORDS);
+            // 短句不再切分，整句就是唯一的短語塊
+            let chunks = if phrases.len() > 1 {
+                phrases
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, phrase)| Chunk { id: unique_id("c", &phrase), index, text: phrase })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            Sentence { id: unique_id("s", &sentence), index, text: sentence, chunks }
+        })
+        .collect();
+
+    SegmentedText { content_id: stable_id("p", text), text: text.trim().to_string(), sentences }
+}
+
+// FNV-1a 64 位哈希，結果在不同版本和平台間保持穩定（標準庫的 DefaultHasher 不保證）
+pub fn stable_id(prefix: &str, text: &str) -> String {
+    let normalized = tokenize_words(text).join(" ");
+    let hash = normalized.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{}-{:012x}", prefix, hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_sentences_and_long_sentences_into_chunks() {
+        let segmented = segment_text(
+            "  Mr. Smith arrived at noon. When the train finally pulled into the station, everyone on the platform started \
+             cheering loudly! Really?!",
+        );
+        let texts: Vec<&str> = segmented.sentences.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts.len(), 3);
+        assert_eq!(texts[0], "Mr. Smith arrived at noon.");
+        assert_eq!(texts[2], "Really?!");
+
+        // 短句沒有短語塊，長句切成不超過上限的短語塊
+        assert!(segmented.sentences[0].chunks.is_empty());
+        let chunks = &segmented.sentences[1].chunks;
+        assert!(chunks.len() >= 2);
+        assert_eq!(chunks[0].text, "When the train finally pulled into the station,");
+        assert!(chunks.iter().all(|c| c.text.split_whitespace().count() <= MAX_CHUNK_WORDS + 2));
+        assert_eq!(chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>().join(" "), texts[1]);
+        assert_eq!(chunks.iter().map(|c| c.index).collect::<Vec<_>>(), (0..chunks.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ids_are_stable_and_ignore_case_and_punctuation() {
+        let first = segment_text("Hello there. How are you?");
+        let second = segment_text("hello there!  How are you");
+        assert_eq!(first.content_id, second.content_id);
+        assert_eq!(first.sentences[0].id, second.sentences[0].id);
+        assert_eq!(first.sentences[1].id, second.sentences[1].id);
+        assert_ne!(first.sentences[0].id, first.sentences[1].id);
+        assert_eq!(stable_id("s", "Hello there."), first.sentences[0].id);
+        assert_ne!(stable_id("s", "Hello"), stable_id("c", "Hello"));
+    }
+
+    #[test]
+    fn repeated_sentences_get_distinct_ids() {
+        let segmented = segment_text("Again. Stop. Again.");
+        let ids: Vec<&str> = segmented.sentences.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids[2], format!("{}-2", ids[0]));
+        assert_eq!(segmented.find_segment(ids[2]), Some("Again."));
+        assert_eq!(segmented.find_segment(ids[1]), Some("Stop."));
+    }
+
+    #[test]
+    fn finds_chunks_by_id() {
+        let segmented = segment_text("When the train finally pulled into the station, everyone on the platform started cheering.");
+        let chunk = &segmented.sentences[0].chunks[1];
+        assert_eq!(segmented.find_segment(&chunk.id), Some(chunk.text.as_str()));
+        assert_eq!(segmented.find_segment("s-missing"), None);
+    }
+}
@@ -0,0 +1,383 @@
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{self, AudioClip, AudioFormat, ANALYSIS_SAMPLE_RATE};
+use crate::gemini_service::Transcript;
+use crate::prosody::{self, dtw_path};
+use crate::text::{matched_word_count, tokenize_words};
+use crate::vad::{self, VadConfig, VadResult};
+
+// 每個跟讀片段的最大詞數
+pub const MAX_SEGMENT_WORDS: usize = 12;
+
+// 對齊特徵：25 ms 幀、10 ms 步長的對數梅爾能量
+const FEATURE_FRAME: usize = 400;
+const FEATURE_HOP: usize = 160;
+const FEATURE_FFT: usize = 512;
+const MEL_BANDS: usize = 24;
+const HOP_SECS: f64 = FEATURE_HOP as f64 / ANALYSIS_SAMPLE_RATE as f64;
+const MAX_ALIGN_CELLS: usize = 4_000_000;
+
+// 跟讀延遲在 0.4 秒以內視為理想，超過 1.5 秒基本是在「跟在後面讀」
+const IDEAL_LAG_SECS: f64 = 0.4;
+const MAX_LAG_SECS: f64 = 1.5;
+// 低於此分數的片段建議重練
+const RETRY_THRESHOLD: f64 = 70.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimingAnalysis {
+    pub onset_lag_ms: f64, // 開口時間相對參考音頻的延遲
+    pub median_lag_ms: f64,
+    pub mean_lag_ms: f64,
+    pub lag_std_ms: f64, // 延遲的波動，越小說明跟得越穩
+    pub max_lag_ms: f64,
+    pub end_lag_ms: f64,
+    pub overlap_ratio: f64, // 參考語音中學習者同時在說話的時間比例
+    pub timing_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowingSegmentResult {
+    pub index: usize,
+    pub text: String,
+    pub timing: TimingAnalysis,
+    pub transcript: Option<String>,
+    pub pronunciation_score: Option<f64>, // 基於識別結果的讀對詞比例，沒有 ASR 時為空
+    pub prosody_score: f64,
+    pub overall_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowingSegmentInfo {
+    pub index: usize,
+    pub text: String,
+    pub duration_secs: f64,
+    pub mime_type: String,
+    pub audio_data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowingSessionInfo {
+    pub session_id: String,
+    pub segments: Vec<ShadowingSegmentInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowingSummary {
+    pub session_id: String,
+    pub segment_count: usize,
+    pub completed_count: usize,
+    pub median_lag_ms: Option<f64>,
+    pub overlap_ratio: Option<f64>,
+    pub timing_score: Option<f64>,
+    pub pronunciation_score: Option<f64>,
+    pub prosody_score: Option<f64>,
+    pub overall_score: Option<f64>,
+    pub needs_practice: Vec<usize>,
+    pub results: Vec<ShadowingSegmentResult>,
+}
+
+pub struct ShadowingSegment {
+    pub text: String,
+    pub reference: AudioClip,
+    pub result: Option<ShadowingSegmentResult>,
+}
+
+// 一次跟讀練習：參考文本切分後的各片段及其參考音頻與評分結果
+pub struct ShadowingSession {
+    pub id: String,
+    pub segments: Vec<ShadowingSegment>,
+}
+
+impl ShadowingSession {
+    pub fn new(segments: Vec<(String, AudioClip)>) -> Self {
+        Self {
+            id: format!("{:016x}", rand::random::<u64>()),
+            segments: segments
+                .into_iter()
+                .map(|(text, reference)| ShadowingSegment { text, reference, result: None })
+                .collect(),
+        }
+    }
+
+    // 參考音頻統一以 WAV 返回給前端播放
+    pub fn info(&self) -> Result<ShadowingSessionInfo, String> {
+        let segments = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                Ok(ShadowingSegmentInfo {
+                    index,
+                    text: segment.text.clone(),
+                    duration_secs: segment.reference.samples.len() as f64
+                        / segment.reference.sample_rate as f64,
+                    mime_type: AudioFormat::Wav.mime_type().to_string(),
+                    audio_data: audio::encode_base64(&segment.reference.to_wav_bytes()?),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(ShadowingSessionInfo { session_id: self.id.clone(), segments })
+    }
+
+    pub fn summary(&self) -> ShadowingSummary {
+        let results: Vec<ShadowingSegmentResult> =
+            self.segments.iter().filter_map(|s| s.result.clone()).collect();
+        let mean = |values: Vec<f64>| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let average = |values: Vec<f64>| mean(values).map(round1);
+
+        let mut needs_practice: Vec<&ShadowingSegmentResult> =
+            results.iter().filter(|r| r.overall_score < RETRY_THRESHOLD).collect();
+        needs_practice.sort_by(|a, b| a.overall_score.total_cmp(&b.overall_score));
+
+        ShadowingSummary {
+            session_id: self.id.clone(),
+            segment_count: self.segments.len(),
+            completed_count: results.len(),
+            median_lag_ms: average(results.iter().map(|r| r.timing.median_lag_ms).collect()),
+            overlap_ratio: mean(results.iter().map(|r| r.timing.overlap_ratio).collect())
+                .map(|ratio| (ratio * 100.0).round() / 100.0),
+            timing_score: average(results.iter().map(|r| r.timing.timing_score).collect()),
+            pronunciation_score: average(results.iter().filter_map(|r| r.pronunciation_score).collect()),
+            prosody_score: average(results.iter().map(|r| r.prosody_score).collect()),
+            overall_score: average(results.iter().map(|r| r.overall_score).collect()),
+            needs_practice: needs_practice.iter().map(|r| r.index).collect(),
+            results,
+        }
+    }
+}
+
+// 評估一個片段的跟讀：時間對齊得到延遲與重疊，另外給出發音與韻律分數
+// playback_offset_secs 是參考音頻開始播放的時刻（以學習者錄音的開頭為零點）
+pub fn score_segment(
+    index: usize,
+    text: &str,
+    reference: &AudioClip,
+    learner: &AudioClip,
+    learner_activity: &VadResult,
+    playback_offset_secs: f64,
+    transcript: Option<&Transcript>,
+) -> Result<ShadowingSegmentResult, String> {
+    let timing = analyze_timing(reference, learner, learner_activity, playback_offset_secs)
+        .ok_or("無法對齊跟讀錄音與參考音頻")?;
+
+    let vad_config = VadConfig::default();
+    let (trimmed, _) = vad::trim_silence(learner, learner_activity, &vad_config);
+    let prosody = prosody::analyze_prosody(&trimmed, Some(reference), text, None);
+
+    let pronunciation_score = transcript.map(|t| {
+        let expected = tokenize_words(text);
+        let spoken = tokenize_words(&t.text);
+        let matched = matched_word_count(&expected, &spoken);
+        round1(100.0 * matched as f64 / expected.len().max(1) as f64)
+    });
+
+    let mut parts = vec![timing.timing_score, prosody.prosody_score];
+    parts.extend(pronunciation_score);
+    let overall_score = round1(parts.iter().sum::<f64>() / parts.len() as f64);
+
+    Ok(ShadowingSegmentResult {
+        index,
+        text: text.to_string(),
+        timing,
+        transcript: transcript.map(|t| t.text.clone()),
+        pronunciation_score,
+        prosody_score: prosody.prosody_score,
+        overall_score,
+    })
+}
+
+pub fn analyze_timing(
+    reference: &AudioClip,
+    learner: &AudioClip,
+    learner_activity: &VadResult,
+    playback_offset_secs: f64,
+) -> Option<TimingAnalysis> {
+    let reference = reference.resampled(ANALYSIS_SAMPLE_RATE);
+    let learner = learner.resampled(ANALYSIS_SAMPLE_RATE);
+    let reference_activity = vad::detect_speech(&reference, &VadConfig::default());
+    let (reference_start, reference_end) = speech_bounds(&reference_activity)?;
+    let (learner_start, learner_end) = speech_bounds(learner_activity)?;
+
+    let reference_features = log_mel_features(&reference.samples);
+    let learner_features = log_mel_features(&learner.samples);
+    let reference_range = frame_range(reference_start, reference_end, reference_features.len())?;
+    let learner_range = frame_range(learner_start, learner_end, learner_features.len())?;
+
+    let (n, m) = (learner_range.1 - learner_range.0, reference_range.1 - reference_range.0);
+    let mut step = 1;
+    while (n / step + 1) * (m / step + 1) > MAX_ALIGN_CELLS {
+        step += 1;
+    }
+    let a = normalized_features(&learner_features[learner_range.0..learner_range.1], step);
+    let b = normalized_features(&reference_features[reference_range.0..reference_range.1], step);
+    let path = dtw_path(&a, &b, |x, y| {
+        x.iter().zip(y).map(|(p, q)| (p - q) * (p - q)).sum::<f32>().sqrt()
+    });
+
+    // 每個參考幀取第一個對應的學習者幀，計算該時刻的延遲
+    let mut lags = Vec::new();
+    let mut last_reference = None;
+    for &(i, j) in &path {
+        if last_reference == Some(j) {
+            continue;
+        }
+        last_reference = Some(j);
+        let learner_time = (learner_range.0 + i * step) as f64 * HOP_SECS;
+        let reference_time = (reference_range.0 + j * step) as f64 * HOP_SECS + playback_offset_secs;
+        lags.push(learner_time - reference_time);
+    }
+    if lags.is_empty() {
+        return None;
+    }
+
+    let mean_lag = lags.iter().sum::<f64>() / lags.len() as f64;
+    let lag_std = (lags.iter().map(|l| (l - mean_lag).powi(2)).sum::<f64>() / lags.len() as f64).sqrt();
+    let mut sorted = lags.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median_lag = sorted[sorted.len() / 2];
+    let max_lag = sorted[sorted.len() - 1];
+    let overlap_ratio = overlap_ratio(&reference_activity, learner_activity, playback_offset_secs);
+
+    Some(TimingAnalysis {
+        onset_lag_ms: to_ms(learner_start - (reference_start + playback_offset_secs)),
+        median_lag_ms: to_ms(median_lag),
+        mean_lag_ms: to_ms(mean_lag),
+        lag_std_ms: to_ms(lag_std),
+        max_lag_ms: to_ms(max_lag),
+        end_lag_ms: to_ms(learner_end - (reference_end + playback_offset_secs)),
+        overlap_ratio: (overlap_ratio * 100.0).round() / 100.0,
+        timing_score: timing_score(median_lag, lag_std, overlap_ratio),
+    })
+}
+
+fn timing_score(median_lag: f64, lag_std: f64, overlap_ratio: f64) -> f64 {
+    // 搶在參考音頻前面說同樣扣分（通常是在看文本朗讀，而不是跟讀）
+    let excess = if median_lag < 0.0 {
+        -2.0 * median_lag
+    } else {
+        (median_lag - IDEAL_LAG_SECS).max(0.0)
+    };
+    let lag_score = 100.0 * (1.0 - excess / (MAX_LAG_SECS - IDEAL_LAG_SECS)).clamp(0.0, 1.0);
+    let stability_score = 100.0 * (1.0 - (lag_std - 0.15) / 0.6).clamp(0.0, 1.0);
+    // 理想延遲下重疊約為 80%
+    let overlap_score = 100.0 * (overlap_ratio / 0.8).clamp(0.0, 1.0);
+    round1(0.5 * lag_score + 0.25 * stability_score + 0.25 * overlap_score)
+}
+
+fn speech_bounds(activity: &VadResult) -> Option<(f64, f64)> {
+    Some((activity.segments.first()?.start_secs, activity.segments.last()?.end_secs))
+}
+
+fn frame_range(start_secs: f64, end_secs: f64, frame_count: usize) -> Option<(usize, usize)> {
+    let start = ((start_secs / HOP_SECS) as usize).min(frame_count);
+    let end = ((end_secs / HOP_SECS).ceil() as usize).min(frame_count);
+    (end > start).then_some((start, end))
+}
+
+fn overlap_ratio(reference: &VadResult, learner: &VadResult, offset: f64) -> f64 {
+    let reference_speech: f64 = reference.segments.iter().map(|s| s.end_secs - s.start_secs).sum();
+    if reference_speech <= 0.0 {
+        return 0.0;
+    }
+    let mut overlap = 0.0;
+    for r in &reference.segments {
+        for l in &learner.segments {
+            overlap += ((r.end_secs + offset).min(l.end_secs) - (r.start_secs + offset).max(l.start_secs)).max(0.0);
+        }
+    }
+    (overlap / reference_speech).min(1.0)
+}
+
+// 按步長抽幀並做倒譜均值歸一化，消除錄音設備與音色帶來的整體頻譜差異
+fn normalized_features(frames: &[[f32; MEL_BANDS]], step: usize) -> Vec<[f32; MEL_BANDS]> {
+    let mut mean = [0.0f32; MEL_BANDS];
+    for frame in frames {
+        for (m, v) in mean.iter_mut().zip(frame) {
+            *m += v / frames.len() as f32;
+        }
+    }
+    frames
+        .iter()
+        .step_by(step.max(1))
+        .map(|frame| {
+            let mut normalized = [0.0f32; MEL_BANDS];
+            for k in 0..MEL_BANDS {
+                normalized[k] = frame[k] - mean[k];
+            }
+            normalized
+        })
+        .collect()
+}
+
+// 16 kHz 信號的對數梅爾能量譜
+fn log_mel_features(samples: &[f32]) -> Vec<[f32; MEL_BANDS]> {
+    if samples.len() < FEATURE_FRAME {
+        return Vec::new();
+    }
+    let mut planner = RealFftPlanner::<f32>::new();
+    let fft = planner.plan_fft_forward(FEATURE_FFT);
+    let window: Vec<f32> = (0..FEATURE_FRAME)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FEATURE_FRAME as f32).cos())
+        .collect();
+    let filters = mel_filterbank();
+
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+    (0..=samples.len() - FEATURE_FRAME)
+        .step_by(FEATURE_HOP)
+        .map(|start| {
+            input.iter_mut().for_each(|v| *v = 0.0);
+            for i in 0..FEATURE_FRAME {
+                input[i] = samples[start + i] * window[i];
+            }
+            let mut bands = [0.0f32; MEL_BANDS];
+            if fft.process(&mut input, &mut spectrum).is_ok() {
+                for (band, filter) in bands.iter_mut().zip(&filters) {
+                    let energy: f32 = filter.iter().map(|&(bin, weight)| spectrum[bin].norm_sqr() * weight).sum();
+                    *band = (energy + 1e-8).log10();
+                }
+            }
+            bands
+        })
+        .collect()
+}
+
+// 80 Hz 到 7.6 kHz 的三角形梅爾濾波器組，每個濾波器表示為 (頻點, 權重) 列表
+fn mel_filterbank() -> Vec<Vec<(usize, f32)>> {
+    let to_mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+    let to_hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+    let (low, high) = (to_mel(80.0), to_mel(7600.0));
+    let bin_hz = ANALYSIS_SAMPLE_RATE as f32 / FEATURE_FFT as f32;
+    let edges: Vec<f32> = (0..MEL_BANDS + 2)
+        .map(|i| to_hz(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32) / bin_hz)
+        .collect();
+
+    (0..MEL_BANDS)
+        .map(|band| {
+            let (left, center, right) = (edges[band], edges[band + 1], edges[band + 2]);
+            (left.floor() as usize..=right.ceil() as usize)
+                .filter(|&bin| bin <= FEATURE_FFT / 2)
+                .filter_map(|bin| {
+                    let f = bin as f32;
+                    let weight = if f < center {
+                        (f - left) / (center - left)
+                    } else {
+                        (right - f) / (right - center)
+                    };
+                    (weight > 0.0).then_some((bin, weight))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn to_ms(secs: f64) -> f64 {
+    (secs * 1000.0).round()
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
@@ -0,0 +1,917 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::database::Database;
+use crate::export::PracticeRecordExport;
+use crate::logging;
+use crate::vocabulary::{self, VocabularyEntry};
+
+const CONFIG_SETTING_KEY: &str = "sync_config";
+const DEVICE_SETTING_KEY: &str = "sync_device_id";
+const CURSOR_SETTING_KEY: &str = "sync_cursor";
+const STATUS_SETTING_KEY: &str = "sync_status";
+
+// 每次推送和拉取的最大變更數
+const BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub enabled: bool,
+    pub endpoint: String, // 例如 http://localhost:3001/api/sync
+    // 同步賬號 id，服務端按它隔離數據；同一學習者的各台設備填同一個值
+    pub sync_id: String,
+    pub token: Option<String>,
+    pub interval_secs: u64,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self { enabled: false, endpoint: String::new(), sync_id: String::new(), token: None, interval_secs: 300 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    PracticeRecord,
+    Vocabulary,
+    Setting,
+}
+
+impl EntityKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntityKind::PracticeRecord => "practice_record",
+            EntityKind::Vocabulary => "vocabulary",
+            EntityKind::Setting => "setting",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "practice_record" => Some(EntityKind::PracticeRecord),
+            "vocabulary" => Some(EntityKind::Vocabulary),
+            "setting" => Some(EntityKind::Setting),
+            _ => None,
+        }
+    }
+}
+
+// 版本向量：設備 id 到該設備修改次數的映射，用於判斷兩個版本是先後關係還是並發修改
+pub type VersionVector = BTreeMap<String, u64>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub entity: EntityKind,
+    pub key: String,
+    pub data: Option<Value>, // None 表示已刪除
+    pub version: VersionVector,
+    pub device_id: String,
+    pub updated_at: i64, // 毫秒時間戳，並發修改時後寫者勝
+}
+
+#[derive(Debug, Serialize)]
+struct PushRequest<'a> {
+    sync_id: &'a str,
+    device_id: &'a str,
+    changes: &'a [Change],
+}
+
+#[derive(Debug, Deserialize)]
+struct PullResponse {
+    changes: Vec<Change>,
+    cursor: i64,
+    #[serde(default)]
+    has_more: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub pushed: u32,
+    pub pulled: u32,
+    pub applied: u32,
+    pub conflicts: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SyncState {
+    last_synced_at: Option<String>,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub device_id: String,
+    pub enabled: bool,
+    pub endpoint: String,
+    pub pending_changes: u32,
+    pub cursor: i64,
+    pub last_synced_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Causality {
+    Before,
+    After,
+    Equal,
+    Concurrent,
+}
+
+// a 相對於 b 的先後關係
+fn compare_vectors(a: &VersionVector, b: &VersionVector) -> Causality {
+    let (mut less, mut greater) = (false, false);
+    for device in a.keys().chain(b.keys()) {
+        let (x, y) = (a.get(device).copied().unwrap_or(0), b.get(device).copied().unwrap_or(0));
+        less |= x < y;
+        greater |= x > y;
+    }
+    match (less, greater) {
+        (false, false) => Causality::Equal,
+        (true, false) => Causality::Before,
+        (false, true) => Causality::After,
+        (true, true) => Causality::Concurrent,
+    }
+}
+
+fn merge_vectors(a: &VersionVector, b: &VersionVector) -> VersionVector {
+    let mut merged = a.clone();
+    for (device, &count) in b {
+        let entry = merged.entry(device.clone()).or_insert(0);
+        *entry = (*entry).max(count);
+    }
+    merged
+}
+
+pub fn load_config(db: &Database) -> Result<SyncConfig, String> {
+    Ok(db.get_setting(CONFIG_SETTING_KEY)?.unwrap_or_default())
+}
+
+pub fn save_config(db: &Database, config: &SyncConfig) -> Result<SyncConfig, String> {
+    let endpoint = config.endpoint.trim().trim_end_matches('/').to_string();
+    if !endpoint.is_empty() && !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+        return Err("同步服務地址必須以 http:// 或 https:// 開頭".to_string());
+    }
+    if config.enabled && endpoint.is_empty() {
+        return Err("啟用同步前請先填寫同步服務地址".to_string());
+    }
+    let sync_id = config.sync_id.trim().to_string();
+    if !sync_id.is_empty()
+        && (!(8..=64).contains(&sync_id.len()) || !sync_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+    {
+        return Err("同步 id 須為 8 到 64 位字母、數字、- 或 _".to_string());
+    }
+    let previous = load_config(db)?;
+    // 第一台設備沒有填寫時自動生成，其他設備填入同一個 id 即可同步同一份數據
+    let sync_id = if !sync_id.is_empty() {
+        sync_id
+    } else if !previous.sync_id.is_empty() {
+        previous.sync_id.clone()
+    } else {
+        format!("{:016x}{:016x}", rand::random::<u64>(), rand::random::<u64>())
+    };
+    let config = SyncConfig {
+        endpoint,
+        sync_id,
+        token: config.token.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(str::to_string),
+        interval_secs: config.interval_secs.clamp(30, 24 * 3600),
+        ..config.clone()
+    };
+    // 換了服務端或同步賬號後從頭拉取
+    if previous.endpoint != config.endpoint || previous.sync_id != config.sync_id {
+        db.set_setting(CURSOR_SETTING_KEY, &0i64)?;
+    }
+    db.set_setting(CONFIG_SETTING_KEY, &config)?;
+    Ok(config)
+}
+
+// 本設備的 id，首次使用時隨機生成
+pub fn device_id(db: &Database) -> Result<String, String> {
+    if let Some(id) = db.get_setting::<String>(DEVICE_SETTING_KEY)? {
+        return Ok(id);
+    }
+    let id = format!("{:016x}", rand::random::<u64>());
+    db.set_setting(DEVICE_SETTING_KEY, &id)?;
+    Ok(id)
+}
+
+pub fn status(db: &Database) -> Result<SyncStatus, String> {
+    let config = load_config(db)?;
+    let state: SyncState = db.get_setting(STATUS_SETTING_KEY)?.unwrap_or_default();
+    let pending_changes = db
+        .conn()
+        .query_row("SELECT COUNT(*) FROM sync_outbox", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    Ok(SyncStatus {
+        device_id: device_id(db)?,
+        enabled: config.enabled,
+        endpoint: config.endpoint,
+        pending_changes,
+        cursor: db.get_setting(CURSOR_SETTING_KEY)?.unwrap_or(0),
+        last_synced_at: state.last_synced_at,
+        last_error: state.last_error,
+    })
+}
+
+fn record_result(db: &Database, result: &Result<SyncReport, String>) -> Result<(), String> {
+    let mut state: SyncState = db.get_setting(STATUS_SETTING_KEY)?.unwrap_or_default();
+    match result {
+        Ok(_) => {
+            state.last_synced_at = Some(
+                db.conn().query_row("SELECT datetime('now')", [], |row| row.get(0)).map_err(|e| e.to_string())?,
+            );
+            state.last_error = None;
+        }
+        Err(e) => state.last_error = Some(e.clone()),
+    }
+    db.set_setting(STATUS_SETTING_KEY, &state)
+}
+
+// 讀取實體當前內容，不存在時返回 None
+fn snapshot(db: &Database, entity: EntityKind, key: &str) -> Result<Option<Value>, String> {
+    let value = match entity {
+        EntityKind::PracticeRecord => db
+            .conn()
+            .query_row(
+                "SELECT uid, topic, scores, feedback, created_at FROM practice_records WHERE uid = ?1",
+                [key],
+                |row| {
+                    let scores: String = row.get(2)?;
+                    Ok(PracticeRecordExport {
+                        uid: row.get(0)?,
+                        topic: row.get(1)?,
+                        scores: serde_json::from_str(&scores).unwrap_or_default(),
+                        feedback: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("查詢練習記錄失敗：{}", e))?
+            .map(serde_json::to_value)
+            .transpose(),
+        // 本地 id 在各設備上不同，不參與同步
+        EntityKind::Vocabulary => vocabulary::entry_by_key(db, key)?
+            .map(|entry| serde_json::to_value(VocabularyEntry { id: 0, ..entry }))
+            .transpose(),
+        EntityKind::Setting => {
+            let value: Option<String> = db
+                .conn()
+                .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("讀取設置失敗：{}", e))?;
+            value.map(|v| serde_json::from_str(&v)).transpose()
+        }
+    };
+    value.map_err(|e| e.to_string())
+}
+
+// 寫入遠端內容；調用方負責暫停觸發器，避免寫入被重新加入發件箱
+fn write_entity(db: &Database, entity: EntityKind, key: &str, data: Option<&Value>) -> Result<(), String> {
+    match (entity, data) {
+        (EntityKind::PracticeRecord, Some(data)) => {
+            let record: PracticeRecordExport =
+                serde_json::from_value(data.clone()).map_err(|e| format!("練習記錄格式錯誤：{}", e))?;
+            let scores = serde_json::to_string(&record.scores).map_err(|e| e.to_string())?;
+            db.conn()
+                .execute(
+                    "INSERT INTO practice_records (uid, topic, scores, feedback, created_at) VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT (uid) DO UPDATE SET
+                         topic = excluded.topic, scores = excluded.scores, feedback = excluded.feedback",
+                    params![key, record.topic, scores, record.feedback, record.created_at],
+                )
+                .map_err(|e| format!("保存練習記錄失敗：{}", e))?;
+        }
+        (EntityKind::PracticeRecord, None) => {
+            db.conn()
+                .execute("DELETE FROM practice_records WHERE uid = ?1", [key])
+                .map_err(|e| format!("刪除練習記錄失敗：{}", e))?;
+        }
+        (EntityKind::Vocabulary, Some(data)) => {
+            let entry: VocabularyEntry =
+                serde_json::from_value(data.clone()).map_err(|e| format!("生詞格式錯誤：{}", e))?;
+            vocabulary::put_entry(db, &entry)?;
+        }
+        (EntityKind::Vocabulary, None) => vocabulary::delete_by_key(db, key)?,
+        (EntityKind::Setting, Some(data)) => db.set_setting(key, data)?,
+        (EntityKind::Setting, None) => {
+            db.conn()
+                .execute("DELETE FROM settings WHERE key = ?1", [key])
+                .map_err(|e| format!("刪除設置失敗：{}", e))?;
+        }
+    }
+    Ok(())
+}
+
+struct LocalVersion {
+    vector: VersionVector,
+    updated_at: i64,
+    device_id: String,
+}
+
+fn load_version(db: &Database, entity: EntityKind, key: &str) -> Result<LocalVersion, String> {
+    let row: Option<(String, i64, String)> = db
+        .conn()
+        .query_row(
+            "SELECT vector, updated_at, device_id FROM sync_versions WHERE entity = ?1 AND entity_key = ?2",
+            params![entity.as_str(), key],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(match row {
+        Some((vector, updated_at, device_id)) => {
+            LocalVersion { vector: serde_json::from_str(&vector).unwrap_or_default(), updated_at, device_id }
+        }
+        None => LocalVersion { vector: VersionVector::new(), updated_at: 0, device_id: String::new() },
+    })
+}
+
+fn store_version(db: &Database, entity: EntityKind, key: &str, version: &LocalVersion) -> Result<(), String> {
+    let vector = serde_json::to_string(&version.vector).map_err(|e| e.to_string())?;
+    db.conn()
+        .execute(
+            "INSERT INTO sync_versions (entity, entity_key, vector, updated_at, device_id) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (entity, entity_key) DO UPDATE SET
+                 vector = excluded.vector, updated_at = excluded.updated_at, device_id = excluded.device_id",
+            params![entity.as_str(), key, vector, version.updated_at, version.device_id],
+        )
+        .map_err(|e| format!("保存同步版本失敗：{}", e))?;
+    Ok(())
+}
+
+// 取出一批待推送的變更。首次取出時遞增本設備的版本號並保存快照，
+// 推送失敗重試時原樣發送，服務端收到重複的變更也不會產生新版本
+fn prepare_push(db: &Database, device: &str) -> Result<Vec<(i64, Change)>, String> {
+    let rows: Vec<(i64, String, String, i64, Option<String>)> = {
+        let mut stmt = db
+            .conn()
+            .prepare("SELECT id, entity, entity_key, changed_at, payload FROM sync_outbox ORDER BY id LIMIT ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([BATCH_SIZE as i64], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("讀取同步發件箱失敗：{}", e))?;
+        rows
+    };
+
+    let mut batch = Vec::new();
+    for (id, entity, key, changed_at, payload) in rows {
+        if let Some(change) = payload.and_then(|p| serde_json::from_str::<Change>(&p).ok()) {
+            batch.push((id, change));
+            continue;
+        }
+        let Some(entity) = EntityKind::parse(&entity) else {
+            db.conn().execute("DELETE FROM sync_outbox WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+            continue;
+        };
+        let mut version = load_version(db, entity, &key)?;
+        *version.vector.entry(device.to_string()).or_insert(0) += 1;
+        version.updated_at = changed_at;
+        version.device_id = device.to_string();
+        let change = Change {
+            entity,
+            key: key.clone(),
+            data: snapshot(db, entity, &key)?,
+            version: version.vector.clone(),
+            device_id: device.to_string(),
+            updated_at: changed_at,
+        };
+        store_version(db, entity, &key, &version)?;
+        let payload = serde_json::to_string(&change).map_err(|e| e.to_string())?;
+        db.conn()
+            .execute("UPDATE sync_outbox SET payload = ?1 WHERE id = ?2", params![payload, id])
+            .map_err(|e| e.to_string())?;
+        batch.push((id, change));
+    }
+    Ok(batch)
+}
+
+// 推送成功後移除發件箱條目；推送期間再次修改過的條目 id 已變，會在下一輪推送
+fn acknowledge(db: &Database, ids: &[i64]) -> Result<(), String> {
+    for id in ids {
+        db.conn().execute("DELETE FROM sync_outbox WHERE id = ?1", [id]).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// 並發修改的解決規則：生詞合併兩邊的內容，其餘實體後寫者勝（時間相同時比較設備 id）
+fn resolve(
+    entity: EntityKind,
+    local: (Option<&Value>, i64, &str),
+    remote: (Option<&Value>, i64, &str),
+) -> Option<Value> {
+    let (winner, loser) = if (remote.1, remote.2) > (local.1, local.2) { (remote, local) } else { (local, remote) };
+    if let (EntityKind::Vocabulary, Some(winner_data), Some(loser_data)) = (entity, winner.0, loser.0) {
+        let parse = |value: &Value| serde_json::from_value::<VocabularyEntry>(value.clone()).ok();
+        if let (Some(a), Some(b)) = (parse(winner_data), parse(loser_data)) {
+            return serde_json::to_value(vocabulary::merge_fields(&a, &b)).ok();
+        }
+    }
+    winner.0.cloned()
+}
+
+// 應用一條遠端變更：遠端版本較新時直接覆蓋，並發修改時按實體規則解決
+fn apply_remote(db: &Database, change: &Change, device: &str, report: &mut SyncReport) -> Result<(), String> {
+    if change.device_id == device {
+        return Ok(());
+    }
+    let (entity, key) = (change.entity, change.key.as_str());
+    let local = load_version(db, entity, key)?;
+    let pending: Option<(i64, Option<String>)> = db
+        .conn()
+        .query_row(
+            "SELECT changed_at, payload FROM sync_outbox WHERE entity = ?1 AND entity_key = ?2",
+            params![entity.as_str(), key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    // 還沒分配版本的本地修改相當於本設備版本號加一
+    let mut effective = local.vector.clone();
+    let (local_time, local_device) = match &pending {
+        Some((changed_at, None)) => {
+            *effective.entry(device.to_string()).or_insert(0) += 1;
+            (*changed_at, device.to_string())
+        }
+        _ => (local.updated_at, local.device_id.clone()),
+    };
+    let merged_vector = merge_vectors(&local.vector, &change.version);
+    let clear_pending = || db.conn().execute(
+        "DELETE FROM sync_outbox WHERE entity = ?1 AND entity_key = ?2",
+        params![entity.as_str(), key],
+    );
+
+    match compare_vectors(&change.version, &effective) {
+        Causality::Before | Causality::Equal => return Ok(()),
+        Causality::After => {
+            write_entity(db, entity, key, change.data.as_ref())?;
+            let version = LocalVersion {
+                vector: merged_vector,
+                updated_at: change.updated_at,
+                device_id: change.device_id.clone(),
+            };
+            store_version(db, entity, key, &version)?;
+            clear_pending().map_err(|e| e.to_string())?;
+        }
+        Causality::Concurrent => {
+            report.conflicts += 1;
+            let local_data = snapshot(db, entity, key)?;
+            let resolved = resolve(
+                entity,
+                (local_data.as_ref(), local_time, &local_device),
+                (change.data.as_ref(), change.updated_at, &change.device_id),
+            );
+            if resolved == local_data {
+                // 本地勝出：記下已見過的遠端版本，未推送的本地修改下次推送時會覆蓋遠端
+                let version = LocalVersion { vector: merged_vector, updated_at: local.updated_at, device_id: local.device_id };
+                store_version(db, entity, key, &version)?;
+                if pending.is_some() {
+                    db.conn()
+                        .execute(
+                            "UPDATE sync_outbox SET payload = NULL WHERE entity = ?1 AND entity_key = ?2",
+                            params![entity.as_str(), key],
+                        )
+                        .map_err(|e| e.to_string())?;
+                }
+                return Ok(());
+            }
+            write_entity(db, entity, key, resolved.as_ref())?;
+            if resolved == change.data {
+                let version = LocalVersion {
+                    vector: merged_vector,
+                    updated_at: change.updated_at,
+                    device_id: change.device_id.clone(),
+                };
+                store_version(db, entity, key, &version)?;
+                clear_pending().map_err(|e| e.to_string())?;
+            } else {
+                // 合併出了兩邊都沒有的新內容，作為本設備的修改推送出去
+                let changed_at = local_time.max(change.updated_at);
+                let version = LocalVersion { vector: merged_vector, updated_at: changed_at, device_id: device.to_string() };
+                store_version(db, entity, key, &version)?;
+                db.conn()
+                    .execute(
+                        "INSERT OR REPLACE INTO sync_outbox (entity, entity_key, changed_at) VALUES (?1, ?2, ?3)",
+                        params![entity.as_str(), key, changed_at],
+                    )
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    report.applied += 1;
+    Ok(())
+}
+
+// 在一個事務中應用一頁遠端變更並保存拉取位置，中途失敗時整頁回滾，下次從同一位置重新拉取
+fn apply_page(db: &Database, page: &PullResponse, device: &str, report: &mut SyncReport) -> Result<(), String> {
+    let tx = db.conn().unchecked_transaction().map_err(|e| e.to_string())?;
+    db.conn().execute("INSERT INTO sync_suppress (active) VALUES (1)", []).map_err(|e| e.to_string())?;
+    for change in &page.changes {
+        apply_remote(db, change, device, report)?;
+    }
+    db.conn().execute("DELETE FROM sync_suppress", []).map_err(|e| e.to_string())?;
+    db.set_setting(CURSOR_SETTING_KEY, &page.cursor)?;
+    tx.commit().map_err(|e| format!("保存同步結果失敗：{}", e))?;
+    report.pulled += page.changes.len() as u32;
+    Ok(())
+}
+
+pub struct SyncClient {
+    client: reqwest::Client,
+    endpoint: String,
+    sync_id: String,
+    token: Option<String>,
+}
+
+impl SyncClient {
+    pub fn new(config: &SyncConfig) -> Result<Self, String> {
+        if config.endpoint.is_empty() {
+            return Err("未配置同步服務地址".to_string());
+        }
+        if config.sync_id.is_empty() {
+            return Err("未配置同步 id".to_string());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("創建 HTTP 客戶端失敗：{}", e))?;
+        if let Some(token) = &config.token {
+            logging::register_secret(token);
+        }
+        Ok(Self {
+            client,
+            endpoint: config.endpoint.clone(),
+            sync_id: config.sync_id.clone(),
+            token: config.token.clone(),
+        })
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn push(&self, device: &str, changes: &[Change]) -> Result<(), String> {
+        let request = self.client.post(format!("{}/push", self.endpoint)).json(&PushRequest { sync_id: &self.sync_id, device_id: device, changes });
+        let response = self.authorize(request).send().await.map_err(|e| format!("連接同步服務失敗：{}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("同步服務拒絕推送：HTTP {}", response.status()));
+        }
+        Ok(())
+    }
+
+    async fn pull(&self, device: &str, cursor: i64) -> Result<PullResponse, String> {
+        let request = self.client.get(format!("{}/pull", self.endpoint)).query(&[
+            ("sync_id", self.sync_id.clone()),
+            ("since", cursor.to_string()),
+            ("device_id", device.to_string()),
+            ("limit", BATCH_SIZE.to_string()),
+        ]);
+        let response = self.authorize(request).send().await.map_err(|e| format!("連接同步服務失敗：{}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("同步服務拒絕拉取：HTTP {}", response.status()));
+        }
+        response.json().await.map_err(|e| format!("同步服務返回格式錯誤：{}", e))
+    }
+}
+
+// 同步期間切換了學習者檔案時中止，避免把一個檔案的數據推送到另一個檔案的同步服務
+fn same_profile<'a>(database: &'a Option<Database>, dir: &Path) -> Result<&'a Database, String> {
+    match database {
+        Some(db) if db.data_dir() == dir => Ok(db),
+        Some(_) => Err("同步期間切換了檔案，已中止".to_string()),
+        None => Err("數據庫未初始化".to_string()),
+    }
+}
+
+// 先推送發件箱中的本地修改，再拉取其他設備的修改。HTTP 請求期間不持有數據庫鎖；
+// 發件箱和拉取位置都保存在數據庫中，離線或失敗後下次從中斷處繼續。
+// 調用方負責保證同一時間只有一次同步
+pub async fn sync_now(database: &Mutex<Option<Database>>) -> Result<SyncReport, String> {
+    let dir = database.lock().await.as_ref().ok_or("數據庫未初始化")?.data_dir().to_path_buf();
+    let result = run_sync(database, &dir).await;
+
+    if let Ok(db) = same_profile(&*database.lock().await, &dir) {
+        if let Err(e) = record_result(db, &result) {
+            tracing::error!(error = %e, "Failed to save sync status");
+        }
+    }
+    result
+}
+
+async fn run_sync(database: &Mutex<Option<Database>>, dir: &Path) -> Result<SyncReport, String> {
+    let (config, device) = {
+        let database = database.lock().await;
+        let db = same_profile(&database, dir)?;
+        (load_config(db)?, device_id(db)?)
+    };
+    let client = SyncClient::new(&config)?;
+    let mut report = SyncReport::default();
+
+    push_pending(database, dir, &client, &device, &mut report).await?;
+    loop {
+        let cursor = {
+            let database = database.lock().await;
+            same_profile(&database, dir)?.get_setting::<i64>(CURSOR_SETTING_KEY)?.unwrap_or(0)
+        };
+        let page = client.pull(&device, cursor).await?;
+        {
+            let database = database.lock().await;
+            apply_page(same_profile(&database, dir)?, &page, &device, &mut report)?;
+        }
+        if !page.has_more || page.changes.is_empty() {
+            break;
+        }
+    }
+    // 解決衝突時合併出的新內容在本次同步中就推送出去
+    push_pending(database, dir, &client, &device, &mut report).await?;
+    Ok(report)
+}
+
+async fn push_pending(
+    database: &Mutex<Option<Database>>,
+    dir: &Path,
+    client: &SyncClient,
+    device: &str,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    loop {
+        let batch = {
+            let database = database.lock().await;
+            prepare_push(same_profile(&database, dir)?, device)?
+        };
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let (ids, changes): (Vec<i64>, Vec<Change>) = batch.into_iter().unzip();
+        client.push(device, &changes).await?;
+        let database = database.lock().await;
+        acknowledge(same_profile(&database, dir)?, &ids)?;
+        report.pushed += changes.len() as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Query, State};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // 模擬服務端：每個同步 id 按到達順序保存變更，拉取時返回同一 id 下其他設備的變更
+    type ChangeLog = Arc<std::sync::Mutex<HashMap<String, Vec<Change>>>>;
+
+    const SYNC_ID: &str = "learner-0001";
+
+    #[derive(Deserialize)]
+    struct PushBody {
+        sync_id: String,
+        changes: Vec<Change>,
+    }
+
+    #[derive(Deserialize)]
+    struct PullQuery {
+        sync_id: String,
+        since: i64,
+        device_id: String,
+        limit: usize,
+    }
+
+    async fn push(State(log): State<ChangeLog>, Json(body): Json<PushBody>) -> Json<Value> {
+        log.lock().unwrap().entry(body.sync_id).or_default().extend(body.changes);
+        Json(serde_json::json!({ "accepted": true }))
+    }
+
+    async fn pull(State(log): State<ChangeLog>, Query(query): Query<PullQuery>) -> Json<Value> {
+        let logs = log.lock().unwrap();
+        let log = logs.get(&query.sync_id).map(Vec::as_slice).unwrap_or_default();
+        let mut changes = Vec::new();
+        let mut cursor = query.since;
+        for (index, change) in log.iter().enumerate().skip(query.since as usize) {
+            if changes.len() == query.limit {
+                break;
+            }
+            cursor = index as i64 + 1;
+            if change.device_id != query.device_id {
+                changes.push(change.clone());
+            }
+        }
+        Json(serde_json::json!({ "changes": changes, "cursor": cursor, "has_more": (cursor as usize) < log.len() }))
+    }
+
+    async fn start_server() -> (String, ChangeLog) {
+        let log = ChangeLog::default();
+        let app = Router::new().route("/push", post(push)).route("/pull", get(pull)).with_state(log.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", address), log)
+    }
+
+    fn open_device(endpoint: &str) -> Mutex<Option<Database>> {
+        open_account(endpoint, SYNC_ID)
+    }
+
+    fn open_account(endpoint: &str, sync_id: &str) -> Mutex<Option<Database>> {
+        let dir = std::env::temp_dir().join(format!("web-chat-sync-{:016x}", rand::random::<u64>()));
+        let db = Database::open(&dir).unwrap();
+        let config =
+            SyncConfig { enabled: true, endpoint: endpoint.to_string(), sync_id: sync_id.to_string(), ..SyncConfig::default() };
+        save_config(&db, &config).unwrap();
+        Mutex::new(Some(db))
+    }
+
+    fn add_word(db: &Database, word: &str) -> VocabularyEntry {
+        let candidate = crate::gemini_service::VocabularyCandidate {
+            word: word.to_string(),
+            part_of_speech: "noun".to_string(),
+            ipa: String::new(),
+            definition: format!("definition of {}", word),
+            translation: String::new(),
+            example: String::new(),
+        };
+        vocabulary::add_candidates(db, &[candidate], "", "B1").unwrap().remove(0)
+    }
+
+    async fn word(device: &Mutex<Option<Database>>, word: &str) -> Option<VocabularyEntry> {
+        vocabulary::entry_by_key(device.lock().await.as_ref().unwrap(), word).unwrap()
+    }
+
+    #[tokio::test]
+    async fn changes_reach_other_device() {
+        let (endpoint, _) = start_server().await;
+        let (laptop, desktop) = (open_device(&endpoint), open_device(&endpoint));
+        {
+            let db = laptop.lock().await;
+            let db = db.as_ref().unwrap();
+            let scores = HashMap::from([("overall".to_string(), 91.0)]);
+            db.insert_practice_record("daily", &scores, "很好", None).unwrap();
+            add_word(db, "journey");
+            db.set_setting("practice_goals", &serde_json::json!({ "minutes_per_day": 20 })).unwrap();
+        }
+
+        let report = sync_now(&laptop).await.unwrap();
+        assert_eq!(report.pushed, 3);
+        let report = sync_now(&desktop).await.unwrap();
+        assert_eq!(report.applied, 3);
+
+        let db = desktop.lock().await;
+        let db = db.as_ref().unwrap();
+        let topic: String = db.conn().query_row("SELECT topic FROM practice_records", [], |row| row.get(0)).unwrap();
+        assert_eq!(topic, "daily");
+        assert!(vocabulary::entry_by_key(db, "journey").unwrap().is_some());
+        let goals: Value = db.get_setting("practice_goals").unwrap().unwrap();
+        assert_eq!(goals["minutes_per_day"], 20);
+        // 應用遠端變更不會重新進入發件箱
+        assert_eq!(status(db).unwrap().pending_changes, 0);
+    }
+
+    #[tokio::test]
+    async fn accounts_are_isolated() {
+        let (endpoint, _) = start_server().await;
+        let (alice, bob) = (open_account(&endpoint, "learner-alice"), open_account(&endpoint, "learner-bob"));
+        add_word(alice.lock().await.as_ref().unwrap(), "meadow");
+        sync_now(&alice).await.unwrap();
+
+        let report = sync_now(&bob).await.unwrap();
+        assert_eq!(report.pulled, 0);
+        assert!(word(&bob, "meadow").await.is_none());
+    }
+
+    #[test]
+    fn sync_id_is_generated_once_and_validated() {
+        let dir = std::env::temp_dir().join(format!("web-chat-sync-{:016x}", rand::random::<u64>()));
+        let db = Database::open(&dir).unwrap();
+        let config = save_config(&db, &SyncConfig::default()).unwrap();
+        assert_eq!(config.sync_id.len(), 32);
+        assert_eq!(save_config(&db, &SyncConfig::default()).unwrap().sync_id, config.sync_id);
+        let invalid = SyncConfig { sync_id: "a/b".to_string(), ..SyncConfig::default() };
+        assert!(save_config(&db, &invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn concurrent_vocabulary_edits_are_merged() {
+        let (endpoint, _) = start_server().await;
+        let (laptop, desktop) = (open_device(&endpoint), open_device(&endpoint));
+        let id = add_word(laptop.lock().await.as_ref().unwrap(), "harbour").id;
+        sync_now(&laptop).await.unwrap();
+        sync_now(&desktop).await.unwrap();
+
+        vocabulary::set_tags(laptop.lock().await.as_ref().unwrap(), id, &["travel".to_string()]).unwrap();
+        {
+            let db = desktop.lock().await;
+            let db = db.as_ref().unwrap();
+            let entry = vocabulary::entry_by_key(db, "harbour").unwrap().unwrap();
+            let update = vocabulary::VocabularyUpdate { translation: Some("港口".to_string()), ..Default::default() };
+            vocabulary::update_entry(db, entry.id, &update).unwrap();
+        }
+
+        sync_now(&laptop).await.unwrap();
+        let report = sync_now(&desktop).await.unwrap();
+        assert_eq!(report.conflicts, 1);
+        sync_now(&laptop).await.unwrap();
+        sync_now(&desktop).await.unwrap();
+
+        for device in [&laptop, &desktop] {
+            let entry = word(device, "harbour").await.unwrap();
+            assert_eq!(entry.tags, vec!["travel".to_string()]);
+            assert_eq!(entry.translation, "港口");
+            assert_eq!(status(device.lock().await.as_ref().unwrap()).unwrap().pending_changes, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_settings_use_last_writer() {
+        let (endpoint, _) = start_server().await;
+        let (laptop, desktop) = (open_device(&endpoint), open_device(&endpoint));
+        laptop.lock().await.as_ref().unwrap().set_setting("learning_goals", &"laptop").unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        desktop.lock().await.as_ref().unwrap().set_setting("learning_goals", &"desktop").unwrap();
+
+        sync_now(&desktop).await.unwrap();
+        sync_now(&laptop).await.unwrap();
+        sync_now(&desktop).await.unwrap();
+
+        for device in [&laptop, &desktop] {
+            let value: String = device.lock().await.as_ref().unwrap().get_setting("learning_goals").unwrap().unwrap();
+            assert_eq!(value, "desktop");
+        }
+    }
+
+    #[tokio::test]
+    async fn deletions_propagate() {
+        let (endpoint, _) = start_server().await;
+        let (laptop, desktop) = (open_device(&endpoint), open_device(&endpoint));
+        let id = add_word(laptop.lock().await.as_ref().unwrap(), "anchor").id;
+        sync_now(&laptop).await.unwrap();
+        sync_now(&desktop).await.unwrap();
+        assert!(word(&desktop, "anchor").await.is_some());
+
+        vocabulary::delete_entry(laptop.lock().await.as_ref().unwrap(), id).unwrap();
+        sync_now(&laptop).await.unwrap();
+        sync_now(&desktop).await.unwrap();
+        assert!(word(&desktop, "anchor").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn resumes_after_being_offline() {
+        let (endpoint, log) = start_server().await;
+        // 先指向一個沒有服務監聽的地址模擬離線
+        let offline = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let laptop = open_device(&offline);
+        add_word(laptop.lock().await.as_ref().unwrap(), "compass");
+        add_word(laptop.lock().await.as_ref().unwrap(), "lantern");
+
+        assert!(sync_now(&laptop).await.is_err());
+        {
+            let db = laptop.lock().await;
+            let status = status(db.as_ref().unwrap()).unwrap();
+            assert_eq!(status.pending_changes, 2);
+            assert!(status.last_error.is_some());
+        }
+
+        {
+            let db = laptop.lock().await;
+            let db = db.as_ref().unwrap();
+            let config = SyncConfig { endpoint: endpoint.clone(), ..load_config(db).unwrap() };
+            save_config(db, &config).unwrap();
+        }
+        let report = sync_now(&laptop).await.unwrap();
+        assert_eq!(report.pushed, 2);
+        assert_eq!(log.lock().unwrap()[SYNC_ID].len(), 2);
+        let status = status(laptop.lock().await.as_ref().unwrap()).unwrap();
+        assert_eq!(status.pending_changes, 0);
+        assert!(status.last_error.is_none());
+
+        let desktop = open_device(&endpoint);
+        sync_now(&desktop).await.unwrap();
+        assert!(word(&desktop, "compass").await.is_some());
+        assert!(word(&desktop, "lantern").await.is_some());
+    }
+
+    #[test]
+    fn version_vectors_compare() {
+        let vector = |pairs: &[(&str, u64)]| pairs.iter().map(|(d, c)| (d.to_string(), *c)).collect::<VersionVector>();
+        assert_eq!(compare_vectors(&vector(&[("a", 2)]), &vector(&[("a", 1)])), Causality::After);
+        assert_eq!(compare_vectors(&vector(&[("a", 1)]), &vector(&[("a", 1), ("b", 1)])), Causality::Before);
+        assert_eq!(compare_vectors(&vector(&[("a", 2)]), &vector(&[("a", 1), ("b", 1)])), Causality::Concurrent);
+        assert_eq!(compare_vectors(&vector(&[]), &vector(&[])), Causality::Equal);
+        assert_eq!(merge_vectors(&vector(&[("a", 2)]), &vector(&[("a", 1), ("b", 3)])), vector(&[("a", 2), ("b", 3)]));
+    }
+}
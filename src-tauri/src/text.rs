@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+// 英語文本的基礎處理工具（分詞、功能詞判斷、分句）
+
+// 常見功能詞，朗讀時一般不重讀
+const FUNCTION_WORDS: &[&str] = &[
+    "a", "an", "the", "and", "but", "or", "nor", "so", "yet", "of", "to", "in", "on", "at", "by",
+    "for", "with", "from", "into", "onto", "as", "than", "that", "this", "these", "those", "is",
+    "am", "are", "was", "were", "be", "been", "being", "do", "does", "did", "have", "has", "had",
+    "will", "would", "shall", "should", "can", "could", "may", "might", "must", "i", "you", "he",
+    "she", "it", "we", "they", "me", "him", "her", "us", "them", "my", "your", "his", "its",
+    "our", "their", "if", "then", "there", "some", "any", "not", "just", "very", "up", "out",
+    "about", "i'm", "it's", "you're", "we're", "they're", "don't", "can't", "i'd", "i'll",
+];
+
+// 語氣填充詞（um、uh 等）
+const FILLED_PAUSES: &[&str] = &["um", "umm", "uh", "uhh", "uhm", "er", "erm", "ah", "eh", "hmm", "mm"];
+
+// 不作為句末的常見縮寫
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "approx",
+];
+
+// 帶時間戳的詞（秒），通常來自語音識別
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedWord {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+// 將文本切分為小寫單詞，保留詞內的撇號（don't、it's）
+pub fn tokenize_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+        .map(|w| w.trim_matches(|c| c == '\'' || c == '’').replace('’', "'"))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+pub fn is_function_word(word: &str) -> bool {
+    FUNCTION_WORDS.contains(&word)
+}
+
+pub fn is_filled_pause(word: &str) -> bool {
+    FILLED_PAUSES.contains(&word)
+}
+
+// 將文本切分為適合跟讀的短語：先按句末標點分句，過長的句子再在逗號等停頓處切開
+pub fn split_phrases(text: &str, max_words: usize) -> Vec<String> {
+    let max_words = max_words.max(3);
+    let mut phrases = Vec::new();
+    for sentence in split_sentences(text) {
+        if sentence.split_whitespace().count() <= max_words {
+            phrases.push(sentence);
+            continue;
+        }
+
+        let mut current: Vec<&str> = Vec::new();
+        for word in sentence.split_whitespace() {
+            current.push(word);
+            let at_pause = word.ends_with([',', ';', ':']) || word == "—" || word == "-";
+            if current.len() >= max_words || (at_pause && current.len() >= 3) {
+                phrases.push(current.join(" "));
+                current.clear();
+            }
+        }
+        if !current.is_empty() {
+            // 剩餘太短時併入前一個短語
+            match phrases.last_mut() {
+                Some(last) if current.len() < 3 && last.split_whitespace().count() + current.len() <= max_words + 2 => {
+                    last.push(' ');
+                    last.push_str(&current.join(" "));
+                }
+                _ => phrases.push(current.join(" ")),
+            }
+        }
+    }
+    phrases
+}
+
+// 按句末標點分句，保留標點
+pub fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？') {
+            // 連續標點（"?!"、"..."）和緊跟的引號歸入同一句
+            while let Some(&next) = chars.peek() {
+                if matches!(next, '.' | '!' | '?' | '"' | '\'' | '”' | '’' | ')') {
+                    current.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if chars.peek().is_none_or(|next| next.is_whitespace()) && !ends_with_abbreviation(&current) {
+                let sentence = current.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !sentence.is_empty() {
+                    sentences.push(sentence);
+                }
+                current.clear();
+            }
+        }
+    }
+    let rest = current.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+    sentences
+}
+
+// 句點前是常見縮寫（Mr.、e.g.）或單個大寫字母（姓名縮寫）時不斷句
+fn ends_with_abbreviation(text: &str) -> bool {
+    let Some(last) = text.split_whitespace().last() else {
+        return false;
+    };
+    if !last.ends_with('.') {
+        return false;
+    }
+    let word = last.trim_end_matches('.').trim_start_matches(['"', '\'', '“', '(']);
+    let is_initial = word.chars().count() == 1 && word.chars().all(|c| c.is_uppercase());
+    is_initial || ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+// 參考詞序列與識別詞序列的最長公共子序列長度，用於估計讀對的詞數
+pub fn matched_word_count(reference: &[String], hypothesis: &[String]) -> usize {
+    let mut previous = vec![0usize; hypothesis.len() + 1];
+    for r in reference {
+        let mut current = vec![0usize; hypothesis.len() + 1];
+        for (j, h) in hypothesis.iter().enumerate() {
+            current[j + 1] = if r == h {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        previous = current;
+    }
+    previous[hypothesis.len()]
+}
@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{frame_energy_db, AudioClip};
+
+// 語音活動檢測參數，前端可按需覆蓋其中部分字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    pub frame_ms: u32,
+    pub threshold_db: f32,      // 高於噪聲底多少 dB 視為語音
+    pub min_speech_ms: u32,     // 短於此值的語音段視為噪聲
+    pub hangover_ms: u32,       // 語音段之間短於此值的間隙會被合併
+    pub padding_ms: u32,        // 裁剪首尾靜音時保留的餘量
+    pub end_silence_ms: u32,    // 說完話後靜音多久自動結束錄音
+    pub max_recording_ms: Option<u32>,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 20,
+            threshold_db: 12.0,
+            min_speech_ms: 120,
+            hangover_ms: 300,
+            padding_ms: 150,
+            end_silence_ms: 1500,
+            max_recording_ms: Some(60_000),
+        }
+    }
+}
+
+// 前端傳入參數的合理範圍，幀長過短會導致除零或逐樣本計算
+const MIN_FRAME_MS: u32 = 5;
+const MAX_FRAME_MS: u32 = 100;
+const MAX_DURATION_MS: u32 = 60_000;
+const MAX_RECORDING_MS: u32 = 10 * 60_000;
+
+impl VadConfig {
+    // 檢查前端傳入的參數，超出範圍時返回錯誤而不是在計算中途出錯
+    pub fn validated(self) -> Result<Self, String> {
+        if !(MIN_FRAME_MS..=MAX_FRAME_MS).contains(&self.frame_ms) {
+            return Err(format!("幀長須在 {} 到 {} 毫秒之間：{}", MIN_FRAME_MS, MAX_FRAME_MS, self.frame_ms));
+        }
+        if !self.threshold_db.is_finite() || !(0.0..=60.0).contains(&self.threshold_db) {
+            return Err(format!("語音閾值須在 0 到 60 dB 之間：{}", self.threshold_db));
+        }
+        for (name, value) in [
+            ("最短語音時長", self.min_speech_ms),
+            ("合併間隙", self.hangover_ms),
+            ("保留餘量", self.padding_ms),
+            ("結束靜音時長", self.end_silence_ms),
+        ] {
+            if value > MAX_DURATION_MS {
+                return Err(format!("{}不能超過 {} 毫秒：{}", name, MAX_DURATION_MS, value));
+            }
+        }
+        if let Some(max_ms) = self.max_recording_ms {
+            if !(self.frame_ms..=MAX_RECORDING_MS).contains(&max_ms) {
+                return Err(format!("最長錄音時長須在 {} 到 {} 毫秒之間：{}", self.frame_ms, MAX_RECORDING_MS, max_ms));
+            }
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeechSegment {
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VadResult {
+    pub segments: Vec<SpeechSegment>,
+    pub total_secs: f64,
+    pub speech_secs: f64,
+    pub leading_silence_secs: f64,
+    pub trailing_silence_secs: f64,
+    pub noise_floor_db: f64,
+}
+
+// 絕對能量下限，低於此值的幀一律當作靜音（dBFS）
+const ABSOLUTE_FLOOR_DB: f32 = -55.0;
+
+// 離線檢測整段錄音中的語音段
+pub fn detect_speech(clip: &AudioClip, config: &VadConfig) -> VadResult {
+    let frame_len = (clip.sample_rate as usize * config.frame_ms as usize / 1000).max(1);
+    let energy = frame_energy_db(&clip.samples, frame_len, frame_len);
+    let frame_secs = config.frame_ms as f64 / 1000.0;
+    let total_secs = clip.samples.len() as f64 / clip.sample_rate.max(1) as f64;
+
+    // 噪聲底取能量的第 10 百分位
+    let noise_floor = percentile(&energy, 0.1).unwrap_or(ABSOLUTE_FLOOR_DB);
+    let threshold = (noise_floor + config.threshold_db).max(ABSOLUTE_FLOOR_DB);
+    let active: Vec<bool> = energy.iter().map(|&db| db > threshold).collect();
+
+    let min_frames = (config.min_speech_ms / config.frame_ms).max(1) as usize;
+    let gap_frames = (config.hangover_ms / config.frame_ms) as usize;
+
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut i = 0;
+    while i < active.len() {
+        if active[i] {
+            let start = i;
+            while i < active.len() && active[i] {
+                i += 1;
+            }
+            match runs.last_mut() {
+                Some(last) if start - last.1 <= gap_frames => last.1 = i,
+                _ => runs.push((start, i)),
+            }
+        } else {
+            i += 1;
+        }
+    }
+    runs.retain(|&(s, e)| e - s >= min_frames);
+
+    let segments: Vec<SpeechSegment> = runs
+        .iter()
+        .map(|&(s, e)| SpeechSegment {
+            start_secs: s as f64 * frame_secs,
+            end_secs: (e as f64 * frame_secs).min(total_secs),
+        })
+        .collect();
+
+    let speech_secs = segments.iter().map(|s| s.end_secs - s.start_secs).sum();
+    let (leading_silence_secs, trailing_silence_secs) = match (segments.first(), segments.last()) {
+        (Some(first), Some(last)) => (first.start_secs, (total_secs - last.end_secs).max(0.0)),
+        _ => (total_secs, total_secs),
+    };
+
+    VadResult {
+        segments,
+        total_secs,
+        speech_secs,
+        leading_silence_secs,
+        trailing_silence_secs,
+        noise_floor_db: noise_floor as f64,
+    }
+}
+
+// 裁剪首尾靜音，返回裁剪後的音頻與起點偏移（秒）
+pub fn trim_silence(clip: &AudioClip, result: &VadResult, config: &VadConfig) -> (AudioClip, f64) {
+    let (Some(first), Some(last)) = (result.segments.first(), result.segments.last()) else {
+        return (clip.clone(), 0.0);
+    };
+    let padding = config.padding_ms as f64 / 1000.0;
+    let start_secs = (first.start_secs - padding).max(0.0);
+    let end_secs = (last.end_secs + padding).min(result.total_secs);
+
+    let rate = clip.sample_rate as f64;
+    let start = ((start_secs * rate) as usize).min(clip.samples.len());
+    let end = ((end_secs * rate) as usize).clamp(start, clip.samples.len());
+    (AudioClip::new(clip.samples[start..end].to_vec(), clip.sample_rate), start_secs)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    EndOfUtterance,
+    MaxDuration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointStatus {
+    pub speech_detected: bool,
+    pub is_speaking: bool,
+    pub elapsed_ms: u64,
+    pub speech_ms: u64,
+    pub trailing_silence_ms: u64,
+    pub stopped: Option<StopReason>,
+}
+
+// 錄音過程中的實時端點檢測：說話後持續靜音超過設定時長即判定說完
+pub struct Endpointer {
+    config: VadConfig,
+    frame_len: usize,
+    pending: Vec<f32>,
+    noise_floor_db: Option<f32>,
+    calibration: Vec<f32>,
+    speech_run_ms: u64,
+    status: EndpointStatus,
+}
+
+// 開頭用於估計噪聲底的幀數
+const CALIBRATION_FRAMES: usize = 10;
+
+impl Endpointer {
+    pub fn new(config: VadConfig, sample_rate: u32) -> Self {
+        let frame_len = (sample_rate as usize * config.frame_ms as usize / 1000).max(1);
+        Self {
+            config,
+            frame_len,
+            pending: Vec::new(),
+            noise_floor_db: None,
+            calibration: Vec::new(),
+            speech_run_ms: 0,
+            status: EndpointStatus {
+                speech_detected: false,
+                is_speaking: false,
+                elapsed_ms: 0,
+                speech_ms: 0,
+                trailing_silence_ms: 0,
+                stopped: None,
+            },
+        }
+    }
+
+    pub fn status(&self) -> &EndpointStatus {
+        &self.status
+    }
+
+    pub fn push(&mut self, samples: &[f32]) -> &EndpointStatus {
+        if self.status.stopped.is_some() {
+            return &self.status;
+        }
+        self.pending.extend_from_slice(samples);
+
+        let frame_ms = self.config.frame_ms as u64;
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= self.frame_len {
+            let frame = &self.pending[consumed..consumed + self.frame_len];
+            consumed += self.frame_len;
+            let db = frame_energy_db(frame, frame.len(), frame.len())[0];
+            self.status.elapsed_ms += frame_ms;
+
+            let noise_floor = match self.noise_floor_db {
+                Some(floor) => floor,
+                None => {
+                    self.calibration.push(db);
+                    if self.calibration.len() >= CALIBRATION_FRAMES {
+                        self.noise_floor_db = percentile(&self.calibration, 0.5);
+                    }
+                    continue;
+                }
+            };
+
+            let threshold = (noise_floor + self.config.threshold_db).max(ABSOLUTE_FLOOR_DB);
+            if db > threshold {
+                self.speech_run_ms += frame_ms;
+                if self.speech_run_ms >= self.config.min_speech_ms as u64 {
+                    self.status.speech_detected = true;
+                    self.status.is_speaking = true;
+                    self.status.trailing_silence_ms = 0;
+                }
+                self.status.speech_ms += frame_ms;
+            } else {
+                self.speech_run_ms = 0;
+                self.status.is_speaking = false;
+                // 噪聲底在非語音幀上緩慢跟蹤環境變化
+                self.noise_floor_db = Some(0.95 * noise_floor + 0.05 * db.min(noise_floor + 6.0));
+                if self.status.speech_detected {
+                    self.status.trailing_silence_ms += frame_ms;
+                }
+            }
+
+            if self.status.speech_detected
+                && self.status.trailing_silence_ms >= self.config.end_silence_ms as u64
+            {
+                self.status.stopped = Some(StopReason::EndOfUtterance);
+                break;
+            }
+            if let Some(max_ms) = self.config.max_recording_ms {
+                if self.status.elapsed_ms >= max_ms as u64 {
+                    self.status.stopped = Some(StopReason::MaxDuration);
+                    break;
+                }
+            }
+        }
+        self.pending.drain(..consumed);
+        &self.status
+    }
+}
+
+fn percentile(values: &[f32], fraction: f32) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let index = ((sorted.len() - 1) as f32 * fraction).round() as usize;
+    Some(sorted[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    // 依次拼接靜音（帶輕微噪聲）和語音（正弦波）片段，秒數為正表示語音、為負表示靜音
+    fn clip(parts: &[f64]) -> AudioClip {
+        let mut samples = Vec::new();
+        for &secs in parts {
+            let count = (secs.abs() * RATE as f64) as usize;
+            let start = samples.len();
+            samples.extend((0..count).map(|i| {
+                let t = (start + i) as f32 / RATE as f32;
+                if secs > 0.0 {
+                    0.3 * (2.0 * std::f32::consts::PI * 200.0 * t).sin()
+                } else {
+                    0.001 * (2.0 * std::f32::consts::PI * 3_000.0 * t).sin()
+                }
+            }));
+        }
+        AudioClip::new(samples, RATE)
+    }
+
+    #[test]
+    fn finds_speech_segments() {
+        let result = detect_speech(&clip(&[-0.5, 0.8, -0.6, 0.5, -0.4]), &VadConfig::default());
+        assert_eq!(result.segments.len(), 2);
+        assert!((result.segments[0].start_secs - 0.5).abs() < 0.05);
+        assert!((result.segments[1].end_secs - 2.4).abs() < 0.05);
+        assert!((result.speech_secs - 1.3).abs() < 0.1);
+        assert!((result.leading_silence_secs - 0.5).abs() < 0.05);
+        assert!((result.trailing_silence_secs - 0.4).abs() < 0.05);
+    }
+
+    #[test]
+    fn merges_short_gaps_and_drops_clicks() {
+        // 0.1 秒的間隙短於 hangover，合併成一段；0.05 秒的聲音短於最短語音時長，忽略
+        let result = detect_speech(&clip(&[-0.5, 0.5, -0.1, 0.5, -0.5, 0.05, -0.5]), &VadConfig::default());
+        assert_eq!(result.segments.len(), 1);
+        assert!((result.segments[0].end_secs - 1.6).abs() < 0.05);
+    }
+
+    #[test]
+    fn trims_leading_and_trailing_silence() {
+        let config = VadConfig::default();
+        let audio = clip(&[-1.0, 1.0, -1.0]);
+        let result = detect_speech(&audio, &config);
+        let (trimmed, offset) = trim_silence(&audio, &result, &config);
+        assert!((offset - 0.85).abs() < 0.05);
+        let duration = trimmed.samples.len() as f64 / RATE as f64;
+        assert!((duration - 1.3).abs() < 0.06, "{}", duration);
+    }
+
+    #[test]
+    fn endpointer_stops_after_trailing_silence() {
+        let config = VadConfig { end_silence_ms: 500, ..VadConfig::default() };
+        let mut endpointer = Endpointer::new(config, RATE);
+        let audio = clip(&[-0.5, 1.0, -1.0]);
+        // 按 100 毫秒一塊推送，模擬錄音過程
+        for chunk in audio.samples.chunks(1_600) {
+            if endpointer.push(chunk).stopped.is_some() {
+                break;
+            }
+        }
+        let status = endpointer.status();
+        assert!(status.speech_detected);
+        assert_eq!(status.stopped, Some(StopReason::EndOfUtterance));
+        assert!(status.elapsed_ms >= 2_000 && status.elapsed_ms <= 2_100, "{}", status.elapsed_ms);
+    }
+
+    #[test]
+    fn endpointer_stops_at_max_duration() {
+        let config = VadConfig { max_recording_ms: Some(1_000), ..VadConfig::default() };
+        let mut endpointer = Endpointer::new(config, RATE);
+        let status = endpointer.push(&clip(&[-2.0]).samples).clone();
+        assert!(!status.speech_detected);
+        assert_eq!(status.stopped, Some(StopReason::MaxDuration));
+        assert_eq!(status.elapsed_ms, 1_000);
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        assert!(VadConfig::default().validated().is_ok());
+        assert!(VadConfig { frame_ms: 0, ..VadConfig::default() }.validated().is_err());
+        assert!(VadConfig { threshold_db: f32::NAN, ..VadConfig::default() }.validated().is_err());
+        assert!(VadConfig { hangover_ms: 600_000, ..VadConfig::default() }.validated().is_err());
+        assert!(VadConfig { max_recording_ms: Some(0), ..VadConfig::default() }.validated().is_err());
+    }
+}
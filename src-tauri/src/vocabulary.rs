@@ -0,0 +1,448 @@
+use rusqlite::{params, params_from_iter, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::gemini_service::VocabularyCandidate;
+use crate::text::tokenize_words;
+
+// SM-2 間隔重複算法的參數
+const DEFAULT_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+
+const ENTRY_COLUMNS: &str = "id, word, part_of_speech, ipa, definition, translation, example, source_passage,
+     level, tags, ease_factor, interval_days, repetitions, lapses, due_at, last_reviewed_at, created_at";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyEntry {
+    pub id: i64,
+    pub word: String,
+    pub part_of_speech: String,
+    pub ipa: String,
+    pub definition: String,
+    pub translation: String,
+    pub example: String,
+    pub source_passage: String,
+    pub level: String,
+    pub tags: Vec<String>,
+    pub ease_factor: f64,
+    pub interval_days: i64,
+    pub repetitions: i64,
+    pub lapses: i64,
+    pub due_at: String,
+    pub last_reviewed_at: Option<String>,
+    pub created_at: String,
+}
+
+// 列表查詢條件，所有字段都可以不填
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VocabularyFilter {
+    pub search: Option<String>,
+    pub tag: Option<String>,
+    pub level: Option<String>,
+    pub due_only: bool,
+    pub limit: Option<u32>,
+}
+
+// 手動編輯詞條，只更新提供了的字段
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VocabularyUpdate {
+    pub word: Option<String>,
+    pub part_of_speech: Option<String>,
+    pub ipa: Option<String>,
+    pub definition: Option<String>,
+    pub translation: Option<String>,
+    pub example: Option<String>,
+}
+
+// 同一個詞不區分大小寫和標點只保存一次
+pub fn word_key(word: &str) -> String {
+    tokenize_words(word).join(" ")
+}
+
+fn row_to_entry(row: &Row) -> rusqlite::Result<VocabularyEntry> {
+    let tags: String = row.get("tags")?;
+    Ok(VocabularyEntry {
+        id: row.get("id")?,
+        word: row.get("word")?,
+        part_of_speech: row.get("part_of_speech")?,
+        ipa: row.get("ipa")?,
+        definition: row.get("definition")?,
+        translation: row.get("translation")?,
+        example: row.get("example")?,
+        source_passage: row.get("source_passage")?,
+        level: row.get("level")?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        ease_factor: row.get("ease_factor")?,
+        interval_days: row.get("interval_days")?,
+        repetitions: row.get("repetitions")?,
+        lapses: row.get("lapses")?,
+        due_at: row.get("due_at")?,
+        last_reviewed_at: row.get("last_reviewed_at")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+// 保存提取出的生詞，已在生詞本中的詞跳過，返回新加入的詞條
+pub fn add_candidates(
+    db: &Database,
+    candidates: &[VocabularyCandidate],
+    source_passage: &str,
+    level: &str,
+) -> Result<Vec<VocabularyEntry>, String> {
+    let mut added = Vec::new();
+    for candidate in candidates {
+        let key = word_key(&candidate.word);
+        if key.is_empty() {
+            continue;
+        }
+        let inserted = db
+            .conn()
+            .execute(
+                "INSERT INTO vocabulary (word, word_key, part_of_speech, ipa, definition, translation, example, source_passage, level)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT (word_key) DO NOTHING",
+                params![
+                    candidate.word.trim(),
+                    key,
+                    candidate.part_of_speech.trim(),
+                    candidate.ipa.trim().trim_matches('/'),
+                    candidate.definition.trim(),
+                    candidate.translation.trim(),
+                    candidate.example.trim(),
+                    source_passage,
+                    level,
+                ],
+            )
+            .map_err(|e| format!("保存生詞失敗：{}", e))?;
+        if inserted > 0 {
+            added.push(get_entry(db, db.conn().last_insert_rowid())?);
+        }
+    }
+    Ok(added)
+}
+
+pub fn get_entry(db: &Database, id: i64) -> Result<VocabularyEntry, String> {
+    db.conn()
+        .query_row(&format!("SELECT {} FROM vocabulary WHERE id = ?1", ENTRY_COLUMNS), [id], row_to_entry)
+        .optional()
+        .map_err(|e| format!("查詢生詞失敗：{}", e))?
+        .ok_or_else(|| format!("生詞 {} 不存在", id))
+}
+
+// 待複習的詞按到期時間排列，其餘按加入時間從新到舊排列
+pub fn list_entries(db: &Database, filter: &VocabularyFilter) -> Result<Vec<VocabularyEntry>, String> {
+    let mut conditions = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+    if let Some(search) = filter.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        values.push(format!("%{}%", search.to_lowercase()));
+        conditions.push(format!(
+            "(word_key LIKE ?{0} OR lower(definition) LIKE ?{0} OR translation LIKE ?{0})",
+            values.len()
+        ));
+    }
+    if let Some(tag) = &filter.tag {
+        values.push(tag.clone());
+        conditions.push(format!("EXISTS (SELECT 1 FROM json_each(vocabulary.tags) WHERE value = ?{})", values.len()));
+    }
+    if let Some(level) = &filter.level {
+        values.push(level.clone());
+        conditions.push(format!("level = ?{}", values.len()));
+    }
+    if filter.due_only {
+        conditions.push("due_at <= datetime('now')".to_string());
+    }
+
+    let mut sql = format!("SELECT {} FROM vocabulary", ENTRY_COLUMNS);
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql.push_str(if filter.due_only { " ORDER BY due_at, id" } else { " ORDER BY created_at DESC, id DESC" });
+    if let Some(limit) = filter.limit {
+        sql.push_str(&format!(" LIMIT {}", limit));
+    }
+
+    let mut stmt = db.conn().prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_from_iter(values), row_to_entry)
+        .map_err(|e| format!("查詢生詞失敗：{}", e))?;
+    rows.collect::<Result<_, _>>().map_err(|e| format!("查詢生詞失敗：{}", e))
+}
+
+pub fn set_tags(db: &Database, id: i64, tags: &[String]) -> Result<VocabularyEntry, String> {
+    let mut tags: Vec<String> = tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+    let json = serde_json::to_string(&tags).map_err(|e| e.to_string())?;
+    let updated = db
+        .conn()
+        .execute(
+            "UPDATE vocabulary SET tags = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![json, id],
+        )
+        .map_err(|e| format!("更新標籤失敗：{}", e))?;
+    if updated == 0 {
+        return Err(format!("生詞 {} 不存在", id));
+    }
+    get_entry(db, id)
+}
+
+pub fn update_entry(db: &Database, id: i64, update: &VocabularyUpdate) -> Result<VocabularyEntry, String> {
+    let entry = get_entry(db, id)?;
+    let pick = |value: &Option<String>, current: String| {
+        value.as_deref().map(|v| v.trim().to_string()).unwrap_or(current)
+    };
+    let word = pick(&update.word, entry.word);
+    let key = word_key(&word);
+    if key.is_empty() {
+        return Err("單詞不能為空".to_string());
+    }
+
+    db.conn()
+        .execute(
+            "UPDATE vocabulary SET word = ?1, word_key = ?2, part_of_speech = ?3, ipa = ?4, definition = ?5,
+                 translation = ?6, example = ?7, updated_at = datetime('now')
+             WHERE id = ?8",
+            params![
+                word,
+                key,
+                pick(&update.part_of_speech, entry.part_of_speech),
+                pick(&update.ipa, entry.ipa),
+                pick(&update.definition, entry.definition),
+                pick(&update.translation, entry.translation),
+                pick(&update.example, entry.example),
+                id,
+            ],
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+                format!("生詞本中已有 \"{}\"", word)
+            }
+            e => format!("更新生詞失敗：{}", e),
+        })?;
+    get_entry(db, id)
+}
+
+pub fn delete_entry(db: &Database, id: i64) -> Result<(), String> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM vocabulary WHERE id = ?1", [id])
+        .map_err(|e| format!("刪除生詞失敗：{}", e))?;
+    if deleted == 0 {
+        return Err(format!("生詞 {} 不存在", id));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeOutcome {
+    Added,
+    Merged,
+    Unchanged,
+}
+
+// 合併同一個詞的兩個版本：以 current 為準補全空白字段、合併標籤，複習進度取較近一次複習的一方
+pub fn merge_fields(current: &VocabularyEntry, other: &VocabularyEntry) -> VocabularyEntry {
+    let mut tags = current.tags.clone();
+    tags.extend(other.tags.iter().cloned());
+    tags.sort();
+    tags.dedup();
+    let fill = |current: &str, other: &str| if current.is_empty() { other.to_string() } else { current.to_string() };
+    // 日期字符串為 ISO 格式，可以直接比較先後
+    let progress = if other.last_reviewed_at > current.last_reviewed_at { other } else { current };
+    VocabularyEntry {
+        part_of_speech: fill(&current.part_of_speech, &other.part_of_speech),
+        ipa: fill(&current.ipa, &other.ipa),
+        definition: fill(&current.definition, &other.definition),
+        translation: fill(&current.translation, &other.translation),
+        example: fill(&current.example, &other.example),
+        tags,
+        ease_factor: progress.ease_factor,
+        interval_days: progress.interval_days,
+        repetitions: progress.repetitions,
+        lapses: progress.lapses,
+        due_at: progress.due_at.clone(),
+        last_reviewed_at: progress.last_reviewed_at.clone(),
+        ..current.clone()
+    }
+}
+
+// 判斷兩個版本的內容是否相同，不比較本地 id
+pub fn same_content(a: &VocabularyEntry, b: &VocabularyEntry) -> bool {
+    let strip = |entry: &VocabularyEntry| VocabularyEntry { id: 0, ..entry.clone() };
+    serde_json::to_value(strip(a)).ok() == serde_json::to_value(strip(b)).ok()
+}
+
+pub fn entry_by_key(db: &Database, key: &str) -> Result<Option<VocabularyEntry>, String> {
+    db.conn()
+        .query_row(&format!("SELECT {} FROM vocabulary WHERE word_key = ?1", ENTRY_COLUMNS), [key], row_to_entry)
+        .optional()
+        .map_err(|e| format!("查詢生詞失敗：{}", e))
+}
+
+// 按詞寫入完整詞條，不存在時新增，存在時覆蓋所有字段
+pub fn put_entry(db: &Database, entry: &VocabularyEntry) -> Result<(), String> {
+    let key = word_key(&entry.word);
+    if key.is_empty() {
+        return Err("單詞不能為空".to_string());
+    }
+    let tags = serde_json::to_string(&entry.tags).map_err(|e| e.to_string())?;
+    db.conn()
+        .execute(
+            "INSERT INTO vocabulary (word, word_key, part_of_speech, ipa, definition, translation, example,
+                 source_passage, level, tags, ease_factor, interval_days, repetitions, lapses, due_at,
+                 last_reviewed_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+             ON CONFLICT (word_key) DO UPDATE SET
+                 word = excluded.word, part_of_speech = excluded.part_of_speech, ipa = excluded.ipa,
+                 definition = excluded.definition, translation = excluded.translation, example = excluded.example,
+                 source_passage = excluded.source_passage, level = excluded.level, tags = excluded.tags,
+                 ease_factor = excluded.ease_factor, interval_days = excluded.interval_days,
+                 repetitions = excluded.repetitions, lapses = excluded.lapses, due_at = excluded.due_at,
+                 last_reviewed_at = excluded.last_reviewed_at, updated_at = datetime('now')",
+            params![
+                entry.word.trim(),
+                key,
+                entry.part_of_speech,
+                entry.ipa,
+                entry.definition,
+                entry.translation,
+                entry.example,
+                entry.source_passage,
+                entry.level,
+                tags,
+                entry.ease_factor,
+                entry.interval_days,
+                entry.repetitions,
+                entry.lapses,
+                entry.due_at,
+                entry.last_reviewed_at,
+                entry.created_at,
+            ],
+        )
+        .map_err(|e| format!("保存生詞失敗：{}", e))?;
+    Ok(())
+}
+
+pub fn delete_by_key(db: &Database, key: &str) -> Result<(), String> {
+    db.conn()
+        .execute("DELETE FROM vocabulary WHERE word_key = ?1", [key])
+        .map_err(|e| format!("刪除生詞失敗：{}", e))?;
+    Ok(())
+}
+
+// 合併導入的詞條：新詞直接加入；已有的詞合併標籤、補全空白字段，複習進度以較近一次複習的一方為準
+pub fn merge_entry(db: &Database, entry: &VocabularyEntry) -> Result<MergeOutcome, String> {
+    let key = word_key(&entry.word);
+    if key.is_empty() {
+        return Ok(MergeOutcome::Unchanged);
+    }
+    let Some(current) = entry_by_key(db, &key)? else {
+        put_entry(db, entry)?;
+        return Ok(MergeOutcome::Added);
+    };
+    let merged = merge_fields(&current, entry);
+    if same_content(&merged, &current) {
+        return Ok(MergeOutcome::Unchanged);
+    }
+    put_entry(db, &merged)?;
+    Ok(MergeOutcome::Merged)
+}
+
+// 記錄一次複習。quality 為 0-5 的自評：3 以下視為忘記，從頭開始間隔
+pub fn review_entry(db: &Database, id: i64, quality: u8) -> Result<VocabularyEntry, String> {
+    if quality > 5 {
+        return Err("複習評分應在 0 到 5 之間".to_string());
+    }
+    let entry = get_entry(db, id)?;
+    let (ease_factor, interval_days, repetitions, lapses) =
+        next_schedule(entry.ease_factor, entry.interval_days, entry.repetitions, entry.lapses, quality);
+
+    db.conn()
+        .execute(
+            "UPDATE vocabulary SET ease_factor = ?1, interval_days = ?2, repetitions = ?3, lapses = ?4,
+                 due_at = datetime('now', '+' || ?2 || ' days'), last_reviewed_at = datetime('now'),
+                 updated_at = datetime('now')
+             WHERE id = ?5",
+            params![ease_factor, interval_days, repetitions, lapses, id],
+        )
+        .map_err(|e| format!("保存複習結果失敗：{}", e))?;
+    get_entry(db, id)
+}
+
+// SM-2：答對時間隔依次為 1 天、6 天，之後按難度係數遞增；難度係數隨評分調整，最低 1.3
+fn next_schedule(ease: f64, interval: i64, repetitions: i64, lapses: i64, quality: u8) -> (f64, i64, i64, i64) {
+    let q = quality as f64;
+    let ease = if ease > 0.0 { ease } else { DEFAULT_EASE };
+    let ease = ((ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE) * 100.0).round() / 100.0;
+    if quality < 3 {
+        return (ease, 1, 0, lapses + 1);
+    }
+    let interval = match repetitions {
+        0 => 1,
+        1 => 6,
+        _ => ((interval.max(1) as f64) * ease).round() as i64,
+    };
+    (ease, interval, repetitions + 1, lapses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(word: &str) -> VocabularyCandidate {
+        VocabularyCandidate {
+            word: word.to_string(),
+            part_of_speech: "noun".to_string(),
+            ipa: "/ˈwɜːd/".to_string(),
+            definition: "a unit of language".to_string(),
+            translation: "詞".to_string(),
+            example: "Say a word.".to_string(),
+        }
+    }
+
+    #[test]
+    fn correct_answers_grow_the_interval() {
+        let mut state = (DEFAULT_EASE, 0, 0, 0);
+        let mut intervals = Vec::new();
+        for _ in 0..4 {
+            state = next_schedule(state.0, state.1, state.2, state.3, 5);
+            intervals.push(state.1);
+        }
+        assert_eq!(intervals, vec![1, 6, 17, 49]);
+        assert_eq!(state, (2.9, 49, 4, 0));
+
+        // 評分 3 勉強答對，間隔照常推進但難度係數下降
+        assert_eq!(next_schedule(2.5, 6, 2, 0, 3), (2.36, 14, 3, 0));
+        assert_eq!(next_schedule(2.5, 6, 2, 0, 4), (2.5, 15, 3, 0));
+    }
+
+    #[test]
+    fn lapses_restart_the_schedule() {
+        assert_eq!(next_schedule(2.5, 17, 3, 0, 2), (2.18, 1, 0, 1));
+        assert_eq!(next_schedule(2.5, 17, 3, 1, 0), (1.7, 1, 0, 2));
+        // 難度係數不低於下限，缺失時按默認值計算
+        assert_eq!(next_schedule(MIN_EASE, 1, 0, 5, 0).0, MIN_EASE);
+        assert_eq!(next_schedule(0.0, 0, 0, 0, 5).0, 2.6);
+    }
+
+    #[test]
+    fn reviews_update_the_stored_entry() {
+        let db = Database::open(&std::env::temp_dir().join(format!("web-chat-vocabulary-{:016x}", rand::random::<u64>()))).unwrap();
+        let added = add_candidates(&db, &[candidate("Word"), candidate("word."), candidate("  ")], "Say a word.", "B1").unwrap();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].ipa, "ˈwɜːd");
+
+        let id = added[0].id;
+        assert!(review_entry(&db, id, 6).is_err());
+        let reviewed = review_entry(&db, id, 5).unwrap();
+        assert_eq!((reviewed.interval_days, reviewed.repetitions), (1, 1));
+        assert!(reviewed.last_reviewed_at.is_some());
+        assert!(list_entries(&db, &VocabularyFilter { due_only: true, ..VocabularyFilter::default() }).unwrap().is_empty());
+
+        let forgotten = review_entry(&db, id, 1).unwrap();
+        assert_eq!((forgotten.interval_days, forgotten.repetitions, forgotten.lapses), (1, 0, 1));
+    }
+}
@@ -373,20 +373,37 @@ const Practice = () => {
         reader.onloadend = async () => {
           const base64Audio = reader.result.split(',')[1];
           
-          // 调用语音识别
-          const text = await invoke('speech_to_text', { audioData: base64Audio });
-          setTranscription(text);
-          
-          // 调用发音评分
-          const scoreResult = await invoke('pronunciation_score', {
-            audioData: base64Audio,
-            referenceText: practiceText
-          });
-          setScores(scoreResult);
-          
-          // 如果啟用AI導師，顯示反饋
-          if (aiTutorEnabled && aiSettings?.apiKey) {
-            setShowAIFeedback(true);
+          try {
+            // 调用语音识别
+            const text = await invoke('speech_to_text', { audioData: base64Audio });
+            setTranscription(text);
+            
+            // 调用发音评分
+            const scoreResult = await invoke('pronunciation_score', {
+              audioData: base64Audio,
+              referenceText: practiceText
+            });
+            setScores(scoreResult);
+            
+            // 如果啟用AI導師，顯示反饋
+            if (aiTutorEnabled && aiSettings?.apiKey) {
+              setShowAIFeedback(true);
+            }
+          } catch (error) {
+            // 錄音質量不合格時，後端返回 "too_noisy: 提示文字" 格式的錯誤
+            const [code, ...rest] = String(error).split(': ');
+            if (['no_speech', 'too_quiet', 'clipped', 'too_noisy'].includes(code)) {
+              Modal.warning({
+                title: '錄音質量不佳，請重新錄音',
+                content: rest.join(': '),
+              });
+            } else {
+              console.error('处理音频失败:', error);
+              Modal.error({
+                title: '处理失败',
+                content: '音频处理失败，请重试。',
+              });
+            }
           }
         };
         reader.readAsDataURL(audioBlob);